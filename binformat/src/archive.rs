@@ -0,0 +1,108 @@
+//! Support for static archives (.a, .lib).
+//!
+//! Every member of the archive is laid out after the previous one, like a static linker would
+//! when pulling in the entire archive. Each member get's it's own segment and the sections of a
+//! member are prefixed with the member's name.
+
+use crate::relocatable::{self, RelocatableDebugInfo};
+use crate::RawSymbol;
+use object::read::archive::ArchiveFile;
+use object::{Object, ObjectKind};
+use processor_shared::{AddressMap, Section, Segment};
+
+pub struct ArchiveDebugInfo<'data> {
+    /// Parsed objects stored in the archive, in the order they're laid out.
+    pub objects: Vec<object::File<'data>>,
+    /// Parsed sections of all members.
+    pub sections: Vec<Section>,
+    /// One segment per member.
+    pub segments: Vec<Segment>,
+    /// Symbols of all members.
+    pub syms: AddressMap<RawSymbol<'data>>,
+}
+
+impl<'data> ArchiveDebugInfo<'data> {
+    pub fn parse(data: &'data [u8]) -> Result<Self, object::Error> {
+        let archive = ArchiveFile::parse(data)?;
+        let mut this = Self {
+            objects: Vec::new(),
+            sections: Vec::new(),
+            segments: Vec::new(),
+            syms: AddressMap::default(),
+        };
+
+        let mut addr = relocatable::BASE_ADDR;
+        for member in archive.members() {
+            let member = member?;
+            let name = String::from_utf8_lossy(member.name()).into_owned();
+            let obj = match member.data(data).and_then(object::File::parse) {
+                Ok(obj) if obj.kind() == ObjectKind::Relocatable => obj,
+                // Things like import descriptors in MSVC .lib's.
+                _ => {
+                    log::complex!(
+                        w "[archive::parse] ",
+                        y "Skipping member ",
+                        b &name,
+                        y " as it isn't a relocatable object."
+                    );
+                    continue;
+                }
+            };
+
+            let debug_info = RelocatableDebugInfo::parse(&obj, addr);
+            for mut section in debug_info.sections {
+                section.name = format!("{name}({})", section.name);
+                this.sections.push(section);
+            }
+
+            this.syms.extend(debug_info.syms);
+            this.segments.push(Segment {
+                name,
+                start: addr,
+                end: debug_info.end,
+            });
+            this.objects.push(obj);
+
+            // Keep members page aligned so they're easy to tell apart.
+            addr = (debug_info.end + 0xfff) & !0xfff;
+        }
+
+        Ok(this)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, GLOBAL};
+    use object::elf;
+
+    #[test]
+    fn members() {
+        // mov eax, [rip + value]
+        let text = [0x8b, 0x05, 0, 0, 0, 0];
+        let syms = [("value", 2, 0, GLOBAL | elf::STT_OBJECT)];
+        let relocs = [(2, 1, elf::R_X86_64_PC32, -4)];
+        let obj = testing::relocatable(elf::EM_X86_64, &text, &[0; 8], 8, &syms, &relocs);
+        let bytes = testing::archive(&[("a.o", &obj), ("b.o", &obj)]);
+        let info = ArchiveDebugInfo::parse(&bytes).unwrap();
+
+        let segments: Vec<_> =
+            info.segments.iter().map(|s| (&s.name[..], s.start, s.end)).collect();
+        assert_eq!(segments, [("a.o", 0x1000, 0x1010), ("b.o", 0x2000, 0x2010)]);
+
+        // Every member is relocated against it's own base.
+        for (member, base) in [("a.o", 0x1000), ("b.o", 0x2000)] {
+            let text = info.sections.iter().find(|s| s.name == format!("{member}(.text)"));
+            let data = info.sections.iter().find(|s| s.name == format!("{member}(.data)"));
+            let (text, data) = (text.unwrap(), data.unwrap());
+            assert_eq!((text.start, data.start), (base, base + 8));
+
+            let field = u32::from_le_bytes(text.bytes()[2..6].try_into().unwrap());
+            assert_eq!(field, (data.start - 4 - (text.start + 2)) as u32);
+        }
+
+        let syms: Vec<_> = info.syms.iter().map(|s| (s.addr, s.item.name)).collect();
+        assert_eq!(syms, [(0x1008, "value"), (0x2008, "value")]);
+    }
+}
@@ -0,0 +1,593 @@
+//! Inspection of Authenticode signatures found in a PE's certificate table.
+//!
+//! A signature is a PKCS#7 `SignedData` blob wrapped in a `WIN_CERTIFICATE`, described in
+//! <https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-attribute-certificate-table-image-only>.
+//! We only decode as much DER as is needed to name the signer and check the image hash, we don't
+//! verify the chain of trust.
+
+use crate::FileInfo;
+use sha1::Sha1;
+use sha2::digest::DynDigest;
+use sha2::Sha256;
+
+const WIN_CERT_TYPE_X509: u16 = 0x0001;
+const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const UTF8_STRING: u8 = 0x0c;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+const BMP_STRING: u8 = 0x1e;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const CONTEXT_0: u8 = 0xa0;
+const CONTEXT_1: u8 = 0xa1;
+
+/// 1.2.840.113549.1.7.2
+const OID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+/// 1.3.14.3.2.26
+const OID_SHA1: &[u8] = &[0x2b, 0x0e, 0x03, 0x02, 0x1a];
+/// 2.16.840.1.101.3.4.2.1
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+/// 1.2.840.113549.1.9.5
+const OID_SIGNING_TIME: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x05];
+/// 1.2.840.113549.1.9.6
+const OID_COUNTER_SIGNATURE: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x06];
+/// 1.3.6.1.4.1.311.3.3.1
+const OID_RFC3161_TIMESTAMP: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x03, 0x03, 0x01];
+/// 1.2.840.113549.1.9.1
+const OID_EMAIL: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01];
+
+/// Parts of the file that aren't covered by the signature.
+pub(crate) struct Exclusions {
+    /// Offset of the optional header's `CheckSum`.
+    pub checksum: usize,
+    /// Offset of the certificate table's data directory entry.
+    pub directory: usize,
+    /// Range of the certificate table itself.
+    pub table: std::ops::Range<usize>,
+}
+
+/// Summarize every signature in the certificate table, `data` being the whole file.
+pub(crate) fn parse(data: &[u8], exclusions: Exclusions) -> Vec<FileInfo> {
+    let mut info = Vec::new();
+    let table = match data.get(exclusions.table.clone()) {
+        Some(table) => table,
+        None => return info,
+    };
+
+    // Each entry is aligned to 8 bytes.
+    let mut offset = 0;
+    while let Some(header) = table.get(offset..offset + 8) {
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let revision = u16::from_le_bytes([header[4], header[5]]);
+        let kind = u16::from_le_bytes([header[6], header[7]]);
+        let certificate = match table.get(offset + 8..offset + len) {
+            Some(certificate) if len >= 8 => certificate,
+            _ => break,
+        };
+
+        let mut entries = vec![
+            ("Revision".to_string(), format!("{}.{}", revision >> 8, revision & 0xff)),
+            ("Type".to_string(), match kind {
+                WIN_CERT_TYPE_X509 => "WIN_CERT_TYPE_X509".to_string(),
+                WIN_CERT_TYPE_PKCS_SIGNED_DATA => "WIN_CERT_TYPE_PKCS_SIGNED_DATA".to_string(),
+                _ => format!("{kind:#x}"),
+            }),
+        ];
+
+        if kind == WIN_CERT_TYPE_PKCS_SIGNED_DATA {
+            match signature(data, &exclusions, certificate) {
+                Some(signature) => entries.extend(signature),
+                None => entries.push(("Error".to_string(), "malformed PKCS#7 data".to_string())),
+            }
+        }
+
+        info.push(FileInfo {
+            title: "Authenticode signature",
+            entries,
+        });
+
+        offset += (len + 7) & !7;
+    }
+
+    info
+}
+
+/// Describe the signer of a PKCS#7 `SignedData` and whether the image hash still matches.
+fn signature(
+    data: &[u8],
+    exclusions: &Exclusions,
+    certificate: &[u8],
+) -> Option<Vec<(String, String)>> {
+    let signed_data = signed_data(certificate)?;
+    let mut entries = Vec::new();
+
+    // SpcIndirectDataContent: the `DigestInfo` follows an attribute describing the image.
+    let mut indirect_data = children(expect(signed_data.content?, SEQUENCE)?);
+    indirect_data.next()?;
+    let mut digest_info = children(expect(indirect_data.next()?, SEQUENCE)?);
+    let algorithm = expect(digest_info.next()?, SEQUENCE)?;
+    let algorithm = expect(children(algorithm).next()?, OBJECT_IDENTIFIER)?;
+    let embedded = expect(digest_info.next()?, OCTET_STRING)?;
+
+    let (algorithm, digest): (&str, Option<Box<dyn DynDigest>>) = match algorithm {
+        OID_SHA1 => ("SHA-1", Some(Box::new(Sha1::default()))),
+        OID_SHA256 => ("SHA-256", Some(Box::new(Sha256::default()))),
+        _ => ("unsupported", None),
+    };
+
+    entries.push(("Digest algorithm".to_string(), algorithm.to_string()));
+    entries.push(("Embedded hash".to_string(), hex(embedded)));
+    if let Some(mut digest) = digest {
+        let table = &exclusions.table;
+        for range in [
+            0..exclusions.checksum,
+            exclusions.checksum + 4..exclusions.directory,
+            exclusions.directory + 8..table.start,
+            table.end..data.len(),
+        ] {
+            digest.update(data.get(range).unwrap_or(&[]));
+        }
+
+        let computed = digest.finalize();
+        let status = match *computed == *embedded {
+            true => "matches",
+            false => "mismatch, the image was modified after signing",
+        };
+        entries.push(("Computed hash".to_string(), format!("{} ({status})", hex(&computed))));
+    }
+
+    entries.extend(signer(&signed_data));
+    Some(entries)
+}
+
+/// Describe the signer of a CMS `SignedData` without looking at what it signed, as
+/// Mach-O code signatures detach the signed content.
+pub(crate) fn detached_signature(cms: &[u8]) -> Option<Vec<(String, String)>> {
+    Some(signer(&signed_data(cms)?))
+}
+
+/// Name the signer along with the certificate issued to it.
+fn signer(signed_data: &SignedData) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    let signer = match &signed_data.signer {
+        Some(signer) => signer,
+        None => return entries,
+    };
+
+    match signed_data.certificates.iter().find_map(|cert| certificate_of(cert, signer)) {
+        Some(cert) => {
+            entries.push(("Signer".to_string(), cert.subject));
+            entries.push(("Issuer".to_string(), cert.issuer));
+            entries.push(("Serial number".to_string(), hex(signer.serial)));
+            entries.push(("Valid from".to_string(), cert.not_before));
+            entries.push(("Valid until".to_string(), cert.not_after));
+        }
+        None => {
+            entries.push(("Issuer".to_string(), name(signer.issuer)));
+            entries.push(("Serial number".to_string(), hex(signer.serial)));
+        }
+    }
+
+    if let Some(time) = &signer.signing_time {
+        entries.push(("Signing time".to_string(), time.clone()));
+    }
+
+    if let Some(time) = &signer.timestamp {
+        entries.push(("Timestamp".to_string(), time.clone()));
+    }
+
+    entries
+}
+
+struct SignedData<'a> {
+    /// The signed content, with any `OCTET STRING` wrapping it removed.
+    content: Option<Der<'a>>,
+    /// Certificates as DER encoded `TBSCertificate`'s.
+    certificates: Vec<&'a [u8]>,
+    signer: Option<SignerInfo<'a>>,
+}
+
+struct SignerInfo<'a> {
+    issuer: &'a [u8],
+    serial: &'a [u8],
+    signing_time: Option<String>,
+    timestamp: Option<String>,
+}
+
+struct Certificate {
+    subject: String,
+    issuer: String,
+    not_before: String,
+    not_after: String,
+}
+
+/// Parse a PKCS#7 `ContentInfo` holding `SignedData`.
+fn signed_data(bytes: &[u8]) -> Option<SignedData<'_>> {
+    let (content_info, _) = next(bytes)?;
+    let mut content_info = children(expect(content_info, SEQUENCE)?);
+    if expect(content_info.next()?, OBJECT_IDENTIFIER)? != OID_SIGNED_DATA {
+        return None;
+    }
+
+    let signed_data = children(expect(content_info.next()?, CONTEXT_0)?).next()?;
+    let mut signed_data = children(expect(signed_data, SEQUENCE)?);
+    expect(signed_data.next()?, INTEGER)?;
+    expect(signed_data.next()?, SET)?;
+
+    let mut encapsulated = children(expect(signed_data.next()?, SEQUENCE)?);
+    encapsulated.next()?;
+
+    // Detached signatures leave out the content.
+    let content = match encapsulated.next() {
+        Some(content) => {
+            let content = children(expect(content, CONTEXT_0)?).next()?;
+
+            // CMS wraps the content in an `OCTET STRING` where PKCS#7 embeds it directly.
+            match content.tag {
+                OCTET_STRING => Some(next(content.content)?.0),
+                _ => Some(content),
+            }
+        }
+        None => None,
+    };
+
+    let mut certificates = Vec::new();
+    let mut signer = None;
+    for value in signed_data {
+        match value.tag {
+            CONTEXT_0 => {
+                for certificate in children(value.content) {
+                    let certificate = expect(certificate, SEQUENCE)?;
+                    let tbs = expect(children(certificate).next()?, SEQUENCE)?;
+                    certificates.push(tbs);
+                }
+            }
+            // Authenticode only allows a single signer, any others are nested signatures.
+            SET => signer = children(value.content).next().and_then(signer_info),
+            _ => {}
+        }
+    }
+
+    Some(SignedData {
+        content,
+        certificates,
+        signer,
+    })
+}
+
+fn signer_info(value: Der<'_>) -> Option<SignerInfo<'_>> {
+    let mut fields = children(expect(value, SEQUENCE)?);
+    expect(fields.next()?, INTEGER)?;
+
+    let mut issuer_and_serial = children(expect(fields.next()?, SEQUENCE)?);
+    let issuer = expect(issuer_and_serial.next()?, SEQUENCE)?;
+    let serial = expect(issuer_and_serial.next()?, INTEGER)?;
+
+    let mut signer = SignerInfo {
+        issuer,
+        serial,
+        signing_time: None,
+        timestamp: None,
+    };
+
+    for value in fields {
+        let is_signed = match value.tag {
+            CONTEXT_0 => true,
+            CONTEXT_1 => false,
+            _ => continue,
+        };
+
+        for attribute in children(value.content) {
+            let mut attribute = children(attribute.content);
+            let kind = match attribute.next() {
+                Some(kind) => kind.content,
+                None => continue,
+            };
+            let value = match attribute.next().and_then(|values| children(values.content).next()) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            match kind {
+                OID_SIGNING_TIME if is_signed => signer.signing_time = time(value),
+                // The timestamp authority's signing time is the time it vouches for.
+                OID_COUNTER_SIGNATURE => {
+                    signer.timestamp = signer_info(value).and_then(|counter| counter.signing_time);
+                }
+                OID_RFC3161_TIMESTAMP => signer.timestamp = tst_info_time(value),
+                _ => {}
+            }
+        }
+    }
+
+    Some(signer)
+}
+
+/// Read the `genTime` of an RFC 3161 timestamp token.
+fn tst_info_time(token: Der) -> Option<String> {
+    let signed_data = signed_data(token.raw)?;
+    let mut tst_info = children(expect(signed_data.content?, SEQUENCE)?);
+
+    // Skip the version, policy, message imprint and serial number.
+    tst_info.nth(3)?;
+    time(tst_info.next()?)
+}
+
+/// Find the certificate issued to the signer.
+fn certificate_of(tbs: &[u8], signer: &SignerInfo) -> Option<Certificate> {
+    let mut fields = children(tbs).peekable();
+
+    // The version is optional.
+    if fields.peek()?.tag == CONTEXT_0 {
+        fields.next();
+    }
+
+    let serial = expect(fields.next()?, INTEGER)?;
+    fields.next()?;
+    let issuer = expect(fields.next()?, SEQUENCE)?;
+    if serial != signer.serial || issuer != signer.issuer {
+        return None;
+    }
+
+    let mut validity = children(expect(fields.next()?, SEQUENCE)?);
+    let not_before = time(validity.next()?)?;
+    let not_after = time(validity.next()?)?;
+    let subject = expect(fields.next()?, SEQUENCE)?;
+
+    Some(Certificate {
+        subject: name(subject),
+        issuer: name(issuer),
+        not_before,
+        not_after,
+    })
+}
+
+/// Format an X.501 `Name` such as `CN=Microsoft Corporation, O=Microsoft Corporation, C=US`.
+fn name(rdn_sequence: &[u8]) -> String {
+    let mut parts = Vec::new();
+
+    for rdn in children(rdn_sequence) {
+        for attribute in children(rdn.content) {
+            let mut attribute = children(attribute.content);
+            let (kind, value) = match (attribute.next(), attribute.next()) {
+                (Some(kind), Some(value)) => (kind.content, value),
+                _ => continue,
+            };
+
+            let kind = match kind {
+                [0x55, 0x04, 0x03] => "CN".to_string(),
+                [0x55, 0x04, 0x06] => "C".to_string(),
+                [0x55, 0x04, 0x07] => "L".to_string(),
+                [0x55, 0x04, 0x08] => "ST".to_string(),
+                [0x55, 0x04, 0x0a] => "O".to_string(),
+                [0x55, 0x04, 0x0b] => "OU".to_string(),
+                OID_EMAIL => "E".to_string(),
+                _ => hex(kind),
+            };
+
+            let value = match value.tag {
+                BMP_STRING => {
+                    let units = value.content.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]]));
+                    String::from_utf16_lossy(&units.collect::<Vec<u16>>())
+                }
+                UTF8_STRING | 0x13 | 0x14 | 0x16 => String::from_utf8_lossy(value.content).to_string(),
+                _ => hex(value.content),
+            };
+
+            parts.push(format!("{kind}={value}"));
+        }
+    }
+
+    parts.join(", ")
+}
+
+/// Format a `UTCTime` or `GeneralizedTime` as `YYYY-MM-DD HH:MM:SS UTC`.
+fn time(value: Der) -> Option<String> {
+    let time = std::str::from_utf8(value.content).ok()?;
+    let time = match value.tag {
+        // Two digit years from 50 onwards are in the 20th century.
+        UTC_TIME => {
+            let year: u32 = time.get(..2)?.parse().ok()?;
+            let century = if year >= 50 { "19" } else { "20" };
+            format!("{century}{time}")
+        }
+        GENERALIZED_TIME => time.to_string(),
+        _ => return None,
+    };
+
+    if !time.get(..14)?.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(format!(
+        "{}-{}-{} {}:{}:{} UTC",
+        &time[..4],
+        &time[4..6],
+        &time[6..8],
+        &time[8..10],
+        &time[10..12],
+        &time[12..14]
+    ))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// A DER encoded value.
+#[derive(Clone, Copy)]
+struct Der<'a> {
+    tag: u8,
+    content: &'a [u8],
+    /// The whole encoding, including the tag and length.
+    raw: &'a [u8],
+}
+
+/// Split the first DER encoded value off of `bytes`.
+fn next(bytes: &[u8]) -> Option<(Der<'_>, &[u8])> {
+    let tag = *bytes.first()?;
+    let first = *bytes.get(1)?;
+
+    let (len, header) = match first {
+        0..=0x7f => (first as usize, 2),
+        // Indefinite lengths aren't allowed in DER.
+        0x81..=0x84 => {
+            let count = (first & 0x7f) as usize;
+            let len = bytes.get(2..2 + count)?;
+            let len = len.iter().fold(0, |len, byte| len << 8 | *byte as usize);
+            (len, 2 + count)
+        }
+        _ => return None,
+    };
+
+    let end = header.checked_add(len)?;
+    let value = Der {
+        tag,
+        content: bytes.get(header..end)?,
+        raw: &bytes[..end],
+    };
+
+    Some((value, &bytes[end..]))
+}
+
+/// Values within a constructed value.
+fn children(content: &[u8]) -> impl Iterator<Item = Der<'_>> {
+    let mut bytes = content;
+    std::iter::from_fn(move || {
+        let (value, rest) = next(bytes)?;
+        bytes = rest;
+        Some(value)
+    })
+}
+
+/// Content of `value` if it has the expected tag.
+fn expect<'a>(value: Der<'a>, tag: u8) -> Option<&'a [u8]> {
+    match value.tag == tag {
+        true => Some(value.content),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `SpcIndirectDataContent` holding the SHA-256 of [`image`], signed by openssl with a
+    /// self-signed certificate for `O=bite, CN=Test Signer` with serial number 0x1234.
+    const SIGNATURE: &[u8] = include_bytes!("../fixtures/authenticode.p7s");
+
+    /// Image with the checksum at 0x40, the certificate directory at 0x80 and the
+    /// certificate table appended.
+    fn image() -> (Vec<u8>, Exclusions) {
+        let mut data: Vec<u8> = (0..=255).collect();
+        data[..2].copy_from_slice(b"MZ");
+
+        let start = data.len();
+        data.extend_from_slice(&(8 + SIGNATURE.len() as u32).to_le_bytes());
+        data.extend_from_slice(&0x0200u16.to_le_bytes());
+        data.extend_from_slice(&WIN_CERT_TYPE_PKCS_SIGNED_DATA.to_le_bytes());
+        data.extend_from_slice(SIGNATURE);
+        data.resize((data.len() + 7) & !7, 0);
+
+        let table = start..data.len();
+        (
+            data,
+            Exclusions {
+                checksum: 0x40,
+                directory: 0x80,
+                table,
+            },
+        )
+    }
+
+    fn entry<'a>(info: &'a [FileInfo], key: &str) -> Option<&'a str> {
+        let entries = &info.first()?.entries;
+        entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn signed_image() {
+        let (data, exclusions) = image();
+        let info = parse(&data, exclusions);
+
+        assert_eq!(info.len(), 1);
+        assert_eq!(entry(&info, "Revision"), Some("2.0"));
+        assert_eq!(entry(&info, "Type"), Some("WIN_CERT_TYPE_PKCS_SIGNED_DATA"));
+        assert_eq!(entry(&info, "Digest algorithm"), Some("SHA-256"));
+        assert_eq!(
+            entry(&info, "Embedded hash"),
+            Some("40c2416cc8eabeabd76b80e6db3462fe935daa300ec84d6adf9062d3fd172599")
+        );
+        assert!(entry(&info, "Computed hash").unwrap().ends_with("(matches)"));
+        assert_eq!(entry(&info, "Signer"), Some("O=bite, CN=Test Signer"));
+        assert_eq!(entry(&info, "Issuer"), Some("O=bite, CN=Test Signer"));
+        assert_eq!(entry(&info, "Serial number"), Some("1234"));
+        assert!(entry(&info, "Signing time").unwrap().ends_with(" UTC"));
+    }
+
+    #[test]
+    fn modified_image() {
+        // The checksum isn't covered by the signature.
+        let (mut data, exclusions) = image();
+        data[0x40] ^= 0xff;
+        let info = parse(&data, exclusions);
+        assert!(entry(&info, "Computed hash").unwrap().ends_with("(matches)"));
+
+        let (mut data, exclusions) = image();
+        data[0x10] ^= 0xff;
+        let info = parse(&data, exclusions);
+        assert!(entry(&info, "Computed hash").unwrap().contains("mismatch"));
+    }
+
+    #[test]
+    fn der_lengths() {
+        let (value, rest) = next(&[OCTET_STRING, 0x02, 0xaa, 0xbb, 0xcc]).unwrap();
+        assert_eq!(
+            (value.tag, value.content, rest),
+            (OCTET_STRING, &[0xaa, 0xbb][..], &[0xcc][..])
+        );
+
+        let mut long = vec![SEQUENCE, 0x82, 0x01, 0x00];
+        long.extend_from_slice(&[0; 0x100]);
+        let (value, rest) = next(&long).unwrap();
+        assert_eq!(
+            (value.content.len(), value.raw.len(), rest.len()),
+            (0x100, 0x104, 0)
+        );
+
+        // Truncated, indefinite and overly long lengths.
+        assert!(next(&[SEQUENCE, 0x03, 0x00]).is_none());
+        assert!(next(&[SEQUENCE, 0x80, 0x00, 0x00]).is_none());
+        assert!(next(&[SEQUENCE, 0x85, 0, 0, 0, 0, 1]).is_none());
+        assert!(next(&[SEQUENCE]).is_none());
+    }
+
+    #[test]
+    fn names_and_times() {
+        // SET { SEQUENCE { OID commonName, BMPString "Hi" } }
+        let rdn = [
+            0x31, 0x0d, 0x30, 0x0b, 0x06, 0x03, 0x55, 0x04, 0x03, 0x1e, 0x04, 0x00, b'H', 0x00,
+            b'i',
+        ];
+        assert_eq!(name(&rdn), "CN=Hi");
+
+        let utc = |time: &'static [u8]| Der {
+            tag: UTC_TIME,
+            content: time,
+            raw: time,
+        };
+        assert_eq!(
+            time(utc(b"991231235959Z")).as_deref(),
+            Some("1999-12-31 23:59:59 UTC")
+        );
+        assert_eq!(
+            time(utc(b"240102030405Z")).as_deref(),
+            Some("2024-01-02 03:04:05 UTC")
+        );
+        assert_eq!(time(utc(b"24010203Z")), None);
+    }
+}
@@ -0,0 +1,850 @@
+//! Decoding of the code signature `LC_CODE_SIGNATURE` points to.
+//!
+//! The signature is a SuperBlob indexing other blobs: a CodeDirectory with a hash of every page,
+//! the requirements the signer imposes, entitlements and a CMS signature over the CodeDirectory.
+//! Unlike the rest of the Mach-O, everything in it is big endian. The layouts are described in
+//! <https://github.com/apple-oss-distributions/xnu/blob/main/osfmk/kern/cs_blobs.h>.
+
+use crate::FileInfo;
+use sha1::Sha1;
+use sha2::digest::DynDigest;
+use sha2::Sha256;
+
+const CSMAGIC_REQUIREMENT: u32 = 0xfade0c00;
+const CSMAGIC_REQUIREMENTS: u32 = 0xfade0c01;
+const CSMAGIC_CODEDIRECTORY: u32 = 0xfade0c02;
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade0cc0;
+const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xfade7171;
+const CSMAGIC_EMBEDDED_DER_ENTITLEMENTS: u32 = 0xfade7172;
+const CSMAGIC_BLOBWRAPPER: u32 = 0xfade0b01;
+
+const CSSLOT_REQUIREMENTS: u32 = 2;
+const CSSLOT_ENTITLEMENTS: u32 = 5;
+const CSSLOT_DER_ENTITLEMENTS: u32 = 7;
+
+/// Requirements are an expression tree that could be nested arbitrarily deep.
+const MAX_DEPTH: usize = 64;
+
+/// Summarize the SuperBlob at `signature`, `data` being the Mach-O the code hashes cover.
+pub(crate) fn parse(data: &[u8], signature: &[u8]) -> Vec<FileInfo> {
+    let mut info = Vec::new();
+
+    if read_u32(signature, 0) != Some(CSMAGIC_EMBEDDED_SIGNATURE) {
+        info.push(FileInfo {
+            title: "Code signature",
+            entries: vec![(
+                "Error".to_string(),
+                "missing embedded signature magic".to_string(),
+            )],
+        });
+        return info;
+    }
+
+    let count = read_u32(signature, 8).unwrap_or(0) as usize;
+    let mut blobs = Vec::new();
+    for idx in 0..count {
+        let (slot, offset) = match (
+            read_u32(signature, 12 + idx * 8),
+            read_u32(signature, 16 + idx * 8),
+        ) {
+            (Some(slot), Some(offset)) => (slot, offset as usize),
+            _ => break,
+        };
+
+        let blob = match blob(signature, offset) {
+            Some(blob) => blob,
+            None => continue,
+        };
+
+        blobs.push((slot, blob));
+    }
+
+    let mut entries = Vec::new();
+    for (slot, blob) in blobs.iter() {
+        let magic = read_u32(blob, 0).unwrap_or(0);
+        let kind = match magic {
+            CSMAGIC_CODEDIRECTORY => "code directory".to_string(),
+            CSMAGIC_REQUIREMENTS => "requirements".to_string(),
+            CSMAGIC_EMBEDDED_ENTITLEMENTS => "entitlements".to_string(),
+            CSMAGIC_EMBEDDED_DER_ENTITLEMENTS => "DER entitlements".to_string(),
+            CSMAGIC_BLOBWRAPPER => "CMS signature".to_string(),
+            _ => format!("unknown magic {magic:#x}"),
+        };
+
+        entries.push((
+            format!("Slot {slot:#x}"),
+            format!("{kind}, {} bytes", blob.len()),
+        ));
+    }
+    info.push(FileInfo {
+        title: "Code signature",
+        entries,
+    });
+
+    for (_, blob) in blobs.iter() {
+        let magic = read_u32(blob, 0).unwrap_or(0);
+        match magic {
+            CSMAGIC_CODEDIRECTORY => info.push(code_directory(data, blob, &blobs)),
+            CSMAGIC_REQUIREMENTS => info.push(requirements(blob)),
+            CSMAGIC_EMBEDDED_ENTITLEMENTS => {
+                let xml = String::from_utf8_lossy(&blob[8..]);
+                let entries = match Plist::new(&xml).parse() {
+                    Some(entries) => entries,
+                    None => vec![("Error".to_string(), "malformed property list".to_string())],
+                };
+
+                info.push(FileInfo {
+                    title: "Entitlements",
+                    entries,
+                });
+            }
+            CSMAGIC_BLOBWRAPPER => {
+                // Ad-hoc signatures have an empty wrapper.
+                let entries = match &blob[8..] {
+                    [] => vec![("Signer".to_string(), "none, ad-hoc signed".to_string())],
+                    cms => match crate::authenticode::detached_signature(cms) {
+                        Some(entries) => entries,
+                        None => vec![("Error".to_string(), "malformed CMS data".to_string())],
+                    },
+                };
+
+                info.push(FileInfo {
+                    title: "CMS signature",
+                    entries,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    info
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+/// A blob at `offset` whose length is stored right after it's magic.
+fn blob(bytes: &[u8], offset: usize) -> Option<&[u8]> {
+    let len = read_u32(bytes, offset.checked_add(4)?)? as usize;
+    if len < 8 {
+        return None;
+    }
+
+    bytes.get(offset..offset.checked_add(len)?)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Hash `bytes` with the algorithm a CodeDirectory names, truncated to it's hash size.
+fn hash(kind: u8, size: usize, bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut digest: Box<dyn DynDigest> = match kind {
+        1 => Box::new(Sha1::default()),
+        2 | 3 => Box::new(Sha256::default()),
+        _ => return None,
+    };
+
+    digest.update(bytes);
+    let mut hash = digest.finalize().into_vec();
+    hash.truncate(size);
+    Some(hash)
+}
+
+/// Describe a CodeDirectory and check it's hashes against the file.
+fn code_directory(data: &[u8], cd: &[u8], blobs: &[(u32, &[u8])]) -> FileInfo {
+    let mut entries = Vec::new();
+    let field = |offset| read_u32(cd, offset).unwrap_or(0);
+    let byte = |offset: usize| cd.get(offset).copied().unwrap_or(0);
+    let string = |offset: usize| {
+        let bytes = cd.get(offset..).unwrap_or(&[]);
+        let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..len]).to_string()
+    };
+
+    let version = field(8);
+    let flags = field(12);
+    let hash_offset = field(16) as usize;
+    let special_slots = field(24) as usize;
+    let code_slots = field(28) as usize;
+    let mut code_limit = field(32) as u64;
+    let hash_size = byte(36) as usize;
+    let hash_type = byte(37);
+    let platform = byte(38);
+    let page_size = match byte(39) {
+        0 => 0,
+        shift => 1u64.checked_shl(shift as u32).unwrap_or(0),
+    };
+
+    entries.push(("Version".to_string(), format!("{version:#x}")));
+    entries.push(("Identifier".to_string(), string(field(20) as usize)));
+    if version >= 0x20200 && field(48) != 0 {
+        entries.push(("Team identifier".to_string(), string(field(48) as usize)));
+    }
+
+    const FLAGS: [(u32, &str); 10] = [
+        (0x1, "host"),
+        (0x2, "adhoc"),
+        (0x100, "hard"),
+        (0x200, "kill"),
+        (0x400, "expires"),
+        (0x800, "restrict"),
+        (0x1000, "enforcement"),
+        (0x2000, "library-validation"),
+        (0x10000, "runtime"),
+        (0x20000, "linker-signed"),
+    ];
+    let names: Vec<&str> = FLAGS
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    entries.push((
+        "Flags".to_string(),
+        format!("{flags:#x} ({})", names.join(", ")),
+    ));
+
+    let algorithm = match hash_type {
+        1 => "SHA-1",
+        2 => "SHA-256",
+        3 => "SHA-256 (truncated)",
+        4 => "SHA-384",
+        _ => "unknown",
+    };
+    entries.push((
+        "Hash type".to_string(),
+        format!("{algorithm}, {hash_size} bytes"),
+    ));
+    if platform != 0 {
+        entries.push(("Platform".to_string(), platform.to_string()));
+    }
+
+    if version >= 0x20300 {
+        let limit64 = read_u32(cd, 56).unwrap_or(0) as u64;
+        let limit64 = limit64 << 32 | read_u32(cd, 60).unwrap_or(0) as u64;
+        if limit64 != 0 {
+            code_limit = limit64;
+        }
+    }
+    entries.push(("Code limit".to_string(), format!("{code_limit:#x}")));
+    entries.push(("Page size".to_string(), format!("{page_size:#x}")));
+
+    if version >= 0x20400 {
+        let base = (field(64) as u64) << 32 | field(68) as u64;
+        let limit = (field(72) as u64) << 32 | field(76) as u64;
+        let flags = (field(80) as u64) << 32 | field(84) as u64;
+        entries.push((
+            "Executable segment".to_string(),
+            format!("base {base:#x}, limit {limit:#x}, flags {flags:#x}"),
+        ));
+    }
+
+    // The CDHash identifying the code is the hash of this blob cut down to 20 bytes.
+    match hash(hash_type, 20, cd) {
+        Some(cdhash) => entries.push(("CDHash".to_string(), hex(&cdhash))),
+        None => entries.push(("CDHash".to_string(), "unsupported hash type".to_string())),
+    }
+
+    // Special slots are stored in reverse order in front of the code slots.
+    for (slot, name) in [
+        (CSSLOT_REQUIREMENTS, "Requirements hash"),
+        (CSSLOT_ENTITLEMENTS, "Entitlements hash"),
+        (CSSLOT_DER_ENTITLEMENTS, "DER entitlements hash"),
+    ] {
+        if slot as usize > special_slots {
+            continue;
+        }
+
+        let start = match hash_offset.checked_sub(slot as usize * hash_size) {
+            Some(start) => start,
+            None => continue,
+        };
+        let stored = match cd.get(start..start + hash_size) {
+            Some(stored) if stored.iter().any(|&b| b != 0) => stored,
+            _ => continue,
+        };
+
+        let computed = blobs
+            .iter()
+            .find(|(idx, _)| *idx == slot)
+            .and_then(|(_, blob)| hash(hash_type, hash_size, blob));
+        let status = match computed {
+            Some(computed) if computed == stored => "matches",
+            Some(_) => "mismatch",
+            None => "blob missing",
+        };
+        entries.push((name.to_string(), format!("{} ({status})", hex(stored))));
+    }
+
+    // Every page up to the code limit is hashed, the last one might be partial. The slot count
+    // is only trusted as far as the code limit and the hashes stored in the blob go.
+    let covered = match page_size {
+        0 => 0,
+        page_size => code_limit.div_ceil(page_size),
+    };
+    let stored = match hash_size {
+        0 => 0,
+        hash_size => cd.len().saturating_sub(hash_offset) / hash_size,
+    };
+    if code_slots as u64 > covered || code_slots > stored {
+        entries.push((
+            "Code pages".to_string(),
+            format!("{code_slots} (more than the code directory covers)"),
+        ));
+    } else if page_size != 0 && hash_size != 0 {
+        let mut matching = 0;
+        for idx in 0..code_slots {
+            let start = idx as u64 * page_size;
+            let end = std::cmp::min(start + page_size, code_limit);
+            let page = data.get(start as usize..end as usize);
+            let stored = cd.get(hash_offset + idx * hash_size..hash_offset + (idx + 1) * hash_size);
+
+            if let (Some(page), Some(stored)) = (page, stored) {
+                if hash(hash_type, hash_size, page).as_deref() == Some(stored) {
+                    matching += 1;
+                }
+            }
+        }
+
+        let status = match matching == code_slots {
+            true => "all match".to_string(),
+            false => format!("{} modified after signing", code_slots - matching),
+        };
+        entries.push(("Code pages".to_string(), format!("{code_slots} ({status})")));
+    }
+
+    FileInfo {
+        title: "Code directory",
+        entries,
+    }
+}
+
+/// Decompile every requirement in a requirement set.
+fn requirements(set: &[u8]) -> FileInfo {
+    let mut entries = Vec::new();
+    let count = read_u32(set, 8).unwrap_or(0) as usize;
+
+    for idx in 0..count {
+        let (kind, offset) = match (read_u32(set, 12 + idx * 8), read_u32(set, 16 + idx * 8)) {
+            (Some(kind), Some(offset)) => (kind, offset as usize),
+            _ => break,
+        };
+
+        let kind = match kind {
+            1 => "Host".to_string(),
+            2 => "Guest".to_string(),
+            3 => "Designated".to_string(),
+            4 => "Library".to_string(),
+            5 => "Plugin".to_string(),
+            _ => format!("Type {kind}"),
+        };
+
+        let requirement = match blob(set, offset) {
+            Some(requirement) if read_u32(requirement, 0) == Some(CSMAGIC_REQUIREMENT) => {
+                requirement
+            }
+            _ => {
+                entries.push((kind, "malformed requirement".to_string()));
+                continue;
+            }
+        };
+
+        // Only expressions are defined as a kind of requirement.
+        let expression = match read_u32(requirement, 8) {
+            Some(1) => Requirement::new(&requirement[12..]).expression().map(|(expr, _)| expr),
+            _ => None,
+        };
+
+        entries.push((
+            kind,
+            expression.unwrap_or_else(|| "unsupported requirement".to_string()),
+        ));
+    }
+
+    FileInfo {
+        title: "Requirements",
+        entries,
+    }
+}
+
+/// Binding strength of a requirement expression, used to place parentheses.
+#[derive(PartialEq, PartialOrd)]
+enum Precedence {
+    Or,
+    And,
+    Primary,
+}
+
+/// Decompiler of the binary requirement language back into it's textual form.
+struct Requirement<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    depth: usize,
+}
+
+impl<'a> Requirement<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            offset: 0,
+            depth: 0,
+        }
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let value = read_u32(self.bytes, self.offset)?;
+        self.offset += 4;
+        Some(value)
+    }
+
+    /// Length prefixed data, padded to 4 bytes.
+    fn data(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        let data = self.bytes.get(self.offset..self.offset.checked_add(len)?)?;
+        self.offset += (len + 3) & !3;
+        Some(data)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        Some(format!("{:?}", String::from_utf8_lossy(self.data()?)))
+    }
+
+    fn slot(&mut self) -> Option<String> {
+        Some(match self.u32()? as i32 {
+            0 => "leaf".to_string(),
+            -1 => "root".to_string(),
+            slot => slot.to_string(),
+        })
+    }
+
+    fn oid(&mut self) -> Option<String> {
+        let der = self.data()?;
+        let (first, rest) = der.split_first()?;
+        let mut arcs = vec![(first / 40) as u64, (first % 40) as u64];
+
+        let mut arc = 0u64;
+        for byte in rest {
+            arc = arc.checked_shl(7)? | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0 {
+                arcs.push(arc);
+                arc = 0;
+            }
+        }
+
+        Some(arcs.iter().map(|arc| arc.to_string()).collect::<Vec<_>>().join("."))
+    }
+
+    /// A match operation that follows a field.
+    fn matcher(&mut self) -> Option<String> {
+        let op = self.u32()?;
+        Some(match op {
+            0 => " /* exists */".to_string(),
+            1 => format!(" = {}", self.string()?),
+            2 => format!(" ~ {}", self.string()?),
+            3 => {
+                let value = self.string()?;
+                format!(" = {}*\"", &value[..value.len() - 1])
+            }
+            4 => {
+                let value = self.string()?;
+                format!(" = \"*{}", &value[1..])
+            }
+            5 => format!(" < {}", self.string()?),
+            6 => format!(" > {}", self.string()?),
+            7 => format!(" <= {}", self.string()?),
+            8 => format!(" >= {}", self.string()?),
+            9..=13 => {
+                let op = ["=", "<", ">", "<=", ">="][op as usize - 9];
+                let time = self.data()?;
+                let time = i64::from_be_bytes(time.try_into().ok()?);
+                format!(" {op} timestamp {time}")
+            }
+            14 => " absent".to_string(),
+            _ => return None,
+        })
+    }
+
+    fn expression(&mut self) -> Option<(String, Precedence)> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+
+        self.depth += 1;
+        let expr = self.op();
+        self.depth -= 1;
+        expr
+    }
+
+    fn op(&mut self) -> Option<(String, Precedence)> {
+        // The upper byte holds flags on how to treat unknown operations.
+        let op = self.u32()? & 0x00ffffff;
+
+        let expr = match op {
+            0 => "never".to_string(),
+            1 => "always".to_string(),
+            2 => format!("identifier {}", self.string()?),
+            3 => "anchor apple".to_string(),
+            4 => {
+                let slot = self.slot()?;
+                format!("certificate {slot} = H\"{}\"", hex(self.data()?))
+            }
+            5 => {
+                let key = self.string()?;
+                format!("info[{key}] = {}", self.string()?)
+            }
+            6 | 7 => {
+                let (separator, precedence) = match op {
+                    6 => (" and ", Precedence::And),
+                    _ => (" or ", Precedence::Or),
+                };
+
+                let mut operands = Vec::new();
+                for _ in 0..2 {
+                    let (expr, inner) = self.expression()?;
+                    operands.push(match inner < precedence {
+                        true => format!("({expr})"),
+                        false => expr,
+                    });
+                }
+
+                return Some((operands.join(separator), precedence));
+            }
+            8 => format!("cdhash H\"{}\"", hex(self.data()?)),
+            9 => {
+                let (expr, inner) = self.expression()?;
+                match inner {
+                    Precedence::Primary => format!("! {expr}"),
+                    _ => format!("! ({expr})"),
+                }
+            }
+            10 => {
+                let key = self.string()?;
+                format!("info[{key}]{}", self.matcher()?)
+            }
+            11 => {
+                let slot = self.slot()?;
+                let field = String::from_utf8_lossy(self.data()?).to_string();
+                format!("certificate {slot}[{field}]{}", self.matcher()?)
+            }
+            12 => format!("certificate {} trusted", self.slot()?),
+            13 => "anchor trusted".to_string(),
+            14 | 17 | 22 => {
+                let slot = self.slot()?;
+                let kind = match op {
+                    14 => "field",
+                    17 => "policy",
+                    _ => "timestamp",
+                };
+                let oid = self.oid()?;
+                format!("certificate {slot}[{kind}.{oid}]{}", self.matcher()?)
+            }
+            15 => "anchor apple generic".to_string(),
+            16 => {
+                let key = self.string()?;
+                format!("entitlement[{key}]{}", self.matcher()?)
+            }
+            18 => format!("anchor apple {}", String::from_utf8_lossy(self.data()?)),
+            19 => format!("({})", String::from_utf8_lossy(self.data()?)),
+            20 => format!("platform = {}", self.u32()?),
+            21 => "notarized".to_string(),
+            23 => "legacy".to_string(),
+            _ => return None,
+        };
+
+        Some((expr, Precedence::Primary))
+    }
+}
+
+/// Reader of the subset of XML property lists that entitlements use.
+struct Plist<'a> {
+    xml: &'a str,
+    offset: usize,
+    depth: usize,
+}
+
+impl<'a> Plist<'a> {
+    fn new(xml: &'a str) -> Self {
+        Self {
+            xml,
+            offset: 0,
+            depth: 0,
+        }
+    }
+
+    /// The top level dictionary as key value pairs.
+    fn parse(&mut self) -> Option<Vec<(String, String)>> {
+        let start = self.xml.find("<dict")?;
+        self.offset = start;
+
+        let mut entries = Vec::new();
+        match self.tag()? {
+            "dict/" => return Some(entries),
+            "dict" => {}
+            _ => return None,
+        }
+
+        loop {
+            match self.tag()? {
+                "/dict" => return Some(entries),
+                "key" => {
+                    let key = self.text("</key>")?;
+                    let value = self.value()?;
+                    entries.push((key, value));
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Skip to the next tag and return what's inside of the angle brackets.
+    fn tag(&mut self) -> Option<&'a str> {
+        let rest = &self.xml[self.offset..];
+        let start = rest.find('<')?;
+
+        // Anything but whitespace between tags is malformed.
+        if !rest[..start].trim().is_empty() {
+            return None;
+        }
+
+        let end = start + rest[start..].find('>')?;
+        self.offset += end + 1;
+        Some(rest[start + 1..end].trim())
+    }
+
+    /// Text up to a closing tag, with entities replaced.
+    fn text(&mut self, close: &str) -> Option<String> {
+        let rest = &self.xml[self.offset..];
+        let end = rest.find(close)?;
+        self.offset += end + close.len();
+
+        Some(
+            rest[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        )
+    }
+
+    fn value(&mut self) -> Option<String> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+
+        self.depth += 1;
+        let value = self.element();
+        self.depth -= 1;
+        value
+    }
+
+    fn element(&mut self) -> Option<String> {
+        Some(match self.tag()? {
+            "true/" => "true".to_string(),
+            "false/" => "false".to_string(),
+            "string/" => String::new(),
+            "array/" => "[]".to_string(),
+            "dict/" => "{}".to_string(),
+            "string" => format!("{:?}", self.text("</string>")?),
+            "integer" => self.text("</integer>")?.trim().to_string(),
+            "real" => self.text("</real>")?.trim().to_string(),
+            "date" => self.text("</date>")?.trim().to_string(),
+            "data" => format!(
+                "<{}>",
+                self.text("</data>")?.split_whitespace().collect::<String>()
+            ),
+            "array" => {
+                let mut values = Vec::new();
+                loop {
+                    let offset = self.offset;
+                    if self.tag()? == "/array" {
+                        break;
+                    }
+
+                    self.offset = offset;
+                    values.push(self.value()?);
+                }
+                format!("[{}]", values.join(", "))
+            }
+            "dict" => {
+                let mut values = Vec::new();
+                loop {
+                    match self.tag()? {
+                        "/dict" => break,
+                        "key" => {
+                            let key = self.text("</key>")?;
+                            values.push(format!("{key:?}: {}", self.value()?));
+                        }
+                        _ => return None,
+                    }
+                }
+                format!("{{{}}}", values.join(", "))
+            }
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn be(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+
+    /// Length prefixed data as an operand, padded to 4 bytes.
+    fn data(bytes: &[u8]) -> Vec<u8> {
+        let mut data = be(&[bytes.len() as u32]);
+        data.extend_from_slice(bytes);
+        data.resize((data.len() + 3) & !3, 0);
+        data
+    }
+
+    /// A requirement set holding a single designated requirement.
+    fn designated(expr: &[u8]) -> Vec<u8> {
+        let mut requirement = be(&[CSMAGIC_REQUIREMENT, 12 + expr.len() as u32, 1]);
+        requirement.extend_from_slice(expr);
+
+        let mut set = be(&[
+            CSMAGIC_REQUIREMENTS,
+            20 + requirement.len() as u32,
+            1,
+            3,
+            20,
+        ]);
+        set.extend_from_slice(&requirement);
+        set
+    }
+
+    fn decompile(expr: &[u8]) -> String {
+        let info = requirements(&designated(expr));
+        assert_eq!(info.entries.len(), 1);
+        assert_eq!(info.entries[0].0, "Designated");
+        info.entries[0].1.clone()
+    }
+
+    #[test]
+    fn requirement_precedence() {
+        // (identifier "a" or identifier "b") and ! anchor apple
+        let mut expr = be(&[6, 7, 2]);
+        expr.extend(data(b"a"));
+        expr.extend(be(&[2]));
+        expr.extend(data(b"b"));
+        expr.extend(be(&[9, 3]));
+        assert_eq!(
+            decompile(&expr),
+            "(identifier \"a\" or identifier \"b\") and ! anchor apple"
+        );
+
+        // identifier "a" or identifier "b" and anchor apple generic
+        let mut expr = be(&[7, 2]);
+        expr.extend(data(b"a"));
+        expr.extend(be(&[6, 2]));
+        expr.extend(data(b"b"));
+        expr.extend(be(&[15]));
+        assert_eq!(
+            decompile(&expr),
+            "identifier \"a\" or identifier \"b\" and anchor apple generic"
+        );
+    }
+
+    #[test]
+    fn requirement_certificates() {
+        let oid = [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x63, 0x64, 0x06, 0x02, 0x06];
+        let mut expr = be(&[14, 0]);
+        expr.extend(data(&oid));
+        expr.extend(be(&[0]));
+        assert_eq!(
+            decompile(&expr),
+            "certificate leaf[field.1.2.840.113635.100.6.2.6] /* exists */"
+        );
+
+        let mut expr = be(&[11, 0]);
+        expr.extend(data(b"subject.OU"));
+        expr.extend(be(&[1]));
+        expr.extend(data(b"ABCDE12345"));
+        assert_eq!(
+            decompile(&expr),
+            "certificate leaf[subject.OU] = \"ABCDE12345\""
+        );
+
+        let mut expr = be(&[4, 0xffffffff]);
+        expr.extend(data(&[0xde, 0xad]));
+        assert_eq!(decompile(&expr), "certificate root = H\"dead\"");
+    }
+
+    #[test]
+    fn requirement_malformed() {
+        // Truncated operand and unknown operation.
+        let mut expr = be(&[2, 16]);
+        expr.extend(b"abc");
+        assert_eq!(decompile(&expr), "unsupported requirement");
+        assert_eq!(decompile(&be(&[0xff])), "unsupported requirement");
+
+        // A negation nested deeper than anyone would write.
+        let mut expr = be(&[9; MAX_DEPTH]);
+        expr.extend(be(&[1]));
+        assert_eq!(decompile(&expr), "unsupported requirement");
+    }
+
+    /// CodeDirectory over 0x1800 bytes of code in 0x1000 byte pages.
+    fn code_directory_blob(data: &[u8], code_slots: u32) -> Vec<u8> {
+        let mut cd = be(&[CSMAGIC_CODEDIRECTORY, 0, 0x20001, 0, 48, 44, 0, code_slots]);
+        cd.extend(be(&[data.len() as u32]));
+        cd.extend([32, 2, 0, 12]);
+        cd.extend(be(&[0]));
+        cd.extend(b"id\0\0");
+        for page in data.chunks(0x1000) {
+            cd.extend(hash(2, 32, page).unwrap());
+        }
+        let len = cd.len() as u32;
+        cd[4..8].copy_from_slice(&len.to_be_bytes());
+        cd
+    }
+
+    fn code_pages(data: &[u8], cd: &[u8]) -> String {
+        let info = code_directory(data, cd, &[]);
+        let entry = info.entries.iter().find(|(name, _)| name == "Code pages");
+        entry.unwrap().1.clone()
+    }
+
+    #[test]
+    fn code_pages_hashes() {
+        let mut data: Vec<u8> = (0..0x1800).map(|idx| idx as u8).collect();
+        let cd = code_directory_blob(&data, 2);
+        assert_eq!(code_pages(&data, &cd), "2 (all match)");
+
+        data[0x1400] ^= 1;
+        assert_eq!(code_pages(&data, &cd), "2 (1 modified after signing)");
+    }
+
+    #[test]
+    fn code_pages_forged_count() {
+        let data: Vec<u8> = (0..0x1800).map(|idx| idx as u8).collect();
+
+        // More slots than pages below the code limit.
+        let mut cd = code_directory_blob(&data, 3);
+        cd.extend([0; 32]);
+        assert_eq!(
+            code_pages(&data, &cd),
+            "3 (more than the code directory covers)"
+        );
+
+        // More slots than hashes in the blob.
+        let mut cd = code_directory_blob(&data, 2);
+        cd[32..36].copy_from_slice(&0x2800u32.to_be_bytes());
+        let cd = &cd[..cd.len() - 32];
+        assert_eq!(
+            code_pages(&data, cd),
+            "2 (more than the code directory covers)"
+        );
+        let cd = code_directory_blob(&data, u32::MAX);
+        assert_eq!(
+            code_pages(&data, &cd),
+            "4294967295 (more than the code directory covers)"
+        );
+    }
+}
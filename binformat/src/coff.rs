@@ -0,0 +1,68 @@
+use processor_shared::{Section, SectionKind};
+use object::pe;
+use object::{Object, ObjectSection, SectionFlags};
+
+/// Common COFF dwarf section names I've found so far.
+const DWARF_SECTIONS: [&str; 20] = [
+    ".debug_abbrev",
+    ".debug_addr",
+    ".debug_aranges",
+    ".debug_cu_index",
+    ".debug_frame",
+    ".debug_info",
+    ".debug_line",
+    ".debug_line_str",
+    ".debug_loc",
+    ".debug_loclists",
+    ".debug_macinfo",
+    ".debug_macro",
+    ".debug_pubnames",
+    ".debug_pubtypes",
+    ".debug_ranges",
+    ".debug_rnglists",
+    ".debug_str",
+    ".debug_str_offsets",
+    ".debug_tu_index",
+    ".debug_types",
+];
+
+/// Classify the sections of a COFF object file (.obj).
+///
+/// Unlike PE images, COFF objects don't have their sections mapped anywhere, so all
+/// addresses are zero until [`crate::relocatable`] lays them out.
+pub(crate) fn parse_sections(obj: &object::File) -> Vec<Section> {
+    let mut sections = Vec::new();
+    for section in obj.sections() {
+        let (name, bytes, start, end) = crate::parse_section_generics(&section);
+        let characteristics = match section.flags() {
+            SectionFlags::Coff { characteristics } => characteristics,
+            _ => 0,
+        };
+
+        let (mut kind, ident) = (SectionKind::Raw, "UNKNOWN");
+
+        // Section contains code.
+        if characteristics & pe::IMAGE_SCN_CNT_CODE != 0 {
+            kind = SectionKind::Code;
+        }
+
+        // Section is only there to give the linker information (e.g. .drectve).
+        if characteristics & (pe::IMAGE_SCN_LNK_INFO | pe::IMAGE_SCN_LNK_REMOVE) != 0 {
+            kind = SectionKind::Unloaded;
+        }
+
+        // CodeView debug info.
+        if name.starts_with(".debug$") {
+            kind = SectionKind::Unloaded;
+        }
+
+        // Section contains DWARF debug info.
+        if DWARF_SECTIONS.contains(&name.as_str()) {
+            kind = SectionKind::Debug;
+        }
+
+        sections.push(Section::new(name, ident, kind, bytes, start, end));
+    }
+
+    sections
+}
@@ -1,11 +1,14 @@
+use std::collections::HashMap;
 use std::fmt;
-use crate::{datastructure, RawSymbol};
+use std::mem::size_of;
+use crate::image::Image;
+use crate::{datastructure, plt, rtti, Class, Datastructure, RawSymbol};
 use processor_shared::{AddressMap, Addressed, Section, SectionKind};
 use object::elf;
-use object::read::elf::{ElfFile, FileHeader, SectionHeader};
+use object::read::elf::{Dyn, ElfFile, ElfSymbol, FileHeader, SectionHeader};
 use object::{
-    Endian, Object, ObjectSection, ObjectSymbol, ObjectSymbolTable, RelocationKind,
-    RelocationTarget,
+    Architecture, Endian, Endianness, Object, ObjectSection, ObjectSymbol, ObjectSymbolTable, RelocationKind, RelocationTarget,
+    SectionIndex, SymbolIndex,
 };
 
 pub struct ElfDebugInfo<'data, Elf: FileHeader> {
@@ -15,6 +18,16 @@ pub struct ElfDebugInfo<'data, Elf: FileHeader> {
     pub sections: Vec<Section>,
     /// Any parsed but not yet relocated symbols.
     pub syms: AddressMap<RawSymbol<'data>>,
+    /// Libraries listed as `DT_NEEDED`.
+    dylibs: Vec<&'data str>,
+    /// Library providing each dynamic symbol according to it's GNU symbol version.
+    modules: Vec<Option<&'data str>>,
+    /// Value of `DT_PLTGOT`.
+    pltgot: Option<u64>,
+    /// GOT slots of imported symbols.
+    slots: HashMap<u64, SymbolIndex>,
+    /// C++ classes recovered from runtime type information.
+    pub classes: Vec<Class>,
 }
 
 impl<'data, Elf: FileHeader> ElfDebugInfo<'data, Elf> {
@@ -23,13 +36,138 @@ impl<'data, Elf: FileHeader> ElfDebugInfo<'data, Elf> {
             obj,
             syms: AddressMap::default(),
             sections: Vec::new(),
+            dylibs: Vec::new(),
+            modules: Vec::new(),
+            pltgot: None,
+            slots: HashMap::new(),
+            classes: Vec::new(),
         };
         this.sections = parse_sections(obj);
         this.parse_symbols();
+        // Both are only used to name imports, so a malformed table shouldn't stop the rest.
+        if let Err(err) = this.parse_versions() {
+            log::complex!(
+                w "[elf::parse_versions] ",
+                y err.to_string(),
+                y "."
+            );
+        }
+        if let Err(err) = this.parse_dynamic() {
+            log::complex!(
+                w "[elf::parse_dynamic] ",
+                y err.to_string(),
+                y "."
+            );
+        }
         this.parse_imports();
+        this.parse_plt();
+        this.parse_rtti();
         Ok(this)
     }
 
+    /// Map every dynamic symbol to the library that has to provide it, using the
+    /// `.gnu.version` and `.gnu.version_r` sections.
+    fn parse_versions(&mut self) -> Result<(), object::Error> {
+        let endian = self.obj.endian();
+        let data = self.obj.data();
+        let section_table = self.obj.raw_header().sections(endian, data)?;
+
+        let versyms = match section_table.gnu_versym(endian, data)? {
+            Some((versyms, _)) => versyms,
+            None => return Ok(()),
+        };
+
+        let (mut verneeds, link) = match section_table.gnu_verneed(endian, data)? {
+            Some(verneeds) => verneeds,
+            None => return Ok(()),
+        };
+
+        let strings = section_table.strings(endian, data, link)?;
+
+        // Version index to the library defining that version.
+        let mut libs: Vec<Option<&'data str>> = Vec::new();
+        while let Some((verneed, mut vernauxs)) = verneeds.next()? {
+            let file = std::str::from_utf8(verneed.file(endian, strings)?).ok();
+            while let Some(vernaux) = vernauxs.next()? {
+                let idx = (vernaux.vna_other.get(endian) & elf::VERSYM_VERSION) as usize;
+                if libs.len() <= idx {
+                    libs.resize(idx + 1, None);
+                }
+                libs[idx] = file;
+            }
+        }
+
+        self.modules = versyms
+            .iter()
+            .map(|versym| {
+                let idx = versym.0.get(endian) & elf::VERSYM_VERSION;
+                libs.get(idx as usize).copied().flatten()
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    /// Collect the needed libraries and resolve MIPS GOT entries, which don't have relocations.
+    fn parse_dynamic(&mut self) -> Result<(), object::Error> {
+        let endian = self.obj.endian();
+        let data = self.obj.data();
+        let section_table = self.obj.raw_header().sections(endian, data)?;
+
+        let (entries, link) = match section_table.dynamic(endian, data)? {
+            Some(dynamic) => dynamic,
+            None => return Ok(()),
+        };
+
+        let strings = section_table.strings(endian, data, link)?;
+
+        let mut local_gotno = None;
+        let mut gotsym = None;
+        for entry in entries {
+            let d_val: u64 = entry.d_val(endian).into();
+            match entry.tag32(endian) {
+                Some(elf::DT_NEEDED) => {
+                    let lib = entry.string(endian, strings)?;
+                    self.dylibs.push(std::str::from_utf8(lib).unwrap_or(""));
+                }
+                Some(elf::DT_PLTGOT) => self.pltgot = Some(d_val),
+                Some(elf::DT_MIPS_LOCAL_GOTNO) => local_gotno = Some(d_val),
+                Some(elf::DT_MIPS_GOTSYM) => gotsym = Some(d_val),
+                _ => {}
+            }
+        }
+
+        if self.obj.raw_header().e_machine(endian) == elf::EM_MIPS {
+            if let (Some(pltgot), Some(local_gotno), Some(gotsym)) =
+                (self.pltgot, local_gotno, gotsym) {
+                self.parse_mips_got(pltgot, local_gotno, gotsym);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// MIPS doesn't emit relocations for it's GOT. Instead the GOT starts with `local_gotno`
+    /// local entries followed by one entry for every dynamic symbol starting at `gotsym`.
+    fn parse_mips_got(&mut self, pltgot: u64, local_gotno: u64, gotsym: u64) {
+        let dyn_syms = match self.obj.dynamic_symbol_table() {
+            Some(dyn_syms) => dyn_syms,
+            None => return,
+        };
+
+        let width = if self.obj.is_64() { 8 } else { 4 };
+        for sym in dyn_syms.symbols() {
+            let idx = sym.index().0 as u64;
+            if idx < gotsym || !sym.is_undefined() {
+                continue;
+            }
+
+            let addr = pltgot + (local_gotno + idx - gotsym) * width;
+            self.slots.insert(addr, sym.index());
+            self.push_import(addr as usize, &sym);
+        }
+    }
+
     pub fn parse_imports(&mut self) {
         let relocations = match self.obj.dynamic_relocations() {
             Some(relocations) => relocations,
@@ -41,57 +179,193 @@ impl<'data, Elf: FileHeader> ElfDebugInfo<'data, Elf> {
             None => return,
         };
 
+        let e_machine = self.obj.raw_header().e_machine(self.obj.endian());
+
         for (r_offset, reloc) in relocations {
-            if let RelocationTarget::Symbol(idx) = reloc.target() {
-                let opt_section = self.obj.sections().find(|section| {
-                    (section.address()..section.address() + section.size()).contains(&r_offset)
-                });
+            let sym = match reloc.target() {
+                RelocationTarget::Symbol(idx) => match dyn_syms.symbol_by_index(idx) {
+                    Ok(sym) => sym,
+                    Err(..) => continue,
+                },
+                _ => continue,
+            };
+
+            match reloc.kind() {
+                // hard-coded address to function which doesn't require a relocation
+                RelocationKind::Absolute => {}
+                // GOT entry or copy of the imported object
+                RelocationKind::Elf(r_type) if is_import_reloc(e_machine, r_type) => {}
+                _ => continue,
+            }
+
+            self.slots.insert(r_offset, sym.index());
+            self.push_import(r_offset as usize, &sym);
+        }
+    }
+
+    /// Give every PLT stub a `name@plt` symbol, by following it to the GOT slot it jumps
+    /// through and the import relocated into that slot.
+    fn parse_plt(&mut self) {
+        let dyn_syms = match self.obj.dynamic_symbol_table() {
+            Some(dyn_syms) => dyn_syms,
+            None => return,
+        };
+
+        let endian = self.obj.endian();
+        let e_machine = self.obj.raw_header().e_machine(endian);
+        let little_endian = self.obj.is_little_endian();
+
+        let section_headers = match self.obj.raw_header().sections(endian, self.obj.data()) {
+            Ok(section_headers) => section_headers,
+            Err(..) => return,
+        };
+
+        for (header, section) in section_headers.iter().zip(self.obj.sections()) {
+            match section.name() {
+                Ok(name) if name.starts_with(".plt") || name == ".MIPS.stubs" => {}
+                _ => continue,
+            }
+
+            let bytes = match section.data() {
+                Ok(bytes) => bytes,
+                Err(..) => continue,
+            };
 
-                let section = match opt_section {
-                    Some(section) => section,
-                    None => continue,
+            let entsize = header.sh_entsize(endian).into() as usize;
+            let stride = plt::stride(e_machine, entsize);
+            let addr = section.address();
+
+            let mut offset = 0;
+            while offset < bytes.len() {
+                let stub_addr = addr + offset as u64;
+                let stub = match plt::decode(
+                    e_machine,
+                    &bytes[offset..],
+                    stub_addr,
+                    self.pltgot,
+                    little_endian,
+                ) {
+                    Some(stub) => stub,
+                    None => {
+                        offset += stride;
+                        continue;
+                    }
                 };
 
-                if let Ok(sym) = dyn_syms.symbol_by_index(idx) {
-                    let name = match sym.name() {
-                        Ok(name) => name,
-                        Err(..) => continue,
-                    };
-
-                    let addr = match reloc.kind() {
-                        // hard-coded address to function which doesn't require a relocation
-                        RelocationKind::Absolute => r_offset as usize,
-                        RelocationKind::Elf(elf::R_X86_64_GLOB_DAT) => r_offset as usize,
-                        RelocationKind::Elf(elf::R_X86_64_COPY) => r_offset as usize,
-                        // address in .got.plt section which contains an address to the function
-                        RelocationKind::Elf(elf::R_X86_64_JUMP_SLOT) => {
-                            let width = if self.obj.is_64() { 8 } else { 4 };
-
-                            let bytes = match section.data_range(r_offset, width) {
-                                Ok(Some(bytes)) => bytes,
-                                _ => continue,
-                            };
-
-                            let phys_addr = if self.obj.is_64() {
-                                self.obj.endian().read_u64_bytes(bytes.try_into().unwrap()) as usize
-                            } else {
-                                self.obj.endian().read_u32_bytes(bytes.try_into().unwrap()) as usize
-                            };
-
-                            // idk why we need this
-                            phys_addr.saturating_sub(6)
+                offset += stub.len.max(stride);
+
+                let idx = match stub.target {
+                    plt::StubTarget::Slot(slot) => match self.slots.get(&slot) {
+                        Some(&idx) => idx,
+                        None => continue,
+                    },
+                    plt::StubTarget::Symbol(idx) => SymbolIndex(idx),
+                };
+
+                let name = match dyn_syms.symbol_by_index(idx).and_then(|sym| sym.name()) {
+                    Ok(name) if !name.is_empty() => name,
+                    _ => continue,
+                };
+
+                // Symbols borrow from the binary, which lives for as long as the processor.
+                let name: &'static str = Box::leak(format!("{name}@plt").into_boxed_str());
+                self.syms.push(Addressed {
+                    addr: stub_addr as usize,
+                    item: RawSymbol { name, module: None, ordinal: None },
+                });
+            }
+        }
+    }
+
+    /// Name classes, vtables and virtual methods using C++ runtime type information.
+    fn parse_rtti(&mut self) {
+        let mut binds = HashMap::new();
+        let mut relocs = HashMap::new();
+
+        if let (Some(relocations), Some(dyn_syms)) =
+            (self.obj.dynamic_relocations(), self.obj.dynamic_symbol_table())
+        {
+            for (r_offset, reloc) in relocations {
+                match reloc.target() {
+                    // `R_*_RELATIVE` relocations of position independent code, only the ones
+                    // with an explicit addend aren't already in place.
+                    RelocationTarget::Absolute if !reloc.has_implicit_addend() => {
+                        relocs.insert(r_offset, reloc.addend() as u64);
+                    }
+                    RelocationTarget::Symbol(idx) => {
+                        let sym = match dyn_syms.symbol_by_index(idx) {
+                            Ok(sym) => sym,
+                            Err(..) => continue,
+                        };
+
+                        if sym.is_undefined() {
+                            if let Ok(name) = sym.name() {
+                                binds.insert(r_offset, name);
+                            }
+                            continue;
                         }
-                        _ => continue,
-                    };
-
-                    // TODO: find modules
-                    self.syms.push(Addressed {
-                        addr,
-                        item: RawSymbol { name, module: None },
-                    });
+
+                        // Exported vtables and `type_info` objects are referenced by name the
+                        // same way imported ones are, as the definition might be interposed.
+                        if let Ok(name) = sym.name() {
+                            if name.starts_with("_ZT") {
+                                binds.insert(r_offset, name);
+                            }
+                        }
+
+                        let addr = sym.address().wrapping_add(reloc.addend() as u64);
+                        relocs.insert(r_offset, addr);
+                    }
+                    _ => {}
                 }
             }
         }
+
+        let endian = match self.obj.is_little_endian() {
+            true => Endianness::Little,
+            false => Endianness::Big,
+        };
+
+        let image = Image {
+            sections: &self.sections,
+            endian,
+            is_64: self.obj.is_64(),
+            base_addr: 0,
+            chained: false,
+            relocs,
+        };
+
+        let rtti = rtti::itanium(&image, &binds, &self.syms, "");
+        self.syms.extend(rtti.syms);
+        self.classes = rtti.classes;
+    }
+
+    /// Attribute an imported symbol to the library providing it.
+    fn module_of(&self, sym: &ElfSymbol<'data, '_, Elf>) -> Option<&'data str> {
+        if !sym.is_undefined() {
+            return None;
+        }
+
+        match self.modules.get(sym.index().0) {
+            Some(&Some(module)) => Some(module),
+            // Unversioned symbols can only be attributed if there's one candidate. Weak
+            // symbols are left alone as they're allowed to not be provided by anyone.
+            _ if self.dylibs.len() == 1 && !sym.is_weak() => Some(self.dylibs[0]),
+            _ => None,
+        }
+    }
+
+    fn push_import(&mut self, addr: usize, sym: &ElfSymbol<'data, '_, Elf>) {
+        let name = match sym.name() {
+            Ok(name) if !name.is_empty() => name,
+            _ => return,
+        };
+
+        let module = self.module_of(sym);
+        self.syms.push(Addressed {
+            addr,
+            item: RawSymbol { name, module, ordinal: None },
+        });
     }
 
     pub fn parse_symbols(&mut self) {
@@ -101,11 +375,134 @@ impl<'data, Elf: FileHeader> ElfDebugInfo<'data, Elf> {
             item: RawSymbol {
                 name: "entry",
                 module: None,
+                ordinal: None,
             },
         });
     }
 }
 
+/// Whether a dynamic relocation fills in the GOT entry or copy of an imported symbol.
+fn is_import_reloc(e_machine: u16, r_type: u32) -> bool {
+    match e_machine {
+        elf::EM_X86_64 => matches!(
+            r_type,
+            elf::R_X86_64_GLOB_DAT | elf::R_X86_64_JUMP_SLOT | elf::R_X86_64_COPY
+        ),
+        elf::EM_386 => matches!(
+            r_type,
+            elf::R_386_GLOB_DAT | elf::R_386_JMP_SLOT | elf::R_386_COPY
+        ),
+        elf::EM_AARCH64 => matches!(
+            r_type,
+            elf::R_AARCH64_GLOB_DAT | elf::R_AARCH64_JUMP_SLOT | elf::R_AARCH64_COPY
+        ),
+        elf::EM_ARM => matches!(
+            r_type,
+            elf::R_ARM_GLOB_DAT | elf::R_ARM_JUMP_SLOT | elf::R_ARM_COPY
+        ),
+        elf::EM_RISCV => matches!(r_type, elf::R_RISCV_JUMP_SLOT | elf::R_RISCV_COPY),
+        elf::EM_MIPS => matches!(r_type, elf::R_MIPS_JUMP_SLOT | elf::R_MIPS_COPY),
+        _ => false,
+    }
+}
+
+/// Symbol index and type packed into a relocation's `r_info`.
+pub fn relocation_info(info: u64, is_64: bool) -> (u32, u32) {
+    if is_64 {
+        ((info >> 32) as u32, (info & 0xffffffff) as u32)
+    } else {
+        ((info >> 8) as u32, (info & 0xff) as u32)
+    }
+}
+
+/// Offset into the linked string table of the name of symbol `index` in a symbol table.
+pub fn symbol_name_offset(symtab: &[u8], index: u32, is_64: bool, endian: Endianness) -> Option<usize> {
+    // `st_name` is the first field of both classes of symbols.
+    let size = if is_64 { size_of::<Elf64Sym>() } else { size_of::<Elf32Sym>() };
+    read_u32(symtab, (index as usize).checked_mul(size)?, endian).map(|offset| offset as usize)
+}
+
+/// Relocation type as it's named by the architecture's ABI.
+pub fn relocation_type(arch: Architecture, r_type: u32) -> Option<&'static str> {
+    macro_rules! names {
+        ($($name:ident),* $(,)?) => {
+            match r_type {
+                $(elf::$name => Some(stringify!($name)),)*
+                _ => None,
+            }
+        };
+    }
+
+    match arch {
+        Architecture::X86_64 | Architecture::X86_64_X32 => names!(
+            R_X86_64_NONE, R_X86_64_64, R_X86_64_PC32, R_X86_64_GOT32, R_X86_64_PLT32,
+            R_X86_64_COPY, R_X86_64_GLOB_DAT, R_X86_64_JUMP_SLOT, R_X86_64_RELATIVE,
+            R_X86_64_GOTPCREL, R_X86_64_32, R_X86_64_32S, R_X86_64_16, R_X86_64_PC16, R_X86_64_8,
+            R_X86_64_PC8, R_X86_64_DTPMOD64, R_X86_64_DTPOFF64, R_X86_64_TPOFF64, R_X86_64_TLSGD,
+            R_X86_64_TLSLD, R_X86_64_DTPOFF32, R_X86_64_GOTTPOFF, R_X86_64_TPOFF32, R_X86_64_PC64,
+            R_X86_64_GOTOFF64, R_X86_64_GOTPC32, R_X86_64_SIZE32, R_X86_64_SIZE64,
+            R_X86_64_GOTPC32_TLSDESC, R_X86_64_TLSDESC_CALL, R_X86_64_TLSDESC,
+            R_X86_64_IRELATIVE, R_X86_64_GOTPCRELX, R_X86_64_REX_GOTPCRELX,
+        ),
+        Architecture::I386 => names!(
+            R_386_NONE, R_386_32, R_386_PC32, R_386_GOT32, R_386_PLT32, R_386_COPY,
+            R_386_GLOB_DAT, R_386_JMP_SLOT, R_386_RELATIVE, R_386_GOTOFF, R_386_GOTPC,
+            R_386_TLS_TPOFF, R_386_TLS_DTPMOD32, R_386_TLS_DTPOFF32, R_386_TLS_TPOFF32,
+            R_386_IRELATIVE, R_386_GOT32X,
+        ),
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => names!(
+            R_AARCH64_NONE, R_AARCH64_ABS64, R_AARCH64_ABS32, R_AARCH64_PREL64, R_AARCH64_PREL32,
+            R_AARCH64_ADR_PREL_PG_HI21, R_AARCH64_ADD_ABS_LO12_NC, R_AARCH64_LDST8_ABS_LO12_NC,
+            R_AARCH64_LDST16_ABS_LO12_NC, R_AARCH64_LDST32_ABS_LO12_NC,
+            R_AARCH64_LDST64_ABS_LO12_NC, R_AARCH64_LDST128_ABS_LO12_NC, R_AARCH64_CONDBR19,
+            R_AARCH64_JUMP26, R_AARCH64_CALL26, R_AARCH64_ADR_GOT_PAGE,
+            R_AARCH64_LD64_GOT_LO12_NC, R_AARCH64_COPY, R_AARCH64_GLOB_DAT,
+            R_AARCH64_JUMP_SLOT, R_AARCH64_RELATIVE, R_AARCH64_TLS_DTPMOD,
+            R_AARCH64_TLS_DTPREL, R_AARCH64_TLS_TPREL, R_AARCH64_TLSDESC, R_AARCH64_IRELATIVE,
+        ),
+        Architecture::Arm => names!(
+            R_ARM_NONE, R_ARM_PC24, R_ARM_ABS32, R_ARM_REL32, R_ARM_CALL, R_ARM_JUMP24,
+            R_ARM_THM_PC22, R_ARM_THM_JUMP24, R_ARM_MOVW_ABS_NC, R_ARM_MOVT_ABS, R_ARM_PREL31,
+            R_ARM_V4BX, R_ARM_COPY, R_ARM_GLOB_DAT, R_ARM_JUMP_SLOT, R_ARM_RELATIVE,
+            R_ARM_TLS_DTPMOD32, R_ARM_TLS_DTPOFF32, R_ARM_TLS_TPOFF32, R_ARM_TLS_DESC,
+            R_ARM_IRELATIVE,
+        ),
+        Architecture::Riscv32 | Architecture::Riscv64 => names!(
+            R_RISCV_NONE, R_RISCV_32, R_RISCV_64, R_RISCV_RELATIVE, R_RISCV_COPY,
+            R_RISCV_JUMP_SLOT, R_RISCV_TLS_DTPMOD32, R_RISCV_TLS_DTPMOD64, R_RISCV_TLS_DTPREL32,
+            R_RISCV_TLS_DTPREL64, R_RISCV_TLS_TPREL32, R_RISCV_TLS_TPREL64, R_RISCV_BRANCH,
+            R_RISCV_JAL, R_RISCV_CALL, R_RISCV_CALL_PLT, R_RISCV_GOT_HI20, R_RISCV_PCREL_HI20,
+            R_RISCV_PCREL_LO12_I, R_RISCV_PCREL_LO12_S, R_RISCV_HI20, R_RISCV_LO12_I,
+            R_RISCV_LO12_S, R_RISCV_ALIGN, R_RISCV_RELAX,
+        ),
+        Architecture::Mips | Architecture::Mips64 => names!(
+            R_MIPS_NONE, R_MIPS_16, R_MIPS_32, R_MIPS_REL32, R_MIPS_26, R_MIPS_HI16, R_MIPS_LO16,
+            R_MIPS_GPREL16, R_MIPS_LITERAL, R_MIPS_GOT16, R_MIPS_PC16, R_MIPS_CALL16,
+            R_MIPS_GPREL32, R_MIPS_64, R_MIPS_TLS_DTPMOD32, R_MIPS_TLS_DTPREL32,
+            R_MIPS_TLS_TPREL32, R_MIPS_COPY, R_MIPS_JUMP_SLOT,
+        ),
+        _ => None,
+    }
+}
+
+/// Renders a relocation's `r_info` as it's type followed by the name of the symbol it's against,
+/// e.g. `R_X86_64_JUMP_SLOT <puts>`.
+pub fn describe_relocation(arch: Architecture, r_type: u32, sym: u32, name: Option<&str>) -> String {
+    let mut description = match relocation_type(arch, r_type) {
+        Some(name) => name.to_string(),
+        None => format!("type {r_type:#x}"),
+    };
+
+    // Symbol 0 is the null symbol, which relocations that don't need a symbol refer to.
+    match name.filter(|name| !name.is_empty()) {
+        Some(name) => description += &format!(" <{name}>"),
+        None if sym != 0 => description += &format!(" <symbol {sym}>"),
+        None => {}
+    }
+
+    description
+}
+
 /// Common ELF dwarf section names I've found so far.
 const DWARF_SECTIONS: [&str; 20] = [
     ".debug_abbrev",
@@ -130,7 +527,7 @@ const DWARF_SECTIONS: [&str; 20] = [
     ".debug_types",
 ];
 
-fn parse_sections<'data, Elf: FileHeader>(obj: &'data ElfFile<'data, Elf>) -> Vec<Section> {
+pub(crate) fn parse_sections<'data, Elf: FileHeader>(obj: &ElfFile<'data, Elf>) -> Vec<Section> {
     let mut sections = Vec::new();
     let endian = obj.endian();
     let section_headers = obj.raw_header().sections(endian, obj.data()).unwrap();
@@ -149,7 +546,11 @@ fn parse_sections<'data, Elf: FileHeader>(obj: &'data ElfFile<'data, Elf>) -> Ve
             // String table.
             elf::SHT_STRTAB => (SectionKind::CString, "STRTAB"),
             // Relocation entries with explicit addends.
-            elf::SHT_RELA => (SectionKind::Raw, "RELA"),
+            elf::SHT_RELA => if obj.is_64() {
+                (SectionKind::Elf64Rela, "RELA")
+            } else {
+                (SectionKind::Elf32Rela, "RELA")
+            },
             // Symbol hash table.
             elf::SHT_HASH => (SectionKind::Raw, "HASH"),
             // Dynamic linking information.
@@ -159,11 +560,15 @@ fn parse_sections<'data, Elf: FileHeader>(obj: &'data ElfFile<'data, Elf>) -> Ve
                 (SectionKind::Elf32Dyn, "DYNAMIC")
             },
             // Notes.
-            elf::SHT_NOTE => (SectionKind::Raw, "NOTE"),
+            elf::SHT_NOTE => (SectionKind::ElfNote, "NOTE"),
             // Program space with no data (bss).
             elf::SHT_NOBITS => (SectionKind::Raw, "NOBITS"),
             // Relocation entries without explicit addends.
-            elf::SHT_REL => (SectionKind::Raw, "REL"),
+            elf::SHT_REL => if obj.is_64() {
+                (SectionKind::Elf64Rel, "REL")
+            } else {
+                (SectionKind::Elf32Rel, "REL")
+            },
             // Reserved section type.
             elf::SHT_SHLIB => (SectionKind::Raw, "SHLIB"),
             // Dynamic linker symbol table.
@@ -173,11 +578,23 @@ fn parse_sections<'data, Elf: FileHeader>(obj: &'data ElfFile<'data, Elf>) -> Ve
                 (SectionKind::Elf32Sym, "DYNSYM")
             },
             // Array of constructors.
-            elf::SHT_INIT_ARRAY => (SectionKind::Raw, "INIT_ARRAY"),
+            elf::SHT_INIT_ARRAY => if obj.is_64() {
+                (SectionKind::Ptr64, "INIT_ARRAY")
+            } else {
+                (SectionKind::Ptr32, "INIT_ARRAY")
+            },
             // Array of destructors.
-            elf::SHT_FINI_ARRAY => (SectionKind::Raw, "FINI_ARRAY"),
+            elf::SHT_FINI_ARRAY => if obj.is_64() {
+                (SectionKind::Ptr64, "FINI_ARRAY")
+            } else {
+                (SectionKind::Ptr32, "FINI_ARRAY")
+            },
             // Array of pre-constructors.
-            elf::SHT_PREINIT_ARRAY => (SectionKind::Raw, "PREINIT_ARRAY"),
+            elf::SHT_PREINIT_ARRAY => if obj.is_64() {
+                (SectionKind::Ptr64, "PREINIT_ARRAY")
+            } else {
+                (SectionKind::Ptr32, "PREINIT_ARRAY")
+            },
             // Section group.
             elf::SHT_GROUP => (SectionKind::Raw, "GROUP"),
             // Extended section indices for a symbol table.
@@ -187,7 +604,7 @@ fn parse_sections<'data, Elf: FileHeader>(obj: &'data ElfFile<'data, Elf>) -> Ve
             // Object attributes.
             elf::SHT_GNU_ATTRIBUTES => (SectionKind::Raw, "GNU_ATTRIBUTES"),
             // GNU-style hash table.
-            elf::SHT_GNU_HASH => (SectionKind::Raw, "GNU_HASH"),
+            elf::SHT_GNU_HASH => (SectionKind::ElfGnuHash, "GNU_HASH"),
             // Prelink library list
             elf::SHT_GNU_LIBLIST => (SectionKind::Raw, "GNU_LIBLIST"),
             // Checksum for DSO content.
@@ -197,11 +614,11 @@ fn parse_sections<'data, Elf: FileHeader>(obj: &'data ElfFile<'data, Elf>) -> Ve
             elf::SHT_SUNW_COMDAT => (SectionKind::Raw, "SUNW_COMDAT"),
             elf::SHT_SUNW_syminfo => (SectionKind::Raw, "SUNW_syminfo"),
             // Version definition section.
-            elf::SHT_GNU_VERDEF => (SectionKind::Raw, "GNU_VERDEF"),
+            elf::SHT_GNU_VERDEF => (SectionKind::ElfVerdef, "GNU_VERDEF"),
             // Version needs section.
-            elf::SHT_GNU_VERNEED => (SectionKind::Raw, "GNU_VERNEED"),
+            elf::SHT_GNU_VERNEED => (SectionKind::ElfVerneed, "GNU_VERNEED"),
             // Version symbol table.
-            elf::SHT_GNU_VERSYM => (SectionKind::Raw, "GNU_VERSYM"),
+            elf::SHT_GNU_VERSYM => (SectionKind::ElfVersym, "GNU_VERSYM"),
             // Start of processor-specific section types.
             elf::SHT_LOPROC => (SectionKind::Raw, "LOPROC"),
             // End of processor-specific section types.
@@ -247,23 +664,279 @@ fn parse_sections<'data, Elf: FileHeader>(obj: &'data ElfFile<'data, Elf>) -> Ve
             kind = SectionKind::Debug;
         }
 
-        sections.push(Section::new(
-            name,
-            ident,
-            kind,
-            bytes,
-            start,
-            end
-        ));
+        let mut section = Section::new(name, ident, kind, bytes, start, end);
+
+        // Symbols, dynamic entries and version records name things through a linked string table,
+        // relocations through a linked symbol table.
+        let link = SectionIndex(header.sh_link(endian) as usize);
+        if let Ok(linked) = section_headers.section(link) {
+            if let Ok(name) = section_headers.section_name(endian, linked) {
+                let name = String::from_utf8_lossy(name).into_owned();
+                match linked.sh_type(endian) {
+                    elf::SHT_STRTAB => section.strings = Some(name),
+                    elf::SHT_SYMTAB | elf::SHT_DYNSYM => section.symbols = Some(name),
+                    _ => {}
+                }
+            }
+        }
+
+        sections.push(section);
     }
 
     sections
 }
 
-#[repr(u64)]
-#[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug)]
-pub enum DynTag {
+fn read_u16(bytes: &[u8], offset: usize, endian: Endianness) -> Option<u16> {
+    let bytes = bytes.get(offset..offset.checked_add(2)?)?;
+    Some(endian.read_u16_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(bytes: &[u8], offset: usize, endian: Endianness) -> Option<u32> {
+    let bytes = bytes.get(offset..offset.checked_add(4)?)?;
+    Some(endian.read_u32_bytes(bytes.try_into().unwrap()))
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+/// Offset of every note in a `SHT_NOTE` section.
+pub fn note_offsets(bytes: &[u8], endian: Endianness) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+
+    while let (Some(namesz), Some(descsz)) =
+        (read_u32(bytes, offset, endian), read_u32(bytes, offset + 4, endian))
+    {
+        offsets.push(offset);
+        offset += 12 + align4(namesz as usize) + align4(descsz as usize);
+    }
+
+    offsets
+}
+
+/// Parse a single note, decoding the descriptors of the GNU notes we know.
+pub fn parse_note(bytes: &[u8], addr: usize, is_64: bool, endian: Endianness) -> Option<Datastructure> {
+    let namesz = read_u32(bytes, 0, endian)? as usize;
+    let descsz = read_u32(bytes, 4, endian)? as usize;
+    let n_type = read_u32(bytes, 8, endian)?;
+    let name = bytes.get(12..12 + namesz)?;
+    let name = name.strip_suffix(b"\0").unwrap_or(name);
+    let desc_offset = 12 + align4(namesz);
+    let desc = bytes.get(desc_offset..desc_offset + descsz)?;
+
+    let mut fields = vec![
+        (addr, "n_namesz", "u32", format!("{namesz:#x}")),
+        (addr + 4, "n_descsz", "u32", format!("{descsz:#x}")),
+        (addr + 8, "n_type", "u32", note_type(name, n_type)),
+        (addr + 12, "name", "str", format!("{:?}", String::from_utf8_lossy(name))),
+    ];
+
+    let desc_addr = addr + desc_offset;
+    match (name, n_type) {
+        (elf::ELF_NOTE_GNU, elf::NT_GNU_BUILD_ID) => {
+            let build_id: String = desc.iter().map(|b| format!("{b:02x}")).collect();
+            fields.push((desc_addr, "build_id", "[u8]", build_id));
+        }
+        (elf::ELF_NOTE_GNU, elf::NT_GNU_ABI_TAG) if descsz >= 16 => {
+            let os = match read_u32(desc, 0, endian)? {
+                elf::ELF_NOTE_OS_LINUX => "Linux",
+                elf::ELF_NOTE_OS_GNU => "GNU",
+                elf::ELF_NOTE_OS_SOLARIS2 => "Solaris",
+                elf::ELF_NOTE_OS_FREEBSD => "FreeBSD",
+                _ => "unknown",
+            };
+            let version = format!(
+                "{os} {}.{}.{}",
+                read_u32(desc, 4, endian)?,
+                read_u32(desc, 8, endian)?,
+                read_u32(desc, 12, endian)?,
+            );
+            fields.push((desc_addr, "abi", "[u32; 4]", version));
+        }
+        (elf::ELF_NOTE_GNU, elf::NT_GNU_PROPERTY_TYPE_0) => {
+            // Properties are padded to the size of a pointer.
+            let align = if is_64 { 8 } else { 4 };
+            let mut offset = 0;
+            while let (Some(pr_type), Some(pr_datasz)) =
+                (read_u32(desc, offset, endian), read_u32(desc, offset + 4, endian))
+            {
+                let data = read_u32(desc, offset + 8, endian).unwrap_or(0);
+                let property = gnu_property(pr_type, data);
+                fields.push((desc_addr + offset, "property", "u32", property));
+                offset += (8 + pr_datasz as usize + align - 1) & !(align - 1);
+            }
+        }
+        (elf::ELF_NOTE_GNU, elf::NT_GNU_GOLD_VERSION) => {
+            let version = desc.strip_suffix(b"\0").unwrap_or(desc);
+            let version = format!("{:?}", String::from_utf8_lossy(version));
+            fields.push((desc_addr, "version", "str", version));
+        }
+        _ if !desc.is_empty() => {
+            let mut hex: String = desc.iter().take(32).map(|b| format!("{b:02x}")).collect();
+            if desc.len() > 32 {
+                hex += "..";
+            }
+            fields.push((desc_addr, "desc", "[u8]", hex));
+        }
+        _ => {}
+    }
+
+    Some(Datastructure {
+        ident: "ElfNote",
+        size: desc_offset + align4(descsz),
+        fields,
+        references: Vec::new(),
+    })
+}
+
+fn note_type(name: &[u8], n_type: u32) -> String {
+    let known = match (name, n_type) {
+        (elf::ELF_NOTE_GNU, elf::NT_GNU_ABI_TAG) => "NT_GNU_ABI_TAG",
+        (elf::ELF_NOTE_GNU, elf::NT_GNU_HWCAP) => "NT_GNU_HWCAP",
+        (elf::ELF_NOTE_GNU, elf::NT_GNU_BUILD_ID) => "NT_GNU_BUILD_ID",
+        (elf::ELF_NOTE_GNU, elf::NT_GNU_GOLD_VERSION) => "NT_GNU_GOLD_VERSION",
+        (elf::ELF_NOTE_GNU, elf::NT_GNU_PROPERTY_TYPE_0) => "NT_GNU_PROPERTY_TYPE_0",
+        _ => return format!("{n_type:#x}"),
+    };
+
+    known.to_string()
+}
+
+fn gnu_property(pr_type: u32, data: u32) -> String {
+    let flags = |names: &[(u32, &str)]| {
+        let set: Vec<&str> = names
+            .iter()
+            .filter(|(bit, _)| data & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        set.join(" | ")
+    };
+
+    match pr_type {
+        elf::GNU_PROPERTY_X86_FEATURE_1_AND => {
+            format!("X86_FEATURE_1_AND: {}", flags(&[(1, "IBT"), (2, "SHSTK")]))
+        }
+        elf::GNU_PROPERTY_AARCH64_FEATURE_1_AND => {
+            format!("AARCH64_FEATURE_1_AND: {}", flags(&[(1, "BTI"), (2, "PAC")]))
+        }
+        elf::GNU_PROPERTY_X86_ISA_1_NEEDED => {
+            let isa = [(1, "BASELINE"), (2, "V2"), (4, "V3"), (8, "V4")];
+            format!("X86_ISA_1_NEEDED: {}", flags(&isa))
+        }
+        elf::GNU_PROPERTY_X86_ISA_1_USED => {
+            let isa = [(1, "BASELINE"), (2, "V2"), (4, "V3"), (8, "V4")];
+            format!("X86_ISA_1_USED: {}", flags(&isa))
+        }
+        elf::GNU_PROPERTY_STACK_SIZE => "STACK_SIZE".to_string(),
+        elf::GNU_PROPERTY_NO_COPY_ON_PROTECTED => "NO_COPY_ON_PROTECTED".to_string(),
+        elf::GNU_PROPERTY_1_NEEDED => format!("1_NEEDED: {data:#x}"),
+        _ => format!("{pr_type:#x}: {data:#x}"),
+    }
+}
+
+/// Kinds of records in `.gnu.version_r` and `.gnu.version_d` sections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionRecord {
+    Verneed,
+    Vernaux,
+    Verdef,
+    Verdaux,
+}
+
+/// Walk the linked lists of a version needs or version definitions section, returning the
+/// offset of every record.
+pub fn version_records(
+    bytes: &[u8],
+    definitions: bool,
+    endian: Endianness,
+) -> Vec<(usize, VersionRecord)> {
+    let mut records = Vec::new();
+
+    // Offsets of the count, first auxiliary entry and next entry fields.
+    let (cnt, aux, next, aux_next) = if definitions { (6, 12, 16, 4) } else { (2, 8, 12, 12) };
+    let (record, aux_record) = if definitions {
+        (VersionRecord::Verdef, VersionRecord::Verdaux)
+    } else {
+        (VersionRecord::Verneed, VersionRecord::Vernaux)
+    };
+
+    let mut offset = 0;
+    while offset < bytes.len() {
+        records.push((offset, record));
+
+        let count = read_u16(bytes, offset + cnt, endian).unwrap_or(0);
+        let mut aux_offset = offset + read_u32(bytes, offset + aux, endian).unwrap_or(0) as usize;
+        for _ in 0..count {
+            if aux_offset >= bytes.len() {
+                break;
+            }
+
+            records.push((aux_offset, aux_record));
+            match read_u32(bytes, aux_offset + aux_next, endian) {
+                Some(0) | None => break,
+                Some(next) => aux_offset += next as usize,
+            }
+        }
+
+        match read_u32(bytes, offset + next, endian) {
+            Some(0) | None => break,
+            Some(next) => offset += next as usize,
+        }
+    }
+
+    records.sort_unstable_by_key(|(offset, _)| *offset);
+    records.dedup_by_key(|(offset, _)| *offset);
+    records
+}
+
+/// Offsets of the parts of a `.gnu.hash` section.
+pub struct GnuHashLayout {
+    pub bloom: usize,
+    pub buckets: usize,
+    pub chains: usize,
+}
+
+impl GnuHashLayout {
+    pub fn parse(bytes: &[u8], is_64: bool, endian: Endianness) -> Option<Self> {
+        let nbuckets = read_u32(bytes, 0, endian)? as usize;
+        let bloom_size = read_u32(bytes, 8, endian)? as usize;
+        let width = if is_64 { 8 } else { 4 };
+
+        let bloom = size_of::<ElfGnuHashHeader>();
+        let buckets = bloom + bloom_size * width;
+        let chains = buckets + nbuckets * 4;
+
+        if chains > bytes.len() {
+            return None;
+        }
+
+        Some(Self { bloom, buckets, chains })
+    }
+}
+
+/// Tag of an entry in the `.dynamic` section.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DynTag(pub u64);
+
+macro_rules! dyn_tags {
+    ($($tag:ident = $value:literal,)*) => {
+        impl DynTag {
+            $(pub const $tag: Self = Self($value);)*
+
+            /// Name of the tag if it's one we know.
+            pub fn name(self) -> Option<&'static str> {
+                match self.0 {
+                    $($value => Some(stringify!($tag)),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+dyn_tags! {
     DT_NULL = 0x0,
     DT_NEEDED = 0x1,
     DT_PLTRELSZ = 0x2,
@@ -342,19 +1015,30 @@ pub enum DynTag {
     DT_MIPS_GOTSYM = 0x70000013,
     DT_MIPS_HIPAGENO = 0x70000014,
     DT_MIPS_RLD_MAP = 0x70000016,
-    DT_MIPS_RLD_MAP_REL = 0x70000035
+    DT_MIPS_RLD_MAP_REL = 0x70000035,
 }
 
 impl fmt::LowerHex for DynTag {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_fmt(format_args!("{:?}", self))
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => fmt::LowerHex::fmt(&self.0, f),
+        }
+    }
+}
+
+impl processor_shared::SwapBytes for DynTag {
+    fn swap_bytes(self) -> Self {
+        Self(self.0.swap_bytes())
     }
 }
 
+unsafe impl object::Pod for DynTag {}
+
 datastructure! {
     pub struct Elf32Sym {
-        st_name: u32,
-        st_value: u32,
+        #[string] st_name: u32,
+        #[address] st_value: u32,
         st_size: u32,
         st_info: u8,
         st_other: u8,
@@ -364,11 +1048,11 @@ datastructure! {
 
 datastructure! {
     pub struct Elf64Sym {
-        st_name: u32,
+        #[string] st_name: u32,
         st_info: u8,
         st_other: u8,
         st_shndx: u16,
-        st_value: u64,
+        #[address] st_value: u64,
         st_size: u64,
     }
 }
@@ -386,3 +1070,392 @@ datastructure! {
         d_val: u32,
     }
 }
+
+datastructure! {
+    pub struct Elf32Rel {
+        #[address] r_offset: u32,
+        #[relocation] r_info: u32,
+    }
+}
+
+datastructure! {
+    pub struct Elf64Rel {
+        #[address] r_offset: u64,
+        #[relocation] r_info: u64,
+    }
+}
+
+datastructure! {
+    pub struct Elf32Rela {
+        #[address] r_offset: u32,
+        #[relocation] r_info: u32,
+        r_addend: i32,
+    }
+}
+
+datastructure! {
+    pub struct Elf64Rela {
+        #[address] r_offset: u64,
+        #[relocation] r_info: u64,
+        r_addend: i64,
+    }
+}
+
+datastructure! {
+    pub struct ElfGnuHashHeader {
+        nbuckets: u32,
+        symoffset: u32,
+        bloom_size: u32,
+        bloom_shift: u32,
+    }
+}
+
+datastructure! {
+    pub struct ElfVersym {
+        vs_index: u16,
+    }
+}
+
+datastructure! {
+    pub struct ElfVerneed {
+        vn_version: u16,
+        vn_cnt: u16,
+        #[string] vn_file: u32,
+        vn_aux: u32,
+        vn_next: u32,
+    }
+}
+
+datastructure! {
+    pub struct ElfVernaux {
+        vna_hash: u32,
+        vna_flags: u16,
+        vna_other: u16,
+        #[string] vna_name: u32,
+        vna_next: u32,
+    }
+}
+
+datastructure! {
+    pub struct ElfVerdef {
+        vd_version: u16,
+        vd_flags: u16,
+        vd_ndx: u16,
+        vd_cnt: u16,
+        vd_hash: u32,
+        vd_aux: u32,
+        vd_next: u32,
+    }
+}
+
+datastructure! {
+    pub struct ElfVerdaux {
+        #[string] vda_name: u32,
+        vda_next: u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, ElfBuilder, GLOBAL};
+    use crate::ToData;
+
+    #[test]
+    fn linked_string_tables() {
+        let syms = [("value", 2, 0, GLOBAL | elf::STT_OBJECT)];
+        let bytes = testing::relocatable(elf::EM_X86_64, &[0x90], &[0; 8], 8, &syms, &[]);
+        let obj = object::read::elf::ElfFile64::<object::Endianness>::parse(&bytes[..]).unwrap();
+        let sections = parse_sections(&obj);
+
+        let strings = |name: &str| {
+            let section = sections.iter().find(|section| section.name == name).unwrap();
+            section.strings.as_deref()
+        };
+
+        assert_eq!(strings(".symtab"), Some(".strtab"));
+        // Relocations link to a symbol table, not a string table.
+        assert_eq!(strings(".rela.text"), None);
+        assert_eq!(strings(".text"), None);
+    }
+
+    #[test]
+    fn big_endian_structures() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&DynTag::DT_NEEDED.0.to_be_bytes());
+        bytes.extend_from_slice(&0x1234u64.to_be_bytes());
+        bytes.extend_from_slice(&DynTag::DT_GNU_HASH.0.to_be_bytes());
+        bytes.extend_from_slice(&0x0102_0304_0506_0708u64.to_be_bytes());
+        let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+        let section = Section::new(
+            ".dynamic".into(),
+            "DYNAMIC",
+            SectionKind::Elf64Dyn,
+            bytes,
+            0x3000,
+            0x3020,
+        );
+
+        let entry = section.read_at::<Elf64Dyn>(0x3000, Endianness::Big).unwrap();
+        assert_eq!(entry.d_tag, DynTag::DT_NEEDED);
+        assert_eq!(entry.d_val, 0x1234);
+
+        let entry = section.read_at::<Elf64Dyn>(0x3010, Endianness::Big).unwrap();
+        assert_eq!(entry.d_tag, DynTag::DT_GNU_HASH);
+        assert_eq!(entry.d_val, 0x0102_0304_0506_0708);
+        assert_eq!(entry.to_fields(0x3010).fields[0].3, "DT_GNU_HASH");
+
+        // Read in the wrong byte order, every field comes back swapped.
+        let entry = section.read_at::<Elf64Dyn>(0x3000, Endianness::Little).unwrap();
+        assert_eq!(entry.d_tag, DynTag(DynTag::DT_NEEDED.0.swap_bytes()));
+        assert_eq!(entry.d_val, 0x1234u64.swap_bytes());
+
+        let entry = section.read_at::<Elf32Rela>(0x3000, Endianness::Big).unwrap();
+        assert_eq!((entry.r_offset, entry.r_info, entry.r_addend), (0, 1, 0));
+    }
+
+    fn string_at(bytes: &[u8], offset: usize) -> &str {
+        let bytes = &bytes[offset..];
+        let end = bytes.iter().position(|&b| b == 0).unwrap();
+        std::str::from_utf8(&bytes[..end]).unwrap()
+    }
+
+    fn field<'a>(datastructure: &'a Datastructure, name: &str) -> &'a str {
+        let field = datastructure.fields.iter().find(|field| field.1 == name).unwrap();
+        &field.3
+    }
+
+    #[test]
+    fn relocations() {
+        let mut builder = ElfBuilder::new(elf::ET_DYN, elf::EM_X86_64);
+        let dynsym = builder.symbols(".dynsym", &[("puts", 0, 0, GLOBAL | elf::STT_FUNC)]);
+        builder.rela(
+            ".rela.plt",
+            dynsym,
+            0,
+            &[
+                (0x3fd0, 1, elf::R_X86_64_JUMP_SLOT, 0),
+                (0x3fc0, 0, elf::R_X86_64_RELATIVE, 0x1130),
+            ],
+        );
+        let bytes = builder.build();
+        let obj = object::read::elf::ElfFile64::<object::Endianness>::parse(&bytes[..]).unwrap();
+        let sections = parse_sections(&obj);
+        let section = |name: &str| sections.iter().find(|section| section.name == name).unwrap();
+
+        let rela = section(".rela.plt");
+        assert_eq!(rela.symbols.as_deref(), Some(".dynsym"));
+        assert_eq!(rela.strings, None);
+        assert_eq!(section(".dynsym").strings.as_deref(), Some(".dynstr"));
+
+        // What the listing shows for `r_info`.
+        let describe = |idx: usize| {
+            let entry = rela.read_at::<Elf64Rela>(rela.start + idx * 24, Endianness::Little);
+            let datastructure = entry.unwrap().to_fields(0);
+            let (info, is_64) = match datastructure.references[1] {
+                (1, crate::Reference::Relocation { info, is_64 }) => (info, is_64),
+                reference => panic!("{reference:?} isn't a relocation"),
+            };
+
+            let (sym, r_type) = relocation_info(info, is_64);
+            let dynsym = section(".dynsym").bytes();
+            let name = symbol_name_offset(dynsym, sym, is_64, Endianness::Little)
+                .map(|offset| string_at(section(".dynstr").bytes(), offset));
+            describe_relocation(Architecture::X86_64, r_type, sym, name)
+        };
+
+        assert_eq!(describe(0), "R_X86_64_JUMP_SLOT <puts>");
+        assert_eq!(describe(1), "R_X86_64_RELATIVE");
+    }
+
+    #[test]
+    fn relocation_info_classes() {
+        assert_eq!(relocation_info(0x0000_0003_0000_0007, true), (3, 7));
+        assert_eq!(relocation_info(0x0000_0507, false), (5, 7));
+
+        assert_eq!(
+            describe_relocation(Architecture::I386, 7, 5, None),
+            "R_386_JMP_SLOT <symbol 5>"
+        );
+        assert_eq!(
+            describe_relocation(
+                Architecture::Aarch64,
+                elf::R_AARCH64_GLOB_DAT,
+                2,
+                Some("environ")
+            ),
+            "R_AARCH64_GLOB_DAT <environ>"
+        );
+        assert_eq!(
+            describe_relocation(Architecture::Arm, 0xff, 0, None),
+            "type 0xff"
+        );
+        assert_eq!(relocation_type(Architecture::Wasm32, 1), None);
+
+        // Indices past the end of the symbol table have no name.
+        assert_eq!(
+            symbol_name_offset(&[0; 32], 2, false, Endianness::Little),
+            None
+        );
+    }
+
+    fn note(name: &[u8], n_type: u32, desc: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(name.len() as u32 + 1).to_le_bytes());
+        bytes.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&n_type.to_le_bytes());
+        bytes.extend_from_slice(name);
+        bytes.push(0);
+        bytes.resize(align4(bytes.len()), 0);
+        bytes.extend_from_slice(desc);
+        bytes.resize(align4(bytes.len()), 0);
+        bytes
+    }
+
+    #[test]
+    fn notes() {
+        let build_id = note(b"GNU", elf::NT_GNU_BUILD_ID, &[0xab, 0xcd, 0xef, 0x01]);
+        let note_abi = [elf::ELF_NOTE_OS_LINUX, 3, 2, 0].map(u32::to_le_bytes).concat();
+        let abi = note(b"GNU", elf::NT_GNU_ABI_TAG, &note_abi);
+        let properties = [elf::GNU_PROPERTY_X86_FEATURE_1_AND, 4, 3, 0].map(u32::to_le_bytes);
+        let property = note(b"GNU", elf::NT_GNU_PROPERTY_TYPE_0, &properties.concat());
+        let unknown = note(b"Go", 4, b"buildid");
+        let section = [&build_id[..], &abi, &property, &unknown].concat();
+
+        let offsets = note_offsets(&section, Endianness::Little);
+        assert_eq!(offsets, [0, 20, 52, 84]);
+
+        let parse = |offset: usize| {
+            parse_note(
+                &section[offset..],
+                0x1000 + offset,
+                true,
+                Endianness::Little,
+            )
+            .unwrap()
+        };
+
+        let note = parse(0);
+        assert_eq!(note.size, 20);
+        assert_eq!(field(&note, "n_type"), "NT_GNU_BUILD_ID");
+        assert_eq!(field(&note, "name"), "\"GNU\"");
+        assert_eq!(field(&note, "build_id"), "abcdef01");
+        assert_eq!(note.fields[4].0, 0x1010);
+
+        assert_eq!(field(&parse(20), "abi"), "Linux 3.2.0");
+        assert_eq!(
+            field(&parse(52), "property"),
+            "X86_FEATURE_1_AND: IBT | SHSTK"
+        );
+
+        let note = parse(84);
+        assert_eq!(field(&note, "n_type"), "0x4");
+        assert_eq!(field(&note, "desc"), "6275696c646964");
+
+        // The descriptor runs past the end of the section.
+        assert!(parse_note(&build_id[..18], 0, true, Endianness::Little).is_none());
+    }
+
+    #[test]
+    fn gnu_hash() {
+        let header = ElfGnuHashHeader {
+            nbuckets: 2,
+            symoffset: 1,
+            bloom_size: 1,
+            bloom_shift: 6,
+        };
+        let mut bytes = object::pod::bytes_of(&header).to_vec();
+        bytes.extend_from_slice(&[0xff; 8]);
+        bytes.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+        bytes.extend_from_slice(&[0x10, 0, 0, 0, 0x21, 0, 0, 0]);
+
+        let layout = GnuHashLayout::parse(&bytes, true, Endianness::Little).unwrap();
+        assert_eq!((layout.bloom, layout.buckets, layout.chains), (16, 24, 32));
+
+        // The bloom filter is made of 32-bit words in 32-bit files.
+        let layout = GnuHashLayout::parse(&bytes, false, Endianness::Little).unwrap();
+        assert_eq!((layout.bloom, layout.buckets, layout.chains), (16, 20, 28));
+
+        assert!(GnuHashLayout::parse(&bytes[..28], true, Endianness::Little).is_none());
+
+        let header = header.to_fields(0x2000);
+        assert_eq!(
+            header.fields[0],
+            (0x2000, "nbuckets", "u32", "0x2".to_string())
+        );
+        assert_eq!(field(&header, "bloom_shift"), "0x6");
+    }
+
+    #[test]
+    fn version_needs() {
+        let verneed = ElfVerneed {
+            vn_version: 1,
+            vn_cnt: 2,
+            vn_file: 1,
+            vn_aux: 16,
+            vn_next: 0,
+        };
+        let mut bytes = object::pod::bytes_of(&verneed).to_vec();
+        for (name, next) in [(11, 16), (22, 0)] {
+            bytes.extend_from_slice(object::pod::bytes_of(&ElfVernaux {
+                vna_hash: 0x0d696914,
+                vna_flags: 0,
+                vna_other: 2,
+                vna_name: name,
+                vna_next: next,
+            }));
+        }
+
+        let records = version_records(&bytes, false, Endianness::Little);
+        assert_eq!(
+            records,
+            [
+                (0, VersionRecord::Verneed),
+                (16, VersionRecord::Vernaux),
+                (32, VersionRecord::Vernaux)
+            ]
+        );
+
+        let verneed = verneed.to_fields(0);
+        assert_eq!(field(&verneed, "vn_cnt"), "0x2");
+        assert!(matches!(
+            verneed.references[..],
+            [(2, crate::Reference::String(1))]
+        ));
+    }
+
+    #[test]
+    fn version_definitions() {
+        let mut bytes = Vec::new();
+        for (ndx, next) in [(1, 28), (2, 0)] {
+            bytes.extend_from_slice(object::pod::bytes_of(&ElfVerdef {
+                vd_version: 1,
+                vd_flags: if ndx == 1 { elf::VER_FLG_BASE } else { 0 },
+                vd_ndx: ndx,
+                vd_cnt: 1,
+                vd_hash: 0,
+                vd_aux: 20,
+                vd_next: next,
+            }));
+            bytes.extend_from_slice(object::pod::bytes_of(&ElfVerdaux {
+                vda_name: ndx as u32,
+                vda_next: 0,
+            }));
+        }
+
+        let records = version_records(&bytes, true, Endianness::Little);
+        assert_eq!(
+            records,
+            [
+                (0, VersionRecord::Verdef),
+                (20, VersionRecord::Verdaux),
+                (28, VersionRecord::Verdef),
+                (48, VersionRecord::Verdaux),
+            ]
+        );
+
+        // A `vd_next` of zero ends the list.
+        bytes[16..20].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(version_records(&bytes, true, Endianness::Little).len(), 2);
+    }
+}
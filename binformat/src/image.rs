@@ -0,0 +1,85 @@
+//! Reading pointers and strings out of a mapped image by virtual address.
+
+use object::Endianness;
+use processor_shared::Section;
+use std::collections::HashMap;
+
+/// Mapped sections of the image, read by virtual address.
+pub(crate) struct Image<'a> {
+    pub sections: &'a [Section],
+    pub endian: Endianness,
+    pub is_64: bool,
+    pub base_addr: u64,
+    /// Pointers are encoded as chained fixups rather than plain addresses.
+    pub chained: bool,
+    /// Pointers only known after applying a relocation, by the address they're stored at.
+    pub relocs: HashMap<u64, u64>,
+}
+
+impl Image<'_> {
+    pub fn section(&self, addr: u64) -> Option<&Section> {
+        let addr = addr as usize;
+        self.sections.iter().find(|s| (s.start..s.end).contains(&addr))
+    }
+
+    pub fn section_by_name(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    pub fn u32(&self, addr: u64) -> Option<u32> {
+        self.section(addr)?.read_at::<u32>(addr as usize, self.endian).ok()
+    }
+
+    pub fn i32(&self, addr: u64) -> Option<i32> {
+        self.u32(addr).map(|value| value as i32)
+    }
+
+    pub fn ptr_size(&self) -> u64 {
+        if self.is_64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// Read a pointer, undoing any chained fixup encoding or relocation.
+    pub fn ptr(&self, addr: u64) -> Option<u64> {
+        self.ptr_in(self.section(addr)?, addr)
+    }
+
+    /// Read a pointer from a section that's known to contain it.
+    pub fn ptr_in(&self, section: &Section, addr: u64) -> Option<u64> {
+        if let Some(&target) = self.relocs.get(&addr) {
+            return Some(target);
+        }
+
+        let ptr = match self.is_64 {
+            true => section.read_at::<u64>(addr as usize, self.endian).ok()?,
+            false => section.read_at::<u32>(addr as usize, self.endian).ok()? as u64,
+        };
+
+        if !self.chained || ptr == 0 {
+            return Some(ptr);
+        }
+
+        // Rebases either hold the target or it's offset from the image's base, authenticated
+        // arm64e rebases only have room for a 32-bit offset.
+        let target = match self.is_64 {
+            true if ptr >> 63 == 1 => ptr & 0xffff_ffff,
+            true => ptr & 0xf_ffff_ffff,
+            false => ptr & 0x3ff_ffff,
+        };
+
+        match target < self.base_addr {
+            true => Some(target + self.base_addr),
+            false => Some(target),
+        }
+    }
+
+    pub fn cstr(&self, addr: u64) -> Option<&'static str> {
+        let section = self.section(addr)?;
+        let bytes = section.static_bytes().get(addr as usize - section.start..)?;
+        let len = bytes.iter().position(|&b| b == 0)?;
+        std::str::from_utf8(&bytes[..len]).ok()
+    }
+}
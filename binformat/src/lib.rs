@@ -1,13 +1,29 @@
 use object::{Object, ObjectSection, ObjectSymbol};
-use processor_shared::{AddressMap, Addressed};
+use processor_shared::{AddressMap, Addressed, Section, SectionKind};
 
+pub mod archive;
+mod authenticode;
+mod codesign;
+pub mod coff;
 pub mod elf;
+mod image;
 pub mod macho;
+mod objc;
+mod ordinals;
 pub mod pe;
+mod plt;
+pub mod relocatable;
+mod resource;
+mod rich;
+mod rtti;
+mod testing;
+mod unwind;
 
 pub struct RawSymbol<'data> {
     pub name: &'data str,
     pub module: Option<&'data str>,
+    /// Ordinal the symbol is exported under (PE only).
+    pub ordinal: Option<u32>,
 }
 
 fn parse_symbol_table<'data, Obj: Object<'data, 'data>>(
@@ -18,7 +34,7 @@ fn parse_symbol_table<'data, Obj: Object<'data, 'data>>(
         match sym.name() {
             Ok(name) => syms.push(Addressed {
                 addr: sym.address() as usize,
-                item: RawSymbol { name, module: None },
+                item: RawSymbol { name, module: None, ordinal: None },
             }),
             Err(err) => {
                 log::complex!(
@@ -34,7 +50,7 @@ fn parse_symbol_table<'data, Obj: Object<'data, 'data>>(
 }
 
 fn parse_section_generics<'data, Obj: ObjectSection<'data>>(
-    section: &'data Obj,
+    section: &Obj,
 ) -> (String, &'static [u8], usize, usize) {
     let name = match section.name() {
         Ok(name) => name,
@@ -67,21 +83,115 @@ fn parse_section_generics<'data, Obj: ObjectSection<'data>>(
     (name.to_string(), bytes, start, end)
 }
 
+/// Give the range `start..end` a section of it's own, splitting up the section containing it.
+///
+/// PE data directories usually live somewhere inside of `.rdata`, this let's them be rendered
+/// as structured data without affecting the rest of the section.
+pub(crate) fn carve_section(
+    sections: &mut Vec<Section>,
+    name: &str,
+    kind: SectionKind,
+    start: usize,
+    end: usize,
+) -> bool {
+    let idx = match sections.iter().position(|section| {
+        section.start <= start && end <= section.end && section.kind != SectionKind::Unloaded
+    }) {
+        Some(idx) => idx,
+        None => return false,
+    };
+
+    let section = sections.remove(idx);
+
+    // A directory that makes up the whole section keeps the section's name.
+    if section.start == start && section.end == end {
+        sections.push(section.slice(section.name.clone(), kind, start, end));
+        return true;
+    }
+
+    if section.start < start {
+        let before = section.slice(section.name.clone(), section.kind.clone(), section.start, start);
+        sections.push(before);
+    }
+
+    sections.push(section.slice(name.to_string(), kind, start, end));
+
+    if end < section.end {
+        let after = section.slice(section.name.clone(), section.kind.clone(), end, section.end);
+        sections.push(after);
+    }
+
+    true
+}
+
+#[derive(Debug, Clone)]
 pub struct Datastructure {
     pub ident: &'static str,
+    /// Number of bytes the datastructure spans.
+    pub size: usize,
     pub fields: Vec<(usize, &'static str, &'static str, String)>,
+    /// Fields that point somewhere else, by index into `fields`.
+    pub references: Vec<(usize, Reference)>,
+}
+
+/// A field value that can be resolved to a name.
+#[derive(Debug, Clone, Copy)]
+pub enum Reference {
+    /// Virtual address of a symbol.
+    Address(usize),
+    /// Offset into the dynamic string table.
+    String(usize),
+    /// `r_info` of an ELF relocation, holding the relocation's type and symbol index.
+    Relocation { info: u64, is_64: bool },
+}
+
+/// Facts about the binary as a whole under a common title, such as who signed it.
+#[derive(Debug, Clone)]
+pub struct FileInfo {
+    pub title: &'static str,
+    pub entries: Vec<(String, String)>,
+}
+
+/// A polymorphic C++ class recovered from it's runtime type information.
+#[derive(Debug, Clone)]
+pub struct Class {
+    /// Mangled symbol that demangles to just the class's name.
+    pub name: String,
+    /// Address of the class's `type_info` or type descriptor.
+    pub typeinfo: usize,
+    /// Names of the classes it directly derives from, mangled like [`Class::name`].
+    pub bases: Vec<String>,
+    /// Every vtable by the address of it's first slot, with the function in each slot.
+    /// Slots bound to an imported function hold the address of the slot instead.
+    pub vtables: Vec<(usize, Vec<usize>)>,
 }
 
 pub trait ToData {
     fn to_fields(&self, addr: usize) -> Datastructure;
 }
 
-// FIXME: This assumes little endianness.
+/// Define a `#[repr(C)]` structure that can be read from a section in either byte order and
+/// rendered field by field.
+///
+/// Fields marked `#[address]` or `#[string]` get resolved to a symbol or a string from the
+/// dynamic string table when rendered, `#[relocation]` fields to a relocation type and symbol.
 #[macro_export]
 macro_rules! datastructure {
+    (@reference address, $value:expr) => {
+        $crate::Reference::Address($value as usize)
+    };
+    (@reference string, $value:expr) => {
+        $crate::Reference::String($value as usize)
+    };
+    (@reference relocation, $value:expr) => {
+        $crate::Reference::Relocation {
+            info: $value as u64,
+            is_64: ::std::mem::size_of_val(&$value) == 8,
+        }
+    };
     (
         pub struct $name:ident {
-            $($field:ident: $ftype:ty,)*
+            $($(#[$reference:ident])? $field:ident: $ftype:ty,)*
         }
     ) => {
         // Apply attributes to the struct
@@ -92,9 +202,18 @@ macro_rules! datastructure {
         }
 
         impl $crate::ToData for $name {
+            #[allow(clippy::vec_init_then_push)]
             fn to_fields(&self, mut addr: usize) -> $crate::Datastructure {
                 let mut fields = Vec::new();
+                #[allow(unused_mut)]
+                let mut references = Vec::new();
                 $(
+                    $(
+                        references.push((
+                            fields.len(),
+                            $crate::datastructure!(@reference $reference, self.$field)
+                        ));
+                    )?
                     fields.push((
                         addr,
                         stringify!($field),
@@ -106,7 +225,17 @@ macro_rules! datastructure {
                 )*
                 $crate::Datastructure {
                     ident: stringify!($name),
+                    size: ::std::mem::size_of::<$name>(),
                     fields,
+                    references,
+                }
+            }
+        }
+
+        impl ::processor_shared::SwapBytes for $name {
+            fn swap_bytes(self) -> Self {
+                Self {
+                    $($field: <$ftype as ::processor_shared::SwapBytes>::swap_bytes(self.$field)),*
                 }
             }
         }
@@ -1,9 +1,14 @@
-use crate::RawSymbol;
+use crate::image::Image;
+use crate::{Class, FileInfo, RawSymbol};
 use processor_shared::{AddressMap, Addressed, Section, SectionKind};
-use object::macho::{self, DyldInfoCommand, DysymtabCommand, LinkeditDataCommand};
-use object::read::macho::{MachHeader, MachOFile, SymbolTable};
-use object::{Endianness, Object, ObjectSection, ObjectSegment, ReadRef, SectionFlags};
-use std::mem::size_of;
+use object::macho::{self, BuildToolVersion, DyldInfoCommand, DysymtabCommand, LinkeditDataCommand};
+use object::U32;
+use object::read::macho::{
+    LoadCommandData, LoadCommandVariant, MachHeader, MachOFile, Nlist, Segment, SymbolTable,
+};
+use object::{Endian, Endianness, Object, ObjectSection, ObjectSegment, ReadRef, SectionFlags};
+use std::collections::{HashMap, HashSet};
+use std::mem::{size_of, size_of_val};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -118,11 +123,21 @@ pub struct MachoDebugInfo<'data, Mach: MachHeader> {
     pub syms: AddressMap<RawSymbol<'data>>,
     /// Parsed sections with extra metadata.
     pub sections: Vec<Section>,
+    /// Selector passed to `objc_msgSend`, by call site.
+    pub selectors: AddressMap<String>,
+    /// Sections of stubs or pointers that are described by the indirect symbol table.
+    indirect_sections: Vec<IndirectSection>,
+    /// Load commands and code signature.
+    pub file_info: Vec<FileInfo>,
+    /// C++ classes recovered from runtime type information.
+    pub classes: Vec<Class>,
     // ---- Required load commands ----
     chained_fixups: Option<&'data LinkeditDataCommand<Mach::Endian>>,
     symtab: Option<SymbolTable<'data, Mach>>,
     dysymtab: Option<&'data DysymtabCommand<Mach::Endian>>,
     dylid_info: Option<&'data DyldInfoCommand<Mach::Endian>>,
+    exports_trie: Option<&'data LinkeditDataCommand<Mach::Endian>>,
+    code_signature: Option<&'data LinkeditDataCommand<Mach::Endian>>,
     // --------------------------------
 }
 
@@ -133,11 +148,17 @@ impl<'data, Mach: MachHeader<Endian = Endianness>> MachoDebugInfo<'data, Mach> {
             base_addr: obj.segments().next().map(|seg| seg.address()).unwrap_or(0),
             syms: AddressMap::default(),
             sections: Vec::new(),
+            selectors: AddressMap::default(),
+            indirect_sections: Vec::new(),
             dylibs: Vec::new(),
+            file_info: Vec::new(),
+            classes: Vec::new(),
             chained_fixups: None,
             symtab: None,
             dysymtab: None,
             dylid_info: None,
+            exports_trie: None,
+            code_signature: None,
         };
         this.sections = parse_sections(obj);
         this.parse_base_addr()?;
@@ -154,6 +175,11 @@ impl<'data, Mach: MachHeader<Endian = Endianness>> MachoDebugInfo<'data, Mach> {
             );
         }
         this.parse_dylid_info()?;
+        this.parse_exports();
+        this.parse_indirect_syms();
+        this.parse_objc();
+        this.parse_rtti();
+        this.parse_code_signature();
         Ok(this)
     }
 
@@ -166,14 +192,52 @@ impl<'data, Mach: MachHeader<Endian = Endianness>> MachoDebugInfo<'data, Mach> {
             self.dylibs.push("");
         }
 
+        let mut entries = Vec::new();
         let mut load_cmds_iter = header.load_commands(endian, self.obj.data(), 0)?;
         while let Some(lcmd) = load_cmds_iter.next()? {
+            match describe_load_cmd(&lcmd, endian) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => {
+                    log::complex!(
+                        w "[macho::parse_load_cmds] ",
+                        y err.to_string(),
+                        y "."
+                    );
+                    let name = match load_cmd_name(lcmd.cmd()) {
+                        Some(name) => name.to_string(),
+                        None => format!("{:#x}", lcmd.cmd()),
+                    };
+                    entries.push((name, "malformed".to_string()));
+                }
+            }
             if let Some(cmd) = lcmd.symtab()? {
                 self.symtab = Some(cmd.symbols(endian, self.obj.data())?);
             }
             if let Some(cmd) = lcmd.dysymtab()? {
                 self.dysymtab = Some(cmd);
             }
+            if let Some((segment, section_data)) = lcmd.segment_32()? {
+                for section in segment.sections(endian, section_data)? {
+                    self.push_indirect_section(
+                        section.addr.get(endian) as u64,
+                        section.size.get(endian) as u64,
+                        section.flags.get(endian),
+                        section.reserved1.get(endian),
+                        section.reserved2.get(endian),
+                    );
+                }
+            }
+            if let Some((segment, section_data)) = lcmd.segment_64()? {
+                for section in segment.sections(endian, section_data)? {
+                    self.push_indirect_section(
+                        section.addr.get(endian),
+                        section.size.get(endian),
+                        section.flags.get(endian),
+                        section.reserved1.get(endian),
+                        section.reserved2.get(endian),
+                    );
+                }
+            }
             if let Some(dylib_info) = lcmd.dyld_info()? {
                 self.dylid_info = Some(dylib_info);
             }
@@ -185,11 +249,153 @@ impl<'data, Mach: MachHeader<Endian = Endianness>> MachoDebugInfo<'data, Mach> {
             if lcmd.cmd() == macho::LC_DYLD_CHAINED_FIXUPS {
                 self.chained_fixups = Some(lcmd.data()?);
             }
+            if lcmd.cmd() == macho::LC_DYLD_EXPORTS_TRIE {
+                self.exports_trie = Some(lcmd.data()?);
+            }
+            if lcmd.cmd() == macho::LC_CODE_SIGNATURE {
+                self.code_signature = Some(lcmd.data()?);
+            }
         }
 
+        self.file_info.push(FileInfo {
+            title: "Load commands",
+            entries,
+        });
+
         Ok(())
     }
 
+    fn push_indirect_section(&mut self, addr: u64, size: u64, flags: u32, reserved1: u32, reserved2: u32) {
+        let ptr_size = if self.obj.is_64() { 8 } else { 4 };
+        let (stride, pointers) = match flags & macho::SECTION_TYPE {
+            // The second reserved field holds the size of a stub.
+            macho::S_SYMBOL_STUBS => (reserved2 as u64, false),
+            macho::S_NON_LAZY_SYMBOL_POINTERS
+            | macho::S_LAZY_SYMBOL_POINTERS
+            | macho::S_LAZY_DYLIB_SYMBOL_POINTERS => (ptr_size, true),
+            _ => return,
+        };
+
+        if stride == 0 {
+            return;
+        }
+
+        self.indirect_sections.push(IndirectSection {
+            addr,
+            size,
+            first: reserved1,
+            stride,
+            pointers,
+        });
+    }
+
+    /// Name every stub and symbol pointer after the symbol it's indirect symbol table entry
+    /// refers to.
+    fn parse_indirect_syms(&mut self) {
+        let endian = self.obj.endian();
+        let (symtab, dysymtab) = match (&self.symtab, self.dysymtab) {
+            (Some(symtab), Some(dysymtab)) => (symtab, dysymtab),
+            _ => return,
+        };
+
+        let offset = dysymtab.indirectsymoff.get(endian) as u64;
+        let count = dysymtab.nindirectsyms.get(endian) as usize;
+        let indices = match self.obj.data().read_slice_at::<U32<Endianness>>(offset, count) {
+            Ok(indices) => indices,
+            Err(()) => {
+                log::complex!(
+                    w "[macho::parse_indirect_syms] ",
+                    y "Failed to read indirect symbol table at offset ",
+                    g format!("{offset:#x}"),
+                    y "."
+                );
+                return;
+            }
+        };
+
+        for section in self.indirect_sections.iter() {
+            // Chained fixups already named the pointers.
+            if section.pointers && self.chained_fixups.is_some() {
+                continue;
+            }
+
+            for idx in 0..section.size / section.stride {
+                let index = match indices.get(section.first as usize + idx as usize) {
+                    Some(index) => index.get(endian),
+                    None => break,
+                };
+
+                if index & (macho::INDIRECT_SYMBOL_LOCAL | macho::INDIRECT_SYMBOL_ABS) != 0 {
+                    continue;
+                }
+
+                let nlist = match symtab.symbol(index as usize) {
+                    Ok(nlist) => nlist,
+                    Err(..) => continue,
+                };
+
+                let name = match nlist.name(endian, symtab.strings()).map(std::str::from_utf8) {
+                    Ok(Ok(name)) if !name.is_empty() => name,
+                    _ => continue,
+                };
+
+                let ordinal = (nlist.n_desc(endian) >> 8) as usize;
+                let module = self.dylibs.get(ordinal).map(|lib| {
+                    // Strip path prefix.
+                    lib.rsplit_once('/').map(|x| x.1).unwrap_or(lib)
+                });
+
+                self.syms.push(Addressed {
+                    addr: (section.addr + idx * section.stride) as usize,
+                    item: RawSymbol {
+                        name,
+                        module: module.filter(|lib| !lib.is_empty()),
+                        ordinal: None,
+                    },
+                });
+            }
+        }
+    }
+
+    fn parse_objc(&mut self) {
+        let objc = crate::objc::parse(
+            &self.sections,
+            self.obj.architecture(),
+            self.obj.endian(),
+            self.obj.is_64(),
+            self.base_addr,
+            self.chained_fixups.is_some(),
+            &self.syms,
+        );
+
+        self.syms.extend(objc.syms);
+        self.selectors = objc.selectors;
+    }
+
+    /// Name classes, vtables and virtual methods using C++ runtime type information.
+    fn parse_rtti(&mut self) {
+        // Imports are bound to the pointers they're referenced through.
+        let binds = self
+            .syms
+            .iter()
+            .filter(|sym| sym.item.module.is_some())
+            .map(|sym| (sym.addr as u64, sym.item.name))
+            .collect();
+
+        let image = Image {
+            sections: &self.sections,
+            endian: self.obj.endian(),
+            is_64: self.obj.is_64(),
+            base_addr: self.base_addr,
+            chained: self.chained_fixups.is_some(),
+            relocs: HashMap::new(),
+        };
+
+        let rtti = crate::rtti::itanium(&image, &binds, &self.syms, "_");
+        self.syms.extend(rtti.syms);
+        self.classes = rtti.classes;
+    }
+
     fn parse_dylid_info(&mut self) -> Result<(), object::Error> {
         let endian = self.obj.endian();
         let dylib_info = match self.dylid_info {
@@ -236,6 +442,68 @@ impl<'data, Mach: MachHeader<Endian = Endianness>> MachoDebugInfo<'data, Mach> {
         Ok(())
     }
 
+    /// Add every symbol the export trie describes, which is all a stripped dylib has left.
+    fn parse_exports(&mut self) {
+        let endian = self.obj.endian();
+        let (offset, size) = match (self.exports_trie, self.dylid_info) {
+            (Some(trie), _) => (trie.dataoff.get(endian), trie.datasize.get(endian)),
+            (None, Some(info)) => (info.export_off.get(endian), info.export_size.get(endian)),
+            (None, None) => return,
+        };
+
+        let trie = match self.obj.data().read_bytes_at(offset as u64, size as u64) {
+            Ok(trie) => trie,
+            Err(()) => {
+                log::complex!(
+                    w "[macho::parse_exports] ",
+                    y "Failed to read export trie at offset ",
+                    g format!("{offset:#x}"),
+                    y "."
+                );
+                return;
+            }
+        };
+
+        let known: HashSet<(usize, &str)> =
+            self.syms.iter().map(|sym| (sym.addr, sym.item.name)).collect();
+
+        for (name, offset) in parse_export_trie(trie) {
+            let addr = (self.base_addr + offset) as usize;
+            if known.contains(&(addr, name.as_str())) {
+                continue;
+            }
+
+            self.syms.push(Addressed {
+                addr,
+                item: RawSymbol {
+                    name: Box::leak(name.into_boxed_str()),
+                    module: None,
+                    ordinal: None,
+                },
+            });
+        }
+    }
+
+    fn parse_code_signature(&mut self) {
+        let endian = self.obj.endian();
+        let signature = match self.code_signature {
+            Some(signature) => signature,
+            None => return,
+        };
+
+        let offset = signature.dataoff.get(endian) as u64;
+        let size = signature.datasize.get(endian) as u64;
+        match self.obj.data().read_bytes_at(offset, size) {
+            Ok(bytes) => self.file_info.extend(crate::codesign::parse(self.obj.data(), bytes)),
+            Err(()) => log::complex!(
+                w "[macho::parse_code_signature] ",
+                y "Failed to read code signature at offset ",
+                g format!("{offset:#x}"),
+                y "."
+            ),
+        }
+    }
+
     fn parse_base_addr(&mut self) -> Result<(), object::Error> {
         // Macho addresses are relative to the __TEXT segment.
         for segment in self.obj.segments() {
@@ -256,6 +524,7 @@ impl<'data, Mach: MachHeader<Endian = Endianness>> MachoDebugInfo<'data, Mach> {
             item: RawSymbol {
                 name: "entry",
                 module: None,
+                ordinal: None,
             },
         });
     }
@@ -400,6 +669,372 @@ fn parse_sections<'data, Mach: MachHeader>(obj: &'data MachOFile<'data, Mach>) -
     sections
 }
 
+fn read_uleb128(bytes: &[u8], offset: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*offset)?;
+        *offset += 1;
+        if shift < 64 {
+            value |= ((byte & 0x7f) as u64) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+}
+
+/// Walk the export trie, returning every exported name with the offset from the image base it's
+/// defined at.
+///
+/// Each node holds terminal info if a name ends there, followed by edges labelled with the
+/// next part of the name. Re-exports and absolute symbols are left out as they don't refer to
+/// anything in this image.
+fn parse_export_trie(trie: &[u8]) -> Vec<(String, u64)> {
+    let mut exports = Vec::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![(0usize, String::new())];
+
+    while let Some((node, prefix)) = stack.pop() {
+        // Malformed tries could have cycles.
+        if !visited.insert(node) {
+            continue;
+        }
+
+        let mut offset = node;
+        let terminal_size = match read_uleb128(trie, &mut offset) {
+            Some(size) => size as usize,
+            None => continue,
+        };
+
+        let children = offset + terminal_size;
+        if terminal_size != 0 {
+            let flags = read_uleb128(trie, &mut offset).unwrap_or(0);
+            let kind = flags & macho::EXPORT_SYMBOL_FLAGS_KIND_MASK as u64;
+            let reexport = flags & macho::EXPORT_SYMBOL_FLAGS_REEXPORT as u64 != 0;
+            if !reexport && kind != macho::EXPORT_SYMBOL_FLAGS_KIND_ABSOLUTE as u64 {
+                // With a stub and resolver the first address is the stub.
+                if let Some(addr) = read_uleb128(trie, &mut offset) {
+                    exports.push((prefix.clone(), addr));
+                }
+            }
+        }
+
+        let mut offset = children;
+        let count = match trie.get(offset) {
+            Some(&count) => count,
+            None => continue,
+        };
+        offset += 1;
+
+        for _ in 0..count {
+            let len = match trie.get(offset..).and_then(|rest| rest.iter().position(|&b| b == 0)) {
+                Some(len) => len,
+                None => break,
+            };
+
+            let edge = String::from_utf8_lossy(&trie[offset..offset + len]);
+            offset += len + 1;
+
+            let child = match read_uleb128(trie, &mut offset) {
+                Some(child) => child as usize,
+                None => break,
+            };
+
+            stack.push((child, format!("{prefix}{edge}")));
+        }
+    }
+
+    exports
+}
+
+fn version(version: u32) -> String {
+    format!("{}.{}.{}", version >> 16, (version >> 8) & 0xff, version & 0xff)
+}
+
+fn platform(platform: u32) -> String {
+    match platform {
+        macho::PLATFORM_MACOS => "macOS".to_string(),
+        macho::PLATFORM_IOS => "iOS".to_string(),
+        macho::PLATFORM_TVOS => "tvOS".to_string(),
+        macho::PLATFORM_WATCHOS => "watchOS".to_string(),
+        macho::PLATFORM_BRIDGEOS => "bridgeOS".to_string(),
+        macho::PLATFORM_MACCATALYST => "Mac Catalyst".to_string(),
+        macho::PLATFORM_IOSSIMULATOR => "iOS Simulator".to_string(),
+        macho::PLATFORM_TVOSSIMULATOR => "tvOS Simulator".to_string(),
+        macho::PLATFORM_WATCHOSSIMULATOR => "watchOS Simulator".to_string(),
+        macho::PLATFORM_DRIVERKIT => "DriverKit".to_string(),
+        11 => "visionOS".to_string(),
+        12 => "visionOS Simulator".to_string(),
+        _ => format!("platform {platform}"),
+    }
+}
+
+fn load_cmd_name(cmd: u32) -> Option<&'static str> {
+    Some(match cmd {
+        macho::LC_SEGMENT => "LC_SEGMENT",
+        macho::LC_SYMTAB => "LC_SYMTAB",
+        macho::LC_SYMSEG => "LC_SYMSEG",
+        macho::LC_THREAD => "LC_THREAD",
+        macho::LC_UNIXTHREAD => "LC_UNIXTHREAD",
+        macho::LC_LOADFVMLIB => "LC_LOADFVMLIB",
+        macho::LC_IDFVMLIB => "LC_IDFVMLIB",
+        macho::LC_IDENT => "LC_IDENT",
+        macho::LC_FVMFILE => "LC_FVMFILE",
+        macho::LC_PREPAGE => "LC_PREPAGE",
+        macho::LC_DYSYMTAB => "LC_DYSYMTAB",
+        macho::LC_LOAD_DYLIB => "LC_LOAD_DYLIB",
+        macho::LC_ID_DYLIB => "LC_ID_DYLIB",
+        macho::LC_LOAD_DYLINKER => "LC_LOAD_DYLINKER",
+        macho::LC_ID_DYLINKER => "LC_ID_DYLINKER",
+        macho::LC_PREBOUND_DYLIB => "LC_PREBOUND_DYLIB",
+        macho::LC_ROUTINES => "LC_ROUTINES",
+        macho::LC_SUB_FRAMEWORK => "LC_SUB_FRAMEWORK",
+        macho::LC_SUB_UMBRELLA => "LC_SUB_UMBRELLA",
+        macho::LC_SUB_CLIENT => "LC_SUB_CLIENT",
+        macho::LC_SUB_LIBRARY => "LC_SUB_LIBRARY",
+        macho::LC_TWOLEVEL_HINTS => "LC_TWOLEVEL_HINTS",
+        macho::LC_PREBIND_CKSUM => "LC_PREBIND_CKSUM",
+        macho::LC_LOAD_WEAK_DYLIB => "LC_LOAD_WEAK_DYLIB",
+        macho::LC_SEGMENT_64 => "LC_SEGMENT_64",
+        macho::LC_ROUTINES_64 => "LC_ROUTINES_64",
+        macho::LC_UUID => "LC_UUID",
+        macho::LC_RPATH => "LC_RPATH",
+        macho::LC_CODE_SIGNATURE => "LC_CODE_SIGNATURE",
+        macho::LC_SEGMENT_SPLIT_INFO => "LC_SEGMENT_SPLIT_INFO",
+        macho::LC_REEXPORT_DYLIB => "LC_REEXPORT_DYLIB",
+        macho::LC_LAZY_LOAD_DYLIB => "LC_LAZY_LOAD_DYLIB",
+        macho::LC_ENCRYPTION_INFO => "LC_ENCRYPTION_INFO",
+        macho::LC_DYLD_INFO => "LC_DYLD_INFO",
+        macho::LC_DYLD_INFO_ONLY => "LC_DYLD_INFO_ONLY",
+        macho::LC_LOAD_UPWARD_DYLIB => "LC_LOAD_UPWARD_DYLIB",
+        macho::LC_VERSION_MIN_MACOSX => "LC_VERSION_MIN_MACOSX",
+        macho::LC_VERSION_MIN_IPHONEOS => "LC_VERSION_MIN_IPHONEOS",
+        macho::LC_FUNCTION_STARTS => "LC_FUNCTION_STARTS",
+        macho::LC_DYLD_ENVIRONMENT => "LC_DYLD_ENVIRONMENT",
+        macho::LC_MAIN => "LC_MAIN",
+        macho::LC_DATA_IN_CODE => "LC_DATA_IN_CODE",
+        macho::LC_SOURCE_VERSION => "LC_SOURCE_VERSION",
+        macho::LC_DYLIB_CODE_SIGN_DRS => "LC_DYLIB_CODE_SIGN_DRS",
+        macho::LC_ENCRYPTION_INFO_64 => "LC_ENCRYPTION_INFO_64",
+        macho::LC_LINKER_OPTION => "LC_LINKER_OPTION",
+        macho::LC_LINKER_OPTIMIZATION_HINT => "LC_LINKER_OPTIMIZATION_HINT",
+        macho::LC_VERSION_MIN_TVOS => "LC_VERSION_MIN_TVOS",
+        macho::LC_VERSION_MIN_WATCHOS => "LC_VERSION_MIN_WATCHOS",
+        macho::LC_NOTE => "LC_NOTE",
+        macho::LC_BUILD_VERSION => "LC_BUILD_VERSION",
+        macho::LC_DYLD_EXPORTS_TRIE => "LC_DYLD_EXPORTS_TRIE",
+        macho::LC_DYLD_CHAINED_FIXUPS => "LC_DYLD_CHAINED_FIXUPS",
+        macho::LC_FILESET_ENTRY => "LC_FILESET_ENTRY",
+        _ => return None,
+    })
+}
+
+/// Name a load command and summarize what it holds.
+fn describe_load_cmd<E: Endian>(
+    lcmd: &LoadCommandData<E>,
+    endian: E,
+) -> Result<(String, String), object::Error> {
+    let name = match load_cmd_name(lcmd.cmd()) {
+        Some(name) => name.to_string(),
+        None => format!("{:#x}", lcmd.cmd()),
+    };
+
+    let string = |s| {
+        lcmd.string(endian, s).map(|s| String::from_utf8_lossy(s).to_string())
+    };
+    let protection = |prot: u32| {
+        let mut perms = String::new();
+        perms.push(if prot & macho::VM_PROT_READ != 0 { 'r' } else { '-' });
+        perms.push(if prot & macho::VM_PROT_WRITE != 0 { 'w' } else { '-' });
+        perms.push(if prot & macho::VM_PROT_EXECUTE != 0 { 'x' } else { '-' });
+        perms
+    };
+    let segment = |segname: &[u8], vm: (u64, u64), file: (u64, u64), prot: u32, nsects: u32| {
+        let len = segname.iter().position(|&b| b == 0).unwrap_or(segname.len());
+        format!(
+            "{}, {:#x}..{:#x}, file {:#x}..{:#x}, {}, {nsects} sections",
+            String::from_utf8_lossy(&segname[..len]),
+            vm.0,
+            vm.0 + vm.1,
+            file.0,
+            file.0 + file.1,
+            protection(prot),
+        )
+    };
+
+    let description = match lcmd.variant()? {
+        LoadCommandVariant::Segment32(cmd, _) => segment(
+            &cmd.segname,
+            (cmd.vmaddr.get(endian) as u64, cmd.vmsize.get(endian) as u64),
+            (cmd.fileoff.get(endian) as u64, cmd.filesize.get(endian) as u64),
+            cmd.initprot.get(endian),
+            cmd.nsects.get(endian),
+        ),
+        LoadCommandVariant::Segment64(cmd, _) => segment(
+            &cmd.segname,
+            (cmd.vmaddr.get(endian), cmd.vmsize.get(endian)),
+            (cmd.fileoff.get(endian), cmd.filesize.get(endian)),
+            cmd.initprot.get(endian),
+            cmd.nsects.get(endian),
+        ),
+        LoadCommandVariant::Symtab(cmd) => format!(
+            "{} symbols at {:#x}, {} bytes of strings at {:#x}",
+            cmd.nsyms.get(endian),
+            cmd.symoff.get(endian),
+            cmd.strsize.get(endian),
+            cmd.stroff.get(endian),
+        ),
+        LoadCommandVariant::Dysymtab(cmd) => format!(
+            "{} local, {} defined external, {} undefined, {} indirect symbols",
+            cmd.nlocalsym.get(endian),
+            cmd.nextdefsym.get(endian),
+            cmd.nundefsym.get(endian),
+            cmd.nindirectsyms.get(endian),
+        ),
+        LoadCommandVariant::Thread(_, state) => format!("{} bytes of thread state", state.len()),
+        LoadCommandVariant::Dylib(cmd) | LoadCommandVariant::IdDylib(cmd) => format!(
+            "{} (compatibility {}, current {})",
+            string(cmd.dylib.name)?,
+            version(cmd.dylib.compatibility_version.get(endian)),
+            version(cmd.dylib.current_version.get(endian)),
+        ),
+        LoadCommandVariant::LoadDylinker(cmd)
+        | LoadCommandVariant::IdDylinker(cmd)
+        | LoadCommandVariant::DyldEnvironment(cmd) => string(cmd.name)?,
+        LoadCommandVariant::SubFramework(cmd) => string(cmd.umbrella)?,
+        LoadCommandVariant::SubUmbrella(cmd) => string(cmd.sub_umbrella)?,
+        LoadCommandVariant::SubClient(cmd) => string(cmd.client)?,
+        LoadCommandVariant::SubLibrary(cmd) => string(cmd.sub_library)?,
+        LoadCommandVariant::Uuid(cmd) => {
+            let uuid: String = cmd.uuid.iter().map(|byte| format!("{byte:02X}")).collect();
+            format!(
+                "{}-{}-{}-{}-{}",
+                &uuid[..8],
+                &uuid[8..12],
+                &uuid[12..16],
+                &uuid[16..20],
+                &uuid[20..]
+            )
+        }
+        LoadCommandVariant::Rpath(cmd) => string(cmd.path)?,
+        LoadCommandVariant::LinkeditData(cmd) => format!(
+            "{} bytes at {:#x}",
+            cmd.datasize.get(endian),
+            cmd.dataoff.get(endian),
+        ),
+        LoadCommandVariant::EncryptionInfo32(cmd) => format!(
+            "{} bytes at {:#x}, {}",
+            cmd.cryptsize.get(endian),
+            cmd.cryptoff.get(endian),
+            if cmd.cryptid.get(endian) != 0 { "encrypted" } else { "not encrypted" },
+        ),
+        LoadCommandVariant::EncryptionInfo64(cmd) => format!(
+            "{} bytes at {:#x}, {}",
+            cmd.cryptsize.get(endian),
+            cmd.cryptoff.get(endian),
+            if cmd.cryptid.get(endian) != 0 { "encrypted" } else { "not encrypted" },
+        ),
+        LoadCommandVariant::DyldInfo(cmd) => format!(
+            "rebase {} bytes, bind {} bytes, weak bind {} bytes, lazy bind {} bytes, \
+             export {} bytes",
+            cmd.rebase_size.get(endian),
+            cmd.bind_size.get(endian),
+            cmd.weak_bind_size.get(endian),
+            cmd.lazy_bind_size.get(endian),
+            cmd.export_size.get(endian),
+        ),
+        LoadCommandVariant::VersionMin(cmd) => format!(
+            "minimum {}, SDK {}",
+            version(cmd.version.get(endian)),
+            version(cmd.sdk.get(endian)),
+        ),
+        LoadCommandVariant::EntryPoint(cmd) => format!(
+            "entry at offset {:#x}, stack size {:#x}",
+            cmd.entryoff.get(endian),
+            cmd.stacksize.get(endian),
+        ),
+        LoadCommandVariant::SourceVersion(cmd) => {
+            // Packed as a24.b10.c10.d10.e10.
+            let version = cmd.version.get(endian);
+            format!(
+                "{}.{}.{}.{}.{}",
+                version >> 40,
+                (version >> 30) & 0x3ff,
+                (version >> 20) & 0x3ff,
+                (version >> 10) & 0x3ff,
+                version & 0x3ff,
+            )
+        }
+        LoadCommandVariant::LinkerOption(cmd) => {
+            let options = lcmd.raw_data().get(size_of_val(cmd)..).unwrap_or(&[]);
+            options
+                .split(|&b| b == 0)
+                .filter(|option| !option.is_empty())
+                .take(cmd.count.get(endian) as usize)
+                .map(String::from_utf8_lossy)
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+        LoadCommandVariant::Note(cmd) => {
+            let len = cmd.data_owner.iter().position(|&b| b == 0).unwrap_or(16);
+            format!(
+                "{}, {} bytes at {:#x}",
+                String::from_utf8_lossy(&cmd.data_owner[..len]),
+                cmd.size.get(endian),
+                cmd.offset.get(endian),
+            )
+        }
+        LoadCommandVariant::BuildVersion(cmd) => {
+            let mut description = format!(
+                "{}, minimum {}, SDK {}",
+                platform(cmd.platform.get(endian)),
+                version(cmd.minos.get(endian)),
+                version(cmd.sdk.get(endian)),
+            );
+
+            let offset = size_of_val(cmd) as u64;
+            let count = cmd.ntools.get(endian) as usize;
+            let tools = lcmd
+                .raw_data()
+                .read_slice_at::<BuildToolVersion<E>>(offset, count)
+                .unwrap_or(&[]);
+            for tool in tools {
+                let name = match tool.tool.get(endian) {
+                    macho::TOOL_CLANG => "clang".to_string(),
+                    macho::TOOL_SWIFT => "swift".to_string(),
+                    macho::TOOL_LD => "ld".to_string(),
+                    4 => "lld".to_string(),
+                    tool => format!("tool {tool}"),
+                };
+                description += &format!(", {name} {}", version(tool.version.get(endian)));
+            }
+
+            description
+        }
+        LoadCommandVariant::FilesetEntry(cmd) => format!(
+            "{} at {:#x}",
+            string(cmd.entry_id)?,
+            cmd.vmaddr.get(endian),
+        ),
+        _ => format!("{} bytes", lcmd.cmdsize()),
+    };
+
+    Ok((name, description))
+}
+
+struct IndirectSection {
+    addr: u64,
+    size: u64,
+    /// Index of the section's first entry in the indirect symbol table.
+    first: u32,
+    /// Size of an entry.
+    stride: u64,
+    /// Whether the entries are pointers rather than stubs.
+    pointers: bool,
+}
+
 #[allow(dead_code)]
 fn parse_dynamic_table<'data>(
     _bytes: &'data [u8],
@@ -778,6 +1413,7 @@ fn parse_chained_fixups<'data, Mach: MachHeader<Endian = Endianness>>(
                                     item: RawSymbol {
                                         name: entry.name,
                                         module,
+                                        ordinal: None,
                                     }
                                 });
                             } else {
@@ -856,3 +1492,44 @@ fn parse_chained_fixups<'data, Mach: MachHeader<Endian = Endianness>>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_trie() {
+        #[rustfmt::skip]
+        let trie = [
+            // Root at 0, with a single edge to 5.
+            0, 1, b'_', 0, 5,
+            // Node at 5, with a cyclic edge back to the root.
+            0, 4,
+            b'm', b'a', b'i', b'n', 0, 26,
+            b'f', b'o', b'o', 0, 31,
+            b'b', b'a', b'r', 0, 36,
+            b'x', 0, 0,
+            // _main at 0x3f50.
+            3, 0, 0xd0, 0x7e, 0,
+            // _foo at 0x1000.
+            3, 0, 0x80, 0x20, 0,
+            // _bar re-exported from the first dylib.
+            3, macho::EXPORT_SYMBOL_FLAGS_REEXPORT as u8, 1, 0, 0,
+        ];
+
+        let mut exports = parse_export_trie(&trie);
+        exports.sort();
+        assert_eq!(
+            exports,
+            [("_foo".to_string(), 0x1000), ("_main".to_string(), 0x3f50)]
+        );
+    }
+
+    #[test]
+    fn export_trie_truncated() {
+        // Edge pointing past the end and an unterminated label.
+        assert!(parse_export_trie(&[0, 1, b'_', 0, 0x7f]).is_empty());
+        assert!(parse_export_trie(&[0, 1, b'_']).is_empty());
+        assert!(parse_export_trie(&[]).is_empty());
+    }
+}
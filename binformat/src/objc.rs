@@ -0,0 +1,427 @@
+//! Recovery of Objective-C runtime metadata from Mach-O images.
+//!
+//! Every class in `__objc_classlist` points to a `class_t`, which points to a `class_ro_t`
+//! holding it's name and method list. Class methods are found the same way through the class's
+//! metaclass. Layouts are described in `objc-runtime-new.h` of Apple's objc4 sources.
+
+use crate::image::Image;
+use crate::RawSymbol;
+use object::{Architecture, Endianness};
+use processor_shared::{AddressMap, Addressed, Section};
+use std::collections::HashMap;
+
+/// Method lists with this flag store 32-bit offsets instead of pointers.
+const METHOD_LIST_IS_RELATIVE: u32 = 0x8000_0000;
+
+/// Bits of `entsizeAndFlags` holding the size of a method.
+const METHOD_LIST_ENTSIZE_MASK: u32 = 0x0000_fffc;
+
+/// Any method lists larger than this are assumed to be garbage.
+const MAX_METHODS: u32 = 0x10000;
+
+/// Most bytes between loading a selector into `rsi` and the call it's used in.
+const MAX_CALL_DISTANCE: usize = 32;
+
+/// Functions that dispatch a message with the selector in their second argument.
+const MSG_SEND: [&str; 4] = [
+    "_objc_msgSend",
+    "_objc_msgSendSuper",
+    "_objc_msgSendSuper2",
+    "_objc_msgSend_stret",
+];
+
+pub(crate) struct Objc {
+    /// Method implementations named `-[Class selector]` or `+[Class selector]`.
+    pub syms: AddressMap<RawSymbol<'static>>,
+    /// Selector passed to `objc_msgSend`, by call site.
+    pub selectors: AddressMap<String>,
+}
+
+pub(crate) fn parse(
+    sections: &[Section],
+    arch: Architecture,
+    endian: Endianness,
+    is_64: bool,
+    base_addr: u64,
+    chained: bool,
+    syms: &AddressMap<RawSymbol>,
+) -> Objc {
+    let image = Image {
+        sections,
+        endian,
+        is_64,
+        base_addr,
+        chained,
+        relocs: HashMap::new(),
+    };
+
+    let mut objc = Objc {
+        syms: AddressMap::default(),
+        selectors: AddressMap::default(),
+    };
+
+    if let Some(classlist) = image.section_by_name("__objc_classlist") {
+        let ptr_size = image.ptr_size();
+        let mut addr = classlist.start as u64;
+        while addr + ptr_size <= classlist.end as u64 {
+            if let Some(class) = image.ptr(addr) {
+                parse_class(&image, class, &mut objc.syms);
+            }
+            addr += ptr_size;
+        }
+    }
+
+    let dispatchers: Vec<u64> = syms
+        .iter()
+        .filter(|sym| MSG_SEND.contains(&sym.item.name))
+        .map(|sym| sym.addr as u64)
+        .collect();
+
+    if let Some(text) = image.section_by_name("__text") {
+        if !dispatchers.is_empty() {
+            parse_call_sites(&image, arch, text, &dispatchers, &mut objc.selectors);
+        }
+    }
+
+    log::complex!(
+        w "[objc::parse] found ",
+        g objc.syms.len().to_string(),
+        w " methods and ",
+        g objc.selectors.len().to_string(),
+        w " message sends."
+    );
+
+    objc
+}
+
+/// Name the instance methods of a `class_t` and the class methods of it's metaclass.
+fn parse_class(image: &Image, class: u64, syms: &mut AddressMap<RawSymbol<'static>>) -> Option<()> {
+    let (name, methods) = parse_class_ro(image, class)?;
+    parse_method_list(image, name, '-', methods, syms);
+
+    let metaclass = image.ptr(class)?;
+    let (_, methods) = parse_class_ro(image, metaclass)?;
+    parse_method_list(image, name, '+', methods, syms);
+
+    Some(())
+}
+
+/// Name and method list of a `class_t`'s `class_ro_t`.
+fn parse_class_ro(image: &Image, class: u64) -> Option<(&'static str, u64)> {
+    let ptr_size = image.ptr_size();
+
+    // The low bits of the data pointer are flags such as whether the class is Swift.
+    let data = image.ptr(class + 4 * ptr_size)? & !(ptr_size - 1);
+
+    // `flags`, `instanceStart` and `instanceSize` are padded up to the first pointer.
+    let ro = match image.is_64 {
+        true => data + 16,
+        false => data + 12,
+    };
+
+    let name = image.cstr(image.ptr(ro + ptr_size)?)?;
+    let methods = image.ptr(ro + 2 * ptr_size)?;
+
+    Some((name, methods))
+}
+
+fn parse_method_list(
+    image: &Image,
+    class: &str,
+    kind: char,
+    list: u64,
+    syms: &mut AddressMap<RawSymbol<'static>>,
+) -> Option<()> {
+    if list == 0 {
+        return None;
+    }
+
+    let flags = image.u32(list)?;
+    let count = image.u32(list + 4)?;
+    let entsize = (flags & METHOD_LIST_ENTSIZE_MASK) as u64;
+    let relative = flags & METHOD_LIST_IS_RELATIVE != 0;
+
+    if count > MAX_METHODS || entsize == 0 {
+        return None;
+    }
+
+    for idx in 0..count as u64 {
+        let method = list + 8 + idx * entsize;
+
+        let (selector, imp) = if relative {
+            // Every field is an offset from itself, the name's offset leads to a selector ref.
+            let selref = method.checked_add_signed(image.i32(method)? as i64)?;
+            let imp = (method + 8).checked_add_signed(image.i32(method + 8)? as i64)?;
+            (image.ptr(selref)?, imp)
+        } else {
+            (image.ptr(method)?, image.ptr(method + 2 * image.ptr_size())?)
+        };
+
+        let selector = match image.cstr(selector) {
+            Some(selector) => selector,
+            None => continue,
+        };
+
+        // Thumb implementations have their lowest bit set.
+        let imp = imp & !1;
+        if imp == 0 {
+            continue;
+        }
+
+        let name: &'static str = Box::leak(format!("{kind}[{class} {selector}]").into_boxed_str());
+        syms.push(Addressed {
+            addr: imp as usize,
+            item: RawSymbol {
+                name,
+                module: None,
+                ordinal: None,
+            },
+        });
+    }
+
+    Some(())
+}
+
+/// Find calls to a message dispatcher and the selector loaded right before them.
+///
+/// Compilers load the selector from `__objc_selrefs` into the second argument register just
+/// before the call, so this only recognizes that sequence instead of tracking registers.
+fn parse_call_sites(
+    image: &Image,
+    arch: Architecture,
+    text: &Section,
+    dispatchers: &[u64],
+    selectors: &mut AddressMap<String>,
+) {
+    let selrefs = match image.section_by_name("__objc_selrefs") {
+        Some(selrefs) => selrefs.start as u64..selrefs.end as u64,
+        None => return,
+    };
+
+    let selector = |selref: u64| match selrefs.contains(&selref) {
+        true => image.ptr(selref).and_then(|addr| image.cstr(addr)),
+        false => None,
+    };
+
+    let bytes = text.bytes();
+    let start = text.start as u64;
+
+    match arch {
+        Architecture::Aarch64 => {
+            // Pages loaded by `adrp`, by register.
+            let mut pages = [None; 32];
+            let mut x1 = None;
+
+            for (idx, insn) in bytes.chunks_exact(4).enumerate() {
+                let pc = start + idx as u64 * 4;
+                let insn = u32::from_le_bytes([insn[0], insn[1], insn[2], insn[3]]);
+
+                if insn & 0x9f00_0000 == 0x9000_0000 {
+                    // adrp xd, page
+                    let immlo = (insn >> 29) & 0b11;
+                    let immhi = (insn >> 5) & 0x7ffff;
+                    let imm = (((immhi << 2 | immlo) << 11) as i32 >> 11) as i64;
+                    let page = (pc & !0xfff).wrapping_add_signed(imm << 12);
+                    pages[(insn & 0x1f) as usize] = Some(page);
+                } else if insn & 0xffc0_0000 == 0xf940_0000 && insn & 0x1f == 1 {
+                    // ldr x1, [xn, #imm]
+                    let rn = ((insn >> 5) & 0x1f) as usize;
+                    let offset = ((insn >> 10) & 0xfff) as u64 * 8;
+                    x1 = pages[rn].and_then(|page| selector(page + offset));
+                    continue;
+                } else if insn & 0xff00_0000 == 0x5800_0000 && insn & 0x1f == 1 {
+                    // ldr x1, label, which the linker relaxes `adrp` and `ldr` pairs to
+                    let imm = ((insn & 0x00ff_ffe0) << 8) as i32 >> 11;
+                    x1 = selector(pc.wrapping_add_signed(imm as i64));
+                    continue;
+                } else if insn & 0xfc00_0000 == 0x9400_0000 {
+                    // bl target
+                    let imm = ((insn & 0x03ff_ffff) << 6) as i32 >> 4;
+                    let target = pc.wrapping_add_signed(imm as i64);
+                    if let (true, Some(selector)) = (dispatchers.contains(&target), x1) {
+                        selectors.push(Addressed {
+                            addr: pc as usize,
+                            item: selector.to_string(),
+                        });
+                    }
+                    pages = [None; 32];
+                    x1 = None;
+                }
+
+                // Most instructions that write a register encode it in the lowest bits.
+                if insn & 0x1f == 1 {
+                    x1 = None;
+                }
+            }
+        }
+        Architecture::X86_64 => {
+            // Selector loaded into `rsi` and where the load ended.
+            let mut rsi = None;
+            let mut idx = 0;
+
+            while idx + 7 <= bytes.len() {
+                let pc = start + idx as u64;
+
+                match bytes[idx..idx + 3] {
+                    // mov rsi, [rip + disp32]
+                    [0x48, 0x8b, 0x35] => {
+                        let disp = i32::from_le_bytes(bytes[idx + 3..idx + 7].try_into().unwrap());
+                        let selref = (pc + 7).wrapping_add_signed(disp as i64);
+                        rsi = selector(selref).map(|selector| (selector, idx + 7));
+                        idx += 7;
+                        continue;
+                    }
+                    // call rel32
+                    [0xe8, ..] => {
+                        let disp = i32::from_le_bytes(bytes[idx + 1..idx + 5].try_into().unwrap());
+                        let target = (pc + 5).wrapping_add_signed(disp as i64);
+                        if dispatchers.contains(&target) {
+                            match rsi.take() {
+                                // Without decoding we can't tell if `rsi` was overwritten, so
+                                // only trust a load that happened shortly before the call.
+                                Some((selector, end)) if idx - end <= MAX_CALL_DISTANCE => {
+                                    selectors.push(Addressed {
+                                        addr: pc as usize,
+                                        item: selector.to_string(),
+                                    });
+                                }
+                                _ => {}
+                            }
+                            idx += 5;
+                            continue;
+                        }
+                    }
+                    _ => {}
+                }
+
+                idx += 1;
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use processor_shared::SectionKind;
+
+    fn section(name: &str, start: usize, bytes: Vec<u8>) -> Section {
+        let end = start + bytes.len();
+        let bytes = Box::leak(bytes.into_boxed_slice());
+        Section::new(name.to_string(), "", SectionKind::Raw, bytes, start, end)
+    }
+
+    fn words(words: &[u64]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn image(sections: &[Section]) -> Image<'_> {
+        Image {
+            sections,
+            endian: Endianness::Little,
+            is_64: true,
+            base_addr: 0,
+            chained: false,
+            relocs: HashMap::new(),
+        }
+    }
+
+    /// Selector names at 0x3000 and a selector reference to each at 0x4000.
+    fn selectors() -> Vec<Section> {
+        vec![
+            section("__objc_methname", 0x3000, b"init\0foo:\0".to_vec()),
+            section("__objc_selrefs", 0x4000, words(&[0x3000, 0x3005])),
+        ]
+    }
+
+    fn names(syms: &AddressMap<RawSymbol<'static>>) -> Vec<(usize, &'static str)> {
+        syms.iter().map(|sym| (sym.addr, sym.item.name)).collect()
+    }
+
+    #[test]
+    fn method_list() {
+        // Entries of `name`, `types` and `imp`, the second being a Thumb implementation.
+        let mut list = vec![24, 0, 0, 0, 2, 0, 0, 0];
+        list.extend(words(&[0x3000, 0, 0x1000, 0x3005, 0, 0x1011]));
+
+        let mut sections = selectors();
+        sections.push(section("__objc_const", 0x2000, list));
+
+        let mut syms = AddressMap::default();
+        parse_method_list(&image(&sections), "Foo", '-', 0x2000, &mut syms);
+        assert_eq!(
+            names(&syms),
+            [(0x1000, "-[Foo init]"), (0x1010, "-[Foo foo:]")]
+        );
+    }
+
+    #[test]
+    fn relative_method_list() {
+        let flags = METHOD_LIST_IS_RELATIVE | 12;
+        let mut list = [flags.to_le_bytes(), 1u32.to_le_bytes()].concat();
+        // Offsets to the selector reference and implementation, from the field itself.
+        list.extend((0x4008 - 0x2008i32).to_le_bytes());
+        list.extend(0i32.to_le_bytes());
+        list.extend((0x1000 - 0x2010i32).to_le_bytes());
+
+        let mut sections = selectors();
+        sections.push(section("__objc_const", 0x2000, list));
+
+        let mut syms = AddressMap::default();
+        parse_method_list(&image(&sections), "Foo", '+', 0x2000, &mut syms);
+        assert_eq!(names(&syms), [(0x1000, "+[Foo foo:]")]);
+    }
+
+    #[test]
+    fn x86_64_call_sites() {
+        let mut text = Vec::new();
+        // mov rsi, [rip + selref]; call _objc_msgSend
+        text.extend([0x48, 0x8b, 0x35, 0xf9, 0x2f, 0x00, 0x00]);
+        text.extend([0xe8, 0xf4, 0x07, 0x00, 0x00]);
+        // mov rsi, [rip + selref]; call something_else
+        text.extend([0x48, 0x8b, 0x35, 0xf5, 0x2f, 0x00, 0x00]);
+        text.extend([0xe8, 0x00, 0x01, 0x00, 0x00]);
+
+        let mut sections = selectors();
+        sections.push(section("__text", 0x1000, text));
+        let image = image(&sections);
+
+        let mut selectors = AddressMap::default();
+        let text = image.section_by_name("__text").unwrap();
+        parse_call_sites(
+            &image,
+            Architecture::X86_64,
+            text,
+            &[0x1800],
+            &mut selectors,
+        );
+
+        let calls: Vec<_> = selectors.iter().map(|s| (s.addr, &s.item[..])).collect();
+        assert_eq!(calls, [(0x1007, "init")]);
+    }
+
+    #[test]
+    fn aarch64_call_sites() {
+        // adrp x8, selrefs; ldr x1, [x8, #8]; bl _objc_msgSend
+        let text = [0xf0000008u32, 0xf9400501, 0x940001fe];
+        let text = text.iter().flat_map(|insn| insn.to_le_bytes()).collect();
+
+        let mut sections = selectors();
+        sections.push(section("__text", 0x1000, text));
+        let image = image(&sections);
+
+        let mut selectors = AddressMap::default();
+        let text = image.section_by_name("__text").unwrap();
+        parse_call_sites(
+            &image,
+            Architecture::Aarch64,
+            text,
+            &[0x1800],
+            &mut selectors,
+        );
+
+        let calls: Vec<_> = selectors.iter().map(|s| (s.addr, &s.item[..])).collect();
+        assert_eq!(calls, [(0x1008, "foo:")]);
+    }
+}
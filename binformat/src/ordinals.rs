@@ -0,0 +1,152 @@
+//! Names of functions that system DLLs commonly export by ordinal only.
+//!
+//! Import tables of programs linking against these only contain the ordinal, so without the
+//! original DLL at hand we'd have nothing better to show than `ws2_32!#23`.
+
+/// Lookup the name of an export given the name of the module that exports it.
+pub(crate) fn lookup(module: &str, ordinal: u16) -> Option<&'static str> {
+    let module = module.to_ascii_lowercase();
+    let module = module.strip_suffix(".dll").unwrap_or(&module);
+
+    match module {
+        "ws2_32" | "wsock32" => winsock(ordinal),
+        "oleaut32" => oleaut32(ordinal),
+        _ => None,
+    }
+}
+
+fn winsock(ordinal: u16) -> Option<&'static str> {
+    Some(match ordinal {
+        1 => "accept",
+        2 => "bind",
+        3 => "closesocket",
+        4 => "connect",
+        5 => "getpeername",
+        6 => "getsockname",
+        7 => "getsockopt",
+        8 => "htonl",
+        9 => "htons",
+        10 => "ioctlsocket",
+        11 => "inet_addr",
+        12 => "inet_ntoa",
+        13 => "listen",
+        14 => "ntohl",
+        15 => "ntohs",
+        16 => "recv",
+        17 => "recvfrom",
+        18 => "select",
+        19 => "send",
+        20 => "sendto",
+        21 => "setsockopt",
+        22 => "shutdown",
+        23 => "socket",
+        51 => "gethostbyaddr",
+        52 => "gethostbyname",
+        53 => "getprotobyname",
+        54 => "getprotobynumber",
+        55 => "getservbyname",
+        56 => "getservbyport",
+        57 => "gethostname",
+        101 => "WSAAsyncSelect",
+        102 => "WSAAsyncGetHostByAddr",
+        103 => "WSAAsyncGetHostByName",
+        104 => "WSAAsyncGetProtoByNumber",
+        105 => "WSAAsyncGetProtoByName",
+        106 => "WSAAsyncGetServByPort",
+        107 => "WSAAsyncGetServByName",
+        108 => "WSACancelAsyncRequest",
+        109 => "WSASetBlockingHook",
+        110 => "WSAUnhookBlockingHook",
+        111 => "WSAGetLastError",
+        112 => "WSASetLastError",
+        113 => "WSACancelBlockingCall",
+        114 => "WSAIsBlocking",
+        115 => "WSAStartup",
+        116 => "WSACleanup",
+        151 => "__WSAFDIsSet",
+        500 => "WEP",
+        _ => return None,
+    })
+}
+
+fn oleaut32(ordinal: u16) -> Option<&'static str> {
+    Some(match ordinal {
+        2 => "SysAllocString",
+        3 => "SysReAllocString",
+        4 => "SysAllocStringLen",
+        5 => "SysReAllocStringLen",
+        6 => "SysFreeString",
+        7 => "SysStringLen",
+        8 => "VariantInit",
+        9 => "VariantClear",
+        10 => "VariantCopy",
+        11 => "VariantCopyInd",
+        12 => "VariantChangeType",
+        13 => "VariantTimeToDosDateTime",
+        14 => "DosDateTimeToVariantTime",
+        15 => "SafeArrayCreate",
+        16 => "SafeArrayDestroy",
+        17 => "SafeArrayGetDim",
+        18 => "SafeArrayGetElemsize",
+        19 => "SafeArrayGetUBound",
+        20 => "SafeArrayGetLBound",
+        21 => "SafeArrayLock",
+        22 => "SafeArrayUnlock",
+        23 => "SafeArrayAccessData",
+        24 => "SafeArrayUnaccessData",
+        25 => "SafeArrayGetElement",
+        26 => "SafeArrayPutElement",
+        27 => "SafeArrayCopy",
+        28 => "DispGetParam",
+        29 => "DispGetIDsOfNames",
+        30 => "DispInvoke",
+        31 => "CreateDispTypeInfo",
+        32 => "CreateStdDispatch",
+        33 => "RegisterActiveObject",
+        34 => "RevokeActiveObject",
+        35 => "GetActiveObject",
+        36 => "SafeArrayAllocDescriptor",
+        37 => "SafeArrayAllocData",
+        38 => "SafeArrayDestroyDescriptor",
+        39 => "SafeArrayDestroyData",
+        40 => "SafeArrayRedim",
+        147 => "VariantChangeTypeEx",
+        148 => "SafeArrayPtrOfIndex",
+        149 => "SysStringByteLen",
+        150 => "SysAllocStringByteLen",
+        161 => "LoadTypeLib",
+        162 => "LoadRegTypeLib",
+        163 => "RegisterTypeLib",
+        164 => "QueryPathOfRegTypeLib",
+        165 => "LHashValOfNameSys",
+        166 => "LHashValOfNameSysA",
+        183 => "LoadTypeLibEx",
+        184 => "SystemTimeToVariantTime",
+        185 => "VariantTimeToSystemTime",
+        186 => "UnRegisterTypeLib",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lookup;
+
+    #[test]
+    fn modules() {
+        assert_eq!(lookup("ws2_32.dll", 23), Some("socket"));
+        assert_eq!(lookup("WS2_32.DLL", 116), Some("WSACleanup"));
+        assert_eq!(lookup("wsock32", 3), Some("closesocket"));
+        assert_eq!(lookup("OLEAUT32.dll", 6), Some("SysFreeString"));
+        assert_eq!(lookup("oleaut32.dll", 161), Some("LoadTypeLib"));
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(lookup("ws2_32.dll", 0), None);
+        assert_eq!(lookup("oleaut32.dll", 1), None);
+        assert_eq!(lookup("kernel32.dll", 23), None);
+        // Only the extension is stripped, not any other suffix.
+        assert_eq!(lookup("ws2_32.dll.mui", 23), None);
+    }
+}
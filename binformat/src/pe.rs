@@ -1,9 +1,11 @@
-use crate::{datastructure, RawSymbol};
+use crate::image::Image;
+use crate::{datastructure, rtti, Class, Datastructure, FileInfo, RawSymbol, Reference, ToData};
 use processor_shared::{AddressMap, Addressed, Section, SectionKind};
 use object::pe;
-use object::read::pe::{ImageNtHeaders, ImageThunkData, PeFile};
+use object::read::pe::{ExportTarget, ImageNtHeaders, ImageThunkData, PeFile};
 use object::LittleEndian as LE;
-use object::Object;
+use object::{Architecture, Endianness, Object};
+use std::collections::{HashMap, HashSet};
 use std::mem::size_of;
 
 datastructure! {
@@ -14,6 +16,112 @@ datastructure! {
     }
 }
 
+datastructure! {
+    pub struct ImageExportDirectory {
+        characteristics: u32,
+        time_date_stamp: u32,
+        major_version: u16,
+        minor_version: u16,
+        name: u32,
+        base: u32,
+        number_of_functions: u32,
+        number_of_names: u32,
+        address_of_functions: u32,
+        address_of_names: u32,
+        address_of_name_ordinals: u32,
+    }
+}
+
+/// Number of base relocations shown per block.
+const RELOCATIONS_PER_BLOCK: usize = 16;
+
+/// Width and meaning of a field in a directory that differs between PE32 and PE32+.
+#[derive(Clone, Copy)]
+enum Field {
+    U16,
+    U32,
+    /// Pointer sized integer.
+    Ptr,
+    /// Pointer sized virtual address.
+    Va,
+}
+
+/// Fields of `IMAGE_LOAD_CONFIG_DIRECTORY` without the process heap flags and affinity mask,
+/// which are in a different order depending on the pointer size.
+const LOAD_CONFIG_FIELDS: [(&str, Field); 53] = [
+    ("size", Field::U32),
+    ("time_date_stamp", Field::U32),
+    ("major_version", Field::U16),
+    ("minor_version", Field::U16),
+    ("global_flags_clear", Field::U32),
+    ("global_flags_set", Field::U32),
+    ("critical_section_default_timeout", Field::U32),
+    ("de_commit_free_block_threshold", Field::Ptr),
+    ("de_commit_total_free_threshold", Field::Ptr),
+    ("lock_prefix_table", Field::Va),
+    ("maximum_allocation_size", Field::Ptr),
+    ("virtual_memory_threshold", Field::Ptr),
+    ("csd_version", Field::U16),
+    ("dependent_load_flags", Field::U16),
+    ("edit_list", Field::Va),
+    ("security_cookie", Field::Va),
+    ("se_handler_table", Field::Va),
+    ("se_handler_count", Field::Ptr),
+    ("guard_cf_check_function_pointer", Field::Va),
+    ("guard_cf_dispatch_function_pointer", Field::Va),
+    ("guard_cf_function_table", Field::Va),
+    ("guard_cf_function_count", Field::Ptr),
+    ("guard_flags", Field::U32),
+    ("code_integrity_flags", Field::U16),
+    ("code_integrity_catalog", Field::U16),
+    ("code_integrity_catalog_offset", Field::U32),
+    ("code_integrity_reserved", Field::U32),
+    ("guard_address_taken_iat_entry_table", Field::Va),
+    ("guard_address_taken_iat_entry_count", Field::Ptr),
+    ("guard_long_jump_target_table", Field::Va),
+    ("guard_long_jump_target_count", Field::Ptr),
+    ("dynamic_value_reloc_table", Field::Va),
+    ("chpe_metadata_pointer", Field::Va),
+    ("guard_rf_failure_routine", Field::Va),
+    ("guard_rf_failure_routine_function_pointer", Field::Va),
+    ("dynamic_value_reloc_table_offset", Field::U32),
+    ("dynamic_value_reloc_table_section", Field::U16),
+    ("reserved2", Field::U16),
+    ("guard_rf_verify_stack_pointer_function_pointer", Field::Va),
+    ("hot_patch_table_offset", Field::U32),
+    ("reserved3", Field::U32),
+    ("enclave_configuration_pointer", Field::Va),
+    ("volatile_metadata_pointer", Field::Va),
+    ("guard_eh_continuation_table", Field::Va),
+    ("guard_eh_continuation_count", Field::Ptr),
+    ("guard_xfg_check_function_pointer", Field::Va),
+    ("guard_xfg_dispatch_function_pointer", Field::Va),
+    ("guard_xfg_table_dispatch_function_pointer", Field::Va),
+    ("cast_guard_os_determined_failure_mode", Field::Va),
+    ("guard_memcpy_function_pointer", Field::Va),
+    ("uma_function_pointers", Field::Va),
+    ("reserved4", Field::U32),
+    ("reserved5", Field::U32),
+];
+
+const GUARD_FLAGS: [(u32, &str); 10] = [
+    (pe::IMAGE_GUARD_CF_INSTRUMENTED, "IMAGE_GUARD_CF_INSTRUMENTED"),
+    (pe::IMAGE_GUARD_CFW_INSTRUMENTED, "IMAGE_GUARD_CFW_INSTRUMENTED"),
+    (pe::IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT, "IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT"),
+    (pe::IMAGE_GUARD_SECURITY_COOKIE_UNUSED, "IMAGE_GUARD_SECURITY_COOKIE_UNUSED"),
+    (pe::IMAGE_GUARD_PROTECT_DELAYLOAD_IAT, "IMAGE_GUARD_PROTECT_DELAYLOAD_IAT"),
+    (pe::IMAGE_GUARD_DELAYLOAD_IAT_IN_ITS_OWN_SECTION, "IMAGE_GUARD_DELAYLOAD_IAT_IN_ITS_OWN_SECTION"),
+    (pe::IMAGE_GUARD_CF_EXPORT_SUPPRESSION_INFO_PRESENT, "IMAGE_GUARD_CF_EXPORT_SUPPRESSION_INFO_PRESENT"),
+    (pe::IMAGE_GUARD_CF_ENABLE_EXPORT_SUPPRESSION, "IMAGE_GUARD_CF_ENABLE_EXPORT_SUPPRESSION"),
+    (pe::IMAGE_GUARD_CF_LONGJUMP_TABLE_PRESENT, "IMAGE_GUARD_CF_LONGJUMP_TABLE_PRESENT"),
+    (IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT, "IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT"),
+];
+
+const IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT: u32 = 0x00400000;
+
+/// `dlattrRva` from `delayimp.h`, set by any linker newer than Visual C++ 6.
+const DLATTR_RVA: u32 = 0x1;
+
 pub struct PeDebugInfo<'data, Pe: ImageNtHeaders> {
     /// Parsed PE32/64 header.
     obj: &'data PeFile<'data, Pe>,
@@ -21,6 +129,12 @@ pub struct PeDebugInfo<'data, Pe: ImageNtHeaders> {
     pub sections: Vec<Section>,
     /// Any parsed but not yet relocated symbols.
     pub syms: AddressMap<RawSymbol<'data>>,
+    /// Data directories rendered as structured data.
+    pub datastructures: AddressMap<Datastructure>,
+    /// Toolchain and signature information.
+    pub file_info: Vec<FileInfo>,
+    /// C++ classes recovered from runtime type information.
+    pub classes: Vec<Class>,
 }
 
 impl<'data, Pe: ImageNtHeaders> PeDebugInfo<'data, Pe> {
@@ -29,13 +143,630 @@ impl<'data, Pe: ImageNtHeaders> PeDebugInfo<'data, Pe> {
             obj,
             syms: AddressMap::default(),
             sections: Vec::new(),
+            datastructures: AddressMap::default(),
+            file_info: Vec::new(),
+            classes: Vec::new(),
         };
         this.sections = parse_sections(obj);
         this.parse_symbols();
         this.parse_imports()?;
+        this.parse_delay_load_imports()?;
+        this.parse_exports()?;
+        this.parse_exceptions();
+        this.parse_resources();
+        this.parse_tls();
+        this.parse_load_config();
+        this.parse_relocations();
+        this.parse_rtti();
+        this.parse_rich_header();
+        this.parse_certificates();
         Ok(this)
     }
 
+    /// Virtual address of a relative virtual address.
+    fn va(&self, rva: u32) -> usize {
+        self.obj.relative_address_base() as usize + rva as usize
+    }
+
+    pub fn parse_exports(&mut self) -> Result<(), object::Error> {
+        let export_table = match self.obj.export_table()? {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+
+        let dir = match self.obj.data_directory(pe::IMAGE_DIRECTORY_ENTRY_EXPORT) {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+
+        let start = self.va(dir.virtual_address.get(LE));
+        let end = start + dir.size.get(LE) as usize;
+        crate::carve_section(&mut self.sections, ".edata", SectionKind::Structured, start, end);
+
+        let directory = export_table.directory();
+        let header = ImageExportDirectory {
+            characteristics: directory.characteristics.get(LE),
+            time_date_stamp: directory.time_date_stamp.get(LE),
+            major_version: directory.major_version.get(LE),
+            minor_version: directory.minor_version.get(LE),
+            name: directory.name.get(LE),
+            base: directory.base.get(LE),
+            number_of_functions: directory.number_of_functions.get(LE),
+            number_of_names: directory.number_of_names.get(LE),
+            address_of_functions: directory.address_of_functions.get(LE),
+            address_of_names: directory.address_of_names.get(LE),
+            address_of_name_ordinals: directory.address_of_name_ordinals.get(LE),
+        };
+
+        let mut datastructure = header.to_fields(start);
+        if let Ok(name) = export_table.name_from_pointer(header.name) {
+            // Fields are in declaration order, `name` is the fifth.
+            datastructure.fields[4].3 += &format!(" {:?}", String::from_utf8_lossy(name));
+        }
+        self.datastructures.push(Addressed {
+            addr: start,
+            item: datastructure,
+        });
+
+        // Export address table, indexed by ordinal minus the ordinal base.
+        let functions = self.va(header.address_of_functions);
+        for (idx, address) in export_table.addresses().iter().enumerate() {
+            let rva = address.get(LE);
+            let ordinal = header.base.wrapping_add(idx as u32);
+            let mut value = format!("{rva:#x} (#{ordinal})");
+            let mut references = Vec::new();
+
+            match export_table.forward_string(rva) {
+                Ok(Some(forward)) => {
+                    value += &format!(" -> {}", String::from_utf8_lossy(forward));
+                }
+                Ok(None) if rva != 0 => references.push((0, Reference::Address(self.va(rva)))),
+                _ => {}
+            }
+
+            let addr = functions + idx * size_of::<u32>();
+            self.datastructures.push(Addressed {
+                addr,
+                item: Datastructure {
+                    ident: "ExportAddress",
+                    size: size_of::<u32>(),
+                    fields: vec![(addr, "address", "u32", value)],
+                    references,
+                },
+            });
+        }
+
+        // Name pointer table, sorted by name for binary searching.
+        let names = self.va(header.address_of_names);
+        for (idx, pointer) in export_table.name_pointers().iter().enumerate() {
+            let pointer = pointer.get(LE);
+            let mut value = format!("{pointer:#x}");
+            if let Ok(name) = export_table.name_from_pointer(pointer) {
+                value += &format!(" {:?}", String::from_utf8_lossy(name));
+            }
+
+            let addr = names + idx * size_of::<u32>();
+            self.datastructures.push(Addressed {
+                addr,
+                item: Datastructure {
+                    ident: "ExportName",
+                    size: size_of::<u32>(),
+                    fields: vec![(addr, "name", "u32", value)],
+                    references: Vec::new(),
+                },
+            });
+        }
+
+        // Ordinal table, parallel to the name pointer table.
+        let ordinals = self.va(header.address_of_name_ordinals);
+        for (idx, ordinal) in export_table.name_ordinals().iter().enumerate() {
+            let index = ordinal.get(LE);
+            let ordinal = header.base.wrapping_add(index as u32);
+
+            let addr = ordinals + idx * size_of::<u16>();
+            self.datastructures.push(Addressed {
+                addr,
+                item: Datastructure {
+                    ident: "ExportOrdinal",
+                    size: size_of::<u16>(),
+                    fields: vec![(addr, "index", "u16", format!("{index:#x} (#{ordinal})"))],
+                    references: Vec::new(),
+                },
+            });
+        }
+
+        for export in export_table.exports()? {
+            let (rva, module) = match export.target {
+                ExportTarget::Address(rva) => (rva, None),
+                // Forwarded exports live in another module, their address is that of the
+                // forwarder string naming it.
+                _ => {
+                    let idx = export.ordinal.wrapping_sub(header.base) as usize;
+                    let rva = match export_table.addresses().get(idx) {
+                        Some(address) => address.get(LE),
+                        None => continue,
+                    };
+
+                    match export_table.forward_string(rva) {
+                        Ok(Some(forward)) => match std::str::from_utf8(forward) {
+                            Ok(forward) => (rva, Some(forward)),
+                            Err(..) => continue,
+                        },
+                        _ => continue,
+                    }
+                }
+            };
+
+            let name = match export.name.map(std::str::from_utf8) {
+                Some(Ok(name)) => name,
+                Some(Err(..)) => continue,
+                None => Box::leak(format!("#{}", export.ordinal).into_boxed_str()),
+            };
+
+            let addr = self.va(rva);
+            self.syms.push(Addressed {
+                addr,
+                item: RawSymbol {
+                    name,
+                    module,
+                    ordinal: Some(export.ordinal),
+                },
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Decode the `RUNTIME_FUNCTION`'s in `.pdata` and the `UNWIND_INFO` they point to.
+    pub fn parse_exceptions(&mut self) {
+        // Other architectures use a different layout.
+        if self.obj.architecture() != Architecture::X86_64 {
+            return;
+        }
+
+        let dir = match self.obj.data_directory(pe::IMAGE_DIRECTORY_ENTRY_EXCEPTION) {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let base = self.obj.relative_address_base() as usize;
+        let start = self.va(dir.virtual_address.get(LE));
+        let end = start + dir.size.get(LE) as usize;
+
+        let read = |addr: usize| read_at(&self.sections, addr);
+
+        // Exception handlers are usually called through an import thunk.
+        let names: HashMap<usize, &str> =
+            self.syms.iter().map(|sym| (sym.addr, sym.item.name)).collect();
+        let handler_name = |addr: usize| -> Option<&str> {
+            if let Some(name) = names.get(&addr) {
+                return Some(name);
+            }
+
+            // jmp [rip + rel32]
+            let bytes = read(addr)?;
+            if bytes.get(..2)? != [0xff, 0x25] {
+                return None;
+            }
+            let rel32 = i32::from_le_bytes(bytes.get(2..6)?.try_into().ok()?);
+            let slot = (addr + 6).wrapping_add(rel32 as isize as usize);
+            names.get(&slot).copied()
+        };
+
+        let mut datastructures = Vec::new();
+        let mut visited = HashSet::new();
+        let mut addr = start;
+        while addr + size_of::<ExceptionDirectoryEntry>() <= end {
+            let bytes = match read(addr).and_then(|bytes| bytes.get(..12)) {
+                Some(bytes) => bytes,
+                None => break,
+            };
+
+            let entry = ExceptionDirectoryEntry {
+                begin_addr: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+                end_addr: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+                unwind_info: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            };
+
+            let mut datastructure = entry.to_fields(addr);
+            let function = base + entry.begin_addr as usize;
+            datastructure.references.push((0, Reference::Address(function)));
+            datastructures.push(Addressed {
+                addr,
+                item: datastructure,
+            });
+
+            // Follow any chained unwind info, which might not have an entry of it's own.
+            let mut next = Some((entry.begin_addr, entry.unwind_info));
+            while let Some((function, info)) = next.take() {
+                // Bit 0 marks an indirect entry on some older binaries.
+                let info = info & !1;
+                if !visited.insert(info) {
+                    break;
+                }
+
+                let function = base + function as usize;
+                let info = base + info as usize;
+                if let Some(unwind) = crate::unwind::parse(info, function, base, read, handler_name) {
+                    datastructures.extend(unwind.datastructures);
+                    next = unwind.chained;
+                }
+            }
+
+            addr += size_of::<ExceptionDirectoryEntry>();
+        }
+
+        crate::carve_section(&mut self.sections, ".pdata", SectionKind::Structured, start, end);
+        self.push_datastructures(datastructures);
+    }
+
+    /// Add datastructures, carving the ranges they cover out of any plain sections they're in.
+    ///
+    /// Most directories are scattered throughout `.rdata` instead of having a section of their
+    /// own, so only the bytes they span are made structured.
+    fn push_datastructures(&mut self, mut datastructures: Vec<Addressed<Datastructure>>) {
+        datastructures.sort_unstable_by_key(|datastructure| datastructure.addr);
+
+        // Datastructures that directly follow one another share a range.
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for datastructure in datastructures.iter() {
+            let start = datastructure.addr;
+            let end = start + std::cmp::max(datastructure.item.size, 1);
+            match ranges.last_mut() {
+                Some((_, last)) if start <= *last => *last = std::cmp::max(*last, end),
+                _ => ranges.push((start, end)),
+            }
+        }
+
+        for (start, end) in ranges {
+            let name = match self.sections.iter().find(|s| s.start <= start && end <= s.end) {
+                Some(section) if section.kind == SectionKind::Raw => section.name.clone(),
+                _ => continue,
+            };
+
+            crate::carve_section(&mut self.sections, &name, SectionKind::Structured, start, end);
+        }
+
+        self.datastructures.extend(AddressMap { mapping: datastructures });
+    }
+
+    pub fn parse_resources(&mut self) {
+        let dir = match self.obj.data_directory(pe::IMAGE_DIRECTORY_ENTRY_RESOURCE) {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let start = self.va(dir.virtual_address.get(LE));
+        let end = start + dir.size.get(LE) as usize;
+        let base = self.obj.relative_address_base() as usize;
+
+        let datastructures = match read_at(&self.sections, start) {
+            Some(bytes) => {
+                let read = |addr: usize| read_at(&self.sections, addr);
+                crate::resource::parse(bytes, start, base, read)
+            }
+            None => return,
+        };
+
+        crate::carve_section(&mut self.sections, ".rsrc", SectionKind::Structured, start, end);
+        self.push_datastructures(datastructures);
+    }
+
+    /// Parse the TLS directory, adding it's callbacks as symbols.
+    ///
+    /// Callbacks run before the entrypoint, which makes them a popular place to hide code.
+    pub fn parse_tls(&mut self) {
+        let dir = match self.obj.data_directory(pe::IMAGE_DIRECTORY_ENTRY_TLS) {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let addr = self.va(dir.virtual_address.get(LE));
+        let fields = [
+            ("start_address_of_raw_data", Field::Va),
+            ("end_address_of_raw_data", Field::Va),
+            ("address_of_index", Field::Va),
+            ("address_of_call_backs", Field::Va),
+            ("size_of_zero_fill", Field::U32),
+            ("characteristics", Field::U32),
+        ];
+
+        let is_64 = self.obj.is_64();
+        let (ident, ptr_size) = if is_64 {
+            ("IMAGE_TLS_DIRECTORY64", 8)
+        } else {
+            ("IMAGE_TLS_DIRECTORY32", 4)
+        };
+
+        let bytes = read_at(&self.sections, addr).unwrap_or(&[]);
+        let (datastructure, values) = match read_fields(bytes, addr, ident, &fields, is_64, usize::MAX) {
+            Some(parsed) => parsed,
+            None => return,
+        };
+
+        let mut datastructures = vec![Addressed { addr, item: datastructure }];
+
+        // Null terminated array of callbacks.
+        let mut callback_addr = values[3] as usize;
+        let callbacks = read_at(&self.sections, callback_addr).unwrap_or(&[]);
+        for (idx, callback) in callbacks.chunks_exact(ptr_size).enumerate() {
+            let callback = if is_64 {
+                u64::from_le_bytes(callback.try_into().unwrap())
+            } else {
+                u32::from_le_bytes(callback.try_into().unwrap()) as u64
+            };
+
+            if callback == 0 {
+                break;
+            }
+
+            let name: &'static str = Box::leak(format!("tls_callback_{idx}").into_boxed_str());
+            self.syms.push(Addressed {
+                addr: callback as usize,
+                item: RawSymbol {
+                    name,
+                    module: None,
+                    ordinal: None,
+                },
+            });
+
+            datastructures.push(Addressed {
+                addr: callback_addr,
+                item: Datastructure {
+                    ident: "TlsCallback",
+                    size: ptr_size,
+                    fields: vec![(
+                        callback_addr,
+                        "callback",
+                        if is_64 { "u64" } else { "u32" },
+                        format!("{callback:#x}"),
+                    )],
+                    references: vec![(0, Reference::Address(callback as usize))],
+                },
+            });
+
+            callback_addr += ptr_size;
+        }
+
+        self.push_datastructures(datastructures);
+    }
+
+    /// Parse `IMAGE_LOAD_CONFIG_DIRECTORY` and the control flow guard tables it points to.
+    pub fn parse_load_config(&mut self) {
+        let dir = match self.obj.data_directory(pe::IMAGE_DIRECTORY_ENTRY_LOAD_CONFIG) {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let addr = self.va(dir.virtual_address.get(LE));
+        let is_64 = self.obj.is_64();
+
+        // 32-bit binaries have the process heap flags before the affinity mask.
+        let (affinity, heap_flags) = (
+            ("process_affinity_mask", Field::Ptr),
+            ("process_heap_flags", Field::U32),
+        );
+        let mut fields = LOAD_CONFIG_FIELDS.to_vec();
+        let idx = fields.iter().position(|(name, _)| *name == "virtual_memory_threshold").unwrap();
+        if is_64 {
+            fields.insert(idx + 1, heap_flags);
+            fields.insert(idx + 1, affinity);
+        } else {
+            fields.insert(idx + 1, affinity);
+            fields.insert(idx + 1, heap_flags);
+        }
+
+        let ident = if is_64 {
+            "IMAGE_LOAD_CONFIG_DIRECTORY64"
+        } else {
+            "IMAGE_LOAD_CONFIG_DIRECTORY32"
+        };
+
+        // The directory grew over time, it's first field says how much of it is present.
+        let bytes = read_at(&self.sections, addr).unwrap_or(&[]);
+        let size = match bytes.get(..4) {
+            Some(size) => u32::from_le_bytes(size.try_into().unwrap()) as usize,
+            None => return,
+        };
+
+        let (mut datastructure, values) = match read_fields(bytes, addr, ident, &fields, is_64, size) {
+            Some(parsed) => parsed,
+            None => return,
+        };
+
+        let value = |name: &str| -> Option<u64> {
+            let idx = fields.iter().position(|(field, _)| *field == name)?;
+            values.get(idx).copied()
+        };
+
+        let guard_flags = value("guard_flags").unwrap_or(0) as u32;
+        if let Some(idx) = datastructure.fields.iter().position(|f| f.1 == "guard_flags") {
+            for (flag, name) in GUARD_FLAGS {
+                if guard_flags & flag != 0 {
+                    datastructure.fields[idx].3 += " | ";
+                    datastructure.fields[idx].3 += name;
+                }
+            }
+        }
+
+        let mut datastructures = vec![Addressed { addr, item: datastructure }];
+
+        // Each entry of a guard table is followed by this many bytes of metadata.
+        let stride = (guard_flags >> 28) as usize;
+        for (ident, table, count) in [
+            ("GuardCFFunction", "guard_cf_function_table", "guard_cf_function_count"),
+            ("GuardLongJumpTarget", "guard_long_jump_target_table", "guard_long_jump_target_count"),
+            ("GuardEHContinuation", "guard_eh_continuation_table", "guard_eh_continuation_count"),
+            ("SEHandler", "se_handler_table", "se_handler_count"),
+        ] {
+            let (table, count) = match (value(table), value(count)) {
+                (Some(table), Some(count)) if table != 0 => (table as usize, count as usize),
+                _ => continue,
+            };
+
+            // Safe exception handlers don't have any metadata.
+            let stride = if ident == "SEHandler" { 0 } else { stride };
+            let bytes = read_at(&self.sections, table).unwrap_or(&[]);
+            for (idx, entry) in bytes.chunks_exact(4 + stride).take(count).enumerate() {
+                let rva = u32::from_le_bytes(entry[..4].try_into().unwrap());
+                let entry_addr = table + idx * (4 + stride);
+
+                let mut fields = vec![(entry_addr, "rva", "u32", format!("{rva:#x}"))];
+                if stride != 0 {
+                    fields.push((entry_addr + 4, "flags", "u8", format!("{:#x}", entry[4])));
+                }
+
+                datastructures.push(Addressed {
+                    addr: entry_addr,
+                    item: Datastructure {
+                        ident,
+                        size: 4 + stride,
+                        fields,
+                        references: vec![(0, Reference::Address(self.va(rva)))],
+                    },
+                });
+            }
+        }
+
+        self.push_datastructures(datastructures);
+    }
+
+    /// Name classes, vftables and virtual methods using MSVC runtime type information, or the
+    /// Itanium ABI's for MinGW binaries.
+    pub fn parse_rtti(&mut self) {
+        // Imports are bound to their slot in the import address table.
+        let binds = self
+            .syms
+            .iter()
+            .filter(|sym| sym.item.module.is_some())
+            .map(|sym| (sym.addr as u64, sym.item.name))
+            .collect();
+
+        let image = Image {
+            sections: &self.sections,
+            endian: Endianness::Little,
+            is_64: self.obj.is_64(),
+            base_addr: self.obj.relative_address_base(),
+            chained: false,
+            relocs: HashMap::new(),
+        };
+
+        let msvc = rtti::msvc(&image, &self.syms);
+        let itanium = rtti::itanium(&image, &binds, &self.syms, "");
+
+        self.syms.extend(msvc.syms);
+        self.syms.extend(itanium.syms);
+        self.classes = msvc.classes;
+        self.classes.extend(itanium.classes);
+    }
+
+    pub fn parse_relocations(&mut self) {
+        let dir = match self.obj.data_directory(pe::IMAGE_DIRECTORY_ENTRY_BASERELOC) {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let start = self.va(dir.virtual_address.get(LE));
+        let end = start + dir.size.get(LE) as usize;
+        let bytes = read_at(&self.sections, start).unwrap_or(&[]);
+        let bytes = &bytes[..std::cmp::min(bytes.len(), end - start)];
+
+        let mut datastructures = Vec::new();
+        let mut offset = 0;
+        while let Some(header) = bytes.get(offset..offset + 8) {
+            let page = u32::from_le_bytes(header[..4].try_into().unwrap());
+            let size = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+            if size < 8 {
+                break;
+            }
+
+            let addr = start + offset;
+            datastructures.push(Addressed {
+                addr,
+                item: Datastructure {
+                    ident: "IMAGE_BASE_RELOCATION",
+                    size: 8,
+                    fields: vec![
+                        (addr, "virtual_address", "u32", format!("{page:#x}")),
+                        (addr + 4, "size_of_block", "u32", format!("{size:#x}")),
+                    ],
+                    references: Vec::new(),
+                },
+            });
+
+            // Entries are shown in chunks so huge blocks don't end up as a single block.
+            let entries = bytes.get(offset + 8..offset + size).unwrap_or(&[]);
+            for (chunk_idx, chunk) in entries.chunks(RELOCATIONS_PER_BLOCK * 2).enumerate() {
+                let chunk_addr = addr + 8 + chunk_idx * RELOCATIONS_PER_BLOCK * 2;
+                let mut fields = Vec::new();
+                for (idx, entry) in chunk.chunks_exact(2).enumerate() {
+                    let entry = u16::from_le_bytes(entry.try_into().unwrap());
+                    let kind = relocation_name(entry >> 12);
+                    let target = self.va(page) + (entry & 0xfff) as usize;
+                    let value = match entry >> 12 {
+                        // Padding to align blocks to 32 bits.
+                        pe::IMAGE_REL_BASED_ABSOLUTE => kind.to_string(),
+                        _ => format!("{kind} {target:#x}"),
+                    };
+                    fields.push((chunk_addr + idx * 2, "entry", "u16", value));
+                }
+
+                if !fields.is_empty() {
+                    datastructures.push(Addressed {
+                        addr: chunk_addr,
+                        item: Datastructure {
+                            ident: "BaseRelocations",
+                            size: chunk.len(),
+                            fields,
+                            references: Vec::new(),
+                        },
+                    });
+                }
+            }
+
+            offset += size;
+        }
+
+        crate::carve_section(&mut self.sections, ".reloc", SectionKind::Structured, start, end);
+        self.push_datastructures(datastructures);
+    }
+
+    /// Decode the Rich header the Microsoft linker leaves in the DOS stub.
+    pub fn parse_rich_header(&mut self) {
+        if let Some(rich) = self.obj.rich_header_info() {
+            self.file_info.push(crate::rich::parse(self.obj.data(), &rich));
+        }
+    }
+
+    /// Decode any Authenticode signatures and check whether the image still matches them.
+    pub fn parse_certificates(&mut self) {
+        let dir = match self.obj.data_directory(pe::IMAGE_DIRECTORY_ENTRY_SECURITY) {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        // Unlike other directories, the certificate table isn't mapped so this is a file offset.
+        let start = dir.virtual_address.get(LE) as usize;
+        let end = start + dir.size.get(LE) as usize;
+
+        let optional_header = self.obj.dos_header().nt_headers_offset() as usize
+            + size_of::<u32>()
+            + size_of::<pe::ImageFileHeader>();
+        let data_directories = match self.obj.is_64() {
+            true => optional_header + 112,
+            false => optional_header + 96,
+        };
+
+        let exclusions = crate::authenticode::Exclusions {
+            checksum: optional_header + 64,
+            directory: data_directories
+                + pe::IMAGE_DIRECTORY_ENTRY_SECURITY * size_of::<pe::ImageDataDirectory>(),
+            table: start..end,
+        };
+
+        self.file_info.extend(crate::authenticode::parse(self.obj.data(), exclusions));
+    }
+
     pub fn parse_imports(&mut self) -> Result<(), object::Error> {
         let import_table = match self.obj.import_table()? {
             Some(table) => table,
@@ -89,8 +820,11 @@ impl<'data, Pe: ImageNtHeaders> PeDebugInfo<'data, Pe> {
                         std::str::from_utf8(module).ok().and_then(|x| x.strip_suffix(".dll"));
                     self.syms.push(Addressed {
                         addr: addr as usize,
-                        item: RawSymbol { name, module },
+                        item: RawSymbol { name, module, ordinal: None },
                     });
+                } else {
+                    let addr = self.va(func_rva);
+                    self.push_ordinal_import(addr, module, func.ordinal());
                 }
 
                 // skip over an entry
@@ -101,6 +835,80 @@ impl<'data, Pe: ImageNtHeaders> PeDebugInfo<'data, Pe> {
         Ok(())
     }
 
+    pub fn parse_delay_load_imports(&mut self) -> Result<(), object::Error> {
+        let data_dirs = self.obj.data_directories();
+        let import_table =
+            match data_dirs.delay_load_import_table(self.obj.data(), &self.obj.section_table())? {
+                Some(table) => table,
+                None => return Ok(()),
+            };
+
+        let mut import_descs = import_table.descriptors()?;
+        while let Some(import_desc) = import_descs.next()? {
+            // Descriptors made by Visual C++ 6 contain virtual addresses instead of RVA's.
+            let image_base = self.obj.relative_address_base();
+            let is_rva_based = import_desc.attributes.get(LE) & DLATTR_RVA != 0;
+            let base = if !is_rva_based && image_base <= u32::MAX as u64 {
+                image_base as u32
+            } else {
+                0
+            };
+
+            let module = import_table.name(import_desc.dll_name_rva.get(LE).wrapping_sub(base))?;
+            let name_table = import_desc.import_name_table_rva.get(LE).wrapping_sub(base);
+            let mut func_rva = import_desc.import_address_table_rva.get(LE).wrapping_sub(base);
+
+            let mut import_name_table = import_table.thunks(name_table)?;
+            while let Some(func) = import_name_table.next::<Pe>()? {
+                let addr = self.va(func_rva);
+                func_rva += size_of::<Pe::ImageThunkData>() as u32;
+
+                if func.is_ordinal() {
+                    self.push_ordinal_import(addr, module, func.ordinal());
+                    continue;
+                }
+
+                let name = match import_table.hint_name(func.address().wrapping_sub(base)) {
+                    Ok((_, name)) => name,
+                    Err(..) => continue,
+                };
+
+                if let Ok(name) = std::str::from_utf8(name) {
+                    let module =
+                        std::str::from_utf8(module).ok().and_then(|x| x.strip_suffix(".dll"));
+                    self.syms.push(Addressed {
+                        addr,
+                        item: RawSymbol { name, module, ordinal: None },
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Import by ordinal, named after our table of well known ordinals or `#ordinal`.
+    fn push_ordinal_import(&mut self, addr: usize, module: &'data [u8], ordinal: u16) {
+        let module = match std::str::from_utf8(module) {
+            Ok(module) => module,
+            Err(..) => return,
+        };
+
+        let name = match crate::ordinals::lookup(module, ordinal) {
+            Some(name) => name,
+            None => Box::leak(format!("#{ordinal}").into_boxed_str()),
+        };
+
+        self.syms.push(Addressed {
+            addr,
+            item: RawSymbol {
+                name,
+                module: Some(module.strip_suffix(".dll").unwrap_or(module)),
+                ordinal: Some(ordinal as u32),
+            },
+        });
+    }
+
     pub fn parse_symbols(&mut self) {
         self.syms.extend(crate::parse_symbol_table(self.obj));
         self.syms.push(Addressed {
@@ -108,11 +916,88 @@ impl<'data, Pe: ImageNtHeaders> PeDebugInfo<'data, Pe> {
             item: RawSymbol {
                 name: "entry",
                 module: None,
+                ordinal: None,
             },
         });
     }
 }
 
+/// Bytes starting at `addr` up until the end of it's section.
+fn read_at(sections: &[Section], addr: usize) -> Option<&[u8]> {
+    let section = sections.iter().find(|s| s.start <= addr && addr < s.end)?;
+    section.bytes().get(addr - section.start..)
+}
+
+/// Read a directory made up of `fields`, stopping after `limit` bytes.
+///
+/// Returns the directory and the value of every field that was read.
+fn read_fields(
+    bytes: &[u8],
+    addr: usize,
+    ident: &'static str,
+    fields: &[(&'static str, Field)],
+    is_64: bool,
+    limit: usize,
+) -> Option<(Datastructure, Vec<u64>)> {
+    let mut datastructure = Datastructure {
+        ident,
+        size: 0,
+        fields: Vec::new(),
+        references: Vec::new(),
+    };
+    let mut values = Vec::new();
+
+    for (name, field) in fields {
+        let (size, tipe) = match field {
+            Field::U16 => (2, "u16"),
+            Field::U32 => (4, "u32"),
+            Field::Ptr | Field::Va if is_64 => (8, "u64"),
+            Field::Ptr | Field::Va => (4, "u32"),
+        };
+
+        let offset = datastructure.size;
+        if offset + size > limit {
+            break;
+        }
+
+        let value = match bytes.get(offset..offset + size) {
+            Some(&[a, b]) => u16::from_le_bytes([a, b]) as u64,
+            Some(&[a, b, c, d]) => u32::from_le_bytes([a, b, c, d]) as u64,
+            Some(value) => u64::from_le_bytes(value.try_into().ok()?),
+            None => break,
+        };
+
+        if let Field::Va = field {
+            if value != 0 {
+                let idx = datastructure.fields.len();
+                datastructure.references.push((idx, Reference::Address(value as usize)));
+            }
+        }
+
+        datastructure.fields.push((addr + offset, name, tipe, format!("{value:#x}")));
+        datastructure.size += size;
+        values.push(value);
+    }
+
+    if values.is_empty() {
+        return None;
+    }
+
+    Some((datastructure, values))
+}
+
+fn relocation_name(kind: u16) -> &'static str {
+    match kind {
+        pe::IMAGE_REL_BASED_ABSOLUTE => "IMAGE_REL_BASED_ABSOLUTE",
+        pe::IMAGE_REL_BASED_HIGH => "IMAGE_REL_BASED_HIGH",
+        pe::IMAGE_REL_BASED_LOW => "IMAGE_REL_BASED_LOW",
+        pe::IMAGE_REL_BASED_HIGHLOW => "IMAGE_REL_BASED_HIGHLOW",
+        pe::IMAGE_REL_BASED_HIGHADJ => "IMAGE_REL_BASED_HIGHADJ",
+        pe::IMAGE_REL_BASED_DIR64 => "IMAGE_REL_BASED_DIR64",
+        _ => "IMAGE_REL_BASED_MACHINE_SPECIFIC",
+    }
+}
+
 /// Common ELF dwarf section names I've found so far.
 const DWARF_SECTIONS: [&str; 20] = [
     ".debug_abbrev",
@@ -180,3 +1065,391 @@ fn parse_sections<'data, Pe: ImageNtHeaders>(obj: &'data PeFile<'data, Pe>) -> V
 
     sections
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::PeBuilder;
+    use object::read::pe::{PeFile32, PeFile64};
+
+    fn datastructure<'a, Pe: ImageNtHeaders>(
+        info: &'a PeDebugInfo<'_, Pe>,
+        addr: usize,
+    ) -> &'a Datastructure {
+        let idx = info.datastructures.search(addr).unwrap();
+        &info.datastructures[idx].item
+    }
+
+    fn symbol<'a, Pe: ImageNtHeaders>(
+        info: &'a PeDebugInfo<'_, Pe>,
+        name: &str,
+    ) -> &'a Addressed<RawSymbol<'a>> {
+        info.syms.iter().find(|sym| sym.item.name == name).unwrap()
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    fn u64s(values: &[u64]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    fn values(datastructure: &Datastructure) -> Vec<(&str, &str)> {
+        datastructure.fields.iter().map(|field| (field.1, &field.3[..])).collect()
+    }
+
+    /// Offset from `addr`, type and value of a field.
+    fn field<'a>(
+        datastructure: &'a Datastructure,
+        addr: usize,
+        name: &str,
+    ) -> (usize, &'a str, &'a str) {
+        let field = datastructure.fields.iter().find(|field| field.1 == name).unwrap();
+        (field.0 - addr, field.2, &field.3[..])
+    }
+
+    /// A load config directory of `size` bytes with `values` written at their offset.
+    fn load_config(size: usize, values: &[(usize, &[u8])]) -> Vec<u8> {
+        let mut bytes = vec![0; size];
+        bytes[..4].copy_from_slice(&(size as u32).to_le_bytes());
+        for (offset, value) in values {
+            bytes[*offset..*offset + value.len()].copy_from_slice(value);
+        }
+        bytes
+    }
+
+    #[test]
+    fn exports() {
+        let mut edata = u32s(&[
+            0,      // characteristics
+            0,      // time_date_stamp
+            0,      // major_version, minor_version
+            0x2040, // name
+            1,      // base
+            3,      // number_of_functions
+            2,      // number_of_names
+            0x2028, // address_of_functions
+            0x2034, // address_of_names
+            0x203c, // address_of_name_ordinals
+        ]);
+        // Functions by ordinal, the third being forwarded.
+        edata.extend(u32s(&[0x1000, 0x1010, 0x2053]));
+        // Names sorted alphabetically, with their index into the functions.
+        edata.extend(u32s(&[0x2049, 0x204f]));
+        edata.extend([2, 0, 0, 0]);
+        edata.extend(b"test.dll\0Alloc\0Run\0NTDLL.RtlAllocateHeap\0");
+
+        let mut builder = PeBuilder::new(true);
+        builder.section(".text", pe::IMAGE_SCN_CNT_CODE, vec![0xc3; 0x20]);
+        let rdata = builder.section(".rdata", pe::IMAGE_SCN_CNT_INITIALIZED_DATA, edata.clone());
+        builder.directory(pe::IMAGE_DIRECTORY_ENTRY_EXPORT, rdata, edata.len() as u32);
+        let bytes = builder.build();
+        let obj = PeFile64::parse(&*bytes).unwrap();
+        let info = PeDebugInfo::parse(&obj).unwrap();
+        let va = |rva: usize| 0x140000000 + rva;
+
+        let run = symbol(&info, "Run");
+        assert_eq!(
+            (run.addr, run.item.ordinal, run.item.module),
+            (va(0x1000), Some(1), None)
+        );
+
+        // Exported by ordinal only.
+        let unnamed = symbol(&info, "#2");
+        assert_eq!((unnamed.addr, unnamed.item.ordinal), (va(0x1010), Some(2)));
+
+        let alloc = symbol(&info, "Alloc");
+        assert_eq!(alloc.addr, va(0x2053));
+        assert_eq!(alloc.item.ordinal, Some(3));
+        assert_eq!(alloc.item.module, Some("NTDLL.RtlAllocateHeap"));
+
+        let directory = datastructure(&info, va(0x2000));
+        assert_eq!(directory.ident, "ImageExportDirectory");
+        assert_eq!(directory.fields[4].3, "0x2040 \"test.dll\"");
+
+        let address = |idx: usize| &datastructure(&info, va(0x2028 + idx * 4)).fields[0].3;
+        assert_eq!(address(0), "0x1000 (#1)");
+        assert_eq!(address(2), "0x2053 (#3) -> NTDLL.RtlAllocateHeap");
+
+        // Names and ordinals pair up by index.
+        assert_eq!(
+            datastructure(&info, va(0x2034)).fields[0].3,
+            "0x2049 \"Alloc\""
+        );
+        assert_eq!(
+            datastructure(&info, va(0x2038)).fields[0].3,
+            "0x204f \"Run\""
+        );
+        assert_eq!(datastructure(&info, va(0x203c)).fields[0].3, "0x2 (#3)");
+        assert_eq!(datastructure(&info, va(0x203e)).fields[0].3, "0x0 (#1)");
+
+        // The directory is the whole section, so it keeps it's name.
+        let edata = info.sections.iter().find(|section| section.start == va(0x2000)).unwrap();
+        assert_eq!((edata.name.as_str(), edata.end), (".rdata", va(0x2069)));
+        assert_eq!(edata.kind, SectionKind::Structured);
+    }
+
+    #[test]
+    fn tls_callbacks() {
+        let va = |rva: u64| 0x140000000 + rva;
+        let mut tls = u64s(&[va(0x2100), va(0x2108), va(0x2110), va(0x2028)]);
+        tls.extend(u32s(&[0, 0x00100000]));
+        tls.extend(u64s(&[va(0x1000), va(0x1010), 0]));
+
+        let mut builder = PeBuilder::new(true);
+        builder.section(".text", pe::IMAGE_SCN_CNT_CODE, vec![0xc3; 0x20]);
+        let rdata = builder.section(".rdata", pe::IMAGE_SCN_CNT_INITIALIZED_DATA, tls);
+        builder.directory(pe::IMAGE_DIRECTORY_ENTRY_TLS, rdata, 0x28);
+        let bytes = builder.build();
+        let obj = PeFile64::parse(&*bytes).unwrap();
+        let info = PeDebugInfo::parse(&obj).unwrap();
+        let va = |rva: usize| 0x140000000 + rva;
+
+        assert_eq!(symbol(&info, "tls_callback_0").addr, va(0x1000));
+        assert_eq!(symbol(&info, "tls_callback_1").addr, va(0x1010));
+        assert!(info.syms.iter().all(|sym| sym.item.name != "tls_callback_2"));
+
+        let directory = datastructure(&info, va(0x2000));
+        assert_eq!(
+            (directory.ident, directory.size),
+            ("IMAGE_TLS_DIRECTORY64", 0x28)
+        );
+        assert_eq!(
+            field(directory, va(0x2000), "address_of_call_backs"),
+            (0x18, "u64", "0x140002028")
+        );
+        assert_eq!(
+            field(directory, va(0x2000), "characteristics"),
+            (0x24, "u32", "0x100000")
+        );
+        assert_eq!(directory.references.len(), 4);
+
+        let callback = datastructure(&info, va(0x2030));
+        assert_eq!(callback.ident, "TlsCallback");
+        assert_eq!(callback.fields[0].3, "0x140001010");
+        assert!(
+            matches!(callback.references[..], [(0, Reference::Address(addr))] if addr == va(0x1010))
+        );
+    }
+
+    #[test]
+    fn load_config_64() {
+        let va = |rva: u64| 0x140000000 + rva;
+        let flags = pe::IMAGE_GUARD_CF_INSTRUMENTED | pe::IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT;
+        let mut rdata = load_config(
+            148,
+            &[
+                (56, &0x1000u64.to_le_bytes()),
+                (64, &0x3u64.to_le_bytes()),
+                (72, &0x4u32.to_le_bytes()),
+                (128, &va(0x2100).to_le_bytes()),
+                (136, &3u64.to_le_bytes()),
+                // Each entry has a byte of metadata.
+                (144, &(flags | 1 << 28).to_le_bytes()),
+            ],
+        );
+        rdata.resize(0x100, 0);
+        rdata.extend([
+            0x00, 0x10, 0, 0, 0x1, 0x10, 0x10, 0, 0, 0x2, 0x20, 0x10, 0, 0, 0x0,
+        ]);
+
+        let mut builder = PeBuilder::new(true);
+        builder.section(".text", pe::IMAGE_SCN_CNT_CODE, vec![0xc3; 0x30]);
+        let rva = builder.section(".rdata", pe::IMAGE_SCN_CNT_INITIALIZED_DATA, rdata);
+        builder.directory(pe::IMAGE_DIRECTORY_ENTRY_LOAD_CONFIG, rva, 148);
+        let bytes = builder.build();
+        let obj = PeFile64::parse(&*bytes).unwrap();
+        let info = PeDebugInfo::parse(&obj).unwrap();
+        let va = |rva: usize| 0x140000000 + rva;
+
+        let directory = datastructure(&info, va(0x2000));
+        assert_eq!(
+            (directory.ident, directory.size),
+            ("IMAGE_LOAD_CONFIG_DIRECTORY64", 148)
+        );
+        let field = |name: &str| field(directory, va(0x2000), name);
+        assert_eq!(field("virtual_memory_threshold"), (56, "u64", "0x1000"));
+        assert_eq!(field("process_affinity_mask"), (64, "u64", "0x3"));
+        assert_eq!(field("process_heap_flags"), (72, "u32", "0x4"));
+        assert_eq!(field("csd_version"), (76, "u16", "0x0"));
+        assert_eq!(
+            field("guard_cf_function_table"),
+            (128, "u64", "0x140002100")
+        );
+        assert_eq!(
+            field("guard_flags"),
+            (
+                144,
+                "u32",
+                "0x10000500 | IMAGE_GUARD_CF_INSTRUMENTED | IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT"
+            )
+        );
+
+        // Fields past the size the directory claims to have aren't read.
+        assert_eq!(directory.fields.last().unwrap().1, "guard_flags");
+
+        let entries: Vec<_> = (0..3)
+            .map(|idx| datastructure(&info, va(0x2100 + idx * 5)))
+            .map(|entry| (entry.ident, entry.size, values(entry)))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (
+                    "GuardCFFunction",
+                    5,
+                    vec![("rva", "0x1000"), ("flags", "0x1")]
+                ),
+                (
+                    "GuardCFFunction",
+                    5,
+                    vec![("rva", "0x1010"), ("flags", "0x2")]
+                ),
+                (
+                    "GuardCFFunction",
+                    5,
+                    vec![("rva", "0x1020"), ("flags", "0x0")]
+                ),
+            ]
+        );
+        let entry = datastructure(&info, va(0x2105));
+        assert!(
+            matches!(entry.references[..], [(0, Reference::Address(addr))] if addr == va(0x1010))
+        );
+    }
+
+    #[test]
+    fn load_config_32() {
+        let va = |rva: u32| 0x400000 + rva;
+        let flags = pe::IMAGE_GUARD_CF_INSTRUMENTED | pe::IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT;
+        let mut rdata = load_config(
+            92,
+            &[
+                (40, &0x1000u32.to_le_bytes()),
+                (44, &0x4u32.to_le_bytes()),
+                (48, &0x3u32.to_le_bytes()),
+                (64, &va(0x2100).to_le_bytes()),
+                (68, &2u32.to_le_bytes()),
+                (80, &va(0x2110).to_le_bytes()),
+                (84, &2u32.to_le_bytes()),
+                (88, &flags.to_le_bytes()),
+            ],
+        );
+        rdata.resize(0x100, 0);
+        rdata.extend(u32s(&[0x1000, 0x1010, 0, 0, 0x1020, 0x1030]));
+
+        let mut builder = PeBuilder::new(false);
+        builder.section(".text", pe::IMAGE_SCN_CNT_CODE, vec![0xc3; 0x40]);
+        let rva = builder.section(".rdata", pe::IMAGE_SCN_CNT_INITIALIZED_DATA, rdata);
+        builder.directory(pe::IMAGE_DIRECTORY_ENTRY_LOAD_CONFIG, rva, 92);
+        let bytes = builder.build();
+        let obj = PeFile32::parse(&*bytes).unwrap();
+        let info = PeDebugInfo::parse(&obj).unwrap();
+        let va = |rva: usize| 0x400000 + rva;
+
+        let directory = datastructure(&info, va(0x2000));
+        assert_eq!(
+            (directory.ident, directory.size),
+            ("IMAGE_LOAD_CONFIG_DIRECTORY32", 92)
+        );
+        let field = |name: &str| field(directory, va(0x2000), name);
+
+        // The heap flags come before the affinity mask in 32-bit binaries.
+        assert_eq!(field("virtual_memory_threshold"), (40, "u32", "0x1000"));
+        assert_eq!(field("process_heap_flags"), (44, "u32", "0x4"));
+        assert_eq!(field("process_affinity_mask"), (48, "u32", "0x3"));
+        assert_eq!(field("csd_version"), (52, "u16", "0x0"));
+        assert_eq!(field("se_handler_table"), (64, "u32", "0x402100"));
+        assert_eq!(
+            field("guard_flags").2,
+            "0x500 | IMAGE_GUARD_CF_INSTRUMENTED | IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT"
+        );
+
+        // Without a stride in the guard flags entries are just an RVA.
+        let entry = |addr: usize| {
+            let entry = datastructure(&info, va(addr));
+            (entry.ident, entry.size, values(entry))
+        };
+        assert_eq!(entry(0x2100), ("SEHandler", 4, vec![("rva", "0x1000")]));
+        assert_eq!(entry(0x2104), ("SEHandler", 4, vec![("rva", "0x1010")]));
+        assert_eq!(
+            entry(0x2110),
+            ("GuardCFFunction", 4, vec![("rva", "0x1020")])
+        );
+        assert_eq!(
+            entry(0x2114),
+            ("GuardCFFunction", 4, vec![("rva", "0x1030")])
+        );
+        assert!(info.datastructures.iter().all(|ds| ds.addr != va(0x2108)));
+    }
+
+    #[test]
+    fn base_relocations() {
+        let entry = |kind: u16, offset: u16| kind << 12 | offset;
+        let mut reloc = u32s(&[0x1000, 16]);
+        for value in [
+            entry(pe::IMAGE_REL_BASED_DIR64, 0x10),
+            entry(pe::IMAGE_REL_BASED_HIGHLOW, 0x20),
+            entry(pe::IMAGE_REL_BASED_DIR64, 0x18),
+            entry(pe::IMAGE_REL_BASED_ABSOLUTE, 0),
+        ] {
+            reloc.extend(value.to_le_bytes());
+        }
+
+        // A block that's split up when shown.
+        reloc.extend(u32s(&[0x2000, 8 + 18 * 2]));
+        for idx in 0..18 {
+            reloc.extend(entry(pe::IMAGE_REL_BASED_DIR64, idx * 8).to_le_bytes());
+        }
+
+        // An empty block ends the directory.
+        reloc.extend(u32s(&[0x3000, 0]));
+        reloc.extend(u32s(&[0x4000, 10, 0xa000]));
+
+        let mut builder = PeBuilder::new(true);
+        builder.section(".text", pe::IMAGE_SCN_CNT_CODE, vec![0xc3; 0x20]);
+        builder.section(".data", pe::IMAGE_SCN_CNT_INITIALIZED_DATA, vec![0; 0x100]);
+        let rva = builder.section(".reloc", pe::IMAGE_SCN_CNT_INITIALIZED_DATA, reloc.clone());
+        builder.directory(pe::IMAGE_DIRECTORY_ENTRY_BASERELOC, rva, reloc.len() as u32);
+        let bytes = builder.build();
+        let obj = PeFile64::parse(&*bytes).unwrap();
+        let info = PeDebugInfo::parse(&obj).unwrap();
+        let va = |rva: usize| 0x140000000 + rva;
+
+        let header = datastructure(&info, va(0x3000));
+        assert_eq!(header.ident, "IMAGE_BASE_RELOCATION");
+        assert_eq!(
+            values(header),
+            [("virtual_address", "0x1000"), ("size_of_block", "0x10")]
+        );
+
+        let entries = datastructure(&info, va(0x3008));
+        assert_eq!((entries.ident, entries.size), ("BaseRelocations", 8));
+        assert_eq!(
+            values(entries),
+            [
+                ("entry", "IMAGE_REL_BASED_DIR64 0x140001010"),
+                ("entry", "IMAGE_REL_BASED_HIGHLOW 0x140001020"),
+                ("entry", "IMAGE_REL_BASED_DIR64 0x140001018"),
+                ("entry", "IMAGE_REL_BASED_ABSOLUTE"),
+            ]
+        );
+
+        let second = datastructure(&info, va(0x3010));
+        assert_eq!(values(second)[1], ("size_of_block", "0x2c"));
+        let chunk = datastructure(&info, va(0x3018));
+        assert_eq!((chunk.size, chunk.fields.len()), (32, 16));
+        let rest = datastructure(&info, va(0x3038));
+        assert_eq!(rest.fields[1].3, "IMAGE_REL_BASED_DIR64 0x140002088");
+        assert_eq!((rest.size, rest.fields.len()), (4, 2));
+
+        let headers =
+            info.datastructures.iter().filter(|ds| ds.item.ident == "IMAGE_BASE_RELOCATION");
+        assert_eq!(headers.count(), 2);
+
+        let section = info.sections.iter().find(|section| section.start == va(0x3000)).unwrap();
+        assert_eq!(section.name, ".reloc");
+        assert_eq!(section.kind, SectionKind::Structured);
+    }
+}
@@ -0,0 +1,405 @@
+//! Decoding of ELF procedure linkage table stubs.
+//!
+//! A PLT stub is a couple of instructions that load an address from a GOT slot and jump to it.
+//! The layouts are fixed by the psABI of each architecture, so instead of pulling in a full
+//! decoder we match on the handful of encodings linkers emit and compute the slot address.
+
+use object::elf;
+
+/// What a PLT stub jumps through.
+pub(crate) enum StubTarget {
+    /// Address of the GOT slot the stub loads it's destination from.
+    Slot(u64),
+    /// Index of the dynamic symbol a MIPS lazy-binding stub resolves.
+    Symbol(usize),
+}
+
+/// A single decoded stub.
+pub(crate) struct Stub {
+    /// Number of bytes the stub occupies.
+    pub len: usize,
+    pub target: StubTarget,
+}
+
+/// Decode a stub starting at `bytes`, which is located at `addr`.
+///
+/// `got` is the value of `DT_PLTGOT`, required for position independent i386 stubs which
+/// address their slots relative to `ebx`.
+pub(crate) fn decode(
+    e_machine: u16,
+    bytes: &[u8],
+    addr: u64,
+    got: Option<u64>,
+    little_endian: bool,
+) -> Option<Stub> {
+    let words = Words { bytes, little_endian };
+
+    match e_machine {
+        elf::EM_X86_64 => decode_x86_64(bytes, addr),
+        elf::EM_386 => decode_i386(bytes, got),
+        elf::EM_AARCH64 => decode_aarch64(words, addr),
+        elf::EM_ARM => decode_arm(words, addr),
+        elf::EM_RISCV => decode_riscv(words, addr),
+        elf::EM_MIPS => decode_mips(words),
+        _ => None,
+    }
+}
+
+/// Number of bytes to advance when there isn't a stub at the current offset.
+pub(crate) fn stride(e_machine: u16, entsize: usize) -> usize {
+    match e_machine {
+        // Variable length instructions, so only entry boundaries are safe to decode.
+        elf::EM_X86_64 | elf::EM_386 if entsize != 0 => entsize,
+        elf::EM_X86_64 | elf::EM_386 => 16,
+        _ => 4,
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Words<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl Words<'_> {
+    fn get(&self, idx: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.bytes.get(idx * 4..idx * 4 + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+}
+
+fn read_i32(bytes: &[u8], offset: usize) -> Option<i32> {
+    let bytes: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+    Some(i32::from_le_bytes(bytes))
+}
+
+/// Skip an `endbr64` or `endbr32` and any `bnd` prefix.
+fn skip_x86_prefixes(bytes: &[u8]) -> usize {
+    let mut offset = 0;
+    if let Some([0xf3, 0x0f, 0x1e, 0xfa | 0xfb]) = bytes.get(..4) {
+        offset += 4;
+    }
+    if bytes.get(offset) == Some(&0xf2) {
+        offset += 1;
+    }
+    offset
+}
+
+/// `jmp [rip + rel32]`, optionally preceded by `endbr64` and `bnd`.
+fn decode_x86_64(bytes: &[u8], addr: u64) -> Option<Stub> {
+    let offset = skip_x86_prefixes(bytes);
+    if bytes.get(offset..offset + 2)? != [0xff, 0x25] {
+        return None;
+    }
+
+    let rel32 = read_i32(bytes, offset + 2)?;
+    let len = offset + 6;
+    let slot = (addr + len as u64).wrapping_add(rel32 as i64 as u64);
+
+    Some(Stub {
+        len,
+        target: StubTarget::Slot(slot),
+    })
+}
+
+/// `jmp [abs32]` or the position independent `jmp [ebx + disp32]`.
+fn decode_i386(bytes: &[u8], got: Option<u64>) -> Option<Stub> {
+    let offset = skip_x86_prefixes(bytes);
+    let slot = match bytes.get(offset..offset + 2)? {
+        [0xff, 0x25] => read_i32(bytes, offset + 2)? as u32 as u64,
+        [0xff, 0xa3] => (got? as u32).wrapping_add(read_i32(bytes, offset + 2)? as u32) as u64,
+        _ => return None,
+    };
+
+    Some(Stub {
+        len: offset + 6,
+        target: StubTarget::Slot(slot),
+    })
+}
+
+/// ```text
+/// adrp x16, page(slot)
+/// ldr  x17, [x16, pageoff(slot)]
+/// add  x16, x16, pageoff(slot)
+/// br   x17
+/// ```
+///
+/// Binaries with branch protection start every stub with a `bti c`.
+fn decode_aarch64(words: Words, addr: u64) -> Option<Stub> {
+    if words.get(0)? == 0xd503245f {
+        let words = Words {
+            bytes: &words.bytes[4..],
+            little_endian: words.little_endian,
+        };
+        let stub = decode_aarch64(words, addr + 4)?;
+        return Some(Stub {
+            len: stub.len + 4,
+            target: stub.target,
+        });
+    }
+
+    let adrp = words.get(0)?;
+    let ldr = words.get(1)?;
+
+    // adrp x16, imm
+    if adrp & 0x9f00001f != 0x90000010 {
+        return None;
+    }
+
+    // ldr x17, [x16, imm] or ldr w17, [x16, imm] for ILP32.
+    let pageoff = match ldr & 0xffc003ff {
+        0xf9400211 => ((ldr >> 10) & 0xfff) as u64 * 8,
+        0xb9400211 => ((ldr >> 10) & 0xfff) as u64 * 4,
+        _ => return None,
+    };
+
+    let immlo = (adrp >> 29) & 0b11;
+    let immhi = (adrp >> 5) & 0x7ffff;
+    let pages = (((immhi << 2 | immlo) << 11) as i32 >> 11) as i64;
+    let page = (addr & !0xfff).wrapping_add((pages << 12) as u64);
+
+    Some(Stub {
+        len: 16,
+        target: StubTarget::Slot(page + pageoff),
+    })
+}
+
+/// ```text
+/// add ip, pc, #imm
+/// add ip, ip, #imm    (repeated)
+/// ldr pc, [ip, #imm]!
+/// ```
+fn decode_arm(words: Words, addr: u64) -> Option<Stub> {
+    // Rotated 8-bit immediate of data processing instructions.
+    let imm = |insn: u32| (insn & 0xff).rotate_right(((insn >> 8) & 0xf) * 2) as u64;
+
+    let first = words.get(0)?;
+    if first & 0xfffff000 != 0xe28fc000 {
+        return None;
+    }
+
+    // Reading pc gives the address of the current instruction plus 8.
+    let mut slot = (addr + 8).wrapping_add(imm(first));
+    for idx in 1..4 {
+        let insn = words.get(idx)?;
+        match insn & 0xfffff000 {
+            0xe28cc000 => slot = slot.wrapping_add(imm(insn)),
+            0xe5bcf000 => {
+                return Some(Stub {
+                    len: (idx + 1) * 4,
+                    target: StubTarget::Slot((slot + (insn & 0xfff) as u64) & 0xffffffff),
+                })
+            }
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+/// ```text
+/// auipc t3, %pcrel_hi(slot)
+/// l[w|d] t3, %pcrel_lo(slot)(t3)
+/// jalr  t1, t3
+/// nop
+/// ```
+fn decode_riscv(words: Words, addr: u64) -> Option<Stub> {
+    let auipc = words.get(0)?;
+    let load = words.get(1)?;
+
+    if auipc & 0xfff != 0xe17 {
+        return None;
+    }
+
+    if load & 0xfffff != 0xe3e03 && load & 0xfffff != 0xe2e03 {
+        return None;
+    }
+
+    let hi = (auipc & 0xfffff000) as i32 as i64;
+    let lo = (load as i32 >> 20) as i64;
+
+    Some(Stub {
+        len: 16,
+        target: StubTarget::Slot(addr.wrapping_add((hi + lo) as u64)),
+    })
+}
+
+/// Either a PLT entry:
+///
+/// ```text
+/// lui    t7, %hi(slot)
+/// l[w|d] t9, %lo(slot)(t7)
+/// jr     t9
+/// addiu  t8, t7, %lo(slot)
+/// ```
+///
+/// or a lazy-binding stub from `.MIPS.stubs`, which encodes the symbol index directly:
+///
+/// ```text
+/// l[w|d] t9, -0x7ff0(gp)
+/// move   t7, ra
+/// jalr   t9
+/// li     t8, index
+/// ```
+fn decode_mips(words: Words) -> Option<Stub> {
+    let first = words.get(0)?;
+    let second = words.get(1)?;
+
+    if first & 0xffff0000 == 0x3c0f0000 {
+        if second & 0xffff0000 != 0x8df90000 && second & 0xffff0000 != 0xddf90000 {
+            return None;
+        }
+
+        let hi = ((first & 0xffff) << 16) as i32 as i64;
+        let lo = (second & 0xffff) as i16 as i64;
+        return Some(Stub {
+            len: 16,
+            target: StubTarget::Slot((hi + lo) as u64),
+        });
+    }
+
+    if first & 0xffff0000 == 0x8f990000 || first & 0xffff0000 == 0xdf990000 {
+        let jalr = words.get(2)?;
+        let li = words.get(3)?;
+
+        // jalr t9 followed by `addiu t8, zero, index` or `ori t8, zero, index`.
+        if jalr != 0x0320f809 {
+            return None;
+        }
+
+        if li & 0xffff0000 != 0x24180000 && li & 0xffff0000 != 0x34180000 {
+            return None;
+        }
+
+        return Some(Stub {
+            len: 16,
+            target: StubTarget::Symbol((li & 0xffff) as usize),
+        });
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn be_words(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+
+    fn le_words(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    /// Length and slot of the stub at `bytes`.
+    fn slot(e_machine: u16, bytes: &[u8], addr: u64, got: Option<u64>) -> Option<(usize, u64)> {
+        let little_endian = e_machine != elf::EM_MIPS;
+        match decode(e_machine, bytes, addr, got, little_endian)? {
+            Stub {
+                len,
+                target: StubTarget::Slot(slot),
+            } => Some((len, slot)),
+            Stub {
+                target: StubTarget::Symbol(_),
+                ..
+            } => None,
+        }
+    }
+
+    #[test]
+    fn x86_64() {
+        // jmp [rip + 0x2fe2]
+        let bytes = [0xff, 0x25, 0xe2, 0x2f, 0x00, 0x00];
+        assert_eq!(
+            slot(elf::EM_X86_64, &bytes, 0x1020, None),
+            Some((6, 0x4008))
+        );
+
+        // endbr64; bnd jmp [rip + 0x2fdd]
+        let bytes = [
+            0xf3, 0x0f, 0x1e, 0xfa, 0xf2, 0xff, 0x25, 0xdd, 0x2f, 0x00, 0x00,
+        ];
+        assert_eq!(
+            slot(elf::EM_X86_64, &bytes, 0x1030, None),
+            Some((11, 0x4018))
+        );
+
+        // push 0; jmp .plt
+        let bytes = [0x68, 0x00, 0x00, 0x00, 0x00, 0xe9, 0xe0, 0xff, 0xff, 0xff];
+        assert_eq!(slot(elf::EM_X86_64, &bytes, 0x1030, None), None);
+    }
+
+    #[test]
+    fn i386() {
+        // jmp [0x804a00c]
+        let bytes = [0xff, 0x25, 0x0c, 0xa0, 0x04, 0x08];
+        assert_eq!(
+            slot(elf::EM_386, &bytes, 0x8049030, None),
+            Some((6, 0x804a00c))
+        );
+
+        // jmp [ebx + 0xc]
+        let bytes = [0xff, 0xa3, 0x0c, 0x00, 0x00, 0x00];
+        assert_eq!(
+            slot(elf::EM_386, &bytes, 0x1030, Some(0x4000)),
+            Some((6, 0x400c))
+        );
+        assert_eq!(slot(elf::EM_386, &bytes, 0x1030, None), None);
+    }
+
+    #[test]
+    fn aarch64() {
+        // adrp x16, 0x30000; ldr x17, [x16, #24]; add x16, x16, #24; br x17
+        let stub = [0x90000110, 0xf9400e11, 0x91006210, 0xd61f0220];
+        let bytes = le_words(&stub);
+        assert_eq!(
+            slot(elf::EM_AARCH64, &bytes, 0x10000, None),
+            Some((16, 0x30018))
+        );
+
+        // Same stub after a `bti c`.
+        let bytes = le_words(&[&[0xd503245f][..], &stub].concat());
+        assert_eq!(
+            slot(elf::EM_AARCH64, &bytes, 0x10000, None),
+            Some((20, 0x30018))
+        );
+    }
+
+    #[test]
+    fn arm() {
+        // add ip, pc, #0, #12; add ip, ip, #4096; ldr pc, [ip, #4]!
+        let bytes = le_words(&[0xe28fc600, 0xe28cca01, 0xe5bcf004]);
+        assert_eq!(slot(elf::EM_ARM, &bytes, 0x1000, None), Some((12, 0x200c)));
+    }
+
+    #[test]
+    fn riscv() {
+        // auipc t3, 0x2; ld t3, 16(t3); jalr t1, t3; nop
+        let bytes = le_words(&[0x00002e17, 0x010e3e03, 0x000e0367, 0x00000013]);
+        assert_eq!(
+            slot(elf::EM_RISCV, &bytes, 0x1000, None),
+            Some((16, 0x3010))
+        );
+    }
+
+    #[test]
+    fn mips() {
+        // lui t7, 0x41; lw t9, 0x1010(t7); jr t9; addiu t8, t7, 0x1010
+        let bytes = be_words(&[0x3c0f0041, 0x8df91010, 0x03200008, 0x25f81010]);
+        assert_eq!(
+            slot(elf::EM_MIPS, &bytes, 0x400000, None),
+            Some((16, 0x411010))
+        );
+
+        // lw t9, -0x7ff0(gp); move t7, ra; jalr t9; li t8, 5
+        let bytes = be_words(&[0x8f998010, 0x03e07825, 0x0320f809, 0x24180005]);
+        let stub = decode(elf::EM_MIPS, &bytes, 0x400000, None, false).unwrap();
+        assert_eq!(stub.len, 16);
+        assert!(matches!(stub.target, StubTarget::Symbol(5)));
+    }
+}
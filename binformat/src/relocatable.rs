@@ -0,0 +1,390 @@
+//! Support for relocatable objects (.o, .obj).
+//!
+//! Relocatable objects have all their sections at address zero and any reference to code or
+//! data outside of the current instruction is left as a hole for the linker to fill in.
+//! To make these readable we do a tiny bit of what a static linker does: give every loaded
+//! section a unique address, place every undefined symbol in a synthetic `extern` section and
+//! apply the relocations we know how to encode.
+
+use crate::RawSymbol;
+use object::elf;
+use object::{
+    Object, ObjectSection, ObjectSymbol, Relocation, RelocationEncoding, RelocationKind,
+    RelocationTarget, SectionIndex, SymbolIndex, SymbolKind, SymbolSection,
+};
+use processor_shared::{AddressMap, Addressed, Section, SectionKind};
+use std::collections::HashMap;
+
+/// Where the first section of a relocatable object get's placed.
+/// This isn't zero as symbols at address zero are considered invalid.
+pub const BASE_ADDR: usize = 0x1000;
+
+pub struct RelocatableDebugInfo<'data> {
+    /// Parsed sections with extra metadata.
+    pub sections: Vec<Section>,
+    /// Symbols with their section's address already applied.
+    pub syms: AddressMap<RawSymbol<'data>>,
+    /// Address after the last section.
+    pub end: usize,
+}
+
+impl<'data> RelocatableDebugInfo<'data> {
+    /// Lay out an ELF or COFF relocatable object starting at `base`.
+    pub fn parse(obj: &object::File<'data>, base: usize) -> Self {
+        let mut sections = match obj {
+            object::File::Elf32(elf) => crate::elf::parse_sections(elf),
+            object::File::Elf64(elf) => crate::elf::parse_sections(elf),
+            object::File::Coff(_) | object::File::CoffBig(_) => crate::coff::parse_sections(obj),
+            _ => Vec::new(),
+        };
+
+        let mut this = Self {
+            sections: Vec::new(),
+            syms: AddressMap::default(),
+            end: base,
+        };
+
+        let bases = this.layout_sections(obj, &mut sections, base);
+        let sym_addrs = this.parse_symbols(obj, &mut sections, &bases);
+        apply_relocations(obj, &mut sections, &bases, &sym_addrs);
+
+        this.sections = sections;
+        this
+    }
+
+    /// Assigns every loaded section an address, returning a mapping from section indices to
+    /// their assigned address.
+    fn layout_sections(
+        &mut self,
+        obj: &object::File<'data>,
+        sections: &mut [Section],
+        base: usize,
+    ) -> HashMap<SectionIndex, usize> {
+        let mut bases = HashMap::new();
+        let mut addr = base;
+
+        for (section, parsed) in obj.sections().zip(sections.iter_mut()) {
+            if let SectionKind::Unloaded | SectionKind::Debug = parsed.kind {
+                continue;
+            }
+
+            let align = section.align().max(1) as usize;
+            let size = section.size() as usize;
+
+            addr = align_up(addr, align);
+            parsed.start = addr;
+            parsed.end = addr + size;
+            bases.insert(section.index(), addr);
+            addr += size;
+        }
+
+        self.end = addr;
+        bases
+    }
+
+    /// Relocate all symbols and place undefined symbols in a synthetic `extern` section.
+    fn parse_symbols(
+        &mut self,
+        obj: &object::File<'data>,
+        sections: &mut Vec<Section>,
+        bases: &HashMap<SectionIndex, usize>,
+    ) -> HashMap<SymbolIndex, usize> {
+        let mut sym_addrs = HashMap::new();
+        let mut externs = Vec::new();
+
+        for sym in obj.symbols() {
+            match sym.section() {
+                SymbolSection::Section(idx) => {
+                    let base = match bases.get(&idx) {
+                        Some(&base) => base,
+                        None => continue,
+                    };
+
+                    let addr = base + sym.address() as usize;
+                    sym_addrs.insert(sym.index(), addr);
+
+                    if let SymbolKind::Section | SymbolKind::File = sym.kind() {
+                        continue;
+                    }
+
+                    match sym.name() {
+                        Ok(name) if !name.is_empty() => self.syms.push(Addressed {
+                            addr,
+                            item: RawSymbol { name, module: None, ordinal: None },
+                        }),
+                        _ => {}
+                    }
+                }
+                SymbolSection::Absolute => {
+                    sym_addrs.insert(sym.index(), sym.address() as usize);
+                }
+                SymbolSection::Undefined | SymbolSection::Common => match sym.name() {
+                    Ok(name) if !name.is_empty() => externs.push((sym.index(), name)),
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        if externs.is_empty() {
+            return sym_addrs;
+        }
+
+        // Every undefined symbol get's a pointer sized slot, similar to a GOT entry.
+        // The first slot is left empty as the section's own label would shadow it.
+        let width = if obj.is_64() { 8 } else { 4 };
+        let start = align_up(self.end, width);
+        let end = start + (externs.len() + 1) * width;
+
+        for (idx, (sym_idx, name)) in externs.into_iter().enumerate() {
+            let addr = start + (idx + 1) * width;
+            sym_addrs.insert(sym_idx, addr);
+            self.syms.push(Addressed {
+                addr,
+                item: RawSymbol { name, module: None, ordinal: None },
+            });
+        }
+
+        // The section is never written to, it exists so the listing has something to show.
+        let bytes: &'static [u8] = Box::leak(vec![0; end - start].into_boxed_slice());
+        let kind = if obj.is_64() {
+            SectionKind::Got64
+        } else {
+            SectionKind::Got32
+        };
+
+        sections.push(Section::new("extern".to_string(), "EXTERN", kind, bytes, start, end));
+        self.end = end;
+        sym_addrs
+    }
+}
+
+fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+fn apply_relocations(
+    obj: &object::File,
+    sections: &mut [Section],
+    bases: &HashMap<SectionIndex, usize>,
+    sym_addrs: &HashMap<SymbolIndex, usize>,
+) {
+    let little_endian = obj.is_little_endian();
+    let mut unsupported = 0;
+
+    for (section, parsed) in obj.sections().zip(sections.iter_mut()) {
+        let base = match bases.get(&section.index()) {
+            Some(&base) => base,
+            None => continue,
+        };
+
+        let mut relocations = section.relocations().peekable();
+        if relocations.peek().is_none() || parsed.bytes().is_empty() {
+            continue;
+        }
+
+        let mut bytes = parsed.bytes().to_vec();
+        for (offset, reloc) in relocations {
+            let target = match reloc.target() {
+                RelocationTarget::Symbol(idx) => sym_addrs.get(&idx).copied(),
+                RelocationTarget::Section(idx) => bases.get(&idx).copied(),
+                RelocationTarget::Absolute => Some(0),
+                _ => None,
+            };
+
+            let target = match target {
+                Some(target) => target as u64,
+                None => {
+                    unsupported += 1;
+                    continue;
+                }
+            };
+
+            let place = base as u64 + offset;
+            if !apply_relocation(&mut bytes, offset as usize, place, target, &reloc, little_endian)
+            {
+                unsupported += 1;
+            }
+        }
+
+        // Relocated sections can't point into the memory mapped file anymore.
+        // These live for as long as the processor does, which is the lifetime of the binary.
+        let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+        *parsed = Section::new(
+            parsed.name.clone(),
+            parsed.ident,
+            parsed.kind.clone(),
+            bytes,
+            parsed.start,
+            parsed.end,
+        );
+    }
+
+    if unsupported > 0 {
+        log::complex!(
+            w "[relocatable::apply_relocations] ",
+            y "Skipped ",
+            g unsupported.to_string(),
+            y " unsupported relocations."
+        );
+    }
+}
+
+fn read_uint(bytes: &[u8], size: usize, little_endian: bool) -> u64 {
+    let mut value = 0u64;
+    for idx in 0..size {
+        let byte = if little_endian { bytes[size - idx - 1] } else { bytes[idx] };
+        value = value << 8 | byte as u64;
+    }
+    value
+}
+
+fn write_uint(bytes: &mut [u8], size: usize, value: u64, little_endian: bool) {
+    for idx in 0..size {
+        let byte = (value >> (idx * 8)) as u8;
+        if little_endian {
+            bytes[idx] = byte;
+        } else {
+            bytes[size - idx - 1] = byte;
+        }
+    }
+}
+
+/// Applies a single relocation `S + A` or `S + A - P`, returning whether it's supported.
+fn apply_relocation(
+    bytes: &mut [u8],
+    offset: usize,
+    place: u64,
+    target: u64,
+    reloc: &Relocation,
+    little_endian: bool,
+) -> bool {
+    let width = match reloc.size() {
+        8 | 16 | 32 | 64 => reloc.size() as usize / 8,
+        // Instruction encodings are always 32-bit.
+        _ => 4,
+    };
+
+    let field = match bytes.get_mut(offset..offset + width) {
+        Some(field) => field,
+        None => return false,
+    };
+
+    let existing = read_uint(field, width, little_endian);
+    let mut addend = reloc.addend() as u64;
+    if reloc.has_implicit_addend() && reloc.encoding() == RelocationEncoding::Generic {
+        // Sign extend the addend stored in the field.
+        let shift = 64 - width * 8;
+        addend = addend.wrapping_add(((existing << shift) as i64 >> shift) as u64);
+    }
+
+    let absolute = target.wrapping_add(addend);
+    let relative = absolute.wrapping_sub(place);
+
+    match (reloc.kind(), reloc.encoding()) {
+        (RelocationKind::Absolute, RelocationEncoding::Generic | RelocationEncoding::X86Signed) => {
+            write_uint(field, width, absolute, little_endian);
+        }
+        (
+            RelocationKind::Relative | RelocationKind::PltRelative | RelocationKind::GotRelative,
+            RelocationEncoding::Generic
+            | RelocationEncoding::X86RipRelative
+            | RelocationEncoding::X86RipRelativeMovq
+            | RelocationEncoding::X86Branch,
+        ) => {
+            write_uint(field, width, relative, little_endian);
+        }
+        // `b` and `bl` instructions.
+        (RelocationKind::Relative | RelocationKind::PltRelative, RelocationEncoding::AArch64Call) => {
+            let imm26 = (relative >> 2) & 0x3ffffff;
+            write_uint(field, 4, existing & !0x3ffffff | imm26, little_endian);
+        }
+        (RelocationKind::Elf(elf::R_AARCH64_ADR_PREL_PG_HI21), _) => {
+            let pages = ((absolute & !0xfff).wrapping_sub(place & !0xfff) as i64 >> 12) as u64;
+            let immlo = (pages & 0b11) << 29;
+            let immhi = ((pages >> 2) & 0x7ffff) << 5;
+            let insn = existing & !(0b11 << 29 | 0x7ffff << 5) | immlo | immhi;
+            write_uint(field, 4, insn, little_endian);
+        }
+        (RelocationKind::Elf(elf::R_AARCH64_ADD_ABS_LO12_NC), _) => {
+            let imm12 = (absolute & 0xfff) << 10;
+            write_uint(field, 4, existing & !(0xfff << 10) | imm12, little_endian);
+        }
+        (RelocationKind::Elf(elf::R_AARCH64_LDST64_ABS_LO12_NC), _) => {
+            let imm12 = ((absolute & 0xfff) >> 3) << 10;
+            write_uint(field, 4, existing & !(0xfff << 10) | imm12, little_endian);
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, GLOBAL};
+
+    fn section<'a>(sections: &'a [Section], name: &str) -> &'a Section {
+        sections.iter().find(|s| s.name == name).unwrap()
+    }
+
+    fn u32_at(section: &Section, offset: usize) -> u32 {
+        u32::from_le_bytes(section.bytes()[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn x86_64() {
+        // call puts; mov eax, [rip + value]
+        let text = [0xe8, 0, 0, 0, 0, 0x8b, 0x05, 0, 0, 0, 0];
+        let syms = [
+            ("value", 2, 0, GLOBAL | elf::STT_OBJECT),
+            ("puts", 0, 0, GLOBAL),
+        ];
+        let relocs = [
+            (1, 2, elf::R_X86_64_PLT32, -4),
+            (7, 1, elf::R_X86_64_PC32, -4),
+        ];
+        let bytes = testing::relocatable(elf::EM_X86_64, &text, &[0; 8], 8, &syms, &relocs);
+        let obj = object::File::parse(&bytes[..]).unwrap();
+        let info = RelocatableDebugInfo::parse(&obj, BASE_ADDR);
+
+        let text = section(&info.sections, ".text");
+        let data = section(&info.sections, ".data");
+        let externs = section(&info.sections, "extern");
+        assert_eq!((text.start, text.end), (0x1000, 0x100b));
+        assert_eq!((data.start, data.end), (0x1010, 0x1018));
+        assert_eq!((externs.start, externs.end), (0x1018, 0x1028));
+        assert_eq!(info.end, 0x1028);
+
+        // The first extern slot is left empty.
+        let puts = 0x1020;
+        assert_eq!(u32_at(text, 1), (puts - 4 - (text.start + 1)) as u32);
+        assert_eq!(u32_at(text, 7), (data.start - 4 - (text.start + 7)) as u32);
+
+        let names: Vec<_> = info.syms.iter().map(|s| (s.addr, s.item.name)).collect();
+        assert!(names.contains(&(data.start, "value")));
+        assert!(names.contains(&(puts, "puts")));
+    }
+
+    #[test]
+    fn aarch64() {
+        // adrp x0, value + 16; add x0, x0, :lo12:value + 16
+        let text = [0x00, 0x00, 0x00, 0x90, 0x00, 0x00, 0x00, 0x91];
+        let syms = [("value", 2, 0, GLOBAL | elf::STT_OBJECT)];
+        let relocs = [
+            (0, 1, elf::R_AARCH64_ADR_PREL_PG_HI21, 0x10),
+            (4, 1, elf::R_AARCH64_ADD_ABS_LO12_NC, 0x10),
+        ];
+        let bytes = testing::relocatable(elf::EM_AARCH64, &text, &[0; 32], 0x1000, &syms, &relocs);
+        let obj = object::File::parse(&bytes[..]).unwrap();
+        let info = RelocatableDebugInfo::parse(&obj, BASE_ADDR);
+
+        let text = section(&info.sections, ".text");
+        assert_eq!(section(&info.sections, ".data").start, 0x2000);
+
+        // adrp x0, 0x2000; add x0, x0, #0x10
+        assert_eq!(u32_at(text, 0), 0xb0000000);
+        assert_eq!(u32_at(text, 4), 0x91004000);
+    }
+}
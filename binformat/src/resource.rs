@@ -0,0 +1,743 @@
+//! Decoding of the PE resource tree and the few resource types worth looking at.
+//!
+//! The tree has three levels: the resource type, it's name or id and the language. Leaves point
+//! to the actual resource data which can be located anywhere in the image.
+
+use crate::{Datastructure, Reference};
+use object::pe;
+use processor_shared::Addressed;
+use std::collections::HashSet;
+
+/// High bit of an entry's name or offset.
+const SUBDIRECTORY: u32 = 0x80000000;
+
+/// Signature of a `VS_FIXEDFILEINFO`.
+const VS_FFI_SIGNATURE: u32 = 0xfeef04bd;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Keys of a `StringFileInfo` table that are documented.
+const VERSION_KEYS: [&str; 12] = [
+    "Comments",
+    "CompanyName",
+    "FileDescription",
+    "FileVersion",
+    "InternalName",
+    "LegalCopyright",
+    "LegalTrademarks",
+    "OriginalFilename",
+    "PrivateBuild",
+    "ProductName",
+    "ProductVersion",
+    "SpecialBuild",
+];
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Read a null terminated UTF-16 string, returning it and the number of bytes it occupies.
+fn read_utf16(bytes: &[u8], offset: usize) -> Option<(String, usize)> {
+    let mut chars = Vec::new();
+    let mut idx = offset;
+    loop {
+        let char = read_u16(bytes, idx)?;
+        idx += 2;
+        if char == 0 {
+            break;
+        }
+        chars.push(char);
+    }
+
+    Some((String::from_utf16_lossy(&chars), idx - offset))
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn type_name(id: u16) -> Option<&'static str> {
+    Some(match id {
+        pe::RT_CURSOR => "RT_CURSOR",
+        pe::RT_BITMAP => "RT_BITMAP",
+        pe::RT_ICON => "RT_ICON",
+        pe::RT_MENU => "RT_MENU",
+        pe::RT_DIALOG => "RT_DIALOG",
+        pe::RT_STRING => "RT_STRING",
+        pe::RT_FONTDIR => "RT_FONTDIR",
+        pe::RT_FONT => "RT_FONT",
+        pe::RT_ACCELERATOR => "RT_ACCELERATOR",
+        pe::RT_RCDATA => "RT_RCDATA",
+        pe::RT_MESSAGETABLE => "RT_MESSAGETABLE",
+        pe::RT_GROUP_CURSOR => "RT_GROUP_CURSOR",
+        pe::RT_GROUP_ICON => "RT_GROUP_ICON",
+        pe::RT_VERSION => "RT_VERSION",
+        pe::RT_DLGINCLUDE => "RT_DLGINCLUDE",
+        pe::RT_PLUGPLAY => "RT_PLUGPLAY",
+        pe::RT_VXD => "RT_VXD",
+        pe::RT_ANICURSOR => "RT_ANICURSOR",
+        pe::RT_ANIICON => "RT_ANIICON",
+        pe::RT_HTML => "RT_HTML",
+        pe::RT_MANIFEST => "RT_MANIFEST",
+        _ => return None,
+    })
+}
+
+struct Walker<'a, F: Fn(usize) -> Option<&'a [u8]>> {
+    /// Bytes of the resource directory up until the end of it's section.
+    bytes: &'a [u8],
+    /// Virtual address of the resource directory.
+    addr: usize,
+    base: usize,
+    read: F,
+    visited: HashSet<usize>,
+    datastructures: Vec<Addressed<Datastructure>>,
+}
+
+/// Decode the resource tree at `addr`, which is at the start of `bytes`.
+///
+/// `read` returns the bytes starting at a virtual address, for reading resource data.
+pub(crate) fn parse<'a>(
+    bytes: &'a [u8],
+    addr: usize,
+    base: usize,
+    read: impl Fn(usize) -> Option<&'a [u8]>,
+) -> Vec<Addressed<Datastructure>> {
+    let mut walker = Walker {
+        bytes,
+        addr,
+        base,
+        read,
+        visited: HashSet::new(),
+        datastructures: Vec::new(),
+    };
+
+    walker.table(0, 0, None);
+    walker.datastructures
+}
+
+impl<'a, F: Fn(usize) -> Option<&'a [u8]>> Walker<'a, F> {
+    fn push(&mut self, addr: usize, item: Datastructure) {
+        self.datastructures.push(Addressed { addr, item });
+    }
+
+    /// An `IMAGE_RESOURCE_DIRECTORY` and it's entries.
+    fn table(&mut self, offset: usize, depth: usize, resource_type: Option<u16>) -> Option<()> {
+        // Malformed trees can contain loops.
+        if depth > 2 || !self.visited.insert(offset) {
+            return None;
+        }
+
+        let named = read_u16(self.bytes, offset + 12)? as usize;
+        let ids = read_u16(self.bytes, offset + 14)? as usize;
+
+        let addr = self.addr + offset;
+        let u16_at = |offset: usize| Some(format!("{:#x}", read_u16(self.bytes, offset)?));
+        let u32_at = |offset: usize| Some(format!("{:#x}", read_u32(self.bytes, offset)?));
+        let header = Datastructure {
+            ident: "IMAGE_RESOURCE_DIRECTORY",
+            size: 16,
+            fields: vec![
+                (addr, "characteristics", "u32", u32_at(offset)?),
+                (addr + 4, "time_date_stamp", "u32", u32_at(offset + 4)?),
+                (addr + 8, "major_version", "u16", u16_at(offset + 8)?),
+                (addr + 10, "minor_version", "u16", u16_at(offset + 10)?),
+                (addr + 12, "number_of_named_entries", "u16", format!("{named:#x}")),
+                (addr + 14, "number_of_id_entries", "u16", format!("{ids:#x}")),
+            ],
+            references: Vec::new(),
+        };
+        self.push(addr, header);
+
+        for idx in 0..named + ids {
+            let entry_offset = offset + 16 + idx * 8;
+            let name = read_u32(self.bytes, entry_offset)?;
+            let data = read_u32(self.bytes, entry_offset + 4)?;
+
+            let (id, name_value) = if name & SUBDIRECTORY != 0 {
+                let string = self.string(name as usize & !SUBDIRECTORY as usize);
+                (None, format!("{name:#x} {:?}", string.unwrap_or_default()))
+            } else {
+                let id = name as u16;
+                let value = match depth {
+                    0 => match type_name(id) {
+                        Some(type_name) => format!("{name:#x} ({type_name})"),
+                        None => format!("{name:#x}"),
+                    },
+                    1 => format!("{name:#x} (#{id})"),
+                    _ => format!("{name:#x} (language)"),
+                };
+                (Some(id), value)
+            };
+
+            // The type is determined by the entries of the root directory.
+            let resource_type = if depth == 0 { id } else { resource_type };
+
+            let data_offset = data as usize & !SUBDIRECTORY as usize;
+            let data_value = if data & SUBDIRECTORY != 0 {
+                format!("{data:#x} (directory)")
+            } else {
+                format!("{data:#x} (data entry)")
+            };
+
+            let entry_addr = self.addr + entry_offset;
+            self.push(
+                entry_addr,
+                Datastructure {
+                    ident: "IMAGE_RESOURCE_DIRECTORY_ENTRY",
+                    size: 8,
+                    fields: vec![
+                        (entry_addr, "name", "u32", name_value),
+                        (entry_addr + 4, "offset_to_data", "u32", data_value),
+                    ],
+                    references: Vec::new(),
+                },
+            );
+
+            if data & SUBDIRECTORY != 0 {
+                self.table(data_offset, depth + 1, resource_type);
+            } else {
+                self.data_entry(data_offset, resource_type);
+            }
+        }
+
+        Some(())
+    }
+
+    /// An `IMAGE_RESOURCE_DIR_STRING_U` naming an entry.
+    fn string(&mut self, offset: usize) -> Option<String> {
+        let len = read_u16(self.bytes, offset)? as usize;
+        let chars: Vec<u16> = (0..len)
+            .map(|idx| read_u16(self.bytes, offset + 2 + idx * 2))
+            .collect::<Option<_>>()?;
+        let string = String::from_utf16_lossy(&chars);
+
+        if self.visited.insert(offset) {
+            let addr = self.addr + offset;
+            self.push(
+                addr,
+                Datastructure {
+                    ident: "IMAGE_RESOURCE_DIR_STRING_U",
+                    size: 2 + len * 2,
+                    fields: vec![
+                        (addr, "length", "u16", format!("{len:#x}")),
+                        (addr + 2, "name_string", "[u16]", format!("{string:?}")),
+                    ],
+                    references: Vec::new(),
+                },
+            );
+        }
+
+        Some(string)
+    }
+
+    /// An `IMAGE_RESOURCE_DATA_ENTRY` and the resource it points to.
+    fn data_entry(&mut self, offset: usize, resource_type: Option<u16>) -> Option<()> {
+        if !self.visited.insert(offset) {
+            return None;
+        }
+
+        let rva = read_u32(self.bytes, offset)?;
+        let size = read_u32(self.bytes, offset + 4)?;
+        let code_page = read_u32(self.bytes, offset + 8)?;
+        let reserved = read_u32(self.bytes, offset + 12)?;
+
+        let addr = self.addr + offset;
+        let data_addr = self.base + rva as usize;
+        self.push(
+            addr,
+            Datastructure {
+                ident: "IMAGE_RESOURCE_DATA_ENTRY",
+                size: 16,
+                fields: vec![
+                    (addr, "offset_to_data", "u32", format!("{rva:#x}")),
+                    (addr + 4, "size", "u32", format!("{size:#x}")),
+                    (addr + 8, "code_page", "u32", format!("{code_page:#x}")),
+                    (addr + 12, "reserved", "u32", format!("{reserved:#x}")),
+                ],
+                references: vec![(0, Reference::Address(data_addr))],
+            },
+        );
+
+        let data = (self.read)(data_addr)?;
+        let data = data.get(..size as usize)?;
+        let resource = match resource_type? {
+            pe::RT_VERSION => version_info(data, data_addr),
+            pe::RT_MANIFEST => manifest(data, data_addr),
+            pe::RT_ICON => icon(data, data_addr),
+            pe::RT_GROUP_ICON => group_icon(data, data_addr),
+            _ => None,
+        };
+
+        if let Some(resource) = resource {
+            self.push(data_addr, resource);
+        }
+
+        Some(())
+    }
+}
+
+/// A `VS_VERSIONINFO`: the fixed file info followed by the `StringFileInfo` and `VarFileInfo`.
+fn version_info(data: &[u8], addr: usize) -> Option<Datastructure> {
+    let len = read_u16(data, 0)? as usize;
+    let value_len = read_u16(data, 2)? as usize;
+    let (key, key_len) = read_utf16(data, 6)?;
+    if key != "VS_VERSION_INFO" {
+        return None;
+    }
+
+    let mut fields = Vec::new();
+    let value = align4(6 + key_len);
+    if value_len >= 52 && read_u32(data, value)? == VS_FFI_SIGNATURE {
+        let version = |offset: usize| -> Option<String> {
+            let ms = read_u32(data, value + offset)?;
+            let ls = read_u32(data, value + offset + 4)?;
+            Some(format!("{}.{}.{}.{}", ms >> 16, ms & 0xffff, ls >> 16, ls & 0xffff))
+        };
+
+        let flag = |offset: usize| -> Option<String> {
+            Some(format!("{:#x}", read_u32(data, value + offset)?))
+        };
+
+        fields.push((addr + value, "signature", "u32", format!("{VS_FFI_SIGNATURE:#x}")));
+        fields.push((addr + value + 8, "file_version", "[u32; 2]", version(8)?));
+        fields.push((addr + value + 16, "product_version", "[u32; 2]", version(16)?));
+        fields.push((addr + value + 28, "file_flags", "u32", flag(28)?));
+        fields.push((addr + value + 32, "file_os", "u32", flag(32)?));
+        fields.push((addr + value + 36, "file_type", "u32", flag(36)?));
+    }
+
+    // Children of the root and of each `StringFileInfo` or `VarFileInfo`.
+    let mut offset = align4(value + value_len);
+    let end = std::cmp::min(len, data.len());
+    while offset < end {
+        let child_len = read_u16(data, offset)? as usize;
+        let (child_key, child_key_len) = read_utf16(data, offset + 6)?;
+        if child_len == 0 {
+            break;
+        }
+
+        let child_end = std::cmp::min(offset + child_len, end);
+        let mut child = align4(offset + 6 + child_key_len);
+        match child_key.as_str() {
+            "StringFileInfo" => {
+                // Each string table is named after the language and code page.
+                while child < child_end {
+                    let table_len = read_u16(data, child)? as usize;
+                    let (_, table_key_len) = read_utf16(data, child + 6)?;
+                    if table_len == 0 {
+                        break;
+                    }
+
+                    let table_end = std::cmp::min(child + table_len, child_end);
+                    let mut string = align4(child + 6 + table_key_len);
+                    while string < table_end {
+                        let string_len = read_u16(data, string)? as usize;
+                        let (key, key_len) = read_utf16(data, string + 6)?;
+                        if string_len == 0 {
+                            break;
+                        }
+
+                        // Strings without a value end right after their key.
+                        let value = align4(string + 6 + key_len);
+                        let value = match value < string + string_len {
+                            true => read_utf16(data, value).map(|(value, _)| value),
+                            false => None,
+                        };
+                        let value = value.unwrap_or_default();
+
+                        let field = match VERSION_KEYS.iter().find(|known| **known == key) {
+                            Some(key) => (addr + string, *key, "str", format!("{value:?}")),
+                            None => (addr + string, "string", "str", format!("{key:?}: {value:?}")),
+                        };
+                        fields.push(field);
+
+                        string = align4(string + string_len);
+                    }
+
+                    child = align4(child + table_len);
+                }
+            }
+            "VarFileInfo" => {
+                let var_len = read_u16(data, child)? as usize;
+                let var_value_len = read_u16(data, child + 2)? as usize;
+                let (_, var_key_len) = read_utf16(data, child + 6)?;
+                let value = align4(child + 6 + var_key_len);
+
+                // Pairs of language and code page identifiers.
+                let mut translations = Vec::new();
+                let mut pair = value;
+                while pair + 4 <= value + var_value_len && pair < child + var_len {
+                    let language = read_u16(data, pair)?;
+                    let code_page = read_u16(data, pair + 2)?;
+                    translations.push(format!("{language:#x} {code_page:#x}"));
+                    pair += 4;
+                }
+
+                let translations = translations.join(", ");
+                fields.push((addr + value, "translation", "[u16; 2]", translations));
+            }
+            _ => {}
+        }
+
+        offset = align4(child_end);
+    }
+
+    if fields.is_empty() {
+        return None;
+    }
+
+    Some(Datastructure {
+        ident: "VS_VERSIONINFO",
+        size: len,
+        fields,
+        references: Vec::new(),
+    })
+}
+
+/// An application manifest, shown line by line.
+fn manifest(data: &[u8], addr: usize) -> Option<Datastructure> {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for line in data.split(|&b| b == b'\n') {
+        let text = String::from_utf8_lossy(line);
+        let text = text.trim_end();
+        if !text.trim().is_empty() {
+            fields.push((addr + offset, "xml", "str", format!("{text:?}")));
+        }
+        offset += line.len() + 1;
+    }
+
+    if fields.is_empty() {
+        return None;
+    }
+
+    Some(Datastructure {
+        ident: "RT_MANIFEST",
+        size: data.len(),
+        fields,
+        references: Vec::new(),
+    })
+}
+
+/// A single icon image, either a PNG or a `BITMAPINFOHEADER` followed by the pixels.
+fn icon(data: &[u8], addr: usize) -> Option<Datastructure> {
+    let be_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+    };
+
+    let fields = if data.starts_with(PNG_SIGNATURE) {
+        // The IHDR chunk always comes first.
+        vec![
+            (addr, "signature", "[u8; 8]", "PNG".to_string()),
+            (addr + 16, "width", "u32", format!("{}", be_u32(16)?)),
+            (addr + 20, "height", "u32", format!("{}", be_u32(20)?)),
+        ]
+    } else {
+        // The height covers both the image and it's AND mask.
+        let height = read_u32(data, 8)? as i32;
+        vec![
+            (addr, "bi_size", "u32", format!("{:#x}", read_u32(data, 0)?)),
+            (addr + 4, "bi_width", "i32", format!("{}", read_u32(data, 4)? as i32)),
+            (addr + 8, "bi_height", "i32", format!("{height} ({} + mask)", height / 2)),
+            (addr + 12, "bi_planes", "u16", format!("{}", read_u16(data, 12)?)),
+            (addr + 14, "bi_bit_count", "u16", format!("{}", read_u16(data, 14)?)),
+            (addr + 16, "bi_compression", "u32", format!("{:#x}", read_u32(data, 16)?)),
+            (addr + 20, "bi_size_image", "u32", format!("{:#x}", read_u32(data, 20)?)),
+        ]
+    };
+
+    Some(Datastructure {
+        ident: "RT_ICON",
+        size: data.len(),
+        fields,
+        references: Vec::new(),
+    })
+}
+
+/// A `GRPICONDIR` listing the different sizes of an icon.
+fn group_icon(data: &[u8], addr: usize) -> Option<Datastructure> {
+    let count = read_u16(data, 4)? as usize;
+    let mut fields = vec![
+        (addr, "reserved", "u16", format!("{:#x}", read_u16(data, 0)?)),
+        (addr + 2, "type", "u16", format!("{:#x}", read_u16(data, 2)?)),
+        (addr + 4, "count", "u16", format!("{count:#x}")),
+    ];
+
+    for idx in 0..count {
+        let offset = 6 + idx * 14;
+        let entry = data.get(offset..offset + 14)?;
+
+        // A width or height of zero means 256 pixels.
+        let width = if entry[0] == 0 { 256 } else { entry[0] as u32 };
+        let height = if entry[1] == 0 { 256 } else { entry[1] as u32 };
+        let bit_count = read_u16(entry, 6)?;
+        let bytes = read_u32(entry, 8)?;
+        let id = read_u16(entry, 12)?;
+
+        let value = format!("{width}x{height} {bit_count}bpp #{id} ({bytes:#x} bytes)");
+        fields.push((addr + offset, "entry", "GRPICONDIRENTRY", value));
+    }
+
+    Some(Datastructure {
+        ident: "RT_GROUP_ICON",
+        size: 6 + count * 14,
+        fields,
+        references: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: usize = 0x400000;
+
+    /// Virtual address of the resource directory.
+    const ADDR: usize = BASE + 0x1000;
+
+    fn utf16(string: &str) -> Vec<u8> {
+        string.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn pad4(bytes: &mut Vec<u8>) {
+        bytes.resize(align4(bytes.len()), 0);
+    }
+
+    /// A version info block, with `value` in bytes or UTF-16 characters depending on `text`.
+    fn block(key: &str, value: &[u8], text: bool, children: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = vec![0; 6];
+        bytes.extend(utf16(key));
+        pad4(&mut bytes);
+        bytes.extend(value);
+        for child in children {
+            pad4(&mut bytes);
+            bytes.extend(child);
+        }
+
+        let len = bytes.len() as u16;
+        let value_len = if text { value.len() / 2 } else { value.len() };
+        bytes[..2].copy_from_slice(&len.to_le_bytes());
+        bytes[2..4].copy_from_slice(&(value_len as u16).to_le_bytes());
+        bytes[4..6].copy_from_slice(&(text as u16).to_le_bytes());
+        bytes
+    }
+
+    fn string(key: &str, value: &str) -> Vec<u8> {
+        match value {
+            "" => block(key, &[], true, &[]),
+            _ => block(key, &utf16(value), true, &[]),
+        }
+    }
+
+    fn version_info() -> Vec<u8> {
+        let fixed: Vec<u8> = [
+            VS_FFI_SIGNATURE,
+            0x10000,    // struc_version
+            0x00010002, // file_version_ms
+            0x00030004, // file_version_ls
+            0x00010002, // product_version_ms
+            0,          // product_version_ls
+            0x3f,       // file_flags_mask
+            0,          // file_flags
+            0x40004,    // file_os
+            1,          // file_type
+            0,          // file_subtype
+            0,          // file_date_ms
+            0,          // file_date_ls
+        ]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+
+        let strings = block(
+            "040904b0",
+            &[],
+            true,
+            &[
+                string("CompanyName", "Acme"),
+                string("PrivateBuild", ""),
+                string("Custom", "x"),
+            ],
+        );
+        let translation = block("Translation", &[0x09, 0x04, 0xb0, 0x04], false, &[]);
+
+        block(
+            "VS_VERSION_INFO",
+            &fixed,
+            false,
+            &[
+                block("StringFileInfo", &[], true, &[strings]),
+                block("VarFileInfo", &[], true, &[translation]),
+            ],
+        )
+    }
+
+    const MANIFEST: &[u8] = b"<assembly>\n  <trustInfo/>\n\n</assembly>\n";
+
+    /// A directory with `named` entries named by a string, followed by entries with an id.
+    fn directory(named: u16, entries: &[(u32, u32)]) -> Vec<u8> {
+        let mut bytes = vec![0; 12];
+        bytes.extend(named.to_le_bytes());
+        bytes.extend((entries.len() as u16 - named).to_le_bytes());
+        bytes.extend(
+            entries
+                .iter()
+                .flat_map(|(name, data)| [*name, *data])
+                .flat_map(u32::to_le_bytes),
+        );
+        bytes
+    }
+
+    fn data_entry(rva: usize, size: usize) -> Vec<u8> {
+        [rva as u32, size as u32, 0, 0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    /// A tree with a version info, a manifest and a named type that loops back to the root.
+    fn tree() -> Vec<u8> {
+        let dir = |offset: u32| SUBDIRECTORY | offset;
+        let name = SUBDIRECTORY | 0xc0;
+        let version = version_info();
+        let manifest_offset = align4(0xd0 + version.len());
+
+        let mut bytes = directory(1, &[(name, dir(0x88)), (16, dir(0x28)), (24, dir(0x58))]);
+        bytes.extend(directory(0, &[(1, dir(0x40))]));
+        bytes.extend(directory(0, &[(0x409, 0xa0)]));
+        bytes.extend(directory(0, &[(1, dir(0x70))]));
+        bytes.extend(directory(0, &[(0x409, 0xb0)]));
+        bytes.extend(directory(0, &[(7, dir(0))]));
+        bytes.extend(data_entry(0x10d0, version.len()));
+        bytes.extend(data_entry(0x1000 + manifest_offset, MANIFEST.len()));
+        bytes.extend(6u16.to_le_bytes());
+        bytes.extend("CUSTOM".encode_utf16().flat_map(u16::to_le_bytes));
+        pad4(&mut bytes);
+        assert_eq!(bytes.len(), 0xd0);
+
+        bytes.extend(version);
+        pad4(&mut bytes);
+        bytes.extend(MANIFEST);
+        bytes
+    }
+
+    fn walk(bytes: &[u8]) -> Vec<Addressed<Datastructure>> {
+        let read = |addr: usize| bytes.get(addr.checked_sub(ADDR)?..);
+        parse(bytes, ADDR, BASE, read)
+    }
+
+    fn find(datastructures: &[Addressed<Datastructure>], offset: usize) -> &Datastructure {
+        &datastructures.iter().find(|ds| ds.addr == ADDR + offset).unwrap().item
+    }
+
+    fn values(datastructure: &Datastructure) -> Vec<(&str, &str)> {
+        datastructure.fields.iter().map(|field| (field.1, &field.3[..])).collect()
+    }
+
+    #[test]
+    fn walk_tree() {
+        let bytes = tree();
+        let datastructures = walk(&bytes);
+
+        let root = find(&datastructures, 0);
+        assert_eq!(root.ident, "IMAGE_RESOURCE_DIRECTORY");
+        assert_eq!(
+            values(root)[4..],
+            [
+                ("number_of_named_entries", "0x1"),
+                ("number_of_id_entries", "0x2")
+            ]
+        );
+
+        let entry = |offset: usize| {
+            let entry = find(&datastructures, offset);
+            assert_eq!(entry.ident, "IMAGE_RESOURCE_DIRECTORY_ENTRY");
+            (&entry.fields[0].3[..], &entry.fields[1].3[..])
+        };
+        assert_eq!(
+            entry(0x10),
+            ("0x800000c0 \"CUSTOM\"", "0x80000088 (directory)")
+        );
+        assert_eq!(entry(0x18), ("0x10 (RT_VERSION)", "0x80000028 (directory)"));
+        assert_eq!(
+            entry(0x20),
+            ("0x18 (RT_MANIFEST)", "0x80000058 (directory)")
+        );
+        assert_eq!(entry(0x38), ("0x1 (#1)", "0x80000040 (directory)"));
+        assert_eq!(entry(0x50), ("0x409 (language)", "0xa0 (data entry)"));
+        assert_eq!(entry(0x98), ("0x7 (#7)", "0x80000000 (directory)"));
+
+        let name = find(&datastructures, 0xc0);
+        assert_eq!(name.ident, "IMAGE_RESOURCE_DIR_STRING_U");
+        assert_eq!((name.size, &name.fields[1].3[..]), (14, "\"CUSTOM\""));
+
+        let data = find(&datastructures, 0xa0);
+        assert_eq!(data.ident, "IMAGE_RESOURCE_DATA_ENTRY");
+        assert_eq!(data.fields[0].3, "0x10d0");
+        assert!(
+            matches!(data.references[..], [(0, Reference::Address(addr))] if addr == ADDR + 0xd0)
+        );
+
+        // The loop back to the root is only walked once.
+        let count = |ident: &str| datastructures.iter().filter(|ds| ds.item.ident == ident).count();
+        assert_eq!(count("IMAGE_RESOURCE_DIRECTORY"), 6);
+        assert_eq!(count("IMAGE_RESOURCE_DIRECTORY_ENTRY"), 8);
+        assert_eq!(count("IMAGE_RESOURCE_DATA_ENTRY"), 2);
+    }
+
+    #[test]
+    fn version_and_manifest() {
+        let bytes = tree();
+        let datastructures = walk(&bytes);
+
+        let version = find(&datastructures, 0xd0);
+        assert_eq!(version.ident, "VS_VERSIONINFO");
+        assert_eq!(version.size, version_info().len());
+        assert_eq!(
+            values(version),
+            [
+                ("signature", "0xfeef04bd"),
+                ("file_version", "1.2.3.4"),
+                ("product_version", "1.2.0.0"),
+                ("file_flags", "0x0"),
+                ("file_os", "0x40004"),
+                ("file_type", "0x1"),
+                ("CompanyName", "\"Acme\""),
+                ("PrivateBuild", "\"\""),
+                ("string", "\"Custom\": \"x\""),
+                ("translation", "0x409 0x4b0"),
+            ]
+        );
+
+        let offset = align4(0xd0 + version.size);
+        let manifest = find(&datastructures, offset);
+        assert_eq!(
+            (manifest.ident, manifest.size),
+            ("RT_MANIFEST", MANIFEST.len())
+        );
+
+        // Blank lines are skipped.
+        let lines: Vec<_> =
+            manifest.fields.iter().map(|f| (f.0 - ADDR - offset, &f.3[..])).collect();
+        assert_eq!(
+            lines,
+            [
+                (0, "\"<assembly>\""),
+                (11, "\"  <trustInfo/>\""),
+                (27, "\"</assembly>\"")
+            ]
+        );
+    }
+
+    #[test]
+    fn not_version_info() {
+        let mut bytes = version_info();
+        bytes[6] = b'X';
+        assert!(super::version_info(&bytes, ADDR).is_none());
+    }
+}
@@ -0,0 +1,334 @@
+//! Decoding of the Rich header, which the Microsoft linker hides in the DOS stub.
+//!
+//! Every `@comp.id` combines the tool that produced an object with the build number of the
+//! toolset, so the header tells us which compilers went into the binary.
+
+use crate::FileInfo;
+use object::read::pe::RichHeaderInfo;
+
+/// Tools in the order every toolset from Visual Studio 2010 onwards lists them.
+const TOOLS: [&str; 7] = ["AliasObj", "Cvtpgd", "Cvtres", "Export", "Implib", "Linker", "Masm"];
+
+/// Flavours of the compiler in the order every toolset from Visual Studio 2010 onwards lists them.
+const COMPILERS: [&str; 11] = [
+    "C",
+    "CPP",
+    "CVTCIL_C",
+    "CVTCIL_CPP",
+    "LTCG_C",
+    "LTCG_CPP",
+    "LTCG_MSIL",
+    "POGO_I_C",
+    "POGO_I_CPP",
+    "POGO_O_C",
+    "POGO_O_CPP",
+];
+
+/// Toolsets that share a layout, by their first product id.
+///
+/// Visual Studio 2015 and later kept using the same product id's.
+const TOOLSETS: [(u16, &str, u32, u32); 5] = [
+    (0x00b5, "Visual Studio 2010 SP1", 1010, 1610),
+    (0x00c7, "Visual Studio 2012", 1100, 1700),
+    (0x00d9, "Visual Studio 2013", 1200, 1800),
+    (0x00eb, "Visual Studio 2013", 1210, 1810),
+    (0x00fd, "Visual Studio 2015", 1400, 1900),
+];
+
+/// Releases of the MSVC 14.x toolset, by the first build number they shipped with.
+const RELEASES: [(u16, &str); 33] = [
+    (23026, "Visual Studio 2015 14.0"),
+    (23506, "Visual Studio 2015 14.0 Update 1"),
+    (23918, "Visual Studio 2015 14.0 Update 2"),
+    (24210, "Visual Studio 2015 14.0 Update 3"),
+    (25017, "Visual Studio 2017 15.0"),
+    (25506, "Visual Studio 2017 15.3"),
+    (25547, "Visual Studio 2017 15.4"),
+    (25830, "Visual Studio 2017 15.5"),
+    (26128, "Visual Studio 2017 15.6"),
+    (26428, "Visual Studio 2017 15.7"),
+    (26726, "Visual Studio 2017 15.8"),
+    (27023, "Visual Studio 2017 15.9"),
+    (27508, "Visual Studio 2019 16.0"),
+    (27702, "Visual Studio 2019 16.1"),
+    (27905, "Visual Studio 2019 16.2"),
+    (28105, "Visual Studio 2019 16.3"),
+    (28314, "Visual Studio 2019 16.4"),
+    (28610, "Visual Studio 2019 16.5"),
+    (28805, "Visual Studio 2019 16.6"),
+    (29110, "Visual Studio 2019 16.7"),
+    (29333, "Visual Studio 2019 16.8"),
+    (29910, "Visual Studio 2019 16.9"),
+    (30037, "Visual Studio 2019 16.10"),
+    (30133, "Visual Studio 2019 16.11"),
+    (30705, "Visual Studio 2022 17.0"),
+    (31104, "Visual Studio 2022 17.1"),
+    (31328, "Visual Studio 2022 17.2"),
+    (31629, "Visual Studio 2022 17.3"),
+    (31933, "Visual Studio 2022 17.4"),
+    (32215, "Visual Studio 2022 17.5"),
+    (32532, "Visual Studio 2022 17.6"),
+    (32822, "Visual Studio 2022 17.7"),
+    (33130, "Visual Studio 2022 17.8"),
+];
+
+/// Summarize the Rich header, `data` being the whole file.
+pub(crate) fn parse(data: &[u8], rich: &RichHeaderInfo) -> FileInfo {
+    let mut entries = vec![
+        ("XOR key".to_string(), format!("{:#010x}", rich.xor_key)),
+        ("Checksum".to_string(), match checksum(data, rich) == rich.xor_key {
+            true => "valid".to_string(),
+            false => "invalid, header was likely modified".to_string(),
+        }),
+    ];
+
+    for entry in rich.unmasked_entries() {
+        let product = (entry.comp_id >> 16) as u16;
+        let build = entry.comp_id as u16;
+        let (tool, toolset) = product_name(product);
+
+        let mut value = format!("build {build}, {} object(s)", entry.count);
+        match toolset {
+            // The product id's stopped changing so the build number determines the release.
+            Some("Visual Studio 2015") if build != 0 => {
+                let release = RELEASES.iter().rev().find(|(first, _)| *first <= build);
+                if let Some((_, release)) = release {
+                    value += &format!(" ({release})");
+                }
+            }
+            Some(toolset) => value += &format!(" ({toolset})"),
+            None => {}
+        }
+
+        entries.push((tool, value));
+    }
+
+    FileInfo {
+        title: "Rich header",
+        entries,
+    }
+}
+
+/// Recompute the XOR key, which is a checksum of the DOS header, stub and `@comp.id`'s.
+fn checksum(data: &[u8], rich: &RichHeaderInfo) -> u32 {
+    let mut checksum = rich.offset as u32;
+
+    for (idx, byte) in data.iter().take(rich.offset).enumerate() {
+        // `e_lfanew` isn't known when the linker computes the checksum.
+        if (0x3c..0x40).contains(&idx) {
+            continue;
+        }
+
+        checksum = checksum.wrapping_add((*byte as u32).rotate_left(idx as u32));
+    }
+
+    for entry in rich.unmasked_entries() {
+        checksum = checksum.wrapping_add(entry.comp_id.rotate_left(entry.count));
+    }
+
+    checksum
+}
+
+/// Name of the tool that produced an object and the Visual Studio release it came with.
+fn product_name(product: u16) -> (String, Option<&'static str>) {
+    let name = match product {
+        0x0000 => "Unmarked",
+        0x0001 => "Import0",
+        0x006d => "Utc1400_C",
+        0x006e => "Utc1400_CPP",
+        0x006f => "Utc1400_C_Std",
+        0x0070 => "Utc1400_CPP_Std",
+        0x0071 => "Utc1400_LTCG_C",
+        0x0072 => "Utc1400_LTCG_CPP",
+        0x0073 => "Utc1400_POGO_I_C",
+        0x0074 => "Utc1400_POGO_I_CPP",
+        0x0075 => "Utc1400_POGO_O_C",
+        0x0076 => "Utc1400_POGO_O_CPP",
+        0x0077 => "Cvtpgd1400",
+        0x0078 => "Linker800",
+        0x0079 => "Cvtomf800",
+        0x007a => "Export800",
+        0x007b => "Implib800",
+        0x007c => "Cvtres800",
+        0x007d => "Masm800",
+        0x007e => "AliasObj800",
+        0x0083..=0x008f => {
+            let compiler = match product - 0x0083 {
+                0 => "C",
+                1 => "CPP",
+                2 => "C_Std",
+                3 => "CPP_Std",
+                flavour => COMPILERS[flavour as usize - 2],
+            };
+            return (format!("Utc1500_{compiler}"), Some("Visual Studio 2008"));
+        }
+        0x0090 => "Cvtpgd1500",
+        0x0091 => "Linker900",
+        0x0092 => "Export900",
+        0x0093 => "Implib900",
+        0x0094 => "Cvtres900",
+        0x0095 => "Masm900",
+        0x0096 => "AliasObj900",
+        0x0097 => "Resource",
+        0x0098..=0x00b4 => {
+            let idx = (product - 0x0098) as usize;
+            let name = match idx {
+                1 => "Cvtpgd1600".to_string(),
+                0..=6 => format!("{}1000", TOOLS[idx]),
+                // The compiler is listed twice, once for the Phoenix backend.
+                7..=17 => format!("Phx1600_{}", COMPILERS[idx - 7]),
+                _ => format!("Utc1600_{}", COMPILERS[idx - 18]),
+            };
+            return (name, Some("Visual Studio 2010"));
+        }
+        _ => {
+            let toolset = TOOLSETS.iter().rev().find(|(first, ..)| *first <= product);
+            return match toolset {
+                Some((first, toolset, tool, compiler)) if product < first + 18 => {
+                    let idx = (product - first) as usize;
+                    let name = match idx {
+                        1 => format!("Cvtpgd{compiler}"),
+                        0..=6 => format!("{}{tool}", TOOLS[idx]),
+                        _ => format!("Utc{compiler}_{}", COMPILERS[idx - 7]),
+                    };
+                    (name, Some(toolset))
+                }
+                _ => (format!("product {product:#x}"), None),
+            };
+        }
+    };
+
+    let toolset = match product {
+        0x006d..=0x007e => Some("Visual Studio 2005"),
+        0x0090..=0x0096 => Some("Visual Studio 2008"),
+        _ => None,
+    };
+
+    (name.to_string(), toolset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::PeBuilder;
+    use object::read::pe::PeFile64;
+
+    /// The program every MSVC linked binary prints when run under DOS.
+    const DOS_STUB: &[u8] = b"\x0e\x1f\xba\x0e\x00\xb4\x09\xcd\x21\xb8\x01\x4c\xcd\x21\
+        This program cannot be run in DOS mode.\r\r\n$\0\0\0\0\0\0\0";
+
+    /// Checksum of the DOS header, `DOS_STUB` and `ENTRIES`, computed separately.
+    const XOR_KEY: u32 = 0xa945c991;
+
+    const ENTRIES: [(u32, u32); 5] = [
+        (0x0105 << 16 | 33130, 12),
+        (0x0102 << 16 | 33130, 1),
+        (0x00aa << 16 | 40219, 3),
+        (0x0093 << 16 | 30729, 2),
+        (0x0001 << 16, 5),
+    ];
+
+    fn masked(values: &[u32], key: u32) -> Vec<u8> {
+        values.iter().flat_map(|value| (value ^ key).to_le_bytes()).collect()
+    }
+
+    /// A PE with `DOS_STUB` and a Rich header masked with `key`.
+    fn pe(key: u32) -> Vec<u8> {
+        let mut stub = DOS_STUB.to_vec();
+        stub.extend(masked(&[u32::from_le_bytes(*b"DanS"), 0, 0, 0], key));
+        for (comp_id, count) in ENTRIES {
+            stub.extend(masked(&[comp_id, count], key));
+        }
+        stub.extend(b"Rich");
+        stub.extend(key.to_le_bytes());
+
+        let mut builder = PeBuilder::new(true);
+        builder.stub(stub);
+        builder.build()
+    }
+
+    fn decode(bytes: &[u8]) -> FileInfo {
+        let obj = PeFile64::parse(bytes).unwrap();
+        parse(obj.data(), &obj.rich_header_info().unwrap())
+    }
+
+    fn entries(info: &FileInfo) -> Vec<(&str, &str)> {
+        info.entries.iter().map(|(key, value)| (&key[..], &value[..])).collect()
+    }
+
+    #[test]
+    fn decode_entries() {
+        let info = decode(&pe(XOR_KEY));
+        assert_eq!(info.title, "Rich header");
+        assert_eq!(
+            entries(&info),
+            [
+                ("XOR key", "0xa945c991"),
+                ("Checksum", "valid"),
+                (
+                    "Utc1900_CPP",
+                    "build 33130, 12 object(s) (Visual Studio 2022 17.8)"
+                ),
+                (
+                    "Linker1400",
+                    "build 33130, 1 object(s) (Visual Studio 2022 17.8)"
+                ),
+                ("Utc1600_C", "build 40219, 3 object(s) (Visual Studio 2010)"),
+                ("Implib900", "build 30729, 2 object(s) (Visual Studio 2008)"),
+                ("Import0", "build 0, 5 object(s)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        // Relocating the NT headers doesn't change the checksum.
+        let mut bytes = pe(XOR_KEY);
+        bytes[0x3c..0x40].copy_from_slice(&0x100u32.to_le_bytes());
+        bytes.splice(0xc0..0xc0, [0; 0x40]);
+        let obj = PeFile64::parse(&*bytes).unwrap();
+        let rich = obj.rich_header_info().unwrap();
+        assert_eq!(checksum(obj.data(), &rich), XOR_KEY);
+
+        let mut bytes = pe(XOR_KEY);
+        bytes[0x4e] = b't';
+        assert_eq!(
+            decode(&bytes).entries[1].1,
+            "invalid, header was likely modified"
+        );
+
+        // Entries are still decoded with the key the header was masked with.
+        let info = decode(&pe(0x12345678));
+        assert_eq!(info.entries[1].1, "invalid, header was likely modified");
+        assert_eq!(info.entries[2].0, "Utc1900_CPP");
+    }
+
+    #[test]
+    fn product_names() {
+        assert_eq!(
+            product_name(0x00fd),
+            ("AliasObj1400".to_string(), Some("Visual Studio 2015"))
+        );
+        assert_eq!(
+            product_name(0x00ff),
+            ("Cvtres1400".to_string(), Some("Visual Studio 2015"))
+        );
+        assert_eq!(
+            product_name(0x00ec),
+            ("Cvtpgd1810".to_string(), Some("Visual Studio 2013"))
+        );
+        assert_eq!(
+            product_name(0x00a0),
+            ("Phx1600_CPP".to_string(), Some("Visual Studio 2010"))
+        );
+        assert_eq!(
+            product_name(0x0084),
+            ("Utc1500_CPP".to_string(), Some("Visual Studio 2008"))
+        );
+        assert_eq!(
+            product_name(0x0078),
+            ("Linker800".to_string(), Some("Visual Studio 2005"))
+        );
+        assert_eq!(product_name(0x0200), ("product 0x200".to_string(), None));
+    }
+}
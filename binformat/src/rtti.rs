@@ -0,0 +1,801 @@
+//! Recovery of C++ class hierarchies from runtime type information.
+//!
+//! Under the Itanium ABI every polymorphic class has a `type_info` object whose vtable pointer
+//! identifies it as a class without bases, with a single public base or with any other set of
+//! bases. Each of the class's vtables is preceded by the offset to the top of the object and a
+//! pointer to that `type_info`, layouts are described in section 2.9.5 of the Itanium C++ ABI.
+//!
+//! MSVC instead stores a pointer to a `RTTICompleteObjectLocator` right before every vftable,
+//! which points to the class's `TypeDescriptor` and a `RTTIClassHierarchyDescriptor` listing
+//! all of it's bases.
+
+use crate::image::Image;
+use crate::{Class, RawSymbol};
+use processor_shared::{AddressMap, Addressed, Section, SectionKind};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+/// Vtables of the `type_info` classes describing a class, indexed by [`Kind`].
+const CLASS_TYPE_INFO: [&str; 3] = [
+    "_ZTVN10__cxxabiv117__class_type_infoE",
+    "_ZTVN10__cxxabiv120__si_class_type_infoE",
+    "_ZTVN10__cxxabiv121__vmi_class_type_infoE",
+];
+
+/// Any class with more direct bases than this is assumed to be garbage.
+const MAX_BASES: u32 = 0x100;
+
+/// Offsets to the top of an object further away than this are assumed to be garbage.
+const MAX_OFFSET_TO_TOP: i64 = 0x100_0000;
+
+/// Signature of a 64-bit `RTTICompleteObjectLocator`, which uses image relative offsets.
+const COL_SIG_REV1: u32 = 1;
+
+pub(crate) struct Rtti {
+    /// Type information, vtables and virtual methods that weren't already named.
+    pub syms: AddressMap<RawSymbol<'static>>,
+    /// Every class that has type information.
+    pub classes: Vec<Class>,
+}
+
+/// What a `type_info` says about the bases of a class.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    /// `__class_type_info`, a class without bases.
+    Root,
+    /// `__si_class_type_info`, a class with a single public non-virtual base at offset zero.
+    Single,
+    /// `__vmi_class_type_info`, any other class.
+    Multiple,
+}
+
+/// Base class as referenced by a `type_info`.
+enum Base<'a> {
+    /// `type_info` defined in this image.
+    Local(u64),
+    /// `type_info` imported from another image, by symbol.
+    Bound(&'a str),
+}
+
+struct TypeInfo<'a> {
+    /// Mangled type without the `_Z` prefix.
+    name: &'static str,
+    /// Where the `type_info` ends.
+    end: u64,
+    bases: Vec<Base<'a>>,
+}
+
+/// Recover classes from Itanium ABI `type_info` objects and vtables.
+///
+/// `binds` are symbols that pointers are bound to when the image is loaded, by the address of the
+/// pointer. Mach-O symbols are prefixed with an underscore, which `prefix` accounts for.
+pub(crate) fn itanium(
+    image: &Image,
+    binds: &HashMap<u64, &str>,
+    syms: &AddressMap<RawSymbol>,
+    prefix: &str,
+) -> Rtti {
+    let mut rtti = Rtti {
+        syms: AddressMap::default(),
+        classes: Vec::new(),
+    };
+
+    let ptr_size = image.ptr_size();
+    let kind_of = |name: &str| {
+        let name = name.strip_prefix(prefix)?;
+        match CLASS_TYPE_INFO.iter().position(|vtable| *vtable == name)? {
+            0 => Some(Kind::Root),
+            1 => Some(Kind::Single),
+            _ => Some(Kind::Multiple),
+        }
+    };
+
+    // A `type_info`'s vtable pointer points past the vtable's offset to top and `type_info`.
+    let mut vptrs = HashMap::new();
+    for sym in syms.iter() {
+        if sym.addr == 0 || binds.contains_key(&(sym.addr as u64)) {
+            continue;
+        }
+
+        if let Some(kind) = kind_of(sym.item.name) {
+            vptrs.insert(sym.addr as u64 + 2 * ptr_size, kind);
+        }
+    }
+
+    if vptrs.is_empty() && !binds.values().any(|name| kind_of(name).is_some()) {
+        return rtti;
+    }
+
+    let mut types = HashMap::new();
+    for section in data_sections(image) {
+        for addr in slots(section, ptr_size) {
+            let kind = match binds.get(&addr) {
+                Some(name) => kind_of(name),
+                None => image.ptr_in(section, addr).and_then(|ptr| vptrs.get(&ptr).copied()),
+            };
+
+            if let Some(kind) = kind {
+                if let Some(typeinfo) = parse_typeinfo(image, binds, prefix, addr, kind) {
+                    types.insert(addr, typeinfo);
+                }
+            }
+        }
+    }
+
+    // Vtables are found by their pointer to a `type_info`, which bases are referenced by too.
+    let mut covered: Vec<(u64, u64)> = types.iter().map(|(&addr, ti)| (addr, ti.end)).collect();
+    covered.sort_unstable();
+    let is_covered = |addr: u64| {
+        let idx = covered.partition_point(|&(start, _)| start <= addr);
+        idx > 0 && addr < covered[idx - 1].1
+    };
+
+    let code = code_ranges(image);
+    let is_code = |ptr: u64| code.iter().any(|range| range.contains(&ptr));
+
+    let mut vtables: HashMap<u64, Vec<(u64, Vec<u64>)>> = HashMap::new();
+    for section in data_sections(image) {
+        for addr in slots(section, ptr_size) {
+            let typeinfo = match image.ptr_in(section, addr) {
+                Some(ptr) if types.contains_key(&ptr) => ptr,
+                _ => continue,
+            };
+
+            if is_covered(addr) || addr < section.start as u64 + ptr_size {
+                continue;
+            }
+
+            // The slot before is the offset to the top of the object, it's zero for the
+            // primary vtable and negative for the secondary ones.
+            let offset_addr = addr - ptr_size;
+            if binds.contains_key(&offset_addr) || image.relocs.contains_key(&offset_addr) {
+                continue;
+            }
+
+            let offset_to_top = match image.ptr_in(section, offset_addr) {
+                Some(offset) if image.is_64 => offset as i64,
+                Some(offset) => offset as u32 as i32 as i64,
+                None => continue,
+            };
+
+            if !(-MAX_OFFSET_TO_TOP..=0).contains(&offset_to_top) {
+                continue;
+            }
+
+            let mut entries = Vec::new();
+            let mut slot = addr + ptr_size;
+            while slot + ptr_size <= section.end as u64 && !is_covered(slot) {
+                // Pure virtual methods and methods inherited from another image are bound.
+                if let Some(name) = binds.get(&slot) {
+                    // Other vtables and `type_info` objects are bound to their own metadata.
+                    match name.strip_prefix(prefix) {
+                        Some(name) if !name.starts_with("_ZT") => entries.push(slot),
+                        _ => break,
+                    }
+                } else {
+                    match image.ptr_in(section, slot) {
+                        Some(ptr) if is_code(ptr) => entries.push(ptr),
+                        _ => break,
+                    }
+                }
+                slot += ptr_size;
+            }
+
+            vtables.entry(typeinfo).or_default().push((addr + ptr_size, entries));
+        }
+    }
+
+    let class_name = |addr: u64| types.get(&addr).map(|ti| format!("_Z{}", ti.name));
+    let mut named: HashSet<usize> = syms.iter().map(|sym| sym.addr).collect();
+
+    // Bases are named first, so that methods they share with their derived classes are named
+    // after the class that introduced them.
+    let mut order: Vec<(usize, u64)> =
+        types.keys().map(|&addr| (depth(&types, addr, 0), addr)).collect();
+    order.sort_unstable();
+
+    for (_, addr) in order {
+        let typeinfo = &types[&addr];
+        let name = typeinfo.name;
+
+        let bases = typeinfo
+            .bases
+            .iter()
+            .filter_map(|base| match base {
+                Base::Local(addr) => class_name(*addr),
+                Base::Bound(sym) => {
+                    let sym = sym.strip_prefix(prefix)?;
+                    sym.strip_prefix("_ZTI").map(|name| format!("_Z{name}"))
+                }
+            })
+            .collect();
+
+        push_sym(
+            &mut rtti.syms,
+            &mut named,
+            addr,
+            format!("{prefix}_ZTI{name}"),
+        );
+        if let Some(string) = image.ptr(addr + ptr_size) {
+            push_sym(
+                &mut rtti.syms,
+                &mut named,
+                string,
+                format!("{prefix}_ZTS{name}"),
+            );
+        }
+
+        // Methods are named as members of the class, which requires a nested name.
+        let scope = match name.strip_prefix('N') {
+            Some(nested) => nested.strip_suffix('E'),
+            None if name.starts_with('Z') => None,
+            None => Some(name),
+        };
+
+        let mut class_vtables = vtables.remove(&addr).unwrap_or_default();
+        class_vtables.sort_unstable_by_key(|(vtable, _)| *vtable);
+
+        // Secondary vtables mostly hold thunks, so only the primary one's methods are named.
+        if let Some((vtable, entries)) = class_vtables.first() {
+            // The symbol of a vtable group is at the primary vtable's offset to top.
+            let start = vtable - 2 * ptr_size;
+            if image.ptr(start) == Some(0) {
+                push_sym(
+                    &mut rtti.syms,
+                    &mut named,
+                    start,
+                    format!("{prefix}_ZTV{name}"),
+                );
+            }
+
+            for (idx, &entry) in entries.iter().enumerate() {
+                let scope = match scope {
+                    Some(scope) if !binds.contains_key(&entry) => scope,
+                    _ => continue,
+                };
+
+                let method = format!("vfunc_{idx}");
+                let method = format!("{prefix}_ZN{scope}{}{method}E", method.len());
+                push_sym(&mut rtti.syms, &mut named, entry & !1, method);
+            }
+        }
+
+        rtti.classes.push(Class {
+            name: format!("_Z{name}"),
+            typeinfo: addr as usize,
+            bases,
+            vtables: class_vtables
+                .into_iter()
+                .map(|(vtable, entries)| {
+                    (
+                        vtable as usize,
+                        entries.into_iter().map(|entry| entry as usize).collect(),
+                    )
+                })
+                .collect(),
+        });
+    }
+
+    log::complex!(
+        w "[rtti::itanium] found ",
+        g rtti.classes.len().to_string(),
+        w " classes."
+    );
+
+    rtti
+}
+
+/// Parse a `type_info` of a class, which starts with a vtable pointer and a pointer to it's
+/// mangled name.
+fn parse_typeinfo<'a>(
+    image: &Image,
+    binds: &HashMap<u64, &'a str>,
+    prefix: &str,
+    addr: u64,
+    kind: Kind,
+) -> Option<TypeInfo<'a>> {
+    let ptr_size = image.ptr_size();
+    let name = image.cstr(image.ptr(addr + ptr_size)?)?;
+
+    // Types local to a translation unit are compared by address, which GCC marks with a '*'.
+    let name = name.strip_prefix('*').unwrap_or(name);
+    if !is_type_name(name) {
+        return None;
+    }
+
+    let base = |addr: u64| match binds.get(&addr) {
+        Some(sym) if sym.strip_prefix(prefix)?.starts_with("_ZTI") => Some(Base::Bound(sym)),
+        Some(_) => None,
+        None => image.ptr(addr).filter(|&ptr| ptr != 0).map(Base::Local),
+    };
+
+    let mut bases = Vec::new();
+    let end = match kind {
+        Kind::Root => addr + 2 * ptr_size,
+        Kind::Single => {
+            bases.push(base(addr + 2 * ptr_size)?);
+            addr + 3 * ptr_size
+        }
+        Kind::Multiple => {
+            let count = image.u32(addr + 2 * ptr_size + 4)?;
+            if count > MAX_BASES {
+                return None;
+            }
+
+            // Every base is described by a pointer and a `long` of offset and flags.
+            let array = addr + 2 * ptr_size + 8;
+            for idx in 0..count as u64 {
+                bases.push(base(array + idx * 2 * ptr_size)?);
+            }
+            array + count as u64 * 2 * ptr_size
+        }
+    };
+
+    Some(TypeInfo { name, end, bases })
+}
+
+/// Recover classes from MSVC complete object locators and type descriptors.
+pub(crate) fn msvc(image: &Image, syms: &AddressMap<RawSymbol>) -> Rtti {
+    let mut rtti = Rtti {
+        syms: AddressMap::default(),
+        classes: Vec::new(),
+    };
+
+    let ptr_size = image.ptr_size();
+
+    // 64-bit images reference structures by their offset from the image's base.
+    let reference = |addr: u64| -> Option<u64> {
+        let value = image.u32(addr)? as u64;
+        match image.is_64 {
+            true => Some(image.base_addr + value),
+            false => Some(value),
+        }
+    };
+
+    // A type descriptor holds a vtable pointer, a spare pointer and the decorated name.
+    let type_descriptor = |addr: u64| -> Option<&'static str> {
+        let name = image.cstr(addr + 2 * ptr_size)?;
+        match name.starts_with(".?AV") || name.starts_with(".?AU") {
+            true => Some(name),
+            false => None,
+        }
+    };
+
+    // Complete object locators by the type descriptor of their class.
+    let mut locators: HashMap<u64, Vec<(u64, u32)>> = HashMap::new();
+    // Direct bases and the total number of bases by type descriptor.
+    let mut hierarchies: HashMap<u64, (Vec<u64>, u32)> = HashMap::new();
+
+    for section in data_sections(image) {
+        let mut addr = section.start as u64;
+        while addr + 24 <= section.end as u64 {
+            let col = addr;
+            addr += 4;
+
+            let signature = match section.read_at::<u32>(col as usize, image.endian) {
+                Ok(signature) => signature,
+                Err(..) => continue,
+            };
+
+            match image.is_64 {
+                // The locator's offset to itself is checked as it's a cheap and strong signal.
+                true if signature == COL_SIG_REV1 => match image.u32(col + 20) {
+                    Some(offset) if offset as u64 + image.base_addr == col => {}
+                    _ => continue,
+                },
+                false if signature == 0 => {}
+                _ => continue,
+            }
+
+            let (offset, td, chd) =
+                match (image.u32(col + 4), reference(col + 12), reference(col + 16)) {
+                    (Some(offset), Some(td), Some(chd)) => (offset, td, chd),
+                    _ => continue,
+                };
+
+            if type_descriptor(td).is_none() {
+                continue;
+            }
+
+            if let Entry::Vacant(entry) = hierarchies.entry(td) {
+                match parse_hierarchy(image, td, chd, &reference) {
+                    Some(hierarchy) => entry.insert(hierarchy),
+                    None => continue,
+                };
+            }
+
+            locators.entry(td).or_default().push((col, offset));
+        }
+    }
+
+    if locators.is_empty() {
+        return rtti;
+    }
+
+    // Every vftable is preceded by a pointer to it's complete object locator.
+    let cols: HashSet<u64> = locators.values().flatten().map(|&(col, _)| col).collect();
+    let code = code_ranges(image);
+    let is_code = |ptr: u64| code.iter().any(|range| range.contains(&ptr));
+
+    let mut vftables: HashMap<u64, (u64, Vec<u64>)> = HashMap::new();
+    for section in data_sections(image) {
+        for addr in slots(section, ptr_size) {
+            let col = match image.ptr_in(section, addr) {
+                Some(ptr) if cols.contains(&ptr) => ptr,
+                _ => continue,
+            };
+
+            let mut entries = Vec::new();
+            let mut slot = addr + ptr_size;
+            while let Some(ptr) = image.ptr_in(section, slot).filter(|&ptr| is_code(ptr)) {
+                entries.push(ptr);
+                slot += ptr_size;
+            }
+
+            vftables.insert(col, (addr + ptr_size, entries));
+        }
+    }
+
+    let name_of = |td: u64| type_descriptor(td).map(|name| format!("?{}", &name[4..]));
+    let mut named: HashSet<usize> = syms.iter().map(|sym| sym.addr).collect();
+
+    // Bases are named first, so that methods they share with their derived classes are named
+    // after the class that introduced them.
+    let mut order: Vec<(u32, u64)> = hierarchies.iter().map(|(&td, h)| (h.1, td)).collect();
+    order.sort_unstable();
+
+    for (_, td) in order {
+        let decorated = match type_descriptor(td) {
+            Some(name) => name,
+            None => continue,
+        };
+        let name = &decorated[4..];
+
+        push_sym(
+            &mut rtti.syms,
+            &mut named,
+            td,
+            format!("??_R0{}@8", &decorated[1..]),
+        );
+
+        let mut class_locators = locators.remove(&td).unwrap_or_default();
+        class_locators.sort_unstable_by_key(|&(_, offset)| offset);
+
+        let mut class_vftables = Vec::new();
+        for (col, offset) in class_locators {
+            // Names of vftables for a base at a non-zero offset also name the base.
+            if offset == 0 {
+                push_sym(&mut rtti.syms, &mut named, col, format!("??_R4{name}6B@"));
+            }
+
+            let (vftable, entries) = match vftables.remove(&col) {
+                Some(vftable) => vftable,
+                None => continue,
+            };
+
+            // Secondary vftables mostly hold adjustor thunks, so only the primary one's
+            // methods are named.
+            if offset == 0 {
+                push_sym(
+                    &mut rtti.syms,
+                    &mut named,
+                    vftable,
+                    format!("??_7{name}6B@"),
+                );
+                for (idx, &entry) in entries.iter().enumerate() {
+                    push_sym(
+                        &mut rtti.syms,
+                        &mut named,
+                        entry,
+                        format!("?vfunc_{idx}@{name}"),
+                    );
+                }
+            }
+
+            class_vftables.push((
+                vftable as usize,
+                entries.into_iter().map(|entry| entry as usize).collect(),
+            ));
+        }
+
+        rtti.classes.push(Class {
+            name: format!("?{name}"),
+            typeinfo: td as usize,
+            bases: hierarchies[&td].0.iter().filter_map(|&base| name_of(base)).collect(),
+            vtables: class_vftables,
+        });
+    }
+
+    log::complex!(
+        w "[rtti::msvc] found ",
+        g rtti.classes.len().to_string(),
+        w " classes."
+    );
+
+    rtti
+}
+
+/// Parse a `RTTIClassHierarchyDescriptor` into the type descriptors of the direct bases and
+/// the total number of bases.
+///
+/// The base class array lists the class itself followed by every base in depth-first order,
+/// where each entry says how many of the entries after it are it's own bases.
+fn parse_hierarchy(
+    image: &Image,
+    td: u64,
+    chd: u64,
+    reference: &impl Fn(u64) -> Option<u64>,
+) -> Option<(Vec<u64>, u32)> {
+    let count = image.u32(chd + 8)?;
+    if count == 0 || count > MAX_BASES {
+        return None;
+    }
+
+    let array = reference(chd + 12)?;
+    let descriptor = |idx: u32| reference(array + idx as u64 * 4);
+
+    // The first entry describes the class itself.
+    if reference(descriptor(0)?)? != td {
+        return None;
+    }
+
+    let mut bases = Vec::new();
+    let mut idx = 1;
+    while idx < count {
+        let bcd = descriptor(idx)?;
+        bases.push(reference(bcd)?);
+        idx += 1 + image.u32(bcd + 4)?;
+    }
+
+    Some((bases, count - 1))
+}
+
+/// Number of bases between a class and the root of it's hierarchy.
+fn depth(types: &HashMap<u64, TypeInfo>, addr: u64, level: usize) -> usize {
+    // Guard against cycles in malformed type information.
+    if level > MAX_BASES as usize {
+        return level;
+    }
+
+    types[&addr]
+        .bases
+        .iter()
+        .filter_map(|base| match base {
+            Base::Local(base) if types.contains_key(base) => Some(depth(types, *base, level + 1)),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(level)
+}
+
+/// Whether a string looks like a mangled type, e.g. `N3foo3BarE`.
+fn is_type_name(name: &str) -> bool {
+    match name.bytes().next() {
+        Some(b'0'..=b'9' | b'N' | b'S' | b'Z') => {}
+        _ => return false,
+    }
+
+    name.bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b == b'.')
+}
+
+fn push_sym(
+    syms: &mut AddressMap<RawSymbol<'static>>,
+    named: &mut HashSet<usize>,
+    addr: u64,
+    name: String,
+) {
+    if addr == 0 || !named.insert(addr as usize) {
+        return;
+    }
+
+    syms.push(Addressed {
+        addr: addr as usize,
+        item: RawSymbol {
+            name: Box::leak(name.into_boxed_str()),
+            module: None,
+            ordinal: None,
+        },
+    });
+}
+
+fn data_sections<'a>(image: &'a Image) -> impl Iterator<Item = &'a Section> {
+    image
+        .sections
+        .iter()
+        .filter(|s| s.kind != SectionKind::Code && s.kind != SectionKind::Unloaded)
+}
+
+fn code_ranges(image: &Image) -> Vec<std::ops::Range<u64>> {
+    image
+        .sections
+        .iter()
+        .filter(|s| s.kind == SectionKind::Code)
+        .map(|s| s.start as u64..s.end as u64)
+        .collect()
+}
+
+/// Addresses of every aligned pointer in a section.
+fn slots(section: &Section, ptr_size: u64) -> impl Iterator<Item = u64> {
+    let start = (section.start as u64 + ptr_size - 1) & !(ptr_size - 1);
+    let end = (section.end as u64).saturating_sub(ptr_size - 1);
+    (start..end).step_by(ptr_size as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object::Endianness;
+
+    fn section(name: &str, kind: SectionKind, start: usize, bytes: Vec<u8>) -> Section {
+        let end = start + bytes.len();
+        let bytes = Box::leak(bytes.into_boxed_slice());
+        Section::new(name.to_string(), "", kind, bytes, start, end)
+    }
+
+    fn words(words: &[u64]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn sym(addr: usize, name: &'static str) -> Addressed<RawSymbol<'static>> {
+        Addressed {
+            addr,
+            item: RawSymbol {
+                name,
+                module: None,
+                ordinal: None,
+            },
+        }
+    }
+
+    /// `Foo` without bases, `Bar` deriving from `Foo` and `ns::Baz` deriving from `Foo` and the
+    /// imported `Ext`. Only `Foo`'s `type_info` vtable is defined in the image.
+    fn sections() -> Vec<Section> {
+        let mut rodata = vec![0; 0x30];
+        rodata[0x00..0x04].copy_from_slice(b"3Foo");
+        rodata[0x10..0x14].copy_from_slice(b"3Bar");
+        rodata[0x20..0x29].copy_from_slice(b"N2ns3BazE");
+
+        let mut data = vec![0; 0x270];
+        let mut put = |addr: usize, values: &[u64]| {
+            let offset = addr - 0x3000;
+            data[offset..offset + values.len() * 8].copy_from_slice(&words(values));
+        };
+
+        // `type_info` objects, with their vtable pointer bound unless it's local.
+        put(0x3100, &[0x5010, 0x2000]);
+        put(0x3110, &[0, 0x2010, 0x3100]);
+        put(0x3128, &[0, 0x2020, 2 << 32, 0x3100, 0x2, 0, 0x802]);
+
+        // Vtables, `Baz` having a secondary vtable for `Ext`.
+        put(0x3200, &[0, 0x3100, 0x1000, 0x1010]);
+        put(0x3220, &[0, 0x3110, 0x1020, 0]);
+        put(0x3240, &[0, 0x3128, 0x1030]);
+        put(0x3258, &[-8i64 as u64, 0x3128, 0x1040]);
+
+        vec![
+            section(".text", SectionKind::Code, 0x1000, vec![0xc3; 0x100]),
+            section(".rodata", SectionKind::Raw, 0x2000, rodata),
+            section(".data.rel.ro", SectionKind::Raw, 0x3000, data),
+        ]
+    }
+
+    fn image(sections: &[Section]) -> Image<'_> {
+        Image {
+            sections,
+            endian: Endianness::Little,
+            is_64: true,
+            base_addr: 0,
+            chained: false,
+            relocs: HashMap::new(),
+        }
+    }
+
+    fn binds() -> HashMap<u64, &'static str> {
+        HashMap::from([
+            (0x3110, "_ZTVN10__cxxabiv120__si_class_type_infoE"),
+            (0x3128, "_ZTVN10__cxxabiv121__vmi_class_type_infoE"),
+            (0x3150, "_ZTI3Ext"),
+            (0x3238, "__cxa_pure_virtual"),
+        ])
+    }
+
+    #[test]
+    fn itanium_classes() {
+        let sections = sections();
+        let image = image(&sections);
+        let syms = AddressMap {
+            mapping: vec![sym(0x5000, "_ZTVN10__cxxabiv117__class_type_infoE")],
+        };
+
+        let rtti = itanium(&image, &binds(), &syms, "");
+        let classes: Vec<_> = rtti
+            .classes
+            .iter()
+            .map(|class| {
+                (
+                    class.name.as_str(),
+                    class.typeinfo,
+                    &class.bases,
+                    &class.vtables,
+                )
+            })
+            .collect();
+        assert_eq!(
+            classes,
+            [
+                (
+                    "_Z3Foo",
+                    0x3100,
+                    &vec![],
+                    &vec![(0x3210, vec![0x1000, 0x1010])]
+                ),
+                (
+                    "_Z3Bar",
+                    0x3110,
+                    &vec!["_Z3Foo".to_string()],
+                    &vec![(0x3230, vec![0x1020, 0x3238])]
+                ),
+                (
+                    "_ZN2ns3BazE",
+                    0x3128,
+                    &vec!["_Z3Foo".to_string(), "_Z3Ext".to_string()],
+                    &vec![(0x3250, vec![0x1030]), (0x3268, vec![0x1040])]
+                ),
+            ]
+        );
+
+        let mut names: Vec<_> = rtti.syms.iter().map(|sym| (sym.addr, sym.item.name)).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                (0x1000, "_ZN3Foo7vfunc_0E"),
+                (0x1010, "_ZN3Foo7vfunc_1E"),
+                (0x1020, "_ZN3Bar7vfunc_0E"),
+                (0x1030, "_ZN2ns3Baz7vfunc_0E"),
+                (0x2000, "_ZTS3Foo"),
+                (0x2010, "_ZTS3Bar"),
+                (0x2020, "_ZTSN2ns3BazE"),
+                (0x3100, "_ZTI3Foo"),
+                (0x3110, "_ZTI3Bar"),
+                (0x3128, "_ZTIN2ns3BazE"),
+                (0x3200, "_ZTV3Foo"),
+                (0x3220, "_ZTV3Bar"),
+                (0x3240, "_ZTVN2ns3BazE"),
+            ]
+        );
+    }
+
+    #[test]
+    fn itanium_named() {
+        let sections = sections();
+        let image = image(&sections);
+
+        // Without the local `type_info` vtable, only classes with bound ones are found.
+        let rtti = itanium(&image, &binds(), &AddressMap::default(), "");
+        let classes: Vec<_> = rtti.classes.iter().map(|class| class.name.as_str()).collect();
+        assert_eq!(classes, ["_Z3Bar", "_ZN2ns3BazE"]);
+
+        // Symbols already in the symbol table aren't named again.
+        let syms = AddressMap {
+            mapping: vec![
+                sym(0x5000, "_ZTVN10__cxxabiv117__class_type_infoE"),
+                sym(0x1000, "_ZN3Foo3runEv"),
+            ],
+        };
+        let rtti = itanium(&image, &binds(), &syms, "");
+        assert!(rtti.syms.iter().all(|sym| sym.addr != 0x1000));
+        assert!(rtti.syms.iter().any(|sym| sym.item.name == "_ZN3Foo7vfunc_1E"));
+    }
+
+    #[test]
+    fn type_names() {
+        assert!(is_type_name("3Foo"));
+        assert!(is_type_name("N2ns3BazE"));
+        assert!(is_type_name("St9exception"));
+        assert!(!is_type_name(""));
+        assert!(!is_type_name("Foo"));
+        assert!(!is_type_name("3Foo bar"));
+    }
+}
@@ -0,0 +1,395 @@
+//! Tiny little-endian ELF64 and PE files for the tests, as there's no writer to build them with.
+#![cfg(test)]
+
+use object::elf;
+use object::endian::{LittleEndian as LE, I64, U16, U32, U64};
+use object::pe;
+use object::pod::{bytes_of, Pod};
+use std::mem::size_of;
+
+/// Header fields and contents of a section, anything left out being zero.
+#[derive(Default)]
+pub struct SectionSpec {
+    pub name: &'static str,
+    pub sh_type: u32,
+    pub flags: u64,
+    pub data: Vec<u8>,
+    pub link: u32,
+    pub info: u32,
+    pub align: u64,
+    pub entsize: u64,
+}
+
+/// An ELF file built up section by section, with index 0 being the null section.
+pub struct ElfBuilder {
+    e_type: u16,
+    machine: u16,
+    sections: Vec<SectionSpec>,
+}
+
+impl ElfBuilder {
+    pub fn new(e_type: u16, machine: u16) -> Self {
+        Self {
+            e_type,
+            machine,
+            sections: Vec::new(),
+        }
+    }
+
+    /// Adds a section, returning it's index.
+    pub fn section(&mut self, section: SectionSpec) -> u32 {
+        self.sections.push(section);
+        self.sections.len() as u32
+    }
+
+    /// Adds a `.symtab` (or `.dynsym`) followed by it's string table, returning the index of
+    /// the symbol table. Symbols are `(name, section index, value, st_info)`.
+    pub fn symbols(&mut self, name: &'static str, syms: &[(&str, u16, u64, u8)]) -> u32 {
+        let (sh_type, strtab_name) = match name {
+            ".dynsym" => (elf::SHT_DYNSYM, ".dynstr"),
+            _ => (elf::SHT_SYMTAB, ".strtab"),
+        };
+
+        let mut strtab = vec![0];
+        let mut symtab = vec![0; 24];
+        for &(sym_name, shndx, value, info) in syms {
+            let st_name = strtab.len() as u32;
+            strtab.extend_from_slice(sym_name.as_bytes());
+            strtab.push(0);
+
+            symtab.extend_from_slice(bytes_of(&elf::Sym64 {
+                st_name: U32::new(LE, st_name),
+                st_info: info,
+                st_other: 0,
+                st_shndx: U16::new(LE, shndx),
+                st_value: U64::new(LE, value),
+                st_size: U64::new(LE, 0),
+            }));
+        }
+
+        let idx = self.section(SectionSpec {
+            name,
+            sh_type,
+            data: symtab,
+            link: self.sections.len() as u32 + 2,
+            info: 1,
+            align: 8,
+            entsize: 24,
+            ..Default::default()
+        });
+        self.section(SectionSpec {
+            name: strtab_name,
+            sh_type: elf::SHT_STRTAB,
+            data: strtab,
+            align: 1,
+            ..Default::default()
+        });
+        idx
+    }
+
+    /// Adds a `.rela` section for `target`, with entries `(offset, symbol, type, addend)`.
+    pub fn rela(
+        &mut self,
+        name: &'static str,
+        symtab: u32,
+        target: u32,
+        relocs: &[(u64, u32, u32, i64)],
+    ) -> u32 {
+        let mut data = Vec::new();
+        for &(offset, sym, r_type, addend) in relocs {
+            data.extend_from_slice(bytes_of(&elf::Rela64 {
+                r_offset: U64::new(LE, offset),
+                r_info: U64::new(LE, (sym as u64) << 32 | r_type as u64),
+                r_addend: I64::new(LE, addend),
+            }));
+        }
+
+        self.section(SectionSpec {
+            name,
+            sh_type: elf::SHT_RELA,
+            data,
+            link: symtab,
+            info: target,
+            align: 8,
+            entsize: 24,
+            ..Default::default()
+        })
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut shstrtab = vec![0];
+        let mut names = Vec::new();
+        for section in self.sections.iter().map(|s| s.name).chain([".shstrtab"]) {
+            names.push(shstrtab.len() as u32);
+            shstrtab.extend_from_slice(section.as_bytes());
+            shstrtab.push(0);
+        }
+
+        let mut bytes = vec![0; 64];
+        let mut offsets = Vec::new();
+        for data in self.sections.iter().map(|s| &s.data).chain([&shstrtab]) {
+            bytes.resize(bytes.len().next_multiple_of(8), 0);
+            offsets.push(bytes.len() as u64);
+            bytes.extend_from_slice(data);
+        }
+
+        bytes.resize(bytes.len().next_multiple_of(8), 0);
+
+        let shoff = bytes.len() as u64;
+        let shnum = self.sections.len() as u16 + 2;
+        let header = elf::FileHeader64 {
+            e_ident: elf::Ident {
+                magic: elf::ELFMAG,
+                class: elf::ELFCLASS64,
+                data: elf::ELFDATA2LSB,
+                version: elf::EV_CURRENT,
+                os_abi: elf::ELFOSABI_NONE,
+                abi_version: 0,
+                padding: [0; 7],
+            },
+            e_type: U16::new(LE, self.e_type),
+            e_machine: U16::new(LE, self.machine),
+            e_version: U32::new(LE, elf::EV_CURRENT as u32),
+            e_entry: U64::new(LE, 0),
+            e_phoff: U64::new(LE, 0),
+            e_shoff: U64::new(LE, shoff),
+            e_flags: U32::new(LE, 0),
+            e_ehsize: U16::new(LE, 64),
+            e_phentsize: U16::new(LE, 0),
+            e_phnum: U16::new(LE, 0),
+            e_shentsize: U16::new(LE, 64),
+            e_shnum: U16::new(LE, shnum),
+            e_shstrndx: U16::new(LE, shnum - 1),
+        };
+        bytes[..64].copy_from_slice(bytes_of(&header));
+
+        let header = |name, section: &SectionSpec, offset, size| elf::SectionHeader64 {
+            sh_name: U32::new(LE, name),
+            sh_type: U32::new(LE, section.sh_type),
+            sh_flags: U64::new(LE, section.flags),
+            sh_addr: U64::new(LE, 0),
+            sh_offset: U64::new(LE, offset),
+            sh_size: U64::new(LE, size),
+            sh_link: U32::new(LE, section.link),
+            sh_info: U32::new(LE, section.info),
+            sh_addralign: U64::new(LE, section.align),
+            sh_entsize: U64::new(LE, section.entsize),
+        };
+
+        bytes.extend_from_slice(bytes_of(&header(0, &SectionSpec::default(), 0, 0)));
+        for (idx, section) in self.sections.iter().enumerate() {
+            let size = section.data.len() as u64;
+            bytes.extend_from_slice(bytes_of(&header(names[idx], section, offsets[idx], size)));
+        }
+
+        let idx = self.sections.len();
+        let size = shstrtab.len() as u64;
+        let section = SectionSpec {
+            sh_type: elf::SHT_STRTAB,
+            align: 1,
+            ..Default::default()
+        };
+        bytes.extend_from_slice(bytes_of(&header(names[idx], &section, offsets[idx], size)));
+        bytes
+    }
+}
+
+/// Relocatable object with a `.text` and `.data`, followed by the given symbols and
+/// relocations against `.text`.
+pub fn relocatable(
+    machine: u16,
+    text: &[u8],
+    data: &[u8],
+    data_align: u64,
+    syms: &[(&str, u16, u64, u8)],
+    relocs: &[(u64, u32, u32, i64)],
+) -> Vec<u8> {
+    let mut builder = ElfBuilder::new(elf::ET_REL, machine);
+    builder.section(SectionSpec {
+        name: ".text",
+        sh_type: elf::SHT_PROGBITS,
+        flags: (elf::SHF_ALLOC | elf::SHF_EXECINSTR) as u64,
+        data: text.to_vec(),
+        align: 16,
+        ..Default::default()
+    });
+    builder.section(SectionSpec {
+        name: ".data",
+        sh_type: elf::SHT_PROGBITS,
+        flags: (elf::SHF_ALLOC | elf::SHF_WRITE) as u64,
+        data: data.to_vec(),
+        align: data_align,
+        ..Default::default()
+    });
+    let symtab = builder.symbols(".symtab", syms);
+    builder.rela(".rela.text", symtab, 1, relocs);
+    builder.build()
+}
+
+/// Symbol info of a global function or object.
+pub const GLOBAL: u8 = elf::STB_GLOBAL << 4;
+
+/// GNU style static archive holding `members`, without a symbol table.
+pub fn archive(members: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut bytes = b"!<arch>\n".to_vec();
+    for (name, data) in members {
+        let header = format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            format!("{name}/"),
+            0,
+            0,
+            0,
+            644,
+            data.len()
+        );
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        if bytes.len() % 2 == 1 {
+            bytes.push(b'\n');
+        }
+    }
+    bytes
+}
+
+/// All zero header, to be filled in field by field.
+fn zeroed<T: Pod>() -> T {
+    *object::pod::from_bytes(&vec![0; size_of::<T>()]).unwrap().0
+}
+
+/// A PE image built up section by section, the first section being at RVA `0x1000` and every
+/// following one `0x1000` bytes after the previous.
+pub struct PeBuilder {
+    is_64: bool,
+    stub: Vec<u8>,
+    directories: Vec<(usize, u32, u32)>,
+    sections: Vec<(&'static str, u32, Vec<u8>)>,
+}
+
+impl PeBuilder {
+    pub fn new(is_64: bool) -> Self {
+        Self {
+            is_64,
+            stub: Vec::new(),
+            directories: Vec::new(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Image base the RVAs are relative to.
+    pub fn image_base(&self) -> u64 {
+        if self.is_64 {
+            0x140000000
+        } else {
+            0x400000
+        }
+    }
+
+    /// Bytes between the DOS header and the NT headers, where the Rich header goes.
+    pub fn stub(&mut self, stub: Vec<u8>) {
+        self.stub = stub;
+    }
+
+    /// Points data directory `idx` at `size` bytes at `rva`.
+    pub fn directory(&mut self, idx: usize, rva: u32, size: u32) {
+        self.directories.push((idx, rva, size));
+    }
+
+    /// Adds a section, returning it's RVA.
+    pub fn section(&mut self, name: &'static str, characteristics: u32, data: Vec<u8>) -> u32 {
+        self.sections.push((name, characteristics, data));
+        self.sections.len() as u32 * 0x1000
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let nt = (size_of::<pe::ImageDosHeader>() + self.stub.len()).next_multiple_of(8);
+        let optional_size = if self.is_64 {
+            size_of::<pe::ImageOptionalHeader64>()
+        } else {
+            size_of::<pe::ImageOptionalHeader32>()
+        } + 16 * size_of::<pe::ImageDataDirectory>();
+        let headers_size = nt
+            + 4
+            + size_of::<pe::ImageFileHeader>()
+            + optional_size
+            + self.sections.len() * size_of::<pe::ImageSectionHeader>();
+        let headers_size = headers_size.next_multiple_of(0x200);
+
+        let mut dos_header: pe::ImageDosHeader = zeroed();
+        dos_header.e_magic = U16::new(LE, pe::IMAGE_DOS_SIGNATURE);
+        dos_header.e_lfanew = U32::new(LE, nt as u32);
+
+        let mut bytes = bytes_of(&dos_header).to_vec();
+        bytes.extend_from_slice(&self.stub);
+        bytes.resize(nt, 0);
+        bytes.extend_from_slice(&pe::IMAGE_NT_SIGNATURE.to_le_bytes());
+
+        let mut file_header: pe::ImageFileHeader = zeroed();
+        file_header.machine = U16::new(
+            LE,
+            if self.is_64 {
+                pe::IMAGE_FILE_MACHINE_AMD64
+            } else {
+                pe::IMAGE_FILE_MACHINE_I386
+            },
+        );
+        file_header.number_of_sections = U16::new(LE, self.sections.len() as u16);
+        file_header.size_of_optional_header = U16::new(LE, optional_size as u16);
+        file_header.characteristics = U16::new(LE, pe::IMAGE_FILE_EXECUTABLE_IMAGE);
+        bytes.extend_from_slice(bytes_of(&file_header));
+
+        let size_of_image = U32::new(LE, (self.sections.len() as u32 + 1) * 0x1000);
+        if self.is_64 {
+            let mut header: pe::ImageOptionalHeader64 = zeroed();
+            header.magic = U16::new(LE, pe::IMAGE_NT_OPTIONAL_HDR64_MAGIC);
+            header.image_base = U64::new(LE, self.image_base());
+            header.section_alignment = U32::new(LE, 0x1000);
+            header.file_alignment = U32::new(LE, 0x200);
+            header.size_of_image = size_of_image;
+            header.size_of_headers = U32::new(LE, headers_size as u32);
+            header.subsystem = U16::new(LE, pe::IMAGE_SUBSYSTEM_WINDOWS_CUI);
+            header.number_of_rva_and_sizes = U32::new(LE, 16);
+            bytes.extend_from_slice(bytes_of(&header));
+        } else {
+            let mut header: pe::ImageOptionalHeader32 = zeroed();
+            header.magic = U16::new(LE, pe::IMAGE_NT_OPTIONAL_HDR32_MAGIC);
+            header.image_base = U32::new(LE, self.image_base() as u32);
+            header.section_alignment = U32::new(LE, 0x1000);
+            header.file_alignment = U32::new(LE, 0x200);
+            header.size_of_image = size_of_image;
+            header.size_of_headers = U32::new(LE, headers_size as u32);
+            header.subsystem = U16::new(LE, pe::IMAGE_SUBSYSTEM_WINDOWS_CUI);
+            header.number_of_rva_and_sizes = U32::new(LE, 16);
+            bytes.extend_from_slice(bytes_of(&header));
+        }
+
+        for idx in 0..16 {
+            let mut directory: pe::ImageDataDirectory = zeroed();
+            if let Some(&(_, rva, size)) = self.directories.iter().find(|dir| dir.0 == idx) {
+                directory.virtual_address = U32::new(LE, rva);
+                directory.size = U32::new(LE, size);
+            }
+            bytes.extend_from_slice(bytes_of(&directory));
+        }
+
+        let mut raw_offset = headers_size;
+        for (idx, (name, characteristics, data)) in self.sections.iter().enumerate() {
+            let mut header: pe::ImageSectionHeader = zeroed();
+            header.name[..name.len()].copy_from_slice(name.as_bytes());
+            header.virtual_size = U32::new(LE, data.len() as u32);
+            header.virtual_address = U32::new(LE, (idx as u32 + 1) * 0x1000);
+            header.size_of_raw_data = U32::new(LE, data.len().next_multiple_of(0x200) as u32);
+            header.pointer_to_raw_data = U32::new(LE, raw_offset as u32);
+            header.characteristics = U32::new(LE, *characteristics);
+            bytes.extend_from_slice(bytes_of(&header));
+            raw_offset += data.len().next_multiple_of(0x200);
+        }
+
+        bytes.resize(headers_size, 0);
+        for (_, _, data) in self.sections.iter() {
+            bytes.extend_from_slice(data);
+            bytes.resize(bytes.len().next_multiple_of(0x200), 0);
+        }
+
+        bytes
+    }
+}
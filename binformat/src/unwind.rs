@@ -0,0 +1,539 @@
+//! Decoding of the x64 `UNWIND_INFO` structures `.pdata` entries point to.
+//!
+//! Layouts are described in <https://learn.microsoft.com/en-us/cpp/build/exception-handling-x64>.
+
+use crate::{Datastructure, Reference};
+use processor_shared::Addressed;
+
+const UNW_FLAG_EHANDLER: u8 = 0x1;
+const UNW_FLAG_UHANDLER: u8 = 0x2;
+const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+/// Returned by a scope's filter to always execute it's handler.
+const EXCEPTION_EXECUTE_HANDLER: u32 = 1;
+
+const REGISTERS: [&str; 16] = [
+    "rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12", "r13",
+    "r14", "r15",
+];
+
+pub(crate) struct Unwind {
+    /// The `UNWIND_INFO` and any language specific data it points to.
+    pub datastructures: Vec<Addressed<Datastructure>>,
+    /// Function and `UNWIND_INFO` RVA's of the entry this one is chained to.
+    pub chained: Option<(u32, u32)>,
+}
+
+/// Decode the `UNWIND_INFO` at `addr`, which belongs to the function at `function`.
+///
+/// `read` returns the bytes starting at a virtual address and `handler_name` the name of an
+/// exception handler, which determines the layout of the language specific data.
+pub(crate) fn parse<'a>(
+    addr: usize,
+    function: usize,
+    base: usize,
+    read: impl Fn(usize) -> Option<&'a [u8]>,
+    handler_name: impl Fn(usize) -> Option<&'a str>,
+) -> Option<Unwind> {
+    let bytes = read(addr)?;
+    let header = bytes.get(..4)?;
+    let version = header[0] & 0b111;
+    let flags = header[0] >> 3;
+    let count = header[2] as usize;
+    let frame_register = REGISTERS[(header[3] & 0xf) as usize];
+    let frame_offset = (header[3] >> 4) as usize * 16;
+
+    if version != 1 && version != 2 {
+        return None;
+    }
+
+    let mut version_flags = version.to_string();
+    for (flag, name) in [
+        (UNW_FLAG_EHANDLER, "UNW_FLAG_EHANDLER"),
+        (UNW_FLAG_UHANDLER, "UNW_FLAG_UHANDLER"),
+        (UNW_FLAG_CHAININFO, "UNW_FLAG_CHAININFO"),
+    ] {
+        if flags & flag != 0 {
+            version_flags += " | ";
+            version_flags += name;
+        }
+    }
+
+    let frame = match header[3] & 0xf {
+        0 => "none".to_string(),
+        _ => format!("{frame_register} + {frame_offset:#x}"),
+    };
+
+    let mut fields = vec![
+        (addr, "version_flags", "u8", version_flags),
+        (addr + 1, "size_of_prolog", "u8", format!("{:#x}", header[1])),
+        (addr + 2, "count_of_codes", "u8", format!("{count:#x}")),
+        (addr + 3, "frame", "u8", frame),
+    ];
+
+    // Link the unwind info back to it's function.
+    let mut references = vec![(0, Reference::Address(function))];
+
+    let codes = bytes.get(4..4 + count * 2)?;
+    let slot = |idx: usize| -> Option<u32> {
+        let bytes = codes.get(idx * 2..idx * 2 + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+    };
+
+    let mut idx = 0;
+    while idx < count {
+        let prolog_offset = codes[idx * 2];
+        let op = codes[idx * 2 + 1] & 0xf;
+        let info = (codes[idx * 2 + 1] >> 4) as usize;
+
+        let decoded = || -> Option<(usize, String)> {
+            Some(match op {
+                0 => (1, format!("UWOP_PUSH_NONVOL {}", REGISTERS[info])),
+                1 if info == 0 => (2, format!("UWOP_ALLOC_LARGE {:#x}", slot(idx + 1)? * 8)),
+                1 => {
+                    let size = slot(idx + 1)? | slot(idx + 2)? << 16;
+                    (3, format!("UWOP_ALLOC_LARGE {size:#x}"))
+                }
+                2 => (1, format!("UWOP_ALLOC_SMALL {:#x}", info * 8 + 8)),
+                3 => (1, format!("UWOP_SET_FPREG {frame_register} = rsp + {frame_offset:#x}")),
+                4 => {
+                    let offset = slot(idx + 1)? * 8;
+                    (2, format!("UWOP_SAVE_NONVOL {} [rsp + {offset:#x}]", REGISTERS[info]))
+                }
+                5 => {
+                    let offset = slot(idx + 1)? | slot(idx + 2)? << 16;
+                    (3, format!("UWOP_SAVE_NONVOL_FAR {} [rsp + {offset:#x}]", REGISTERS[info]))
+                }
+                6 if version == 2 => (2, "UWOP_EPILOG".to_string()),
+                6 => (2, "UWOP_SAVE_XMM".to_string()),
+                7 => (3, "UWOP_SPARE_CODE".to_string()),
+                8 => {
+                    let offset = slot(idx + 1)? * 16;
+                    (2, format!("UWOP_SAVE_XMM128 xmm{info} [rsp + {offset:#x}]"))
+                }
+                9 => {
+                    let offset = slot(idx + 1)? | slot(idx + 2)? << 16;
+                    (3, format!("UWOP_SAVE_XMM128_FAR xmm{info} [rsp + {offset:#x}]"))
+                }
+                10 if info == 1 => (1, "UWOP_PUSH_MACHFRAME with error code".to_string()),
+                10 => (1, "UWOP_PUSH_MACHFRAME".to_string()),
+                _ => (1, format!("unknown {op:#x}")),
+            })
+        };
+
+        // The codes are truncated.
+        let (slots, code) = match decoded() {
+            Some(decoded) => decoded,
+            None => break,
+        };
+
+        let tipe = match slots {
+            1 => "u16",
+            2 => "[u16; 2]",
+            _ => "[u16; 3]",
+        };
+
+        fields.push((addr + 4 + idx * 2, "code", tipe, format!("{prolog_offset:#x}: {code}")));
+        idx += slots;
+    }
+
+    // Unwind codes are padded to an even count.
+    let offset = 4 + ((count + 1) & !1) * 2;
+    let read_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+    };
+
+    let mut unwind = Unwind {
+        datastructures: Vec::new(),
+        chained: None,
+    };
+
+    let mut end = offset;
+    if flags & UNW_FLAG_CHAININFO != 0 {
+        let begin = read_u32(offset)?;
+        let func_end = read_u32(offset + 4)?;
+        let info = read_u32(offset + 8)?;
+
+        references.push((fields.len(), Reference::Address(base + begin as usize)));
+        fields.push((addr + offset, "chained_begin_addr", "u32", format!("{begin:#x}")));
+        fields.push((addr + offset + 4, "chained_end_addr", "u32", format!("{func_end:#x}")));
+        fields.push((addr + offset + 8, "chained_unwind_info", "u32", format!("{info:#x}")));
+
+        unwind.chained = Some((begin, info));
+        end += 12;
+    } else if flags & (UNW_FLAG_EHANDLER | UNW_FLAG_UHANDLER) != 0 {
+        let handler = read_u32(offset)?;
+        let name = handler_name(base + handler as usize);
+        let mut value = format!("{handler:#x}");
+        match name {
+            // Handlers called through an import thunk don't have a symbol of their own.
+            Some(name) => value += &format!(" <{name}>"),
+            None => references.push((fields.len(), Reference::Address(base + handler as usize))),
+        }
+        fields.push((addr + offset, "exception_handler", "u32", value));
+        end += 4;
+
+        match name {
+            Some("__C_specific_handler" | "__GSHandlerCheck_SEH") => {
+                let scopes = read_u32(end)?;
+                fields.push((addr + end, "scope_count", "u32", format!("{scopes:#x}")));
+                end += 4;
+
+                for _ in 0..scopes {
+                    let names = ["begin_address", "end_address", "handler_address", "jump_target"];
+                    for (idx, name) in names.into_iter().enumerate() {
+                        let value = read_u32(end + idx * 4)?;
+
+                        // The handler is either a filter function or a constant.
+                        if name == "handler_address" && value == EXCEPTION_EXECUTE_HANDLER {
+                            let value = "EXCEPTION_EXECUTE_HANDLER".to_string();
+                            fields.push((addr + end + idx * 4, name, "u32", value));
+                            continue;
+                        }
+
+                        if value != 0 {
+                            let reference = Reference::Address(base + value as usize);
+                            references.push((fields.len(), reference));
+                        }
+                        fields.push((addr + end + idx * 4, name, "u32", format!("{value:#x}")));
+                    }
+                    end += 16;
+                }
+            }
+            Some("__CxxFrameHandler3" | "__CxxFrameHandler" | "__GSHandlerCheck_EH") => {
+                let func_info = read_u32(end)?;
+                fields.push((addr + end, "func_info", "u32", format!("{func_info:#x}")));
+                end += 4;
+
+                let func_info = base + func_info as usize;
+                if let Some(datastructure) = parse_func_info(func_info, base, &read) {
+                    unwind.datastructures.push(Addressed {
+                        addr: func_info,
+                        item: datastructure,
+                    });
+                }
+            }
+            Some("__CxxFrameHandler4" | "__GSHandlerCheck_EH4") => {
+                // Compressed `FuncInfo4`, which we don't decode.
+                let func_info = read_u32(end)?;
+                references.push((fields.len(), Reference::Address(base + func_info as usize)));
+                fields.push((addr + end, "func_info", "u32", format!("{func_info:#x}")));
+                end += 4;
+            }
+            _ => {}
+        }
+    }
+
+    unwind.datastructures.push(Addressed {
+        addr,
+        item: Datastructure {
+            ident: "UNWIND_INFO",
+            size: end,
+            fields,
+            references,
+        },
+    });
+
+    Some(unwind)
+}
+
+/// Decode the `FuncInfo` used by `__CxxFrameHandler3` to describe a function's try blocks and
+/// destructors to run during unwinding.
+fn parse_func_info<'a>(
+    addr: usize,
+    base: usize,
+    read: impl Fn(usize) -> Option<&'a [u8]>,
+) -> Option<Datastructure> {
+    let bytes = read(addr)?;
+    let read_u32 = |idx: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(bytes.get(idx * 4..idx * 4 + 4)?.try_into().ok()?))
+    };
+
+    // The upper bits are used by `/BBT`.
+    let magic = read_u32(0)?;
+    let fields_count = match magic & 0x1fffffff {
+        0x19930520 => 8,
+        0x19930521 => 9,
+        0x19930522 => 10,
+        _ => return None,
+    };
+
+    let names = [
+        ("magic_number", false),
+        ("max_state", false),
+        ("unwind_map", true),
+        ("try_blocks", false),
+        ("try_block_map", true),
+        ("ip_to_state_count", false),
+        ("ip_to_state_map", true),
+        ("unwind_help", false),
+        ("es_type_list", true),
+        ("eh_flags", false),
+    ];
+
+    let mut fields = Vec::new();
+    let mut references = Vec::new();
+    for (idx, (name, is_rva)) in names.into_iter().take(fields_count).enumerate() {
+        let value = read_u32(idx)?;
+        if is_rva && value != 0 {
+            references.push((fields.len(), Reference::Address(base + value as usize)));
+        }
+        fields.push((addr + idx * 4, name, "u32", format!("{value:#x}")));
+    }
+
+    Some(Datastructure {
+        ident: "FuncInfo",
+        size: fields_count * 4,
+        fields,
+        references,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: usize = 0x140000000;
+
+    /// Address of the exception handler the unwind info points to.
+    const HANDLER: u32 = 0x3000;
+
+    /// An unwind code, as it's stored in the first slot it takes up.
+    fn code(prolog_offset: u8, op: u8, info: u8) -> u16 {
+        u16::from_le_bytes([prolog_offset, op | info << 4])
+    }
+
+    /// `UNWIND_INFO` with the given slots, followed by `trailer`.
+    fn unwind_info(version: u8, flags: u8, frame: u8, slots: &[u16], trailer: &[u32]) -> Vec<u8> {
+        let mut bytes = vec![version | flags << 3, 0x20, slots.len() as u8, frame];
+        bytes.extend(slots.iter().flat_map(|slot| slot.to_le_bytes()));
+        if slots.len() % 2 == 1 {
+            bytes.extend([0, 0]);
+        }
+        bytes.extend(trailer.iter().flat_map(|value| value.to_le_bytes()));
+        bytes
+    }
+
+    /// Decodes `info` placed at RVA `0x2000`, with `extra` placed at RVA `0x4000`.
+    fn decode(info: &[u8], extra: &[u8], handler: &'static str) -> Option<Unwind> {
+        let mut image = vec![0; 0x5000];
+        image[0x2000..0x2000 + info.len()].copy_from_slice(info);
+        image[0x4000..0x4000 + extra.len()].copy_from_slice(extra);
+
+        let read = |addr: usize| image.get(addr.checked_sub(BASE)?..);
+        let handler_name = |addr: usize| (addr == BASE + HANDLER as usize).then_some(handler);
+        parse(BASE + 0x2000, BASE + 0x1000, BASE, read, handler_name)
+    }
+
+    fn values(datastructure: &Datastructure) -> Vec<(usize, &str, &str)> {
+        let fields = datastructure.fields.iter();
+        fields.map(|field| (field.0 - BASE - 0x2000, field.1, &field.3[..])).collect()
+    }
+
+    fn unwind_info_of(unwind: &Unwind) -> &Datastructure {
+        let info = unwind.datastructures.iter().find(|ds| ds.item.ident == "UNWIND_INFO");
+        &info.unwrap().item
+    }
+
+    #[test]
+    fn prolog_codes() {
+        let slots = [
+            code(0x10, 1, 0),
+            0x20,
+            code(0x0c, 1, 1),
+            0x0008,
+            0x0001,
+            code(0x08, 5, 3),
+            0x0010,
+            0x0001,
+            code(0x06, 4, 6),
+            0x0003,
+            code(0x04, 2, 3),
+            code(0x02, 3, 0),
+            code(0x01, 0, 5),
+        ];
+        // Frame register rbp at rsp + 0x20.
+        let info = unwind_info(1, 0, 5 | 2 << 4, &slots, &[]);
+        let unwind = decode(&info, &[], "").unwrap();
+        let info = unwind_info_of(&unwind);
+
+        assert_eq!(
+            values(info),
+            [
+                (0, "version_flags", "1"),
+                (1, "size_of_prolog", "0x20"),
+                (2, "count_of_codes", "0xd"),
+                (3, "frame", "rbp + 0x20"),
+                (4, "code", "0x10: UWOP_ALLOC_LARGE 0x100"),
+                (8, "code", "0xc: UWOP_ALLOC_LARGE 0x10008"),
+                (14, "code", "0x8: UWOP_SAVE_NONVOL_FAR rbx [rsp + 0x10010]"),
+                (20, "code", "0x6: UWOP_SAVE_NONVOL rsi [rsp + 0x18]"),
+                (24, "code", "0x4: UWOP_ALLOC_SMALL 0x20"),
+                (26, "code", "0x2: UWOP_SET_FPREG rbp = rsp + 0x20"),
+                (28, "code", "0x1: UWOP_PUSH_NONVOL rbp"),
+            ]
+        );
+
+        let tipes: Vec<_> = info.fields[4..].iter().map(|field| field.2).collect();
+        assert_eq!(
+            tipes,
+            ["[u16; 2]", "[u16; 3]", "[u16; 3]", "[u16; 2]", "u16", "u16", "u16"]
+        );
+
+        // 13 codes are padded to 14.
+        assert_eq!(info.size, 4 + 14 * 2);
+        assert!(
+            matches!(info.references[..], [(0, Reference::Address(addr))] if addr == BASE + 0x1000)
+        );
+        assert!(unwind.chained.is_none());
+    }
+
+    #[test]
+    fn versions() {
+        let slots = [code(0x01, 6, 0), 0x0001];
+        let epilog = decode(&unwind_info(2, 0, 0, &slots, &[]), &[], "").unwrap();
+        let epilog = values(unwind_info_of(&epilog));
+        assert_eq!(epilog[3], (3, "frame", "none"));
+        assert_eq!(epilog[4], (4, "code", "0x1: UWOP_EPILOG"));
+
+        // The same op meant saving an xmm register in version 1.
+        let save = decode(&unwind_info(1, 0, 0, &slots, &[]), &[], "").unwrap();
+        assert_eq!(
+            values(unwind_info_of(&save))[4],
+            (4, "code", "0x1: UWOP_SAVE_XMM")
+        );
+
+        assert!(decode(&unwind_info(3, 0, 0, &slots, &[]), &[], "").is_none());
+    }
+
+    #[test]
+    fn truncated_codes() {
+        // A large allocation whose size would be in the slots after the last one.
+        let slots = [code(0x02, 0, 3), code(0x01, 1, 1)];
+        let unwind = decode(&unwind_info(1, 0, 0, &slots, &[]), &[], "").unwrap();
+        let codes: Vec<_> = values(unwind_info_of(&unwind)).into_iter().skip(4).collect();
+        assert_eq!(codes, [(4, "code", "0x2: UWOP_PUSH_NONVOL rbx")]);
+    }
+
+    #[test]
+    fn chained() {
+        let info = unwind_info(
+            1,
+            UNW_FLAG_CHAININFO,
+            0,
+            &[code(0x01, 0, 3)],
+            &[0x1000, 0x1100, 0x2100],
+        );
+        let unwind = decode(&info, &[], "").unwrap();
+        assert_eq!(unwind.chained, Some((0x1000, 0x2100)));
+
+        let info = unwind_info_of(&unwind);
+        assert_eq!(info.size, 8 + 12);
+        assert_eq!(
+            values(info)[4..],
+            [
+                (4, "code", "0x1: UWOP_PUSH_NONVOL rbx"),
+                (8, "chained_begin_addr", "0x1000"),
+                (12, "chained_end_addr", "0x1100"),
+                (16, "chained_unwind_info", "0x2100"),
+            ]
+        );
+        assert!(
+            matches!(info.references[1], (5, Reference::Address(addr)) if addr == BASE + 0x1000)
+        );
+    }
+
+    #[test]
+    fn scope_table() {
+        let scopes = [
+            2,
+            0x1000,
+            0x1010,
+            EXCEPTION_EXECUTE_HANDLER,
+            0x1020,
+            0x1030,
+            0x1040,
+            0x1500,
+            0,
+        ];
+        let trailer = [&[HANDLER][..], &scopes].concat();
+        let info = unwind_info(1, UNW_FLAG_EHANDLER | UNW_FLAG_UHANDLER, 0, &[], &trailer);
+        let unwind = decode(&info, &[], "__C_specific_handler").unwrap();
+        let info = unwind_info_of(&unwind);
+
+        assert_eq!(
+            values(info)[..5],
+            [
+                (
+                    0,
+                    "version_flags",
+                    "1 | UNW_FLAG_EHANDLER | UNW_FLAG_UHANDLER"
+                ),
+                (1, "size_of_prolog", "0x20"),
+                (2, "count_of_codes", "0x0"),
+                (3, "frame", "none"),
+                (4, "exception_handler", "0x3000 <__C_specific_handler>"),
+            ]
+        );
+        assert_eq!(
+            values(info)[5..],
+            [
+                (8, "scope_count", "0x2"),
+                (12, "begin_address", "0x1000"),
+                (16, "end_address", "0x1010"),
+                (20, "handler_address", "EXCEPTION_EXECUTE_HANDLER"),
+                (24, "jump_target", "0x1020"),
+                (28, "begin_address", "0x1030"),
+                (32, "end_address", "0x1040"),
+                (36, "handler_address", "0x1500"),
+                (40, "jump_target", "0x0"),
+            ]
+        );
+        assert_eq!(info.size, 44);
+
+        // A named handler isn't referenced, nor are the constant handler and empty jump target.
+        let referenced: Vec<_> = info.references.iter().map(|(idx, _)| *idx).collect();
+        assert_eq!(referenced, [0, 6, 7, 9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn func_info() {
+        let info = unwind_info(1, UNW_FLAG_EHANDLER, 0, &[], &[HANDLER, 0x4000]);
+        let func_info = |magic: u32| {
+            let values = [magic, 3, 0x4100, 1, 0x4200, 4, 0x4300, 0x28, 0, 1];
+            let extra: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+            let unwind = decode(&info, &extra, "__CxxFrameHandler3").unwrap();
+            let func_info = unwind.datastructures.iter().find(|ds| ds.item.ident == "FuncInfo");
+            func_info.map(|ds| (ds.addr - BASE, ds.item.clone()))
+        };
+
+        let (addr, v1) = func_info(0x19930520).unwrap();
+        assert_eq!(addr, 0x4000);
+        assert_eq!((v1.size, v1.fields.len()), (32, 8));
+        assert_eq!(v1.fields[7].1, "unwind_help");
+        // Only the non-zero RVA's are referenced.
+        let referenced: Vec<_> = v1.references.iter().map(|(idx, _)| *idx).collect();
+        assert_eq!(referenced, [2, 4, 6]);
+
+        // Version 2 adds the exception specification list, version 3 the flags.
+        assert_eq!(func_info(0x19930521).unwrap().1.fields.len(), 9);
+        let v3 = func_info(0x19930522).unwrap().1;
+        assert_eq!(
+            (v3.size, v3.fields[9].1, &v3.fields[9].3[..]),
+            (40, "eh_flags", "0x1")
+        );
+
+        // The upper bits are ignored.
+        assert_eq!(func_info(0x39930521).unwrap().1.fields.len(), 9);
+        assert!(func_info(0x12345678).is_none());
+
+        // The compressed `FuncInfo4` is only pointed to.
+        let unwind = decode(&info, &[], "__CxxFrameHandler4").unwrap();
+        assert_eq!(unwind.datastructures.len(), 1);
+        let info = unwind_info_of(&unwind);
+        assert_eq!(values(info)[5], (8, "func_info", "0x4000"));
+        assert!(
+            matches!(info.references[..], [_, (5, Reference::Address(addr))] if addr == BASE + 0x4000)
+        );
+    }
+}
@@ -19,9 +19,15 @@ OPTIONS:
   -D, --disassemble   Path to object you're disassembling
   -T, --tracing       Trace all syscalls performed
   -C, --config        Path to config used for disassembling
+  -I, --import        Merge symbols from a map file, nm output or a CSV/JSON name list
+  -M, --demangle      Demangle symbols given as arguments or found in text read from stdin
+  -A, --ansi          Color demangled symbols using ANSI escape codes
+  -K, --scheme        Print the mangling scheme of each demangled symbol
   -B, --debug         Enable extra debug information";
 
-const ABBRV: &[&str] = &["-H", "-L", "-S", "-D", "-C", "-T", "-B"];
+const ABBRV: &[&str] = &[
+    "-H", "-L", "-S", "-D", "-C", "-I", "-M", "-A", "-K", "-T", "-B",
+];
 const NAMES: &[&str] = &[
     "--help",
     "--libs",
@@ -30,6 +36,10 @@ const NAMES: &[&str] = &[
     "--disassemble",
     "--tracing",
     "--config",
+    "--import",
+    "--demangle",
+    "--ansi",
+    "--scheme",
     "--debug",
 ];
 
@@ -58,12 +68,31 @@ pub struct Cli {
 
     /// Optional path to config.
     pub config: Option<PathBuf>,
+
+    /// Paths to symbols to merge into the ones found in the object.
+    pub imports: Vec<PathBuf>,
+
+    /// Demangle symbols instead of opening an object.
+    pub demangle: bool,
+
+    /// Color demangled symbols using ANSI escape codes.
+    pub ansi: bool,
+
+    /// Print the mangling scheme of each demangled symbol.
+    pub scheme: bool,
+
+    /// Symbols to demangle, text from stdin is filtered if there are none.
+    pub symbols: Vec<String>,
 }
 
 impl Cli {
     pub fn parse() -> Self {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    fn parse_from(args: impl IntoIterator<Item = String>) -> Self {
         let mut cli = Cli::default();
-        let mut args = std::env::args().skip(1).peekable();
+        let mut args = args.into_iter();
 
         while let Some(arg) = args.next() {
             match arg.as_str() {
@@ -96,39 +125,40 @@ impl Cli {
                         }
                     }
                 }
+                "-I" | "--import" => match args.next() {
+                    Some(path) => cli.imports.push(PathBuf::from(path)),
+                    None => exit!(1 => "Missing path to symbols to import."),
+                },
+                "-M" | "--demangle" => cli.demangle = true,
+                "-A" | "--ansi" => cli.ansi = true,
+                "-K" | "--scheme" => cli.scheme = true,
                 "-T" | "--tracing" => cli.tracing = true,
                 "-B" | "--debug" => cli.debug = true,
-                unknown => {
-                    let mut distance = u32::MAX;
-                    let mut best_guess = "";
-                    for name in NAMES {
-                        let d = triple_accel::levenshtein_exp(unknown.as_bytes(), name.as_bytes());
-                        if d < distance {
-                            distance = d;
-                            best_guess = name;
-                        }
-                    }
-
-                    // A guess that's less than 3 `steps` away from a correct arg.
-                    if distance < 4 {
-                        exit!(1 => "Unknown cmd arg '{unknown}' did you mean '{best_guess}'?")
-                    } else {
-                        exit!(1 => "Unknown cmd arg '{unknown}' was entered.");
-                    }
-                }
+                // Symbols to demangle can come before or after `--demangle`, or between any of
+                // the flags modifying it.
+                symbol if !symbol.starts_with('-') => cli.symbols.push(arg),
+                unknown => unknown_arg(unknown),
             }
         }
 
+        if let Some(unknown) = cli.symbols.first().filter(|_| !cli.demangle) {
+            unknown_arg(unknown);
+        }
+
         cli.validate_args();
         cli
     }
 
     fn validate_args(&mut self) {
+        if (self.ansi || self.scheme) && !self.demangle {
+            exit!(1 => "Invalid combination of arguements.\n\n{HELP}");
+        }
+
         if self.disassemble || self.libs || self.names {
             if self.path.is_none() {
                 exit!(1 => "Missing path to an object.");
             }
-        } else {
+        } else if !self.demangle {
             // no action arguments were given
             self.disassemble = true;
             return;
@@ -138,8 +168,85 @@ impl Cli {
             exit!(1 => "Invalid combination of arguements.\n\n{HELP}");
         }
 
-        if self.disassemble as usize + self.libs as usize + self.names as usize > 1 {
+        let actions = [self.disassemble, self.libs, self.names, self.demangle];
+        if actions.iter().filter(|action| **action).count() > 1 {
             exit!(1 => "Invalid combination of arguements.\n\n{HELP}");
         }
     }
 }
+
+fn unknown_arg(unknown: &str) -> ! {
+    let mut distance = u32::MAX;
+    let mut best_guess = "";
+    for name in NAMES {
+        let d = triple_accel::levenshtein_exp(unknown.as_bytes(), name.as_bytes());
+        if d < distance {
+            distance = d;
+            best_guess = name;
+        }
+    }
+
+    // A guess that's less than 3 `steps` away from a correct arg.
+    if distance < 4 {
+        exit!(1 => "Unknown cmd arg '{unknown}' did you mean '{best_guess}'?")
+    } else {
+        exit!(1 => "Unknown cmd arg '{unknown}' was entered.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn demangle_symbols_anywhere() {
+        let cli = parse(&["-M", "-A", "_ZN3foo3barEv"]);
+        assert!(cli.demangle && cli.ansi);
+        assert_eq!(cli.symbols, ["_ZN3foo3barEv"]);
+
+        let cli = parse(&[
+            "_ZN3foo3barEv",
+            "--demangle",
+            "?baz@@YAXXZ",
+            "-K",
+            "_RNvC3foo3bar",
+        ]);
+        assert!(cli.demangle && cli.scheme && !cli.ansi);
+        assert_eq!(
+            cli.symbols,
+            ["_ZN3foo3barEv", "?baz@@YAXXZ", "_RNvC3foo3bar"]
+        );
+    }
+
+    #[test]
+    fn demangle_stdin() {
+        let cli = parse(&["-A", "-M"]);
+        assert!(cli.demangle && cli.ansi);
+        assert!(cli.symbols.is_empty());
+        assert!(!cli.disassemble);
+    }
+
+    #[test]
+    fn object_paths() {
+        let cli = parse(&["-I", "a.map", "-D", "/bin/ls", "--import", "b.csv"]);
+        assert!(cli.disassemble && !cli.demangle);
+        assert_eq!(cli.path, Some(PathBuf::from("/bin/ls")));
+        assert_eq!(
+            cli.imports,
+            [PathBuf::from("a.map"), PathBuf::from("b.csv")]
+        );
+
+        let cli = parse(&["-N", "/bin/ls"]);
+        assert!(cli.names && !cli.disassemble);
+        assert_eq!(cli.path, Some(PathBuf::from("/bin/ls")));
+
+        // Without an action the object is disassembled.
+        let cli = parse(&["-S"]);
+        assert!(cli.simplify && cli.disassemble);
+        assert!(cli.symbols.is_empty());
+    }
+}
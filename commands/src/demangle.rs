@@ -0,0 +1,175 @@
+//! Demangling symbols given as arguments or found in text read from stdin, like `c++filt`.
+
+use crate::Cli;
+use debugvault::demangler;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+
+/// Prefixes a symbol in text must start with to be demangled.
+const PREFIXES: &[&str] = &["_Z", "__Z", "_R", "__R", "?", "$s", "_$s", "$S", "_T0"];
+
+fn is_symbol_char(chr: char) -> bool {
+    chr.is_ascii_alphanumeric() || matches!(chr, '_' | '$' | '.')
+}
+
+/// Length of the symbol `text` starts with.
+fn symbol_len(text: &str) -> usize {
+    // MSVC symbols start with a `?` and are made up of `?` and `@` delimited names.
+    if text.starts_with('?') {
+        let is_msvc_char = |chr| is_symbol_char(chr) || matches!(chr, '?' | '@');
+        return text.find(|chr| !is_msvc_char(chr)).unwrap_or(text.len());
+    }
+
+    // Symbol versions such as `@@Base` are left as is, calls through the PLT are demangled.
+    let len = text.find(|chr| !is_symbol_char(chr)).unwrap_or(text.len());
+    match text[len..].strip_prefix("@plt") {
+        Some(rest) if !rest.starts_with(|chr: char| chr.is_ascii_alphanumeric() || chr == '_') => {
+            len + "@plt".len()
+        }
+        _ => len,
+    }
+}
+
+/// Demangled form of `symbol`, if it's in any of the supported mangling schemes.
+fn render(symbol: &str, cli: &Cli) -> Option<String> {
+    let (demangled, scheme) = demangler::parse_with_scheme(symbol);
+    let scheme = scheme?;
+    let mut out = String::new();
+
+    for token in demangled.tokens() {
+        if cli.ansi {
+            let [r, g, b, _] = token.color.to_array();
+            let _ = write!(out, "\x1b[38;2;{r};{g};{b}m{}", &token.text[..]);
+        } else {
+            out += &token.text;
+        }
+    }
+
+    if cli.ansi {
+        out += "\x1b[0m";
+    }
+
+    if cli.scheme {
+        let _ = write!(out, " [{scheme}]");
+    }
+
+    Some(out)
+}
+
+/// Replaces every mangled symbol in `line` with it's demangled form.
+fn filter(line: &str, cli: &Cli) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find(|chr| is_symbol_char(chr) || chr == '?') {
+        out += &rest[..start];
+        rest = &rest[start..];
+
+        let end = symbol_len(rest);
+        let word = &rest[..end];
+        rest = &rest[end..];
+
+        // A period ending a sentence isn't part of the symbol.
+        let symbol = word.trim_end_matches('.');
+        let demangled = if PREFIXES.iter().any(|prefix| symbol.starts_with(prefix)) {
+            render(symbol, cli)
+        } else {
+            None
+        };
+
+        match demangled {
+            Some(demangled) => {
+                out += &demangled;
+                out += &word[symbol.len()..];
+            }
+            None => out += word,
+        }
+    }
+
+    out += rest;
+    out
+}
+
+/// Prints the demangled arguments, or every line of stdin with it's symbols demangled.
+pub fn demangle(cli: &Cli) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+
+    if !cli.symbols.is_empty() {
+        for symbol in cli.symbols.iter() {
+            // Arguments are whole symbols, so they don't need a known prefix.
+            let demangled = render(symbol, cli).unwrap_or_else(|| symbol.clone());
+            writeln!(stdout, "{demangled}")?;
+        }
+
+        return Ok(());
+    }
+
+    for line in io::stdin().lock().lines() {
+        writeln!(stdout, "{}", filter(&line?, cli))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_in_text() {
+        let cli = Cli::default();
+
+        assert_eq!(
+            filter("call _ZN4core3fmt5write17h0123456789abcdefE@plt", &cli),
+            "call core::fmt::write@plt"
+        );
+        assert_eq!(
+            filter("  401126:  e8 00 00 00 00  call <_RNvC4bite6decode>.", &cli),
+            "  401126:  e8 00 00 00 00  call <bite::decode>."
+        );
+        assert_eq!(
+            filter("main calls ?f@@YAXXZ", &cli),
+            "main calls void __cdecl f(void)"
+        );
+        assert_eq!(filter("_Z3addii@@Base", &cli), "add@@Base");
+        assert_eq!(
+            filter("_ZN3foo3barEv@GLIBCXX_3.4", &cli),
+            "foo::bar@GLIBCXX_3.4"
+        );
+        assert_eq!(filter("see _Z3addii?", &cli), "see add?");
+    }
+
+    #[test]
+    fn plain_text() {
+        let cli = Cli::default();
+
+        assert_eq!(filter("Is _Zfoo mangled?", &cli), "Is _Zfoo mangled?");
+        assert_eq!(filter("foo_Z3barv, main", &cli), "foo_Z3barv, main");
+        assert_eq!(filter("mail@example.com?", &cli), "mail@example.com?");
+        assert_eq!(filter("Why? ?", &cli), "Why? ?");
+        assert_eq!(filter("", &cli), "");
+    }
+
+    #[test]
+    fn scheme() {
+        let cli = Cli {
+            scheme: true,
+            ..Cli::default()
+        };
+
+        assert_eq!(filter("_Z3addii", &cli), "add [itanium]");
+        assert_eq!(filter("_RNvC4bite6decode", &cli), "bite::decode [rust-v0]");
+    }
+
+    #[test]
+    fn ansi() {
+        let cli = Cli {
+            ansi: true,
+            ..Cli::default()
+        };
+
+        let colored = filter("_Z3addii", &cli);
+        assert!(colored.starts_with("\x1b[38;2;"));
+        assert!(colored.ends_with("add\x1b[0m"));
+    }
+}
@@ -5,12 +5,14 @@ use crate::debug::CompleteExpr;
 
 pub const HELP: &str = "\
 Available commands:
-    pwd                -- Display the current path
-    cd <path>          -- Change the current directory to the specified path
-    quit               -- Exit the program
-    goto <expr>        -- Jump to code/data at the specified expression
-    clear              -- Clear out terminal
-    help               -- Display this help message";
+    pwd                   -- Display the current path
+    cd <path>             -- Change the current directory to the specified path
+    quit                  -- Exit the program
+    goto <expr>           -- Jump to code/data at the specified expression
+    import-symbols <path> -- Merge symbols from a map file, nm output or a CSV/JSON name list
+    export-symbols <path> -- Write symbols as .json, .csv or otherwise as an ELF symbol file
+    clear                 -- Clear out terminal
+    help                  -- Display this help message";
 
 #[derive(Debug, PartialEq)]
 pub enum Command {
@@ -19,6 +21,8 @@ pub enum Command {
     ChangeDir(PathBuf),
     Quit,
     Goto(usize),
+    ImportSymbols(PathBuf),
+    ExportSymbols(PathBuf),
     Clear,
     Help,
 }
@@ -89,6 +93,8 @@ fn possible_command(unknown: &str) -> Option<&str> {
         "quit",
         "run",
         "goto",
+        "import-symbols",
+        "export-symbols",
         "set",
         "break",
         "delete",
@@ -213,6 +219,30 @@ impl<'src> Context<'src> {
         Err(Error::PathDoesntExist(path))
     }
 
+    /// Path to a file that's about to be written, which only has to be in an existing directory.
+    fn parse_output_path(&mut self) -> Result<PathBuf, Error> {
+        let start = self.offset;
+        let s = self.parse_arg("path")?;
+        let path = expand_homedir(PathBuf::from(s));
+
+        let dir = match path.parent() {
+            Some(parent) if parent.as_os_str().is_empty() => Path::new("."),
+            Some(parent) => parent,
+            None => return Err(Error::PathIsntFile(path)),
+        };
+
+        if path.is_dir() {
+            return Err(Error::PathIsntFile(path));
+        }
+
+        if !dir.is_dir() {
+            self.autocomplete_path(start, s);
+            return Err(Error::PathDoesntExist(dir.to_path_buf()));
+        }
+
+        Ok(path)
+    }
+
     fn parse_dir_path(&mut self) -> Result<PathBuf, Error> {
         let start = self.offset;
         let s = self.parse_arg("path").unwrap_or_default();
@@ -347,6 +377,8 @@ impl<'src> Context<'src> {
             "cd" => Command::ChangeDir(self.parse_dir_path()?),
             "quit" | "q" => Command::Quit,
             "goto" | "g" => Command::Goto(self.parse_debug_expr()?),
+            "import-symbols" => Command::ImportSymbols(self.parse_file_path()?),
+            "export-symbols" => Command::ExportSymbols(self.parse_output_path()?),
             "clear" => Command::Clear,
             "help" | "?" => Command::Help,
             name => return Err(Error::UnknownName(name.to_string())),
@@ -431,6 +463,18 @@ mod tests {
         eval_eq!("cd . ", Command::ChangeDir(PathBuf::from(".")));
     }
 
+    #[test]
+    fn export_symbols() {
+        eval_eq!("export-symbols syms.json", Command::ExportSymbols(PathBuf::from("syms.json")));
+        eval_eq!("export-symbols ./a.debug", Command::ExportSymbols(PathBuf::from("./a.debug")));
+    }
+
+    #[test]
+    #[should_panic]
+    fn export_symbols_invalid() {
+        eval_eq!("export-symbols /???/a.csv", Command::ExportSymbols(PathBuf::from("/???/a.csv")));
+    }
+
     #[test]
     #[should_panic]
     fn change_dir_invalid() {
@@ -1,8 +1,10 @@
 mod cli;
 mod debug;
+mod demangle;
 mod gui;
 
 pub use cli::Cli;
+pub use demangle::demangle;
 pub use gui::{Command, Error as CommandError, HELP as CMD_HELP};
 use once_cell::sync::Lazy;
 
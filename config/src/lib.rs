@@ -6,11 +6,21 @@ use egui::Color32;
 use serde::de::{self, Deserializer, Visitor};
 use serde::Deserialize;
 use std::fmt;
+use std::path::PathBuf;
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     #[serde(default = "defaults::colors")]
     pub colors: Colors,
+    /// Directories searched for separate debug info, besides `/usr/lib/debug`.
+    #[serde(default = "defaults::debug_dirs")]
+    pub debug_dirs: Vec<PathBuf>,
+    /// Symbol stores searched for PDB's, laid out like `name.pdb/GUIDAGE/name.pdb`.
+    #[serde(default = "defaults::symbol_dirs")]
+    pub symbol_dirs: Vec<PathBuf>,
+    /// Shorten standard library types in demangled names, same as passing `--simplify`.
+    #[serde(default)]
+    pub simplify: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -143,6 +153,7 @@ impl Colors {
 /// config.yaml.
 mod defaults {
     use egui::Color32;
+    use std::path::PathBuf;
 
     pub fn config() -> super::Config {
         serde_yaml::from_str("").unwrap()
@@ -157,6 +168,38 @@ mod defaults {
         serde_yaml::from_str("").unwrap()
     }
 
+    /// Where debuginfod's client caches the debug info it downloads.
+    pub fn debug_dirs() -> Vec<PathBuf> {
+        match std::env::var_os("DEBUGINFOD_CACHE_PATH") {
+            Some(path) => vec![PathBuf::from(path)],
+            None => {
+                dirs::cache_dir().map(|dir| dir.join("debuginfod_client")).into_iter().collect()
+            }
+        }
+    }
+
+    /// Local directories of `_NT_SYMBOL_PATH`, such as the cache in `srv*C:\symbols*https://..`.
+    pub fn symbol_dirs() -> Vec<PathBuf> {
+        let symbol_path = match std::env::var("_NT_SYMBOL_PATH") {
+            Ok(symbol_path) => symbol_path,
+            Err(_) => return Vec::new(),
+        };
+
+        symbol_path
+            .split(';')
+            .flat_map(|entry| entry.split('*'))
+            .filter(|part| {
+                !part.is_empty()
+                    && !part.eq_ignore_ascii_case("srv")
+                    && !part.eq_ignore_ascii_case("cache")
+                    && !part.eq_ignore_ascii_case("symsrv")
+                    && !part.ends_with(".dll")
+                    && !part.contains("://")
+            })
+            .map(PathBuf::from)
+            .collect()
+    }
+
     pub fn anything() -> Color32 {
         Color32::from_rgb(0xc8, 0xc8, 0xc8)
     }
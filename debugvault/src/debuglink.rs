@@ -0,0 +1,135 @@
+//! Finding the separate debug file a binary got stripped into, by it's build-id or it's
+//! `.gnu_debuglink`.
+
+use config::CONFIG;
+use object::Object;
+use std::path::{Path, PathBuf};
+
+/// Where distributions install separate debug files.
+const SYSTEM_DEBUG_DIR: &str = "/usr/lib/debug";
+
+/// Expands a leading `~` in the configured directories.
+pub(crate) fn expand_home(dir: &Path) -> PathBuf {
+    match (dir.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// Paths a separate debug file could be at, most specific first.
+fn candidates(obj: &object::File, path: &Path) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    let dirs: Vec<PathBuf> = CONFIG.debug_dirs.iter().map(|dir| expand_home(dir)).collect();
+
+    if let Ok(Some(build_id)) = obj.build_id() {
+        if build_id.len() > 1 {
+            let hex: String = build_id.iter().map(|byte| format!("{byte:02x}")).collect();
+            let (prefix, rest) = hex.split_at(2);
+
+            for dir in std::iter::once(Path::new(SYSTEM_DEBUG_DIR)).chain(dirs.iter().map(|d| &**d))
+            {
+                candidates.push(dir.join(".build-id").join(prefix).join(format!("{rest}.debug")));
+            }
+
+            // debuginfod keeps a directory per build-id.
+            for dir in dirs.iter() {
+                candidates.push(dir.join(&hex).join("debuginfo"));
+            }
+        }
+    }
+
+    if let Ok(Some((name, _))) = obj.gnu_debuglink() {
+        if let Ok(name) = std::str::from_utf8(name) {
+            let parent = path.parent().unwrap_or(Path::new(""));
+            let absolute = std::fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf());
+
+            candidates.push(parent.join(name));
+            candidates.push(parent.join(".debug").join(name));
+            if let Ok(relative) = absolute.strip_prefix("/") {
+                candidates.push(Path::new(SYSTEM_DEBUG_DIR).join(relative).join(name));
+            }
+            for dir in dirs.iter() {
+                candidates.push(dir.join(name));
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Whether the debug file at `candidate` was made from the same build as `obj`.
+fn matches(obj: &object::File, candidate: &Path) -> bool {
+    let build_id = match obj.build_id() {
+        Ok(Some(build_id)) => build_id,
+        // Without a build-id there's nothing to compare, the name will have to do.
+        _ => return true,
+    };
+
+    let file = match std::fs::File::open(candidate) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+
+    let mmap = match unsafe { memmap2::Mmap::map(&file) } {
+        Ok(mmap) => mmap,
+        Err(_) => return false,
+    };
+
+    match object::File::parse(&*mmap) {
+        Ok(debug_obj) => match debug_obj.build_id() {
+            Ok(Some(debug_build_id)) => debug_build_id == build_id,
+            _ => true,
+        },
+        Err(_) => false,
+    }
+}
+
+/// Path of the separate debug file for the binary at `path`.
+pub fn locate(obj: &object::File, path: &Path) -> Option<PathBuf> {
+    let binary = std::fs::canonicalize(path).ok();
+
+    candidates(obj, path).into_iter().find(|candidate| {
+        // A debug link could be named the same as the binary it's in.
+        candidate.is_file()
+            && std::fs::canonicalize(candidate).ok() != binary
+            && matches(obj, candidate)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Object with the build-id `abcdef01` and a debug link to `app.debug`.
+    const DEBUGLINK: &[u8] = include_bytes!("../fixtures/debuglink.o");
+
+    #[test]
+    fn candidate_paths() {
+        let obj = object::File::parse(DEBUGLINK).unwrap();
+        let path = Path::new("/nonexistent/bin/app");
+        let dirs: Vec<PathBuf> = CONFIG.debug_dirs.iter().map(|dir| expand_home(dir)).collect();
+
+        let mut expected = vec![PathBuf::from("/usr/lib/debug/.build-id/ab/cdef01.debug")];
+        for dir in dirs.iter() {
+            expected.push(dir.join(".build-id/ab/cdef01.debug"));
+        }
+        for dir in dirs.iter() {
+            expected.push(dir.join("abcdef01/debuginfo"));
+        }
+        expected.push(PathBuf::from("/nonexistent/bin/app.debug"));
+        expected.push(PathBuf::from("/nonexistent/bin/.debug/app.debug"));
+        expected.push(PathBuf::from("/usr/lib/debug/nonexistent/bin/app.debug"));
+        for dir in dirs.iter() {
+            expected.push(dir.join("app.debug"));
+        }
+
+        assert_eq!(candidates(&obj, path), expected);
+    }
+
+    #[test]
+    fn missing_debug_file() {
+        let obj = object::File::parse(DEBUGLINK).unwrap();
+        assert_eq!(locate(&obj, Path::new("/nonexistent/bin/app")), None);
+        assert!(!matches(&obj, Path::new("/nonexistent/bin/app.debug")));
+    }
+}
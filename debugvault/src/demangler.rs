@@ -2,8 +2,63 @@
 
 use tokenizing::{Token, Color32};
 use config::CONFIG;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 
+/// Whether names get simplified regardless of the config, as requested by `--simplify`.
+static SIMPLIFY: AtomicBool = AtomicBool::new(false);
+
+/// Shorten standard library types in demangled names, besides when the config asks for it.
+pub fn set_simplify(enabled: bool) {
+    SIMPLIFY.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether either `--simplify` or the config asks for shortened names.
+pub fn simplifying() -> bool {
+    SIMPLIFY.load(Ordering::Relaxed) || CONFIG.simplify
+}
+
+/// Mangling scheme a symbol was recognized as being in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    RustLegacy,
+    Itanium,
+    Rust,
+    Swift,
+    Msvc,
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::RustLegacy => "rust-legacy",
+            Self::Itanium => "itanium",
+            Self::Rust => "rust-v0",
+            Self::Swift => "swift",
+            Self::Msvc => "msvc",
+        })
+    }
+}
+
+/// Demangle a symbol of any of the supported mangling schemes, or keep it as is.
 pub fn parse(s: &str) -> TokenStream {
+    parse_with_scheme(s).0
+}
+
+/// Demangle a symbol, along with the scheme it's mangled in if it's mangled at all.
+pub fn parse_with_scheme(s: &str) -> (TokenStream, Option<Scheme>) {
+    let (mut demangled, scheme) = demangle(s);
+
+    if simplifying() {
+        if let Some(tokens) = crate::simplify::tokens(demangled.tokens()) {
+            demangled.tokens = tokens;
+        }
+    }
+
+    (demangled, scheme)
+}
+
+fn demangle(s: &str) -> (TokenStream, Option<Scheme>) {
     // symbols without leading underscores are accepted as
     // dbghelp in windows strips them away
 
@@ -11,28 +66,40 @@ pub fn parse(s: &str) -> TokenStream {
     let s = s.strip_suffix("$plt").unwrap_or(s);
     let s = s.strip_suffix("$pltgot").unwrap_or(s);
 
+    // synthetic symbols for ELF PLT stubs
+    if let Some(s) = s.strip_suffix("@plt") {
+        let (mut demangled, scheme) = demangle(s);
+        demangled.push("@plt", CONFIG.colors.asm.annotation);
+        return (demangled, scheme);
+    }
+
     // parse rust symbols
     if let Some(s) = crate::rust_legacy::parse(s) {
-        return s;
+        return (s, Some(Scheme::RustLegacy));
     }
 
     // parse gnu/llvm/C/C++ symbols
     if let Some(s) = crate::itanium::parse(s) {
-        return s;
+        return (s, Some(Scheme::Itanium));
     }
 
     // parse rust symbols that match the v0 mangling scheme
     if let Some(s) = crate::rust::parse(s) {
-        return s;
+        return (s, Some(Scheme::Rust));
+    }
+
+    // parse swift symbols
+    if let Some(s) = crate::swift::parse(s) {
+        return (s, Some(Scheme::Swift));
     }
 
     // parse windows msvc C/C++ symbols
     if let Some(s) = crate::msvc::parse(s) {
-        return s;
+        return (s, Some(Scheme::Msvc));
     }
 
     // return the original mangled symbol on failure
-    TokenStream::simple(s)
+    (TokenStream::simple(s), None)
 }
 
 #[derive(Debug)]
@@ -1,5 +1,7 @@
 use crate::intern::InternMap;
-use crate::{AddressMap, Addressed, FileAttr};
+use crate::types::{Encoding, Member, Type, TypeDb, TypeId, Variable};
+use crate::{AddressMap, Addressed, FileAttr, Location};
+use gimli::{Endianity, Section};
 use object::{Object, ObjectSection, ObjectSymbol};
 use rustc_hash::FxHasher;
 use std::borrow::Cow;
@@ -7,6 +9,7 @@ use std::collections::HashMap;
 use std::hash::BuildHasherDefault;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use typed_arena::Arena;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -39,42 +42,343 @@ impl From<object::Error> for Error {
 pub struct Dwarf {
     /// Mapping from addresses starting at the header base to source files.
     pub file_attrs: AddressMap<FileAttr>,
+    /// Entry point of every function, by it's possibly mangled name.
+    pub functions: AddressMap<Arc<str>>,
+    /// Address ranges of functions and the calls inlined into them.
+    pub scopes: Scopes,
+    /// Types and the global variables using them.
+    pub types: TypeDb,
+    /// Address ranges where local variables and parameters are live.
+    pub locals: Locals,
+}
+
+/// A function or a call that got inlined into one, covering `start..end`.
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub start: usize,
+    pub end: usize,
+    /// How deeply nested the scope's entry is in it's compilation unit.
+    depth: usize,
+    /// Possibly mangled name of the function.
+    pub name: Arc<str>,
+    /// Whether the scope is an inlined call instead of an out of line function.
+    pub inlined: bool,
+    /// Location of an inlined call in the caller.
+    pub call_site: Option<(Arc<Path>, usize)>,
+}
+
+/// Scopes sorted by their start, for finding every scope that contains an address.
+#[derive(Debug, Default)]
+pub struct Scopes {
+    scopes: Vec<Scope>,
+    /// Furthest end of the scopes up to and including the one at the same index.
+    max_end: Vec<usize>,
+}
+
+impl Scopes {
+    pub fn extend(&mut self, other: Self) {
+        self.scopes.extend(other.scopes);
+    }
+
+    pub fn sort(&mut self) {
+        self.scopes.sort_unstable_by_key(|scope| (scope.start, scope.depth));
+        self.max_end = self
+            .scopes
+            .iter()
+            .scan(0, |max_end, scope| {
+                *max_end = scope.end.max(*max_end);
+                Some(*max_end)
+            })
+            .collect();
+    }
+
+    /// Every scope containing `addr`, innermost first.
+    pub fn containing(&self, addr: usize) -> Vec<&Scope> {
+        let mut found = Vec::new();
+        let mut idx = self.scopes.partition_point(|scope| scope.start <= addr);
+
+        // Scopes are nested, so once none of the earlier scopes reach `addr` there's no more.
+        while idx > 0 && self.max_end[idx - 1] > addr {
+            idx -= 1;
+            if self.scopes[idx].end > addr {
+                found.push(&self.scopes[idx]);
+            }
+        }
+
+        found.sort_by_key(|scope| std::cmp::Reverse(scope.depth));
+        found
+    }
+}
+
+/// Where a local lives, before knowing the canonical frame address.
+#[derive(Debug, Clone, Copy)]
+pub enum Place {
+    Location(Location),
+    /// Stored at an offset from the canonical frame address.
+    Cfa(i64),
+}
+
+/// A local variable or parameter that's live in `start..end`.
+#[derive(Debug, Clone)]
+pub struct Local {
+    pub start: usize,
+    pub end: usize,
+    pub name: Arc<str>,
+    pub place: Place,
+}
+
+/// Locals sorted by their start, for finding every local that's live at an address.
+#[derive(Debug, Default)]
+pub struct Locals {
+    locals: Vec<Local>,
+    /// Furthest end of the locals up to and including the one at the same index.
+    max_end: Vec<usize>,
+}
+
+impl Locals {
+    pub fn extend(&mut self, other: Self) {
+        self.locals.extend(other.locals);
+    }
+
+    pub fn sort(&mut self) {
+        self.locals.sort_unstable_by_key(|local| local.start);
+        self.max_end = self
+            .locals
+            .iter()
+            .scan(0, |max_end, local| {
+                *max_end = local.end.max(*max_end);
+                Some(*max_end)
+            })
+            .collect();
+    }
+
+    /// Whether any local is relative to the canonical frame address.
+    pub fn uses_cfa(&self) -> bool {
+        self.locals.iter().any(|local| matches!(local.place, Place::Cfa(_)))
+    }
+
+    /// Every local live at `addr`, in order of their start.
+    pub fn containing(&self, addr: usize) -> impl Iterator<Item = &Local> {
+        let end = self.locals.partition_point(|local| local.start <= addr);
+        let start = self.max_end[..end].partition_point(|&max_end| max_end <= addr);
+        self.locals[start..end].iter().filter(move |local| local.end > addr)
+    }
+}
+
+/// Canonical frame address of the addresses in `start..end`, relative to a register.
+#[derive(Debug, Clone, Copy)]
+struct CallFrame {
+    start: usize,
+    end: usize,
+    register: u16,
+    offset: i64,
+}
+
+/// Rows of the call frame information, sorted by their start.
+#[derive(Debug, Default)]
+pub struct CallFrames {
+    rows: Vec<CallFrame>,
+}
+
+impl CallFrames {
+    pub fn sort(&mut self) {
+        self.rows.sort_unstable_by_key(|row| row.start);
+    }
+
+    /// Canonical frame address rules from `.eh_frame` and `.debug_frame`.
+    pub fn parse(obj: &object::File) -> Self {
+        let endian = if obj.is_little_endian() {
+            gimli::RunTimeEndian::Little
+        } else {
+            gimli::RunTimeEndian::Big
+        };
+
+        let mut frames = Self::default();
+        let address_size = if obj.is_64() { 8 } else { 4 };
+
+        let mut bases = gimli::BaseAddresses::default();
+        if let Some(section) = obj.section_by_name(".text") {
+            bases = bases.set_text(section.address());
+        }
+        if let Some(section) = obj.section_by_name(".got") {
+            bases = bases.set_got(section.address());
+        }
+
+        if let Some(section) = obj.section_by_name(".eh_frame") {
+            if let Ok(data) = section.uncompressed_data() {
+                let mut eh_frame = gimli::EhFrame::new(&data, endian);
+                eh_frame.set_address_size(address_size);
+                let bases = bases.clone().set_eh_frame(section.address());
+                if let Err(err) = dump_call_frame_rows(&eh_frame, &bases, &mut frames) {
+                    log::complex!(
+                        w "[dwarf::call_frames] ",
+                        y "Failed to parse .eh_frame: ",
+                        y format!("{err:?}."),
+                    );
+                }
+            }
+        }
+
+        if let Some(section) = obj.section_by_name(".debug_frame") {
+            if let Ok(data) = section.uncompressed_data() {
+                let mut debug_frame = gimli::DebugFrame::new(&data, endian);
+                debug_frame.set_address_size(address_size);
+                if let Err(err) = dump_call_frame_rows(&debug_frame, &bases, &mut frames) {
+                    log::complex!(
+                        w "[dwarf::call_frames] ",
+                        y "Failed to parse .debug_frame: ",
+                        y format!("{err:?}."),
+                    );
+                }
+            }
+        }
+
+        frames
+    }
+
+    /// Register and offset the canonical frame address is computed from at `addr`.
+    pub fn cfa(&self, addr: usize) -> Option<(u16, i64)> {
+        let idx = self.rows.partition_point(|row| row.start <= addr).checked_sub(1)?;
+        let row = self.rows[idx];
+        if row.end > addr {
+            Some((row.register, row.offset))
+        } else {
+            None
+        }
+    }
 }
 
 impl Dwarf {
-    pub fn parse(obj: &object::File) -> Result<Self> {
+    /// Parses the DWARF in `obj`, including the split units it's skeleton units refer to.
+    pub fn parse(obj: &object::File, path: &Path) -> Result<Self> {
         let endian = if obj.is_little_endian() {
             gimli::RunTimeEndian::Little
         } else {
             gimli::RunTimeEndian::Big
         };
 
+        // Files holding split units or supplementary DWARF, which must outlive their sections.
+        let mmaps = Arena::new();
         let arena_data = Arena::new();
         let arena_relocations = Arena::new();
 
         let mut load_section = |id: gimli::SectionId| {
-            load_file_section(id, obj, endian, &arena_data, &arena_relocations)
+            load_file_section(id, obj, endian, false, &arena_data, &arena_relocations)
         };
 
         let mut dwarf = gimli::Dwarf::load(&mut load_section)?;
         dwarf.populate_abbreviations_cache(gimli::AbbreviationsCacheStrategy::All);
-        let file_attrs = dump_line(&dwarf)?;
 
-        Ok(Dwarf { file_attrs })
+        let map = |path: &Path| -> Result<&memmap2::Mmap> {
+            let file = std::fs::File::open(path)?;
+            Ok(&*mmaps.alloc(unsafe { memmap2::Mmap::map(&file)? }))
+        };
+
+        // Compressed DWARF made by dwz moves what units share into a supplementary file.
+        if let Ok(Some((link, _))) = obj.gnu_debugaltlink() {
+            let link = Path::new(std::str::from_utf8(link).unwrap_or_default());
+            let sup_path = path.parent().unwrap_or(Path::new("")).join(link);
+            let sup = map(&sup_path).and_then(|mmap| {
+                let sup_obj = object::File::parse(&**mmap)?;
+                dwarf.load_sup(|id| {
+                    load_file_section(id, &sup_obj, endian, false, &arena_data, &arena_relocations)
+                })
+            });
+
+            if let Err(err) = sup {
+                log::complex!(
+                    w "[dwarf::parse] ",
+                    y "Failed to load supplementary debug info ",
+                    b sup_path.display().to_string(),
+                    y format!(": {err:?}."),
+                );
+            }
+        }
+
+        let mut split = SplitUnits {
+            path,
+            package: None,
+            load: Box::new(|path: &Path| {
+                let mmap = map(path)?;
+                let split_obj = object::File::parse(&**mmap)?;
+                gimli::Dwarf::load(|id| {
+                    load_file_section(id, &split_obj, endian, true, &arena_data, &arena_relocations)
+                })
+            }),
+            missing: 0,
+        };
+
+        // Split units of every compilation unit can be packaged together next to the binary.
+        let mut package_path = path.as_os_str().to_owned();
+        package_path.push(".dwp");
+        let package_path = PathBuf::from(package_path);
+        if package_path.is_file() {
+            let package = map(&package_path).and_then(|mmap| {
+                let package_obj = object::File::parse(&**mmap)?;
+                let load = |id: gimli::SectionId| {
+                    let obj = &package_obj;
+                    load_file_section(id, obj, endian, true, &arena_data, &arena_relocations)
+                };
+
+                // Packages don't have a `.debug_addr`, so it's loaded as empty.
+                let empty = load(gimli::SectionId::DebugAddr)?;
+                let mut load_section = |id: gimli::SectionId| {
+                    let section = load(id)?;
+
+                    // GNU's dwp writes an index without any slots when there's no type units,
+                    // which gimli rejects.
+                    if id == gimli::SectionId::DebugTuIndex && is_empty_index(section.section) {
+                        return Ok(empty.clone());
+                    }
+                    Ok(section)
+                };
+                gimli::DwarfPackage::load(&mut load_section, empty.clone())
+            });
+
+            match package {
+                Ok(package) => split.package = Some(package),
+                Err(err) => log::complex!(
+                    w "[dwarf::parse] ",
+                    y "Failed to load split debug info package ",
+                    b package_path.display().to_string(),
+                    y format!(": {err:?}."),
+                ),
+            }
+        }
+
+        dump_units(&dwarf, &mut split)
     }
 
     pub fn load(path: &Path) -> Result<Self> {
         let file = std::fs::File::open(path)?;
         let mmap = unsafe { memmap2::Mmap::map(&file)? };
         let obj = object::File::parse(&*mmap)?;
-        Self::parse(&obj)
+        Self::parse(&obj, path)
     }
 
     pub fn merge(&mut self, other: Self) {
         self.file_attrs.extend(other.file_attrs);
+        self.functions.extend(other.functions);
+        self.scopes.extend(other.scopes);
+        self.types.extend(other.types);
+        self.locals.extend(other.locals);
     }
 }
 
+/// Loads the split units in a `.dwo`.
+type LoadSplit<'a, R> = Box<dyn FnMut(&Path) -> Result<gimli::Dwarf<R>> + 'a>;
+
+/// Where to find the split units that skeleton units refer to.
+struct SplitUnits<'a, R: Reader> {
+    /// Path of the binary with the skeleton units.
+    path: &'a Path,
+    /// Split units packaged together in a `.dwp`.
+    package: Option<gimli::DwarfPackage<R>>,
+    load: LoadSplit<'a, R>,
+    /// Number of skeleton units whose split unit wasn't found.
+    missing: usize,
+}
+
 trait Reader: gimli::Reader<Offset = usize> + Send + Sync {}
 
 impl<'input, Endian: gimli::Endianity + Send + Sync> Reader for gimli::EndianSlice<'input, Endian> {}
@@ -290,11 +594,14 @@ fn load_file_section<'input, 'arena, Endian: gimli::Endianity>(
     id: gimli::SectionId,
     file: &object::File<'input>,
     endian: Endian,
+    split: bool,
     arena_data: &'arena Arena<Cow<'input, [u8]>>,
     arena_relocations: &'arena Arena<RelocationMap>,
 ) -> Result<Relocate<'arena, gimli::EndianSlice<'arena, Endian>>> {
     let mut relocations = RelocationMap::default();
-    let name = if file.format() == object::BinaryFormat::Xcoff {
+    let name = if split {
+        id.dwo_name()
+    } else if file.format() == object::BinaryFormat::Xcoff {
         id.xcoff_name()
     } else {
         Some(id.name())
@@ -319,9 +626,21 @@ fn load_file_section<'input, 'arena, Endian: gimli::Endianity>(
     })
 }
 
-fn dump_line<R: Reader>(dwarf: &gimli::Dwarf<R>) -> Result<AddressMap<FileAttr>> {
+/// Whether a `.debug_cu_index` or `.debug_tu_index` lists neither units nor slots.
+fn is_empty_index<R: gimli::Reader<Offset = usize>>(mut index: R) -> bool {
+    // The unit and slot counts follow the version and section count.
+    let counts = index.skip(8).and_then(|_| Ok((index.read_u32()?, index.read_u32()?)));
+    matches!(counts, Ok((0, 0)))
+}
+
+fn dump_units<R: Reader>(dwarf: &gimli::Dwarf<R>, split: &mut SplitUnits<R>) -> Result<Dwarf> {
     let mut iter = dwarf.units();
     let mut file_attrs = AddressMap::default();
+    let mut functions = AddressMap::default();
+    let mut scopes = Vec::new();
+    let mut locals = Vec::new();
+    let mut names = HashMap::new();
+    let mut types = TypeDb::default();
     let path_cache = InternMap::new();
 
     let mut id = 0;
@@ -330,8 +649,8 @@ fn dump_line<R: Reader>(dwarf: &gimli::Dwarf<R>) -> Result<AddressMap<FileAttr>>
             Ok(unit) => unit,
             Err(err) => {
                 log::complex!(
-                    w "[dwarf::dump_line] ",
-                    y "Failed to parse unit root entry for dump_line: ",
+                    w "[dwarf::dump_units] ",
+                    y "Failed to parse unit root entry: ",
                     y format!("{err:?}."),
                 );
                 continue;
@@ -344,16 +663,92 @@ fn dump_line<R: Reader>(dwarf: &gimli::Dwarf<R>) -> Result<AddressMap<FileAttr>>
                 y format!("{err:?}."),
             );
         }
+
+        // Skeleton units only hold the line program, the rest is in their split unit.
+        let split_unit = match split_unit(&unit, dwarf, split) {
+            Ok(split_unit) => split_unit,
+            Err(err) => {
+                log::complex!(
+                    w "[dwarf::split_unit] ",
+                    y "Failed to load split unit: ",
+                    y format!("{err:?}."),
+                );
+                None
+            }
+        };
+        let (unit, dwarf) = match &split_unit {
+            Some((split_dwarf, split_unit)) => (split_unit, split_dwarf),
+            None => (&unit, dwarf),
+        };
+
+        let mut ctx = ScopeContext {
+            id,
+            path_cache: &path_cache,
+            names: &mut names,
+            functions: &mut functions,
+            scopes: &mut scopes,
+            locals: &mut locals,
+        };
+        if let Err(err) = dump_scopes(&mut ctx, unit, dwarf) {
+            log::complex!(
+                w "[dwarf::dump_scopes] ",
+                y "Failed to dump functions: ",
+                y format!("{err:?}."),
+            );
+        }
+        if let Err(err) = dump_types(&mut types, unit, dwarf) {
+            log::complex!(
+                w "[dwarf::dump_types] ",
+                y "Failed to dump types: ",
+                y format!("{err:?}."),
+            );
+        }
         id += 1;
     }
+    if split.missing > 0 {
+        log::complex!(
+            w "[dwarf::dump_units] ",
+            y "Split debug info of ",
+            g split.missing.to_string(),
+            y " units is missing."
+        );
+    }
     if path_cache.len() > 0 {
         log::complex!(
-            w "[dwarf::dump_line] indexed ",
+            w "[dwarf::dump_units] indexed ",
             g path_cache.len().to_string(),
             w " source files."
         );
     }
-    Ok(file_attrs)
+    if !scopes.is_empty() {
+        log::complex!(
+            w "[dwarf::dump_units] found ",
+            g functions.len().to_string(),
+            w " functions and ",
+            g scopes.iter().filter(|scope| scope.inlined).count().to_string(),
+            w " inlined calls."
+        );
+    }
+    if !locals.is_empty() {
+        log::complex!(
+            w "[dwarf::dump_units] found ",
+            g locals.len().to_string(),
+            w " ranges of local variables."
+        );
+    }
+    Ok(Dwarf {
+        file_attrs,
+        functions,
+        scopes: Scopes {
+            scopes,
+            max_end: Vec::new(),
+        },
+        types,
+        locals: Locals {
+            locals,
+            max_end: Vec::new(),
+        },
+    })
 }
 
 fn dump_line_program<R: Reader>(
@@ -365,12 +760,6 @@ fn dump_line_program<R: Reader>(
 ) -> Result<()> {
     if let Some(program) = unit.line_program.clone() {
         let mut rows = program.rows();
-        let comp_dir = unit
-            .comp_dir
-            .as_ref()
-            .map(|dir| dir.to_string_lossy().unwrap_or_default().into_owned())
-            .map(PathBuf::from)
-            .unwrap_or_default();
 
         while let Some((header, row)) = rows.next_row()? {
             let line = match row.line() {
@@ -385,33 +774,11 @@ fn dump_line_program<R: Reader>(
                 gimli::ColumnType::Column(column) => column.get() as usize - 1,
                 gimli::ColumnType::LeftEdge => 0,
             };
-            let file = match row.file(header) {
-                Some(file) => file,
+            let path = match file_path(id, path_cache, unit, dwarf, header, row.file_index())? {
+                Some(path) => path,
                 None => continue,
             };
 
-            // Try to use cached path if possible, prevents extra allocations.
-            // A path is uniquely identified by it's comp_dir, directory id and it's file id.
-            let key = id << 48 | row.file_index() << 24 | file.directory_index();
-            let path = match path_cache.get(&key) {
-                Some(cached) => cached,
-                None => {
-                    let mut path = comp_dir.clone();
-
-                    if let Some(dir) = file.directory(header) {
-                        if let Ok(path_comp) = dwarf.attr_string(unit, dir)?.to_string_lossy() {
-                            path.push(&*path_comp);
-                        }
-                    }
-
-                    if let Ok(path_comp) = dwarf.attr_string(unit, file.path_name())?.to_string_lossy() {
-                        path.push(&*path_comp);
-                    }
-
-                    path_cache.add(key, &path)
-                }
-            };
-
             file_attrs.push(Addressed {
                 addr: row.address() as usize,
                 item: FileAttr {
@@ -426,3 +793,915 @@ fn dump_line_program<R: Reader>(
 
     Ok(())
 }
+
+/// The split unit and it's sections that a skeleton unit refers to, either in the `.dwp` next
+/// to the binary or in the unit's `.dwo`.
+fn split_unit<R: Reader>(
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    split: &mut SplitUnits<R>,
+) -> Result<Option<(gimli::Dwarf<R>, gimli::Unit<R>)>> {
+    let dwo_id = match unit.dwo_id {
+        Some(dwo_id) => dwo_id,
+        None => return Ok(None),
+    };
+
+    let packaged = match &split.package {
+        Some(package) => package.find_cu(dwo_id, dwarf)?,
+        None => None,
+    };
+
+    let mut split_dwarf = match packaged {
+        Some(split_dwarf) => split_dwarf,
+        None => {
+            let name = match unit.dwo_name()? {
+                Some(name) => dwarf.attr_string(unit, name)?.to_string_lossy()?.into_owned(),
+                None => return Ok(None),
+            };
+
+            let mut dwo_path = unit
+                .comp_dir
+                .as_ref()
+                .map(|dir| dir.to_string_lossy().unwrap_or_default().into_owned())
+                .map(PathBuf::from)
+                .unwrap_or_default();
+            dwo_path.push(&name);
+
+            // The build directory might be gone, but the `.dwo` could've been moved along.
+            if !dwo_path.is_file() {
+                let file_name = Path::new(&name).file_name().unwrap_or_default();
+                dwo_path = split.path.parent().unwrap_or(Path::new("")).join(file_name);
+            }
+
+            if !dwo_path.is_file() {
+                split.missing += 1;
+                return Ok(None);
+            }
+
+            let mut split_dwarf = (split.load)(&dwo_path)?;
+            split_dwarf.make_dwo(dwarf);
+            split_dwarf
+        }
+    };
+
+    // File names are in the skeleton's line program, so their strings are too.
+    split_dwarf.debug_line_str = dwarf.debug_line_str.clone();
+
+    let mut headers = split_dwarf.units();
+    while let Some(header) = headers.next()? {
+        let mut split_unit = split_dwarf.unit(header)?;
+        if split_unit.dwo_id != Some(dwo_id) {
+            continue;
+        }
+
+        split_unit.copy_relocated_attributes(unit);
+        if split_unit.line_program.is_none() {
+            split_unit.line_program = unit.line_program.clone();
+        }
+        if split_unit.comp_dir.is_none() {
+            split_unit.comp_dir = unit.comp_dir.clone();
+        }
+
+        return Ok(Some((split_dwarf, split_unit)));
+    }
+
+    split.missing += 1;
+    Ok(None)
+}
+
+/// Path of a file in a unit's line program.
+fn file_path<R: Reader>(
+    id: u64,
+    path_cache: &InternMap<u64, Path>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    header: &gimli::LineProgramHeader<R>,
+    file_index: u64,
+) -> Result<Option<Arc<Path>>> {
+    let file = match header.file(file_index) {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+
+    // Try to use cached path if possible, prevents extra allocations.
+    // A path is uniquely identified by it's comp_dir, directory id and it's file id.
+    let key = id << 48 | file_index << 24 | file.directory_index();
+    if let Some(cached) = path_cache.get(&key) {
+        return Ok(Some(cached));
+    }
+
+    let mut path = unit
+        .comp_dir
+        .as_ref()
+        .map(|dir| dir.to_string_lossy().unwrap_or_default().into_owned())
+        .map(PathBuf::from)
+        .unwrap_or_default();
+
+    if let Some(dir) = file.directory(header) {
+        if let Ok(path_comp) = dwarf.attr_string(unit, dir)?.to_string_lossy() {
+            path.push(&*path_comp);
+        }
+    }
+
+    if let Ok(path_comp) = dwarf.attr_string(unit, file.path_name())?.to_string_lossy() {
+        path.push(&*path_comp);
+    }
+
+    Ok(Some(path_cache.add(key, &path)))
+}
+
+/// How many declarations or abstract instances are followed to find a function's name.
+const MAX_ORIGIN_DEPTH: usize = 8;
+
+/// State shared between the units while collecting scopes.
+struct ScopeContext<'a> {
+    id: u64,
+    path_cache: &'a InternMap<u64, Path>,
+    /// Function names, so that every inlined call of a function shares it's name.
+    names: &'a mut HashMap<String, Arc<str>>,
+    functions: &'a mut AddressMap<Arc<str>>,
+    scopes: &'a mut Vec<Scope>,
+    locals: &'a mut Vec<Local>,
+}
+
+impl ScopeContext<'_> {
+    fn intern(&mut self, name: String) -> Arc<str> {
+        match self.names.get(&name) {
+            Some(name) => Arc::clone(name),
+            None => {
+                let interned: Arc<str> = Arc::from(&name[..]);
+                self.names.insert(name, Arc::clone(&interned));
+                interned
+            }
+        }
+    }
+}
+
+/// A function, inlined call or block that encloses locals.
+struct Enclosing {
+    depth: isize,
+    ranges: Vec<(usize, usize)>,
+    /// Where `DW_OP_fbreg` is relative to.
+    frame_base: Option<Place>,
+}
+
+fn dump_scopes<R: Reader>(
+    ctx: &mut ScopeContext,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+) -> Result<()> {
+    let header = unit.line_program.as_ref().map(|program| program.header());
+    let mut entries = unit.entries();
+    let mut depth = 0;
+    let mut discarded = None;
+    let mut enclosing: Vec<Enclosing> = Vec::new();
+
+    while let Some((delta, entry)) = entries.next_dfs()? {
+        depth += delta;
+
+        // Calls inlined into a function that got discarded are left relative to address zero.
+        match discarded {
+            Some(discarded_depth) if depth > discarded_depth => continue,
+            _ => discarded = None,
+        }
+
+        while matches!(enclosing.last(), Some(scope) if scope.depth >= depth) {
+            enclosing.pop();
+        }
+
+        let parent = match enclosing.last() {
+            Some(scope) if scope.depth == depth - 1 => Some(scope),
+            _ => None,
+        };
+
+        let inlined = match entry.tag() {
+            gimli::DW_TAG_subprogram => false,
+            gimli::DW_TAG_inlined_subroutine => true,
+            gimli::DW_TAG_lexical_block => {
+                if let Some(parent) = parent {
+                    let mut ranges = Vec::new();
+                    let mut iter = dwarf.die_ranges(unit, entry)?;
+                    while let Some(range) = iter.next()? {
+                        if range.begin != 0 && range.begin < range.end {
+                            ranges.push((range.begin as usize, range.end as usize));
+                        }
+                    }
+
+                    // Blocks without a range of their own are as wide as their parent.
+                    if ranges.is_empty() {
+                        ranges = parent.ranges.clone();
+                    }
+
+                    let frame_base = parent.frame_base;
+                    enclosing.push(Enclosing { depth, ranges, frame_base });
+                }
+                continue;
+            }
+            gimli::DW_TAG_variable | gimli::DW_TAG_formal_parameter => {
+                if let Some(parent) = parent {
+                    dump_local(ctx, unit, dwarf, entry, parent)?;
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        let name = die_name(unit, dwarf, entry, 0)?.unwrap_or_default();
+        let name = ctx.intern(name);
+
+        let mut call_site = None;
+        if inlined {
+            let call_line = match entry.attr_value(gimli::DW_AT_call_line)? {
+                Some(value) => value.udata_value().unwrap_or(0) as usize,
+                None => 0,
+            };
+
+            if let (Some(gimli::AttributeValue::FileIndex(file_index)), Some(header)) =
+                (entry.attr_value(gimli::DW_AT_call_file)?, header)
+            {
+                if let Some(path) =
+                    file_path(ctx.id, ctx.path_cache, unit, dwarf, header, file_index)?
+                {
+                    call_site = Some((path, call_line));
+                }
+            }
+        }
+
+        let mut ranges = Vec::new();
+        let mut iter = dwarf.die_ranges(unit, entry)?;
+        while let Some(range) = iter.next()? {
+            // Functions discarded by the linker are left at address zero.
+            if range.begin == 0 || range.begin >= range.end {
+                continue;
+            }
+
+            ranges.push((range.begin as usize, range.end as usize));
+            ctx.scopes.push(Scope {
+                start: range.begin as usize,
+                end: range.end as usize,
+                depth: depth as usize,
+                name: Arc::clone(&name),
+                inlined,
+                call_site: call_site.clone(),
+            });
+        }
+
+        let entry_point = match ranges.first() {
+            Some(&(start, _)) => start,
+            None => {
+                discarded = Some(depth);
+                continue;
+            }
+        };
+
+        if !inlined && !name.is_empty() {
+            ctx.functions.push(Addressed {
+                addr: entry_point,
+                item: name,
+            });
+        }
+
+        // Inlined calls share the frame of the function they're inlined into.
+        let frame_base = match (inlined, entry.attr_value(gimli::DW_AT_frame_base)?) {
+            (false, Some(gimli::AttributeValue::Exprloc(expr))) => {
+                evaluate_place(expr, unit.encoding(), None)?
+            }
+            (false, _) => None,
+            (true, _) => parent.and_then(|parent| parent.frame_base),
+        };
+
+        enclosing.push(Enclosing { depth, ranges, frame_base });
+    }
+
+    Ok(())
+}
+
+/// Every range where a local variable or parameter is stored somewhere known.
+fn dump_local<R: Reader>(
+    ctx: &mut ScopeContext,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+    parent: &Enclosing,
+) -> Result<()> {
+    let location = match entry.attr_value(gimli::DW_AT_location)? {
+        Some(location) => location,
+        None => return Ok(()),
+    };
+
+    let name = match die_name(unit, dwarf, entry, 0)? {
+        Some(name) => ctx.intern(name),
+        None => return Ok(()),
+    };
+
+    // A single expression holds for as long as the enclosing scope.
+    if let gimli::AttributeValue::Exprloc(expr) = location {
+        if let Some(place) = evaluate_place(expr, unit.encoding(), parent.frame_base)? {
+            for &(start, end) in &parent.ranges {
+                ctx.locals.push(Local {
+                    start,
+                    end,
+                    name: Arc::clone(&name),
+                    place,
+                });
+            }
+        }
+
+        return Ok(());
+    }
+
+    let mut locations = match dwarf.attr_locations(unit, location)? {
+        Some(locations) => locations,
+        None => return Ok(()),
+    };
+
+    while let Some(location) = locations.next()? {
+        if location.range.begin == 0 || location.range.begin >= location.range.end {
+            continue;
+        }
+
+        if let Some(place) = evaluate_place(location.data, unit.encoding(), parent.frame_base)? {
+            ctx.locals.push(Local {
+                start: location.range.begin as usize,
+                end: location.range.end as usize,
+                name: Arc::clone(&name),
+                place,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Where an expression says a value lives, if it's a register or somewhere relative to one.
+///
+/// Anything that needs a stack machine, such as computed values or pieces, isn't supported.
+fn evaluate_place<R: Reader>(
+    expr: gimli::Expression<R>,
+    encoding: gimli::Encoding,
+    frame_base: Option<Place>,
+) -> Result<Option<Place>> {
+    let mut ops = expr.operations(encoding);
+    let place = match ops.next()? {
+        Some(gimli::Operation::Register { register }) => {
+            Place::Location(Location::Register(register.0))
+        }
+        Some(gimli::Operation::RegisterOffset { register, offset, .. }) => {
+            Place::Location(Location::Memory { register: register.0, offset })
+        }
+        Some(gimli::Operation::FrameOffset { offset }) => match frame_base {
+            // A frame base in a register is the address in it, not something stored there.
+            Some(Place::Location(Location::Register(register))) => {
+                Place::Location(Location::Memory { register, offset })
+            }
+            Some(Place::Location(Location::Memory { register, offset: base })) => {
+                Place::Location(Location::Memory { register, offset: base + offset })
+            }
+            Some(Place::Cfa(base)) => Place::Cfa(base + offset),
+            None => return Ok(None),
+        },
+        Some(gimli::Operation::CallFrameCFA) => Place::Cfa(0),
+        _ => return Ok(None),
+    };
+
+    if ops.next()?.is_some() {
+        return Ok(None);
+    }
+
+    Ok(Some(place))
+}
+
+fn dump_call_frame_rows<R: gimli::Reader, S: gimli::UnwindSection<R>>(
+    section: &S,
+    bases: &gimli::BaseAddresses,
+    frames: &mut CallFrames,
+) -> Result<()> {
+    let mut ctx = gimli::UnwindContext::new();
+    let mut entries = section.entries(bases);
+
+    while let Some(entry) = entries.next()? {
+        let partial = match entry {
+            gimli::CieOrFde::Fde(partial) => partial,
+            gimli::CieOrFde::Cie(_) => continue,
+        };
+
+        let fde = match partial.parse(S::cie_from_offset) {
+            Ok(fde) => fde,
+            Err(_) => continue,
+        };
+
+        // Functions discarded by the linker are left at address zero.
+        if fde.initial_address() == 0 {
+            continue;
+        }
+
+        let mut rows = fde.rows(section, bases, &mut ctx)?;
+        while let Some(row) = rows.next_row()? {
+            if let gimli::CfaRule::RegisterAndOffset { register, offset } = row.cfa() {
+                frames.rows.push(CallFrame {
+                    start: row.start_address() as usize,
+                    end: row.end_address() as usize,
+                    register: register.0,
+                    offset: *offset,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Name of a function's entry, preferring the mangled name of it's declaration or abstract
+/// instance over the entry's plain name.
+fn die_name<R: Reader>(
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+    depth: usize,
+) -> Result<Option<String>> {
+    for attr in [gimli::DW_AT_linkage_name, gimli::DW_AT_MIPS_linkage_name] {
+        if let Some(value) = entry.attr_value(attr)? {
+            return Ok(Some(dwarf.attr_string(unit, value)?.to_string_lossy()?.into_owned()));
+        }
+    }
+
+    if depth < MAX_ORIGIN_DEPTH {
+        for attr in [gimli::DW_AT_abstract_origin, gimli::DW_AT_specification] {
+            if let Some(gimli::AttributeValue::UnitRef(offset)) = entry.attr_value(attr)? {
+                let origin = unit.entry(offset)?;
+                if let Some(name) = die_name(unit, dwarf, &origin, depth + 1)? {
+                    return Ok(Some(name));
+                }
+            }
+        }
+    }
+
+    match entry.attr_value(gimli::DW_AT_name)? {
+        Some(value) => Ok(Some(dwarf.attr_string(unit, value)?.to_string_lossy()?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+/// Id of the type at `offset` in the unit, reserving one if it hasn't been described yet.
+fn type_id(db: &mut TypeDb, ids: &mut HashMap<usize, TypeId>, offset: usize) -> TypeId {
+    *ids.entry(offset).or_insert_with(|| {
+        db.types.push(Type::Unknown);
+        db.types.len() - 1
+    })
+}
+
+/// Attributes describing types and variables, read in a single pass over an entry.
+struct TypeAttrs<R: Reader> {
+    name: Option<gimli::AttributeValue<R>>,
+    tipe: Option<usize>,
+    byte_size: Option<u64>,
+    encoding: Option<gimli::DwAte>,
+    member_location: Option<gimli::AttributeValue<R>>,
+    bit_size: Option<u64>,
+    data_bit_offset: Option<u64>,
+    bit_offset: Option<u64>,
+    const_value: Option<i64>,
+    count: Option<u64>,
+    location: Option<gimli::Expression<R>>,
+    specification: Option<gimli::UnitOffset>,
+}
+
+impl<R: Reader> TypeAttrs<R> {
+    fn parse(entry: &gimli::DebuggingInformationEntry<R>) -> Result<Self> {
+        let mut this = Self {
+            name: None,
+            tipe: None,
+            byte_size: None,
+            encoding: None,
+            member_location: None,
+            bit_size: None,
+            data_bit_offset: None,
+            bit_offset: None,
+            const_value: None,
+            count: None,
+            location: None,
+            specification: None,
+        };
+
+        let mut attrs = entry.attrs();
+        while let Some(attr) = attrs.next()? {
+            let value = attr.value();
+            match attr.name() {
+                gimli::DW_AT_name => this.name = Some(value),
+                gimli::DW_AT_type => {
+                    if let gimli::AttributeValue::UnitRef(offset) = value {
+                        this.tipe = Some(offset.0);
+                    }
+                }
+                gimli::DW_AT_byte_size => this.byte_size = value.udata_value(),
+                gimli::DW_AT_encoding => {
+                    if let gimli::AttributeValue::Encoding(encoding) = value {
+                        this.encoding = Some(encoding);
+                    }
+                }
+                gimli::DW_AT_data_member_location => this.member_location = Some(value),
+                gimli::DW_AT_bit_size => this.bit_size = value.udata_value(),
+                gimli::DW_AT_data_bit_offset => this.data_bit_offset = value.udata_value(),
+                gimli::DW_AT_bit_offset => this.bit_offset = value.udata_value(),
+                gimli::DW_AT_const_value => {
+                    this.const_value = match value {
+                        gimli::AttributeValue::Sdata(value) => Some(value),
+                        value => value.udata_value().map(|value| value as i64),
+                    }
+                }
+                gimli::DW_AT_count => this.count = value.udata_value(),
+                gimli::DW_AT_upper_bound if this.count.is_none() => {
+                    this.count = value.udata_value().map(|bound| bound + 1);
+                }
+                gimli::DW_AT_location => {
+                    if let gimli::AttributeValue::Exprloc(expr) = value {
+                        this.location = Some(expr);
+                    }
+                }
+                gimli::DW_AT_specification => {
+                    if let gimli::AttributeValue::UnitRef(offset) = value {
+                        this.specification = Some(offset);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(this)
+    }
+
+    fn name(&self, unit: &gimli::Unit<R>, dwarf: &gimli::Dwarf<R>) -> Result<Option<String>> {
+        match self.name.clone() {
+            Some(value) => {
+                let name = dwarf.attr_string(unit, value)?;
+                Ok(Some(name.to_string_lossy()?.into_owned()))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+fn dump_types<R: Reader>(
+    db: &mut TypeDb,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+) -> Result<()> {
+    let little_endian = dwarf.debug_info.reader().endian().is_little_endian();
+
+    // Types are referenced by their offset in the unit, which can be before they're described.
+    let mut ids = HashMap::new();
+
+    // Structures, enums and arrays that their children describe, by depth.
+    let mut parents: Vec<(isize, TypeId)> = Vec::new();
+
+    let mut entries = unit.entries();
+    let mut depth = 0;
+
+    while let Some((delta, entry)) = entries.next_dfs()? {
+        depth += delta;
+
+        while matches!(parents.last(), Some((parent_depth, _)) if *parent_depth >= depth) {
+            parents.pop();
+        }
+
+        let parent = match parents.last() {
+            Some((parent_depth, parent)) if *parent_depth == depth - 1 => Some(*parent),
+            _ => None,
+        };
+
+        match entry.tag() {
+            gimli::DW_TAG_base_type
+            | gimli::DW_TAG_structure_type
+            | gimli::DW_TAG_class_type
+            | gimli::DW_TAG_union_type
+            | gimli::DW_TAG_enumeration_type
+            | gimli::DW_TAG_pointer_type
+            | gimli::DW_TAG_reference_type
+            | gimli::DW_TAG_rvalue_reference_type
+            | gimli::DW_TAG_array_type
+            | gimli::DW_TAG_typedef
+            | gimli::DW_TAG_const_type
+            | gimli::DW_TAG_volatile_type
+            | gimli::DW_TAG_subroutine_type
+            | gimli::DW_TAG_variable => {}
+            // Children only matter to the types they describe.
+            gimli::DW_TAG_member
+            | gimli::DW_TAG_inheritance
+            | gimli::DW_TAG_enumerator
+            | gimli::DW_TAG_subrange_type
+            | gimli::DW_TAG_formal_parameter
+            | gimli::DW_TAG_unspecified_parameters
+                if parent.is_some() => {}
+            _ => continue,
+        }
+
+        let attrs = TypeAttrs::parse(entry)?;
+        let target = attrs.tipe.map(|offset| type_id(db, &mut ids, offset));
+        let size = attrs.byte_size.map(|size| size as usize);
+
+        let tipe = match entry.tag() {
+            gimli::DW_TAG_base_type => {
+                let encoding = match attrs.encoding {
+                    Some(gimli::DW_ATE_signed) => Encoding::Signed,
+                    Some(gimli::DW_ATE_signed_char | gimli::DW_ATE_unsigned_char) => {
+                        Encoding::Char
+                    }
+                    Some(gimli::DW_ATE_float) => Encoding::Float,
+                    Some(gimli::DW_ATE_boolean) => Encoding::Bool,
+                    _ => Encoding::Unsigned,
+                };
+
+                Type::Base {
+                    name: attrs.name(unit, dwarf)?.unwrap_or_default(),
+                    size: size.unwrap_or(0),
+                    encoding,
+                }
+            }
+            gimli::DW_TAG_structure_type | gimli::DW_TAG_class_type | gimli::DW_TAG_union_type => {
+                Type::Struct {
+                    name: attrs.name(unit, dwarf)?,
+                    size: size.unwrap_or(0),
+                    members: Vec::new(),
+                    is_union: entry.tag() == gimli::DW_TAG_union_type,
+                }
+            }
+            gimli::DW_TAG_enumeration_type => Type::Enum {
+                name: attrs.name(unit, dwarf)?,
+                size: size.or_else(|| target.and_then(|target| db.size_of(target))).unwrap_or(0),
+                variants: Vec::new(),
+            },
+            gimli::DW_TAG_pointer_type
+            | gimli::DW_TAG_reference_type
+            | gimli::DW_TAG_rvalue_reference_type => Type::Pointer {
+                size: size.unwrap_or(unit.encoding().address_size as usize),
+                target,
+            },
+            gimli::DW_TAG_array_type => Type::Array {
+                elem: match target {
+                    Some(target) => target,
+                    None => {
+                        db.types.push(Type::Unknown);
+                        db.types.len() - 1
+                    }
+                },
+                count: 1,
+            },
+            gimli::DW_TAG_typedef => Type::Typedef {
+                name: attrs.name(unit, dwarf)?.unwrap_or_default(),
+                target,
+            },
+            gimli::DW_TAG_const_type => Type::Qualified { qualifier: "const", target },
+            gimli::DW_TAG_volatile_type => Type::Qualified { qualifier: "volatile", target },
+            gimli::DW_TAG_subroutine_type => Type::Function {
+                ret: target,
+                params: Vec::new(),
+                variadic: false,
+            },
+            gimli::DW_TAG_member | gimli::DW_TAG_inheritance => {
+                if let (Some(parent), Some(tipe)) = (parent, target) {
+                    let name = attrs.name(unit, dwarf)?;
+                    if let Some(member) = dump_member(&attrs, unit, name, tipe, little_endian)? {
+                        if let Type::Struct { members, .. } = &mut db.types[parent] {
+                            members.push(member);
+                        }
+                    }
+                }
+                continue;
+            }
+            gimli::DW_TAG_enumerator => {
+                if let (Some(parent), Some(value)) = (parent, attrs.const_value) {
+                    if let (Type::Enum { variants, .. }, Some(name)) =
+                        (&mut db.types[parent], attrs.name(unit, dwarf)?)
+                    {
+                        variants.push((value, name));
+                    }
+                }
+                continue;
+            }
+            gimli::DW_TAG_formal_parameter | gimli::DW_TAG_unspecified_parameters => {
+                if let Some(Type::Function { params, variadic, .. }) =
+                    parent.map(|parent| &mut db.types[parent])
+                {
+                    if entry.tag() == gimli::DW_TAG_unspecified_parameters {
+                        *variadic = true;
+                    } else if let Some(target) = target {
+                        params.push(target);
+                    }
+                }
+                continue;
+            }
+            gimli::DW_TAG_subrange_type => {
+                if let Some(parent) = parent {
+                    if let Type::Array { count, .. } = &mut db.types[parent] {
+                        // Dimensions that overflow can't describe a real array.
+                        let dimension = attrs.count.unwrap_or(0) as usize;
+                        *count = count.checked_mul(dimension).unwrap_or(0);
+                    }
+                }
+                continue;
+            }
+            gimli::DW_TAG_variable => {
+                if let Some((addr, name, tipe)) = dump_variable(&attrs, unit, dwarf)? {
+                    let tipe = type_id(db, &mut ids, tipe);
+                    db.variables.push(Addressed {
+                        addr,
+                        item: Variable { name, tipe },
+                    });
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        let is_parent = matches!(
+            tipe,
+            Type::Struct { .. } | Type::Enum { .. } | Type::Array { .. } | Type::Function { .. }
+        );
+        let id = type_id(db, &mut ids, entry.offset().0);
+        db.types[id] = tipe;
+
+        if is_parent {
+            parents.push((depth, id));
+        }
+    }
+
+    Ok(())
+}
+
+fn dump_member<R: Reader>(
+    attrs: &TypeAttrs<R>,
+    unit: &gimli::Unit<R>,
+    name: Option<String>,
+    tipe: TypeId,
+    little_endian: bool,
+) -> Result<Option<Member>> {
+    let offset = match attrs.member_location.clone() {
+        Some(gimli::AttributeValue::Exprloc(expr)) => {
+            // Old producers describe the offset as an expression.
+            let mut ops = expr.operations(unit.encoding());
+            match ops.next()? {
+                Some(gimli::Operation::PlusConstant { value }) => value as usize,
+                _ => return Ok(None),
+            }
+        }
+        Some(value) => match value.udata_value() {
+            Some(offset) => offset as usize,
+            None => return Ok(None),
+        },
+        None => 0,
+    };
+
+    let width = match attrs.bit_size {
+        Some(width) => width as usize,
+        None => return Ok(Some(Member { name, offset, tipe, bits: None })),
+    };
+
+    let bit_offset = match (attrs.data_bit_offset, attrs.byte_size, attrs.bit_offset) {
+        (Some(bit_offset), ..) => Some(bit_offset as usize),
+        // DWARF 2 and 3 count the bits from the most significant end of the storage unit.
+        (None, Some(storage), Some(msb_offset)) if little_endian => (storage as usize * 8)
+            .checked_sub(msb_offset as usize + width)
+            .map(|bit_offset| offset * 8 + bit_offset),
+        (None, Some(_), Some(msb_offset)) => Some(offset * 8 + msb_offset as usize),
+        _ => None,
+    };
+
+    Ok(bit_offset.map(|bit_offset| Member {
+        name,
+        offset: bit_offset / 8,
+        tipe,
+        bits: Some((bit_offset % 8, width)),
+    }))
+}
+
+/// Address, name and type offset of a variable that lives at a fixed address.
+fn dump_variable<R: Reader>(
+    attrs: &TypeAttrs<R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+) -> Result<Option<(usize, String, usize)>> {
+    let expr = match attrs.location.clone() {
+        Some(expr) => expr,
+        None => return Ok(None),
+    };
+
+    let mut ops = expr.operations(unit.encoding());
+    let addr = match ops.next()? {
+        Some(gimli::Operation::Address { address }) => address,
+        Some(gimli::Operation::AddressIndex { index }) => dwarf.address(unit, index)?,
+        _ => return Ok(None),
+    };
+
+    // Thread locals and the like are computed from more than just an address.
+    if addr == 0 || ops.next()?.is_some() {
+        return Ok(None);
+    }
+
+    let mut name = attrs.name(unit, dwarf)?;
+    let mut tipe = attrs.tipe;
+
+    // Definitions of static members and previously declared variables refer to the declaration.
+    if let Some(offset) = attrs.specification {
+        let decl = TypeAttrs::parse(&unit.entry(offset)?)?;
+        if name.is_none() {
+            name = decl.name(unit, dwarf)?;
+        }
+
+        if tipe.is_none() {
+            tipe = decl.tipe;
+        }
+    }
+
+    match (name, tipe) {
+        (Some(name), Some(tipe)) => Ok(Some((addr as usize, name, tipe))),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn parse() -> Dwarf {
+        let bytes = testing::inlined_calls();
+        let obj = object::File::parse(&*bytes).unwrap();
+        let mut dwarf = Dwarf::parse(&obj, Path::new("/nonexistent/bar")).unwrap();
+        dwarf.scopes.sort();
+        dwarf
+    }
+
+    /// Name, range, whether it's inlined and the call site of every scope containing `addr`.
+    fn scopes(dwarf: &Dwarf, addr: usize) -> Vec<(&str, usize, usize, bool, Option<String>)> {
+        let scopes = dwarf.scopes.containing(addr).into_iter();
+        scopes
+            .map(|scope| {
+                let call_site = scope.call_site.as_ref();
+                let call_site = call_site.map(|(path, line)| format!("{}:{line}", path.display()));
+                (
+                    &*scope.name,
+                    scope.start,
+                    scope.end,
+                    scope.inlined,
+                    call_site,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn functions() {
+        let dwarf = parse();
+        let functions: Vec<_> = dwarf.functions.iter().map(|f| (f.addr, &*f.item)).collect();
+
+        // Abstract instances, declarations and discarded functions have no entry point.
+        assert_eq!(functions, [(0x1000, "bar"), (0x1040, "_ZL6helperv")]);
+    }
+
+    #[test]
+    fn inlined_scopes() {
+        let dwarf = parse();
+
+        assert_eq!(
+            scopes(&dwarf, 0x1014),
+            [
+                (
+                    "baz",
+                    0x1014,
+                    0x1018,
+                    true,
+                    Some("/src/foo.rs:42".to_string())
+                ),
+                (
+                    "foo",
+                    0x1010,
+                    0x1020,
+                    true,
+                    Some("/src/bar.rs:7".to_string())
+                ),
+                ("bar", 0x1000, 0x1040, false, None),
+            ]
+        );
+        assert_eq!(scopes(&dwarf, 0x1018).len(), 2);
+        assert_eq!(
+            scopes(&dwarf, 0x1020),
+            [("bar", 0x1000, 0x1040, false, None)]
+        );
+        assert_eq!(
+            scopes(&dwarf, 0x1040),
+            [("_ZL6helperv", 0x1040, 0x1050, false, None)]
+        );
+        assert!(scopes(&dwarf, 0x1050).is_empty());
+
+        // Calls inlined into a discarded function are left relative to address zero.
+        assert!(scopes(&dwarf, 0x8).is_empty());
+    }
+
+    #[test]
+    fn shared_names() {
+        let dwarf = parse();
+        let scopes = dwarf.scopes.containing(0x1014);
+        let bar = dwarf.functions.iter().find(|f| f.addr == 0x1000).unwrap();
+        assert!(Arc::ptr_eq(&scopes[2].name, &bar.item));
+    }
+}
@@ -0,0 +1,839 @@
+//! Recovery of symbols and line tables from the metadata the Go runtime carries.
+//!
+//! The runtime needs function names and line tables to print stack traces, so even a stripped
+//! binary contains them in `runtime.pclntab`. It's layout changed in Go 1.16, 1.18 and 1.20,
+//! `debug/gosym` in the standard library is the reference for every version.
+
+use crate::{AddressMap, Addressed, FileAttr, RawSymbol};
+use object::{Object, ObjectSection, ObjectSymbol, SectionKind};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+pub struct Go<'data> {
+    /// Functions and type descriptors.
+    pub syms: AddressMap<RawSymbol<'data>>,
+    /// Mapping from addresses starting at the header base to source files.
+    pub file_attrs: AddressMap<FileAttr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Version {
+    Go12,
+    Go116,
+    Go117,
+    Go118,
+    Go120,
+}
+
+impl Version {
+    fn from_magic(magic: u32) -> Option<Self> {
+        Some(match magic {
+            0xfffffffb => Self::Go12,
+            0xfffffffa => Self::Go116,
+            0xfffffff0 => Self::Go118,
+            0xfffffff1 => Self::Go120,
+            _ => return None,
+        })
+    }
+}
+
+/// Data sections by virtual address, to follow the pointers in `moduledata`.
+struct Memory<'data> {
+    sections: Vec<(u64, &'data [u8])>,
+}
+
+impl<'data> Memory<'data> {
+    fn new(obj: &object::File<'data>) -> Self {
+        let mut sections = Vec::new();
+        for section in obj.sections() {
+            if section.kind() == SectionKind::Text || section.address() == 0 {
+                continue;
+            }
+
+            if let Ok(data) = section.data() {
+                sections.push((section.address(), data));
+            }
+        }
+
+        Self { sections }
+    }
+
+    fn get(&self, addr: u64) -> Option<&'data [u8]> {
+        self.sections.iter().find_map(|&(start, data)| {
+            let offset = addr.checked_sub(start)? as usize;
+            data.get(offset..)
+        })
+    }
+}
+
+/// Readers for the byte order and pointer size of the binary.
+#[derive(Clone, Copy)]
+struct Reader {
+    little_endian: bool,
+    ptr_size: usize,
+}
+
+impl Reader {
+    fn u32(&self, bytes: &[u8], offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = bytes.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+        Some(match self.little_endian {
+            true => u32::from_le_bytes(bytes),
+            false => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn uintptr(&self, bytes: &[u8], offset: usize) -> Option<u64> {
+        if self.ptr_size == 4 {
+            return self.u32(bytes, offset).map(|value| value as u64);
+        }
+
+        let bytes: [u8; 8] = bytes.get(offset..offset.checked_add(8)?)?.try_into().ok()?;
+        Some(match self.little_endian {
+            true => u64::from_le_bytes(bytes),
+            false => u64::from_be_bytes(bytes),
+        })
+    }
+
+    /// The n'th pointer sized word.
+    fn word(&self, bytes: &[u8], idx: usize) -> Option<u64> {
+        self.uintptr(bytes, idx * self.ptr_size)
+    }
+}
+
+fn cstr(bytes: &[u8], offset: usize) -> Option<&str> {
+    let bytes = bytes.get(offset..)?;
+    let len = bytes.iter().position(|&b| b == 0)?;
+    std::str::from_utf8(&bytes[..len]).ok()
+}
+
+fn uvarint(bytes: &[u8], offset: &mut usize) -> Option<u32> {
+    let mut value = 0u32;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*offset)?;
+        *offset += 1;
+        value |= ((byte & 0x7f) as u32).checked_shl(shift)?;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+}
+
+struct Pclntab<'data> {
+    data: &'data [u8],
+    version: Version,
+    reader: Reader,
+    quantum: u32,
+    text_start: u64,
+    nfunctab: usize,
+    funcnametab: &'data [u8],
+    cutab: &'data [u8],
+    filetab: &'data [u8],
+    pctab: &'data [u8],
+    functab: &'data [u8],
+}
+
+impl<'data> Pclntab<'data> {
+    /// Parse the header, without yet knowing where `.text` starts.
+    fn parse(data: &'data [u8]) -> Option<Self> {
+        let magic = data.get(..4)?;
+        let (version, little_endian) = match (
+            Version::from_magic(u32::from_le_bytes(magic.try_into().ok()?)),
+            Version::from_magic(u32::from_be_bytes(magic.try_into().ok()?)),
+        ) {
+            (Some(version), _) => (version, true),
+            (None, Some(version)) => (version, false),
+            (None, None) => return None,
+        };
+
+        // The magic is followed by two bytes of padding, the instruction size quantum and the
+        // pointer size.
+        let quantum = *data.get(6)? as u32;
+        let ptr_size = *data.get(7)? as usize;
+        if data[4..6] != [0, 0] || ![1, 2, 4].contains(&quantum) || ![4, 8].contains(&ptr_size) {
+            return None;
+        }
+
+        let reader = Reader {
+            little_endian,
+            ptr_size,
+        };
+        let offset = |idx: usize| reader.uintptr(data, 8 + idx * ptr_size).map(|off| off as usize);
+        let table = |idx: usize| data.get(offset(idx)?..);
+
+        let mut this = Self {
+            data,
+            version,
+            reader,
+            quantum,
+            text_start: 0,
+            nfunctab: offset(0)?,
+            funcnametab: &[],
+            cutab: &[],
+            filetab: &[],
+            pctab: data,
+            functab: &[],
+        };
+
+        match version {
+            Version::Go12 => {
+                this.functab = data.get(8 + ptr_size..)?;
+                let size = this.nfunctab.checked_mul(2 * ptr_size)? + ptr_size;
+                let filetab = reader.u32(this.functab, size)? as usize;
+                this.filetab = data.get(filetab..)?;
+                this.funcnametab = data;
+            }
+            Version::Go116 | Version::Go117 => {
+                this.funcnametab = table(2)?;
+                this.cutab = table(3)?;
+                this.filetab = table(4)?;
+                this.pctab = table(5)?;
+                this.functab = table(6)?;
+            }
+            Version::Go118 | Version::Go120 => {
+                this.text_start = offset(2)? as u64;
+                this.funcnametab = table(3)?;
+                this.cutab = table(4)?;
+                this.filetab = table(5)?;
+                this.pctab = table(6)?;
+                this.functab = table(7)?;
+            }
+        }
+
+        // Every function has an entry and an offset, followed by the end of the last function.
+        let size = (this.nfunctab.checked_mul(2)? + 1).checked_mul(this.functab_field_size())?;
+        this.functab = this.functab.get(..size)?;
+        Some(this)
+    }
+
+    /// Starting with Go 1.18 function addresses are 32-bit offsets from the start of `.text`.
+    fn functab_field_size(&self) -> usize {
+        match self.version >= Version::Go118 {
+            true => 4,
+            false => self.reader.ptr_size,
+        }
+    }
+
+    fn functab_field(&self, idx: usize) -> Option<u64> {
+        match self.version >= Version::Go118 {
+            true => self.reader.u32(self.functab, idx * 4).map(|field| field as u64),
+            false => self.reader.uintptr(self.functab, idx * self.reader.ptr_size),
+        }
+    }
+
+    /// Address of the first function, as moduledata's `minpc` has it.
+    fn min_pc(&self) -> Option<u64> {
+        Some(self.functab_field(0)? + self.text_start)
+    }
+
+    /// A `_func` structure, found through it's offset in the function table.
+    fn func(&self, idx: usize) -> Option<Func<'data>> {
+        let entry = self.functab_field(idx * 2)? + self.text_start;
+        let offset = self.functab_field(idx * 2 + 1)? as usize;
+
+        // Before Go 1.16 the offset is relative to the table itself, after it's relative to the
+        // function table.
+        let data = match self.version {
+            Version::Go12 => self.data.get(offset..)?,
+            _ => self.functab_base()?.get(offset..)?,
+        };
+
+        Some(Func { entry, data })
+    }
+
+    /// Whether the first function agrees with the function table on where it starts, to rule out
+    /// data that merely looks like a header.
+    fn is_consistent(&self) -> bool {
+        let func = match self.func(0) {
+            Some(func) => func,
+            None => return false,
+        };
+
+        let entry = match self.version >= Version::Go118 {
+            true => self.reader.u32(func.data, 0).map(|entry| entry as u64),
+            false => self.reader.uintptr(func.data, 0),
+        };
+
+        entry.is_some() && entry == self.functab_field(0)
+    }
+
+    fn functab_base(&self) -> Option<&'data [u8]> {
+        let offset = self.functab.as_ptr() as usize - self.data.as_ptr() as usize;
+        self.data.get(offset..)
+    }
+
+    /// Read the n'th 32-bit field of a `_func`, after the entry.
+    fn field(&self, func: &Func, idx: usize) -> Option<u32> {
+        let entry_size = self.functab_field_size();
+        self.reader.u32(func.data, entry_size + (idx - 1) * 4)
+    }
+
+    /// Decode a pc-value table into the values starting at every pc they change.
+    fn pcvalue(&self, offset: u32, entry: u64) -> Vec<(u64, i32)> {
+        let mut values = Vec::new();
+        if offset == 0 {
+            return values;
+        }
+
+        let table = match self.pctab.get(offset as usize..) {
+            Some(table) => table,
+            None => return values,
+        };
+
+        let mut pos = 0;
+        let mut pc = entry;
+        let mut value = -1i32;
+        while let Some(delta) = uvarint(table, &mut pos) {
+            // A zero value delta ends the table, except for the first entry.
+            if delta == 0 && !values.is_empty() {
+                break;
+            }
+
+            let delta = match delta & 1 != 0 {
+                true => !(delta >> 1) as i32,
+                false => (delta >> 1) as i32,
+            };
+
+            let pc_delta = match uvarint(table, &mut pos) {
+                Some(pc_delta) => pc_delta as u64 * self.quantum as u64,
+                None => break,
+            };
+
+            value = value.wrapping_add(delta);
+            values.push((pc, value));
+            pc += pc_delta;
+        }
+
+        values
+    }
+
+    /// Offset into the file table of the name of a file, by it's number within a function.
+    fn file_offset(&self, func: &Func, file: i32) -> Option<usize> {
+        if file < 0 {
+            return None;
+        }
+
+        let offset = match self.version {
+            Version::Go12 => self.reader.u32(self.filetab, file as usize * 4)?,
+            _ => {
+                // Files are numbered per compilation unit.
+                let cu = self.field(func, 8)? as usize;
+                match self.reader.u32(self.cutab, (cu + file as usize) * 4)? {
+                    u32::MAX => return None,
+                    offset => offset,
+                }
+            }
+        };
+
+        Some(offset as usize)
+    }
+
+    fn file_name(&self, offset: usize) -> Option<&'data str> {
+        match self.version {
+            Version::Go12 => cstr(self.data, offset),
+            _ => cstr(self.filetab, offset),
+        }
+    }
+}
+
+struct Func<'data> {
+    entry: u64,
+    data: &'data [u8],
+}
+
+/// Find the function table through the sections or symbols that name it.
+fn find_pclntab<'data>(obj: &object::File<'data>) -> Option<(u64, &'data [u8])> {
+    for section in obj.sections() {
+        if let Ok(".gopclntab" | "__gopclntab") = section.name() {
+            return Some((section.address(), section.data().ok()?));
+        }
+    }
+
+    let symbol = obj.symbols().find(|sym| sym.name() == Ok("runtime.pclntab"))?;
+    let section = obj.section_by_index(symbol.section_index()?).ok()?;
+    let offset = symbol.address().checked_sub(section.address())? as usize;
+    Some((symbol.address(), section.data().ok()?.get(offset..)?))
+}
+
+/// Without a section or symbol, the function table has to be found by it's header.
+///
+/// This is the case for PE binaries and externally linked ELF binaries, where the table is merged
+/// into some read-only data section.
+fn scan_pclntab<'data>(obj: &object::File<'data>) -> Option<(u64, &'data [u8])> {
+    for section in obj.sections() {
+        if section.kind() == SectionKind::Text || section.address() == 0 {
+            continue;
+        }
+
+        let data = match section.data() {
+            Ok(data) => data,
+            Err(..) => continue,
+        };
+
+        // The table is pointer aligned.
+        for offset in (0..data.len().saturating_sub(8)).step_by(4) {
+            let magic = [0xfb, 0xfa, 0xf0, 0xf1];
+            let candidate = &data[offset..];
+            let is_header = (magic.contains(&candidate[0]) && candidate[1..4] == [0xff; 3])
+                || (candidate[..3] == [0xff; 3] && magic.contains(&candidate[3]));
+
+            if is_header && Pclntab::parse(candidate).is_some_and(|table| table.is_consistent()) {
+                return Some((section.address() + offset as u64, candidate));
+            }
+        }
+    }
+
+    None
+}
+
+/// Release the binary was built with, from the version string the build info points at.
+///
+/// Go 1.17 kept the function table of 1.16, so this is the only way to tell them apart. Before
+/// Go 1.18 the build info holds a pointer to `runtime.buildVersion` after the magic, the pointer
+/// size and flags.
+fn build_version<'data>(memory: &Memory<'data>, reader: Reader) -> Option<&'data str> {
+    const MAGIC: &[u8] = b"\xff Go buildinf:";
+
+    for &(_, data) in memory.sections.iter() {
+        // The build info is 16 byte aligned.
+        for offset in (0..data.len()).step_by(16) {
+            let info = &data[offset..];
+            if !info.starts_with(MAGIC) {
+                continue;
+            }
+
+            let version = memory.get(reader.uintptr(info, 16)?)?;
+            let len = reader.word(version, 1)? as usize;
+            let version = memory.get(reader.word(version, 0)?)?.get(..len)?;
+            return std::str::from_utf8(version).ok();
+        }
+    }
+
+    None
+}
+
+/// Find `runtime.firstmoduledata`, which starts by pointing at the function table.
+///
+/// Before Go 1.16 it starts with `pclntable` followed by the `ftab` slice, later on it starts
+/// with the header followed by slices of each table.
+fn find_moduledata<'data>(
+    memory: &Memory<'data>,
+    table: &Pclntab,
+    pclntab_addr: u64,
+) -> Option<&'data [u8]> {
+    let reader = table.reader;
+    let ptr_size = reader.ptr_size;
+    let functab_offset = table.functab.as_ptr() as u64 - table.data.as_ptr() as u64;
+    let (check_idx, expected) = match table.version {
+        Version::Go12 => (3, pclntab_addr + functab_offset),
+        _ => (16, pclntab_addr + functab_offset),
+    };
+
+    for &(_, data) in memory.sections.iter() {
+        for offset in (0..data.len()).step_by(ptr_size) {
+            let candidate = &data[offset..];
+            if reader.word(candidate, 0) != Some(pclntab_addr) {
+                continue;
+            }
+
+            if reader.word(candidate, check_idx) == Some(expected) {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
+/// Indices of `text`, `types`, `etypes` and the `typelinks` slice in moduledata.
+fn moduledata_layout(version: Version) -> (usize, Option<(usize, usize, usize)>) {
+    match version {
+        // The fields before typelinks vary a lot between releases.
+        Version::Go12 => (12, None),
+        Version::Go116 | Version::Go117 => (22, Some((35, 36, 40))),
+        // Adds `rodata` and `gofunc` after `etypes`.
+        Version::Go118 => (22, Some((35, 36, 42))),
+        // Adds `covctrs` and `ecovctrs` before `end`.
+        Version::Go120 => (22, Some((37, 38, 44))),
+    }
+}
+
+/// Name every type descriptor listed in moduledata's `typelinks`.
+fn parse_typelinks(
+    memory: &Memory,
+    table: &Pclntab,
+    moduledata: &[u8],
+    layout: (usize, usize, usize),
+    syms: &mut AddressMap<RawSymbol>,
+) -> Option<()> {
+    let reader = table.reader;
+    let ptr_size = reader.ptr_size;
+    let (types_idx, etypes_idx, typelinks_idx) = layout;
+    let types = reader.word(moduledata, types_idx)?;
+    let etypes = reader.word(moduledata, etypes_idx)?;
+    let typelinks = reader.word(moduledata, typelinks_idx)?;
+    let count = reader.word(moduledata, typelinks_idx + 1)? as usize;
+
+    if types == 0 || etypes < types {
+        return None;
+    }
+
+    let links = memory.get(typelinks)?;
+    let prefix = match table.version >= Version::Go120 {
+        true => "type:",
+        false => "type.",
+    };
+
+    for idx in 0..count {
+        let offset = reader.u32(links, idx * 4)? as u64;
+        let addr = types + offset;
+        if addr >= etypes {
+            continue;
+        }
+
+        // `_type` starts with size, ptrdata, hash, tflag, align, fieldAlign, kind, equal, gcdata
+        // followed by the offset of it's name.
+        let typ = match memory.get(addr) {
+            Some(typ) => typ,
+            None => continue,
+        };
+        let tflag = *typ.get(2 * ptr_size + 4)?;
+        let name_off = reader.u32(typ, 4 * ptr_size + 8)? as u64;
+        let name = match memory.get(types + name_off) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        // A name is a byte of flags followed by it's length, a varint since Go 1.17.
+        let (len, start) = match table.version {
+            Version::Go116 => match name.get(1..3) {
+                Some(len) => (u16::from_be_bytes([len[0], len[1]]) as usize, 3),
+                None => continue,
+            },
+            _ => {
+                let mut pos = 1;
+                match uvarint(name, &mut pos) {
+                    Some(len) => (len as usize, pos),
+                    None => continue,
+                }
+            }
+        };
+
+        let name = match name.get(start..start + len).map(std::str::from_utf8) {
+            Some(Ok(name)) => name,
+            _ => continue,
+        };
+
+        // With `tflagExtraStar` the name has a star prepended that isn't part of the type.
+        let name = match tflag & 2 != 0 {
+            true => name.strip_prefix('*').unwrap_or(name),
+            false => name,
+        };
+
+        syms.push(Addressed {
+            addr: addr as usize,
+            item: RawSymbol {
+                name: Box::leak(format!("{prefix}{name}").into_boxed_str()),
+                module: None,
+                ordinal: None,
+            },
+        });
+    }
+
+    Some(())
+}
+
+pub fn parse<'data>(obj: &object::File<'data>) -> Option<Go<'data>> {
+    let (pclntab_addr, data) = match find_pclntab(obj) {
+        Some(pclntab) => pclntab,
+        None => scan_pclntab(obj)?,
+    };
+
+    let table = match Pclntab::parse(data) {
+        Some(table) => table,
+        None => {
+            log::complex!(
+                w "[golang::parse] ",
+                y "Found a malformed pclntab at ",
+                g format!("{pclntab_addr:#x}"),
+                y "."
+            );
+            return None;
+        }
+    };
+
+    let text = obj
+        .sections()
+        .find(|section| section.name() == Ok(".text") || section.name() == Ok("__text"))
+        .map(|section| section.address());
+
+    Some(recover(&Memory::new(obj), table, pclntab_addr, text))
+}
+
+/// Recover functions, their line tables and types given the function table at `pclntab_addr`.
+fn recover<'data>(
+    memory: &Memory<'data>,
+    mut table: Pclntab<'data>,
+    pclntab_addr: u64,
+    text: Option<u64>,
+) -> Go<'data> {
+    if table.version == Version::Go116 {
+        let version = build_version(memory, table.reader);
+        if version.is_some_and(|version| version.starts_with("go1.17")) {
+            table.version = Version::Go117;
+        }
+    }
+
+    let moduledata = find_moduledata(memory, &table, pclntab_addr);
+    let (text_idx, typelinks) = moduledata_layout(table.version);
+
+    // The header's text start is zero in position independent binaries until it's relocated,
+    // so prefer moduledata's and otherwise assume the text section starts with the first function.
+    if table.version >= Version::Go118 && table.text_start == 0 {
+        let moduledata_text =
+            moduledata.and_then(|moduledata| table.reader.word(moduledata, text_idx));
+        table.text_start = match moduledata_text {
+            Some(text) if text != 0 => text,
+            _ => text.unwrap_or(0),
+        };
+    }
+
+    let mut syms = AddressMap::default();
+    let mut file_attrs = AddressMap::default();
+    let mut path_cache: HashMap<usize, Arc<Path>> = HashMap::new();
+
+    for idx in 0..table.nfunctab {
+        let func = match table.func(idx) {
+            Some(func) => func,
+            None => continue,
+        };
+
+        let name = match table.field(&func, 1).and_then(|off| cstr(table.funcnametab, off as usize))
+        {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+
+        syms.push(Addressed {
+            addr: func.entry as usize,
+            item: RawSymbol {
+                name,
+                module: None,
+                ordinal: None,
+            },
+        });
+
+        let (pcfile, pcln) = match (table.field(&func, 5), table.field(&func, 6)) {
+            (Some(pcfile), Some(pcln)) => (pcfile, pcln),
+            _ => continue,
+        };
+
+        let files = table.pcvalue(pcfile, func.entry);
+        for (pc, line) in table.pcvalue(pcln, func.entry) {
+            if line < 0 {
+                continue;
+            }
+
+            // The file that covers this pc is the last one that starts before it.
+            let file = match files.partition_point(|&(start, _)| start <= pc) {
+                0 => continue,
+                idx => files[idx - 1].1,
+            };
+
+            let offset = match table.file_offset(&func, file) {
+                Some(offset) => offset,
+                None => continue,
+            };
+
+            let path = match path_cache.get(&offset) {
+                Some(path) => Arc::clone(path),
+                None => match table.file_name(offset) {
+                    Some(name) => {
+                        let path: Arc<Path> = Path::new(name).into();
+                        path_cache.insert(offset, Arc::clone(&path));
+                        path
+                    }
+                    None => continue,
+                },
+            };
+
+            file_attrs.push(Addressed {
+                addr: pc as usize,
+                item: FileAttr {
+                    path,
+                    line: line as usize,
+                    column_start: 0,
+                    column_end: 0,
+                },
+            });
+        }
+    }
+
+    if let (Some(moduledata), Some(layout)) = (moduledata, typelinks) {
+        // Make sure the layout is right before trusting any other field.
+        if table.reader.word(moduledata, text_idx - 2) == table.min_pc() {
+            parse_typelinks(memory, &table, moduledata, layout, &mut syms);
+        }
+    }
+
+    log::complex!(
+        w "[golang::recover] found ",
+        g syms.len().to_string(),
+        w " symbols and ",
+        g path_cache.len().to_string(),
+        w " source files in the pclntab."
+    );
+
+    Go { syms, file_attrs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PCLNTAB: u64 = 0x500000;
+    const MODULEDATA: u64 = 0x600000;
+    const TYPES: u64 = 0x700000;
+    const BUILDINFO: u64 = 0x800000;
+
+    fn words(words: &[u64]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    /// Go 1.16 function table with `main.main` at 0x401000 and `main.add` at 0x401040, both in
+    /// `main.go` on lines 10 and 12.
+    fn pclntab() -> Vec<u8> {
+        let funcnametab = b"\0main.main\0main.add\0".to_vec();
+        let cutab = u32s(&[0]);
+        let filetab = b"main.go\0".to_vec();
+        // The file table at 1 followed by the line table at 4.
+        let pctab = vec![0, 2, 0x20, 0, 22, 0x10, 4, 0x10, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let mut functab = words(&[0x401000, 40, 0x401040, 88, 0x401080]);
+        for (entry, name) in [(0x401000, 1), (0x401040, 11)] {
+            functab.extend(words(&[entry]));
+            functab.extend(u32s(&[name, 0, 0, 0, 1, 4, 0, 0, 0, 0]));
+        }
+
+        let mut offset = 64;
+        let mut offsets = Vec::new();
+        for table in [&funcnametab, &cutab, &filetab, &pctab] {
+            offsets.push(offset);
+            offset += table.len() as u64;
+        }
+
+        let mut data = vec![0xfa, 0xff, 0xff, 0xff, 0, 0, 1, 8];
+        data.extend(words(&[2, 1]));
+        data.extend(words(&offsets));
+        data.extend(words(&[offset]));
+        for table in [funcnametab, cutab, filetab, pctab, functab] {
+            data.extend(table);
+        }
+        data
+    }
+
+    /// `runtime.firstmoduledata` of Go 1.16 and 1.17, listing a single type.
+    fn moduledata(functab: u64) -> Vec<u8> {
+        let mut fields = [0; 45];
+        fields[0] = PCLNTAB;
+        fields[16] = functab;
+        fields[20] = 0x401000;
+        fields[22] = 0x401000;
+        fields[35] = TYPES;
+        fields[36] = TYPES + 0x300;
+        fields[40] = TYPES + 0x200;
+        fields[41] = 1;
+        words(&fields)
+    }
+
+    /// Type descriptor at 0x40 named `*main.T`, with the star being extra.
+    fn types(name: &[u8]) -> Vec<u8> {
+        let mut types = vec![0; 0x300];
+        types[0x40 + 20] = 2;
+        types[0x40 + 40..0x40 + 44].copy_from_slice(&0x100u32.to_le_bytes());
+        types[0x100..0x100 + name.len()].copy_from_slice(name);
+        types[0x200..0x204].copy_from_slice(&0x40u32.to_le_bytes());
+        types
+    }
+
+    fn buildinfo(version: &str) -> Vec<u8> {
+        let mut info = b"\xff Go buildinf:\x08\x00".to_vec();
+        info.extend(words(&[BUILDINFO + 0x20, 0]));
+        info.extend(words(&[BUILDINFO + 0x30, version.len() as u64]));
+        info.extend(version.as_bytes());
+        info
+    }
+
+    fn leak(bytes: Vec<u8>) -> &'static [u8] {
+        Box::leak(bytes.into_boxed_slice())
+    }
+
+    fn recover_module(name: &[u8], version: &str) -> Go<'static> {
+        let pclntab = leak(pclntab());
+        let table = Pclntab::parse(pclntab).unwrap();
+        assert!(table.is_consistent());
+
+        let functab = PCLNTAB + 64 + 20 + 4 + 8 + 16;
+        let memory = Memory {
+            sections: vec![
+                (PCLNTAB, pclntab),
+                (MODULEDATA, leak(moduledata(functab))),
+                (TYPES, leak(types(name))),
+                (BUILDINFO, leak(buildinfo(version))),
+            ],
+        };
+
+        recover(&memory, table, PCLNTAB, None)
+    }
+
+    fn names(go: &Go<'static>) -> Vec<(usize, &'static str)> {
+        let mut names: Vec<_> = go.syms.iter().map(|sym| (sym.addr, sym.item.name)).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn go116() {
+        let go = recover_module(b"\x00\x00\x07*main.T", "go1.16.15");
+        assert_eq!(
+            names(&go),
+            [
+                (0x401000, "main.main"),
+                (0x401040, "main.add"),
+                (0x700040, "type.main.T")
+            ]
+        );
+
+        let lines: Vec<_> = go
+            .file_attrs
+            .iter()
+            .map(|attr| (attr.addr, attr.item.path.to_str().unwrap(), attr.item.line))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (0x401000, "main.go", 10),
+                (0x401010, "main.go", 12),
+                (0x401040, "main.go", 10),
+                (0x401050, "main.go", 12)
+            ]
+        );
+    }
+
+    #[test]
+    fn go117() {
+        // Same function table, but type names have a varint length.
+        let go = recover_module(b"\x00\x07*main.T", "go1.17.3");
+        assert_eq!(names(&go)[2], (0x700040, "type.main.T"));
+
+        // Read as a Go 1.16 name, the length would run far past the name.
+        let go = recover_module(b"\x00\x07*main.T", "go1.16.15");
+        assert_eq!(names(&go).len(), 2);
+    }
+}
@@ -0,0 +1,381 @@
+//! Symbols from outside of the binary, such as linker map files, `nm` output or name lists
+//! exported by other disassemblers as CSV or JSON.
+
+use binformat::RawSymbol;
+use processor_shared::{AddressMap, Addressed};
+
+/// Column names an exported address could be under.
+const ADDRESS_COLUMNS: &[&str] = &["address", "addr", "start", "ea", "va", "location", "offset"];
+
+/// Column names an exported name could be under.
+const NAME_COLUMNS: &[&str] = &["name", "symbol", "function", "label"];
+
+/// Parses an address, which is hexadecimal unless stated otherwise as that's what tools print.
+fn parse_addr(s: &str) -> Option<usize> {
+    let s = s.trim().trim_matches('"');
+    let hex = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => hex,
+        None => s.strip_suffix('h').unwrap_or(s),
+    };
+
+    if hex.is_empty() {
+        return None;
+    }
+
+    usize::from_str_radix(hex, 16).ok()
+}
+
+/// Whether `name` looks like a symbol rather than a path, section or expression.
+fn is_symbol(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.starts_with('*')
+        && !name.starts_with("0x")
+        && !name.contains(['/', '\\', '=', '(', ')', ':'])
+}
+
+fn push(syms: &mut AddressMap<RawSymbol<'static>>, addr: usize, name: &str) {
+    if addr == 0 || name.is_empty() {
+        return;
+    }
+
+    syms.push(Addressed {
+        addr,
+        item: RawSymbol {
+            name: Box::leak(name.to_string().into_boxed_str()),
+            module: None,
+            ordinal: None,
+        },
+    });
+}
+
+/// MSVC `link /MAP`, which lists symbols as `0001:00000010  name  0000000140001010 f  a.obj`.
+fn parse_msvc_map(text: &str, syms: &mut AddressMap<RawSymbol<'static>>) {
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 {
+            continue;
+        }
+
+        let is_section_offset = match fields[0].split_once(':') {
+            Some((section, offset)) => parse_addr(section).is_some() && parse_addr(offset).is_some(),
+            None => false,
+        };
+
+        if !is_section_offset {
+            continue;
+        }
+
+        if let Some(addr) = parse_addr(fields[2]) {
+            push(syms, addr, fields[1]);
+        }
+    }
+}
+
+/// GNU ld `-Map`, which lists symbols as `0x0000000000401126  name` below the sections.
+fn parse_gnu_map(text: &str, syms: &mut AddressMap<RawSymbol<'static>>) {
+    let memory_map = match text.find("Linker script and memory map") {
+        Some(idx) => &text[idx..],
+        None => text,
+    };
+
+    for line in memory_map.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 2 || !fields[0].starts_with("0x") || !is_symbol(fields[1]) {
+            continue;
+        }
+
+        if let Some(addr) = parse_addr(fields[0]) {
+            push(syms, addr, fields[1]);
+        }
+    }
+}
+
+/// lld's `-Map`, which lists symbols as `401126  401126  b  1  name` below their input section.
+fn parse_lld_map(text: &str, syms: &mut AddressMap<RawSymbol<'static>>) {
+    for line in text.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 5 || !is_symbol(fields[4]) {
+            continue;
+        }
+
+        if fields[..4].iter().all(|field| parse_addr(field).is_some()) {
+            if let Some(addr) = parse_addr(fields[0]) {
+                push(syms, addr, fields[4]);
+            }
+        }
+    }
+}
+
+/// `nm` output, which lists symbols as `0000000000401126 T name`.
+fn parse_nm(text: &str, syms: &mut AddressMap<RawSymbol<'static>>) {
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields[1].len() != 1 {
+            continue;
+        }
+
+        // Absolute and debugging symbols aren't at an address in the binary.
+        if matches!(fields[1], "a" | "A" | "N" | "n" | "U") {
+            continue;
+        }
+
+        if let Some(addr) = parse_addr(fields[0]) {
+            // Demangled names contain spaces.
+            push(syms, addr, &fields[2..].join(" "));
+        }
+    }
+}
+
+/// Whether `line` is shaped like `nm` output, `<address> <type> <name>` or `<type> <name>` for
+/// symbols without an address, as demangled names can otherwise pass for CSV.
+fn is_nm_line(line: &str) -> bool {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let is_type = |field: &str| field.len() == 1 && field.chars().all(|c| c.is_ascii_alphabetic());
+
+    match fields[..] {
+        [addr, tipe, _, ..] if addr.chars().all(|c| c.is_ascii_hexdigit()) => is_type(tipe),
+        [tipe, _, ..] => matches!(tipe, "U" | "w" | "v"),
+        _ => false,
+    }
+}
+
+/// Comma or tab separated columns, with an optional header naming the columns.
+fn parse_csv(text: &str, syms: &mut AddressMap<RawSymbol<'static>>) {
+    let delimiter = match text.lines().next() {
+        Some(line) if line.contains('\t') => '\t',
+        Some(line) if line.contains(';') && !line.contains(',') => ';',
+        _ => ',',
+    };
+
+    // Fields are quoted when they contain the delimiter, with quotes inside of them doubled.
+    let split = |line: &str| -> Vec<String> {
+        let mut fields = vec![String::new()];
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+
+        while let Some(chr) = chars.next() {
+            match chr {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    fields.last_mut().unwrap().push('"');
+                }
+                '"' => quoted = !quoted,
+                chr if chr == delimiter && !quoted => fields.push(String::new()),
+                chr => fields.last_mut().unwrap().push(chr),
+            }
+        }
+
+        fields.iter().map(|field| field.trim().to_string()).collect()
+    };
+
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty()).peekable();
+    let header = match lines.peek() {
+        Some(line) => split(line),
+        None => return,
+    };
+
+    let column = |names: &[&str]| {
+        header.iter().position(|field| names.contains(&&*field.to_ascii_lowercase()))
+    };
+
+    let (addr_column, name_column) = match (column(ADDRESS_COLUMNS), column(NAME_COLUMNS)) {
+        (Some(addr_column), Some(name_column)) => {
+            lines.next();
+            (addr_column, name_column)
+        }
+        // Without a header the address is assumed to come first, unless it doesn't parse.
+        _ if header.first().and_then(|field| parse_addr(field)).is_some() => (0, 1),
+        _ => (1, 0),
+    };
+
+    for line in lines {
+        let fields = split(line);
+        let (addr, name) = match (fields.get(addr_column), fields.get(name_column)) {
+            (Some(addr), Some(name)) => (addr, name),
+            _ => continue,
+        };
+
+        if let Some(addr) = parse_addr(addr) {
+            push(syms, addr, name);
+        }
+    }
+}
+
+/// Any list or map of objects that have both a name and an address, however deeply nested.
+fn parse_json(text: &str, syms: &mut AddressMap<RawSymbol<'static>>) {
+    fn walk(value: &serde_json::Value, syms: &mut AddressMap<RawSymbol<'static>>) {
+        match value {
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    walk(value, syms);
+                }
+            }
+            serde_json::Value::Object(mapping) => {
+                let field = |names: &[&str]| {
+                    mapping
+                        .iter()
+                        .find(|(key, _)| names.contains(&&*key.to_ascii_lowercase()))
+                        .map(|(_, value)| value)
+                };
+
+                let addr = match field(ADDRESS_COLUMNS) {
+                    Some(serde_json::Value::Number(addr)) => addr.as_u64().map(|a| a as usize),
+                    Some(serde_json::Value::String(addr)) => parse_addr(addr),
+                    _ => None,
+                };
+
+                match (addr, field(NAME_COLUMNS).and_then(|name| name.as_str())) {
+                    (Some(addr), Some(name)) => push(syms, addr, name),
+                    _ => {
+                        for value in mapping.values() {
+                            walk(value, syms);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match serde_json::from_str(text) {
+        Ok(value) => walk(&value, syms),
+        Err(err) => log::complex!(
+            w "[index::import] ",
+            y format!("failed to parse json: {err}."),
+        ),
+    }
+}
+
+/// Parses symbols from any of the supported formats, guessing which one it's in.
+pub fn parse(text: &str) -> AddressMap<RawSymbol<'static>> {
+    let mut syms = AddressMap::default();
+    let trimmed = text.trim_start();
+    let first_line = trimmed.lines().next().unwrap_or_default();
+
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        parse_json(text, &mut syms);
+    } else if text.contains("Publics by Value") {
+        parse_msvc_map(text, &mut syms);
+    } else if first_line.split_whitespace().take(2).eq(["VMA", "LMA"]) {
+        parse_lld_map(trimmed, &mut syms);
+    } else if text.contains("Linker script and memory map") || text.contains("Memory Configuration")
+    {
+        parse_gnu_map(text, &mut syms);
+    } else if is_nm_line(first_line) {
+        parse_nm(text, &mut syms);
+    } else if first_line.contains([',', '\t', ';']) {
+        parse_csv(text, &mut syms);
+    } else {
+        parse_nm(text, &mut syms);
+    }
+
+    syms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(text: &str) -> Vec<(usize, &'static str)> {
+        parse(text).iter().map(|sym| (sym.addr, sym.item.name)).collect()
+    }
+
+    #[test]
+    fn msvc_map() {
+        let text =
+            " Address         Publics by Value              Rva+Base               Lib:Object
+
+ 0000:00000000       __guard_flags              0000000000000000     <absolute>
+ 0001:00000010       ?add@@YAHHH@Z              0000000140001010 f   a.obj
+ 0001:00000030       main                       0000000140001030 f   a.obj
+";
+        assert_eq!(
+            names(text),
+            [(0x140001010, "?add@@YAHHH@Z"), (0x140001030, "main")]
+        );
+    }
+
+    #[test]
+    fn gnu_map() {
+        let text = "Memory Configuration
+
+Linker script and memory map
+
+ .text          0x0000000000401020       0x26 /tmp/a.o
+                0x0000000000401020                main
+                0x0000000000401040                _Z3addii
+                [!provide]                        PROVIDE (etext = .)
+";
+        assert_eq!(names(text), [(0x401020, "main"), (0x401040, "_Z3addii")]);
+    }
+
+    #[test]
+    fn lld_map() {
+        let text = "             VMA              LMA     Size Align Out     In      Symbol
+          401120           401120       26    16 .text
+          401120           401120       26    16         /tmp/a.o:(.text)
+          401120           401120        0     1                 main
+";
+        assert_eq!(names(text), [(0x401120, "main")]);
+    }
+
+    #[test]
+    fn nm() {
+        let text = "                 U puts
+0000000000401126 T main
+0000000000404028 b completed.0
+0000000000000000 a a.c
+";
+        assert_eq!(names(text), [(0x401126, "main"), (0x404028, "completed.0")]);
+    }
+
+    #[test]
+    fn nm_demangled() {
+        let text =
+            "0000000000001139 T std::vector<int, std::allocator<int> >::push_back(int const&)
+                 U operator delete(void*, unsigned long)
+0000000000001180 W std::vector<int, std::allocator<int> >::~vector()
+";
+        assert_eq!(
+            names(text),
+            [
+                (
+                    0x1139,
+                    "std::vector<int, std::allocator<int> >::push_back(int const&)"
+                ),
+                (0x1180, "std::vector<int, std::allocator<int> >::~vector()")
+            ]
+        );
+
+        let text = "                 U operator delete(void*, unsigned long)
+0000000000001139 T foo(int, int)
+";
+        assert_eq!(names(text), [(0x1139, "foo(int, int)")]);
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            names("Name,Address\nmain,0x401126\n\"add\",00401140\n"),
+            [(0x401126, "main"), (0x401140, "add")]
+        );
+        assert_eq!(
+            names("401126\tmain\n401140\tadd\n"),
+            [(0x401126, "main"), (0x401140, "add")]
+        );
+        assert_eq!(
+            names("address,size,name,module\n0x401126,16,\"add(int, int)\",\n"),
+            [(0x401126, "add(int, int)")]
+        );
+    }
+
+    #[test]
+    fn json() {
+        let text = r#"{"functions": [
+            {"name": "main", "address": 4198694},
+            {"name": "add", "addr": "0x401140", "size": 16}
+        ]}"#;
+        assert_eq!(names(text), [(0x401126, "main"), (0x401140, "add")]);
+    }
+}
@@ -1,24 +1,35 @@
 use binformat::RawSymbol;
 use common::*;
-use demangler::TokenStream;
 use dwarf::Dwarf;
+use object::Object;
 use processor_shared::{AddressMap, Addressed};
 use radix_trie::{Trie, TrieCommon};
+use std::collections::HashSet;
 use std::path::Path;
 use std::sync::Arc;
 use std::{fmt, process::Command};
 use tokenizing::Token;
 
 mod common;
-mod demangler;
+mod debuglink;
+pub mod demangler;
 mod dwarf;
 mod error;
+mod golang;
+mod import;
 mod intern;
 mod itanium;
 mod msvc;
 mod pdb;
 mod rust;
 mod rust_legacy;
+mod simplify;
+mod swift;
+mod testing;
+mod types;
+
+pub use demangler::{set_simplify, TokenStream};
+pub use types::{Field, Layout, TypeDb, Variable};
 
 pub enum Error {
     Object(object::Error),
@@ -35,10 +46,58 @@ pub struct FileAttr {
     pub column_end: usize,
 }
 
+/// A call that got inlined, as seen from an address inside of it.
+#[derive(Debug, Clone)]
+pub struct InlineFrame {
+    /// Demangled name of the inlined function.
+    pub function: String,
+    /// Demangled name of the function it got inlined into.
+    pub caller: String,
+    /// Range of the inlined code.
+    pub start: usize,
+    pub end: usize,
+    /// Where in the inlined function the address is.
+    pub location: Option<(Arc<Path>, usize)>,
+    /// Where in the caller the function got called.
+    pub call_site: Option<(Arc<Path>, usize)>,
+}
+
+impl fmt::Display for InlineFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some((path, line)) => {
+                let file = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+                write!(f, "inlined {} from {file}:{line} into {}", self.function, self.caller)
+            }
+            None => write!(f, "inlined {} into {}", self.function, self.caller),
+        }
+    }
+}
+
+/// Where a local variable or parameter is stored, using DWARF register numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    /// Held in a register.
+    Register(u16),
+    /// Stored at an offset from the address in a register.
+    Memory { register: u16, offset: i64 },
+}
+
+/// A local variable or parameter, as seen from an address where it's live.
+#[derive(Debug, Clone)]
+pub struct Local {
+    pub name: Arc<str>,
+    pub location: Location,
+}
+
 pub struct Symbol {
     name: TokenStream,
     name_as_str: ArcStr,
+    /// Name as it's stored in the binary, if demangling changed it.
+    mangled: Option<ArcStr>,
     module: Option<String>,
+    ordinal: Option<u32>,
+    prototype: Option<String>,
     is_intrinsics: bool,
 }
 
@@ -67,13 +126,48 @@ impl Default for Symbol {
         Self {
             name: TokenStream::new(""),
             name_as_str: ArcStr::new(""),
+            mangled: None,
             module: None,
+            ordinal: None,
+            prototype: None,
             is_intrinsics: false,
         }
     }
 }
 
 impl Symbol {
+    fn parse(addr: usize, item: &RawSymbol, types: &TypeDb) -> Self {
+        let demangled = demangler::parse(item.name);
+        let is_intrinsics = is_name_an_intrinsic(item.name);
+        let name_as_str = String::from_iter(demangled.tokens().iter().map(|t| &t.text[..]));
+        let name_as_str = ArcStr::new(&name_as_str);
+        let mangled = (item.name != &**name_as_str).then(|| ArcStr::new(item.name));
+
+        // MSVC mangled names already encode the whole prototype.
+        let prototype = match types.functions.search(addr) {
+            Ok(idx) if !item.name.starts_with('?') => {
+                types.prototype(types.functions[idx].item, &name_as_str)
+            }
+            _ => None,
+        };
+
+        // The name is already simplified, the types debug info describes aren't.
+        let prototype = match prototype {
+            Some(prototype) if demangler::simplifying() => Some(simplify::text(&prototype)),
+            prototype => prototype,
+        };
+
+        Symbol {
+            name_as_str,
+            mangled,
+            name: demangled,
+            module: item.module.map(|x| x.to_string()),
+            ordinal: item.ordinal,
+            prototype,
+            is_intrinsics,
+        }
+    }
+
     #[inline]
     pub fn name(&self) -> &[Token] {
         self.name.tokens()
@@ -84,10 +178,30 @@ impl Symbol {
         self.module.as_deref()
     }
 
+    /// Ordinal the symbol is exported under (PE only).
+    #[inline]
+    pub fn ordinal(&self) -> Option<u32> {
+        self.ordinal
+    }
+
     pub fn as_str(&self) -> &str {
         &self.name_as_str
     }
 
+    /// Name as it's stored in the binary, before it got demangled.
+    pub fn mangled(&self) -> &str {
+        match &self.mangled {
+            Some(mangled) => mangled,
+            None => self.as_str(),
+        }
+    }
+
+    /// Return and parameter types of the function, if described by debug info.
+    #[inline]
+    pub fn prototype(&self) -> Option<&str> {
+        self.prototype.as_deref()
+    }
+
     /// Is the function a unnamed compiler generated artifact.
     pub fn intrinsic(&self) -> bool {
         self.is_intrinsics
@@ -121,6 +235,18 @@ pub struct Index {
     /// The addresses are sorted.
     pub file_attrs: AddressMap<FileAttr>,
 
+    /// Address ranges of functions and the calls inlined into them.
+    scopes: dwarf::Scopes,
+
+    /// Address ranges where local variables and parameters are live.
+    locals: dwarf::Locals,
+
+    /// Canonical frame address of every address described by call frame information.
+    frames: dwarf::CallFrames,
+
+    /// Types and global variables described by debug info.
+    pub types: TypeDb,
+
     /// Prefix tree for finding symbols.
     trie: Trie<ArcStr, Arc<Symbol>>,
 
@@ -139,13 +265,33 @@ impl Index {
         let dwarf = match obj {
             object::File::MachO32(_) => macho_dwarf(obj, path)?,
             object::File::MachO64(_) => macho_dwarf(obj, path)?,
-            _ => dwarf::Dwarf::parse(obj)?,
+            _ => separate_dwarf(obj, path)?,
         };
 
         this.file_attrs.extend(dwarf.file_attrs);
+        this.scopes = dwarf.scopes;
+        this.locals = dwarf.locals;
+
+        // Locals relative to the canonical frame address can't be resolved without it.
+        if this.locals.uses_cfa() {
+            this.frames = dwarf::CallFrames::parse(obj);
+        }
+        this.types = dwarf.types;
+
+        if let Some(mut go) = golang::parse(obj) {
+            // Go's line table is only needed when there's no DWARF.
+            if this.file_attrs.is_empty() {
+                this.file_attrs.extend(go.file_attrs);
+            }
+
+            // Unstripped binaries already name the same functions in their symbol table.
+            let named: HashSet<usize> = syms.iter().map(|sym| sym.addr).collect();
+            go.syms.retain(|sym| !named.contains(&sym.addr));
+            syms.extend(go.syms);
+        }
 
         let mut pdb = None;
-        if let Some(parsed_pdb) = pdb::PDB::parse(obj) {
+        if let Some(parsed_pdb) = pdb::PDB::parse(obj, path) {
             pdb = Some(parsed_pdb?);
         }
 
@@ -154,20 +300,31 @@ impl Index {
         if let Some(ref mut pdb) = pdb {
             this.file_attrs.extend(std::mem::take(&mut pdb.file_attrs));
             syms.extend(std::mem::take(&mut pdb.syms));
+            this.types.extend(std::mem::take(&mut pdb.types));
         }
 
+        // Static functions are often missing from the symbol table, but not from DWARF.
+        let named: HashSet<usize> = syms.iter().map(|sym| sym.addr).collect();
+        for Addressed { addr, item: name } in dwarf.functions.iter() {
+            if !named.contains(addr) {
+                syms.push(Addressed {
+                    addr: *addr,
+                    item: RawSymbol {
+                        name: Box::leak(name.to_string().into_boxed_str()),
+                        module: None,
+                        ordinal: None,
+                    },
+                });
+            }
+        }
+
+        // Function types are looked up by address while parsing the symbols.
+        this.types.functions.sort_unstable();
+        let types = &this.types;
+
         log::PROGRESS.set("Parsing symbols.", syms.len());
         parallel_compute(syms.mapping, &mut this.syms, |Addressed { addr, item }| {
-            let demangled = demangler::parse(item.name);
-            let is_intrinsics = is_name_an_intrinsic(item.name);
-            let name_as_str = String::from_iter(demangled.tokens().iter().map(|t| &t.text[..]));
-            let name_as_str = ArcStr::new(&name_as_str);
-            let symbol = Symbol {
-                name_as_str,
-                name: demangled,
-                module: item.module.map(|x| x.to_string()),
-                is_intrinsics,
-            };
+            let symbol = Symbol::parse(*addr, item, types);
 
             log::PROGRESS.step();
             Addressed {
@@ -213,6 +370,53 @@ impl Index {
 
         // Keep file attrs sorted so it can be binary searched.
         self.file_attrs.sort_unstable();
+
+        // Keep scopes sorted so they can be binary searched.
+        self.scopes.sort();
+        self.locals.sort();
+        self.frames.sort();
+
+        // Keep variables sorted so they can be binary searched, the same variable is often
+        // described by more than one unit.
+        self.types.variables.sort_unstable();
+        self.types.variables.dedup_by_key(|var| var.addr);
+    }
+
+    /// Merges symbols from a linker map, `nm` output or an exported name list, replacing the ones
+    /// at the same addresses. Returns the number of symbols imported.
+    pub fn import(&mut self, path: &Path) -> std::io::Result<usize> {
+        let text = std::fs::read_to_string(path)?;
+        let mut imported = import::parse(&text);
+
+        // Aliases are listed at the same address, only the first one is kept.
+        imported.sort();
+        imported.dedup_by_key(|sym| sym.addr);
+
+        let addrs: HashSet<usize> = imported.iter().map(|sym| sym.addr).collect();
+
+        self.syms.retain(|sym| !addrs.contains(&sym.addr));
+        for Addressed { addr, item } in imported.iter() {
+            self.syms.push(Addressed {
+                addr: *addr,
+                item: Arc::new(Symbol::parse(*addr, item, &self.types)),
+            });
+        }
+
+        self.sort_and_validate();
+
+        // Replaced symbols would otherwise still be found by their old names.
+        self.trie = Trie::new();
+        self.build_prefix_tree();
+
+        log::complex!(
+            w "[index::import] imported ",
+            g addrs.len().to_string(),
+            w " symbols from ",
+            g path.display().to_string(),
+            w "."
+        );
+
+        Ok(addrs.len())
     }
 
     fn build_prefix_tree(&mut self) {
@@ -240,6 +444,80 @@ impl Index {
         }
     }
 
+    /// Calls inlined at `addr`, innermost first.
+    pub fn inline_frames(&self, addr: usize) -> Vec<InlineFrame> {
+        self.inline_frames_where(addr, |_| true)
+    }
+
+    /// Calls inlined at `addr` whose inlined code starts right there, innermost first.
+    pub fn inline_frames_starting(&self, addr: usize) -> Vec<InlineFrame> {
+        self.inline_frames_where(addr, |scope| scope.start == addr)
+    }
+
+    /// Only scopes that are kept get demangled, as that's where the time goes.
+    fn inline_frames_where(
+        &self,
+        addr: usize,
+        keep: impl Fn(&dwarf::Scope) -> bool,
+    ) -> Vec<InlineFrame> {
+        let scopes = self.scopes.containing(addr);
+        let mut frames = Vec::new();
+        let mut location = self.get_file_by_addr(addr).map(|attr| (attr.path.clone(), attr.line));
+
+        for (idx, scope) in scopes.iter().enumerate() {
+            if !scope.inlined {
+                break;
+            }
+
+            if keep(scope) {
+                let caller = match scopes.get(idx + 1) {
+                    Some(caller) => demangled_str(&caller.name),
+                    None => String::new(),
+                };
+
+                frames.push(InlineFrame {
+                    function: demangled_str(&scope.name),
+                    caller,
+                    start: scope.start,
+                    end: scope.end,
+                    location,
+                    call_site: scope.call_site.clone(),
+                });
+            }
+
+            // The outer call continues where this one got called.
+            location = scope.call_site.clone();
+        }
+
+        frames
+    }
+
+    /// Local variables and parameters live at `addr`.
+    pub fn locals(&self, addr: usize) -> Vec<Local> {
+        let mut locals = Vec::new();
+
+        for local in self.locals.containing(addr) {
+            let location = match local.place {
+                dwarf::Place::Location(location) => location,
+                // Offsets from the canonical frame address depend on how far along the function is.
+                dwarf::Place::Cfa(offset) => match self.frames.cfa(addr) {
+                    Some((register, cfa_offset)) => Location::Memory {
+                        register,
+                        offset: cfa_offset + offset,
+                    },
+                    None => continue,
+                },
+            };
+
+            locals.push(Local {
+                name: Arc::clone(&local.name),
+                location,
+            });
+        }
+
+        locals
+    }
+
     pub fn get_sym_by_addr(&self, addr: usize) -> Option<Arc<Symbol>> {
         match self.syms.search(addr) {
             Ok(idx) => Some(self.syms[idx].item.clone()),
@@ -254,14 +532,15 @@ impl Index {
     /// Only used for tests.
     #[doc(hidden)]
     pub fn insert_func(&mut self, addr: usize, name: &str) {
+        let sym = RawSymbol {
+            name,
+            module: None,
+            ordinal: None,
+        };
+
         self.syms.push(Addressed {
             addr,
-            item: Arc::new(Symbol {
-                name: TokenStream::simple(name),
-                name_as_str: ArcStr::new(name),
-                module: None,
-                is_intrinsics: false,
-            }),
+            item: Arc::new(Symbol::parse(addr, &sym, &self.types)),
         })
     }
 
@@ -276,6 +555,10 @@ impl Index {
     }
 }
 
+fn demangled_str(name: &str) -> String {
+    String::from_iter(demangler::parse(name).tokens().iter().map(|t| &t.text[..]))
+}
+
 /// Sort the first 100 strings by length if they have a matching prefix.
 fn sort_by_shortest_match(input: &[&ArcStr], prefix: &str) -> Vec<String> {
     let mut matches: Vec<String> = Vec::new();
@@ -295,8 +578,42 @@ fn sort_by_shortest_match(input: &[&ArcStr], prefix: &str) -> Vec<String> {
     matches
 }
 
+/// Demangle a symbol of any of the supported mangling schemes, or keep it as is.
+pub fn demangle(s: &str) -> TokenStream {
+    demangler::parse(s)
+}
+
+/// DWARF of a binary, merged with the separate debug file it's been stripped into.
+pub fn separate_dwarf(obj: &object::File, path: &Path) -> Result<Dwarf, dwarf::Error> {
+    let mut dwarf = Dwarf::parse(obj, path)?;
+
+    // Binaries with split DWARF still have a `.debug_info` with skeleton units.
+    if obj.section_by_name(".debug_info").is_some() {
+        return Ok(dwarf);
+    }
+
+    if let Some(debug_path) = debuglink::locate(obj, path) {
+        log::complex!(
+            w "[index::separate_dwarf] found debug info in ",
+            b debug_path.display().to_string(),
+            w "."
+        );
+
+        match Dwarf::load(&debug_path) {
+            Ok(separate) => dwarf.merge(separate),
+            Err(err) => log::complex!(
+                w "[index::separate_dwarf] ",
+                y "Failed to load separate debug info: ",
+                y format!("{err:?}."),
+            ),
+        }
+    }
+
+    Ok(dwarf)
+}
+
 pub fn macho_dwarf(obj: &object::File, path: &Path) -> Result<Dwarf, dwarf::Error> {
-    let mut dwarf = Dwarf::parse(obj)?;
+    let mut dwarf = Dwarf::parse(obj, path)?;
 
     let ext = if let Some(exist_ext) = path.extension().and_then(|ext| ext.to_str()) {
         exist_ext.to_string() + ".dSYM"
@@ -333,3 +650,88 @@ pub fn macho_dwarf(obj: &object::File, path: &Path) -> Result<Dwarf, dwarf::Erro
 
     Ok(dwarf)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse() -> Index {
+        let bytes = testing::inlined_calls();
+        let obj = object::File::parse(&*bytes).unwrap();
+
+        // `bar` is in the symbol table, the static `helper` isn't.
+        let mut syms = AddressMap::default();
+        syms.push(Addressed {
+            addr: 0x1000,
+            item: RawSymbol {
+                name: "bar",
+                module: None,
+                ordinal: None,
+            },
+        });
+
+        match Index::parse(&obj, Path::new("/nonexistent/bar"), syms) {
+            Ok(index) => index,
+            Err(_) => panic!("failed to parse the index"),
+        }
+    }
+
+    fn frame(frame: &InlineFrame) -> (String, usize, usize, Option<String>) {
+        let call_site = frame.call_site.as_ref();
+        let call_site = call_site.map(|(path, line)| format!("{}:{line}", path.display()));
+        (frame.to_string(), frame.start, frame.end, call_site)
+    }
+
+    #[test]
+    fn static_functions() {
+        let index = parse();
+        let names: Vec<_> = index.functions().map(|f| (f.addr, f.item.as_str())).collect();
+        assert_eq!(names, [(0x1000, "bar"), (0x1040, "helper")]);
+        assert_eq!(index.get_func_by_name("helper"), Some(0x1040));
+    }
+
+    #[test]
+    fn inline_frames() {
+        let index = parse();
+
+        let frames: Vec<_> = index.inline_frames(0x1014).iter().map(frame).collect();
+        assert_eq!(
+            frames,
+            [
+                (
+                    "inlined baz from foo.rs:60 into foo".to_string(),
+                    0x1014,
+                    0x1018,
+                    Some("/src/foo.rs:42".to_string())
+                ),
+                (
+                    "inlined foo from foo.rs:42 into bar".to_string(),
+                    0x1010,
+                    0x1020,
+                    Some("/src/bar.rs:7".to_string())
+                ),
+            ]
+        );
+
+        // Without a line table entry at the address there's nowhere to point at.
+        let frames: Vec<_> = index.inline_frames(0x101c).iter().map(|f| f.to_string()).collect();
+        assert_eq!(frames, ["inlined foo into bar"]);
+
+        assert!(index.inline_frames(0x1020).is_empty());
+        assert!(index.inline_frames(0x1044).is_empty());
+    }
+
+    #[test]
+    fn inline_frames_starting() {
+        let index = parse();
+        let starting = |addr| -> Vec<_> {
+            let frames = index.inline_frames_starting(addr);
+            frames.iter().map(|frame| frame.function.clone()).collect()
+        };
+
+        assert_eq!(starting(0x1010), ["foo"]);
+        assert_eq!(starting(0x1014), ["baz"]);
+        assert!(starting(0x1012).is_empty());
+        assert!(starting(0x1000).is_empty());
+    }
+}
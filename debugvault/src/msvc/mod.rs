@@ -1246,6 +1246,12 @@ impl Parse for Intrinsics {
                 b'O' => Intrinsics::CopyCtorClosure,
                 b'R' => match ctx.take()? {
                     b'0' => {
+                        // Class types are prefixed by the qualifiers of the described object.
+                        if ctx.eat(b'?') {
+                            let quali = Qualifiers::parse(ctx, backrefs)?;
+                            ctx.push_modifiers(quali.0);
+                        }
+
                         let tipe = Type::parse(ctx, backrefs)?;
                         let modi = Modifiers::parse(ctx, backrefs)?;
                         Intrinsics::RTTITypeDescriptor(modi, Box::new(tipe))
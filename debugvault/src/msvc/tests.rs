@@ -386,6 +386,13 @@ fn function_pointer_rtti_r0() {
     eq!("??_R0P6AXPEAUname0@@@Z@8" => "void (__cdecl *)(struct name0 *) `RTTI Type Descriptor'");
 }
 
+#[test]
+fn class_rtti_r0() {
+    eq!("??_R0?AUname0@@@8" => "struct name0 `RTTI Type Descriptor'");
+    eq!("??_R0?BVname0@@@8" => "class name0 const `RTTI Type Descriptor'");
+    eq!("??_R0?AV?$name0@H@name1@@@8" => "class name1::name0<int> `RTTI Type Descriptor'");
+}
+
 // #[test]
 // fn stack_overflow() {
 //     eq!("?Create@CDialog@@$$FUAEHIPAVCWnd@@@Z" => "virtual int __thiscall CDialog::Create(unsigned int,class CWnd *)");
@@ -3141,10 +3148,10 @@ fn special_template_parameters_questionnumber() {
 //     eq!("??_R4testAccessLevel@@6B@" => "const testAccessLevel::`RTTI Complete Object Locator'");
 // }
 //
-// #[test]
-// fn source8_cv() {
-//     eq!("??_R0?AVtestAccessLevel@@@8" => "class testAccessLevel `RTTI Type Descriptor'");
-// }
+#[test]
+fn source8_cv() {
+    eq!("??_R0?AVtestAccessLevel@@@8" => "class testAccessLevel `RTTI Type Descriptor'");
+}
 //
 // #[test]
 // fn source8_cw() {
@@ -1,10 +1,14 @@
+use crate::debuglink::expand_home;
 use crate::intern::InternMap;
+use crate::types::{Encoding, Member, Type, TypeDb, TypeId, Variable};
 use crate::{AddressMap, Addressed, FileAttr, RawSymbol};
+use config::CONFIG;
 use crossbeam_queue::SegQueue;
 use object::Object;
 use pdb::{FallibleIterator, SymbolData};
 use std::borrow::Cow;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 
@@ -17,32 +21,149 @@ pub struct PDB<'data> {
     dbi: pdb::DebugInformation<'data>,
     /// Mapping from addresses starting at the header base to functions.
     pub syms: AddressMap<RawSymbol<'data>>,
+    /// Types of global variables and functions.
+    pub types: TypeDb,
+}
+
+/// Procedure symbols that refer to the id stream instead of the type stream.
+const PROC_ID_KINDS: [pdb::SymbolKind; 3] = [0x1146, 0x1147, 0x1156];
+
+/// A symbol with a type, before the type is converted.
+enum Typed {
+    Variable(String, pdb::TypeIndex),
+    Function(pdb::TypeIndex),
+    /// Function that refers to a function id, which in turn refers to it's type.
+    FunctionId(pdb::IdIndex),
 }
 
 impl<'data> PDB<'data> {
-    pub fn parse(obj: &object::File<'data>) -> Option<Result<Pin<Box<Self>>, pdb::Error>> {
-        fn open_pdb(obj: &object::File) -> Option<std::fs::File> {
-            let pdb = obj.pdb_info().ok()??;
-            let path = std::str::from_utf8(pdb.path()).ok()?;
-            std::fs::File::open(path).ok()
+    pub fn parse(
+        obj: &object::File<'data>,
+        path: &Path,
+    ) -> Option<Result<Pin<Box<Self>>, pdb::Error>> {
+        let info = obj.pdb_info().ok()??;
+        locate(&info, path).map(|pdb| parse_pdb(obj, pdb))
+    }
+}
+
+/// Name of a symbol store's directory for a PDB, it's GUID followed by it's age.
+fn symstore_key(guid: [u8; 16], age: u32) -> String {
+    // The first three fields of the GUID are stored little-endian.
+    let mut key = format!(
+        "{:08X}{:04X}{:04X}",
+        u32::from_le_bytes([guid[0], guid[1], guid[2], guid[3]]),
+        u16::from_le_bytes([guid[4], guid[5]]),
+        u16::from_le_bytes([guid[6], guid[7]]),
+    );
+
+    for byte in &guid[8..] {
+        key += &format!("{byte:02X}");
+    }
+
+    key += &format!("{age:X}");
+    key
+}
+
+/// Paths the PDB could be at, most specific first.
+fn candidates(info: &object::read::CodeView, path: &Path) -> Vec<PathBuf> {
+    let key = symstore_key(info.guid(), info.age());
+    candidates_by_key(info.path(), &key, path)
+}
+
+/// Paths the PDB `embedded` in the binary at `path` could be at, `key` being it's
+/// [`symstore_key`].
+fn candidates_by_key(embedded: &[u8], key: &str, path: &Path) -> Vec<PathBuf> {
+    let embedded = match std::str::from_utf8(embedded) {
+        Ok(embedded) => embedded,
+        Err(_) => return Vec::new(),
+    };
+
+    // The embedded path is usually a Windows path.
+    let name = embedded.rsplit(['\\', '/']).next().unwrap_or(embedded);
+    let parent = path.parent().unwrap_or(Path::new(""));
+
+    let mut candidates = vec![PathBuf::from(embedded), parent.join(name)];
+    for dir in CONFIG.symbol_dirs.iter() {
+        candidates.push(expand_home(dir).join(name).join(key).join(name));
+    }
+
+    candidates
+}
+
+/// Opens the first PDB that was made from the same build as the binary.
+fn locate<'data>(
+    info: &object::read::CodeView,
+    path: &Path,
+) -> Option<pdb::PDB<'data, std::fs::File>> {
+    for candidate in candidates(info, path) {
+        let file = match std::fs::File::open(&candidate) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+
+        let mut pdb = match pdb::PDB::open(file) {
+            Ok(pdb) => pdb,
+            Err(err) => {
+                log::complex!(
+                    w "[index::pdb::locate] failed to open ",
+                    g candidate.display().to_string(),
+                    w ": ",
+                    y format!("{err}."),
+                );
+                continue;
+            }
+        };
+
+        let (guid, age) = match pdb.pdb_information() {
+            Ok(pdb_info) => (pdb_info.guid.to_bytes_le(), pdb_info.age),
+            Err(_) => continue,
+        };
+
+        // The age in the debug information stream is the one that's kept in sync with the binary.
+        let age = pdb.debug_information().ok().and_then(|dbi| dbi.age()).unwrap_or(age);
+
+        if guid != info.guid() {
+            log::complex!(
+                w "[index::pdb::locate] ",
+                g candidate.display().to_string(),
+                y " has a mismatching GUID, it's from a different build.",
+            );
+            continue;
+        }
+
+        if age != info.age() {
+            log::complex!(
+                w "[index::pdb::locate] ",
+                g candidate.display().to_string(),
+                y format!(" has age {age} instead of {}, it's from a different build.", info.age()),
+            );
+            continue;
         }
 
-        open_pdb(obj).map(|file| parse_pdb(obj, file))
+        log::complex!(
+            w "[index::pdb::locate] found pdb at ",
+            g candidate.display().to_string(),
+            w "."
+        );
+
+        return Some(pdb);
     }
+
+    None
 }
 
 fn parse_pdb<'data>(
     obj: &object::File<'data>,
-    file: std::fs::File,
+    mut pdb: pdb::PDB<'data, std::fs::File>,
 ) -> Result<Pin<Box<PDB<'data>>>, pdb::Error> {
     let base_addr = obj.relative_address_base() as usize;
-    let mut pdb = pdb::PDB::open(file)?;
 
     let mut this = Box::pin(PDB {
         file_attrs: AddressMap::default(),
         global_syms: pdb.global_symbols()?,
         dbi: pdb.debug_information()?,
         syms: AddressMap::default(),
+        types: TypeDb::default(),
     });
 
     // Mapping from offset's to rva's.
@@ -90,6 +211,7 @@ fn parse_pdb<'data>(
 
     // Parse local symbols.
     let mut file_attrs = AddressMap::default();
+    let mut typed = AddressMap::default();
     std::thread::scope(|s| -> Result<_, pdb::Error> {
         let thread_count = std::thread::available_parallelism().unwrap().get();
         let threads: Vec<_> = (0..thread_count)
@@ -97,6 +219,7 @@ fn parse_pdb<'data>(
                 s.spawn(|| -> Result<_, pdb::Error> {
                     let mut syms = AddressMap::default();
                     let mut file_attrs = AddressMap::default();
+                    let mut typed = AddressMap::default();
 
                     while let Some((id, module_name, module_info)) = module_info_queue.pop() {
                         parse_pdb_module(
@@ -109,19 +232,21 @@ fn parse_pdb<'data>(
                             &string_table,
                             &mut file_attrs,
                             &mut syms,
+                            &mut typed,
                         )?;
                         log::PROGRESS.step();
                     }
 
-                    Ok((file_attrs, syms))
+                    Ok((file_attrs, syms, typed))
                 })
             })
             .collect();
 
         for thread in threads {
-            let (local_file_attrs, local_syms) = thread.join().unwrap()?;
+            let (local_file_attrs, local_syms, local_typed) = thread.join().unwrap()?;
             file_attrs.extend(local_file_attrs);
             this.syms.extend(local_syms);
+            typed.extend(local_typed);
         }
 
         Ok(())
@@ -156,9 +281,17 @@ fn parse_pdb<'data>(
 
                 this.syms.push(Addressed {
                     addr: base_addr + addr,
-                    item: RawSymbol { name, module: None },
+                    item: RawSymbol { name, module: None, ordinal: None },
                 });
             }
+            Ok(SymbolData::Data(data)) => {
+                if let Some(rva) = data.offset.to_rva(&address_map) {
+                    typed.push(Addressed {
+                        addr: base_addr + rva.0 as usize,
+                        item: Typed::Variable(data.name.to_string().into_owned(), data.type_index),
+                    });
+                }
+            }
             Ok(_) => {
                 // TODO: implement support for other types of symbols
             }
@@ -170,9 +303,105 @@ fn parse_pdb<'data>(
         };
     }
 
+    this.types = parse_types(&mut pdb, typed, if obj.is_64() { 8 } else { 4 })?;
+
     Ok(this)
 }
 
+/// Converts the types of global variables and functions from the type and id streams.
+fn parse_types<'s, S: pdb::Source<'s> + 's>(
+    pdb: &mut pdb::PDB<'s, S>,
+    typed: AddressMap<Typed>,
+    pointer_size: usize,
+) -> Result<TypeDb, pdb::Error> {
+    let type_information = pdb.type_information()?;
+    let mut finder = type_information.finder();
+    let mut definitions = HashMap::new();
+
+    let mut types = type_information.iter();
+    while let Some(tipe) = types.next()? {
+        finder.update(&types);
+
+        // Forward references are resolved by the name of the definition.
+        let key = match tipe.parse() {
+            Ok(pdb::TypeData::Class(class)) if !class.properties.forward_reference() => {
+                class.unique_name.unwrap_or(class.name)
+            }
+            Ok(pdb::TypeData::Union(union)) if !union.properties.forward_reference() => {
+                union.unique_name.unwrap_or(union.name)
+            }
+            Ok(pdb::TypeData::Enumeration(enumeration))
+                if !enumeration.properties.forward_reference() =>
+            {
+                enumeration.unique_name.unwrap_or(enumeration.name)
+            }
+            _ => continue,
+        };
+
+        definitions.entry(key.to_string().into_owned()).or_insert(tipe.index());
+    }
+
+    // Old PDB's don't have an id stream.
+    let id_information = pdb.id_information().ok();
+    let mut id_finder = id_information.as_ref().map(|ids| ids.finder());
+    if let (Some(ids), Some(id_finder)) = (&id_information, &mut id_finder) {
+        let mut iter = ids.iter();
+        while iter.next()?.is_some() {
+            id_finder.update(&iter);
+        }
+    }
+
+    let mut converter = TypeConverter {
+        finder: &finder,
+        definitions: &definitions,
+        ids: HashMap::new(),
+        pending: Vec::new(),
+        pointer_size,
+        db: TypeDb::default(),
+    };
+
+    for Addressed { addr, item } in typed.mapping {
+        let function_type = match item {
+            Typed::Variable(name, tipe) => {
+                let tipe = converter.id(tipe);
+                converter.db.variables.push(Addressed {
+                    addr,
+                    item: Variable { name, tipe },
+                });
+                continue;
+            }
+            Typed::Function(tipe) => tipe,
+            Typed::FunctionId(id) => {
+                let id = match id_finder.as_ref().and_then(|finder| finder.find(id).ok()) {
+                    Some(id) => id,
+                    None => continue,
+                };
+
+                match id.parse() {
+                    Ok(pdb::IdData::Function(function)) => function.function_type,
+                    Ok(pdb::IdData::MemberFunction(function)) => function.function_type,
+                    _ => continue,
+                }
+            }
+        };
+
+        let tipe = converter.id(function_type);
+        converter.db.functions.push(Addressed { addr, item: tipe });
+    }
+
+    converter.run();
+
+    if !converter.db.types.is_empty() {
+        log::complex!(
+            w "[index::pdb::parse_types] converted ",
+            g converter.db.types.len().to_string(),
+            w " types."
+        );
+    }
+
+    Ok(converter.db)
+}
+
 #[allow(clippy::too_many_arguments)]
 fn parse_pdb_module<'data>(
     module_id: u64,
@@ -184,11 +413,13 @@ fn parse_pdb_module<'data>(
     string_table: &pdb::StringTable<'data>,
     file_attrs: &mut AddressMap<FileAttr>,
     syms: &mut AddressMap<RawSymbol<'data>>,
+    typed: &mut AddressMap<Typed>,
 ) -> Result<(), pdb::Error> {
     let program = module_info.line_program()?;
     let mut symbols = module_info.symbols()?;
 
     while let Some(symbol) = symbols.next()? {
+        let kind = symbol.raw_kind();
         match symbol.parse() {
             Ok(SymbolData::Public(symbol)) if symbol.function => {
                 let addr = match symbol.offset.to_rva(address_map) {
@@ -203,10 +434,27 @@ fn parse_pdb_module<'data>(
 
                 syms.push(Addressed {
                     addr: base_addr + addr,
-                    item: RawSymbol { name, module: module_name },
+                    item: RawSymbol { name, module: module_name, ordinal: None },
                 });
             }
+            Ok(SymbolData::Data(data)) => {
+                if let Some(rva) = data.offset.to_rva(address_map) {
+                    typed.push(Addressed {
+                        addr: base_addr + rva.0 as usize,
+                        item: Typed::Variable(data.name.to_string().into_owned(), data.type_index),
+                    });
+                }
+            }
             Ok(SymbolData::Procedure(proc)) => {
+                if let Some(rva) = proc.offset.to_rva(address_map) {
+                    let item = match PROC_ID_KINDS.contains(&kind) {
+                        true => Typed::FunctionId(pdb::IdIndex(proc.type_index.0)),
+                        false => Typed::Function(proc.type_index),
+                    };
+
+                    typed.push(Addressed { addr: base_addr + rva.0 as usize, item });
+                }
+
                 let mut lines = program.lines_for_symbol(proc.offset);
                 while let Some(line_info) = lines.next()? {
                     let addr = match line_info.offset.to_rva(address_map) {
@@ -258,3 +506,660 @@ fn parse_pdb_module<'data>(
 
     Ok(())
 }
+
+/// Most field lists followed for a single type, in case they continue into each other.
+const MAX_FIELD_LISTS: usize = 64;
+
+/// How deeply type records are followed for their size, in case they're cyclic.
+const MAX_DEPTH: usize = 32;
+
+/// Converts type records into a [`TypeDb`], only describing the types that are referenced.
+struct TypeConverter<'a, 't> {
+    finder: &'a pdb::TypeFinder<'t>,
+    /// Definitions of structures, unions and enums by their unique name, so that forward
+    /// references can be resolved.
+    definitions: &'a HashMap<String, pdb::TypeIndex>,
+    ids: HashMap<pdb::TypeIndex, TypeId>,
+    /// Types that have an id but haven't been described yet.
+    pending: Vec<(pdb::TypeIndex, TypeId)>,
+    pointer_size: usize,
+    db: TypeDb,
+}
+
+impl<'t> TypeConverter<'_, 't> {
+    fn parse(&self, index: pdb::TypeIndex) -> Option<pdb::TypeData<'t>> {
+        self.finder.find(index).and_then(|item| item.parse()).ok()
+    }
+
+    /// Index of the definition of a structure, union or enum that's only declared at `index`.
+    fn definition(&self, index: pdb::TypeIndex) -> pdb::TypeIndex {
+        let key = match self.parse(index) {
+            Some(pdb::TypeData::Class(class)) if class.properties.forward_reference() => {
+                class.unique_name.unwrap_or(class.name)
+            }
+            Some(pdb::TypeData::Union(union)) if union.properties.forward_reference() => {
+                union.unique_name.unwrap_or(union.name)
+            }
+            Some(pdb::TypeData::Enumeration(enumeration))
+                if enumeration.properties.forward_reference() =>
+            {
+                enumeration.unique_name.unwrap_or(enumeration.name)
+            }
+            _ => return index,
+        };
+
+        self.definitions.get(&*key.to_string()).copied().unwrap_or(index)
+    }
+
+    /// Id of the type at `index`, reserving one if it hasn't been described yet.
+    fn id(&mut self, index: pdb::TypeIndex) -> TypeId {
+        if let Some(id) = self.ids.get(&index) {
+            return *id;
+        }
+
+        let definition = self.definition(index);
+        let id = match self.ids.get(&definition) {
+            Some(id) => *id,
+            None => {
+                self.db.types.push(Type::Unknown);
+                let id = self.db.types.len() - 1;
+                self.ids.insert(definition, id);
+                self.pending.push((definition, id));
+                id
+            }
+        };
+
+        self.ids.insert(index, id);
+        id
+    }
+
+    /// Id of the type at `index`, or `None` if it's `void`.
+    fn target(&mut self, index: pdb::TypeIndex) -> Option<TypeId> {
+        match self.parse(index) {
+            Some(pdb::TypeData::Primitive(pdb::PrimitiveType {
+                kind: pdb::PrimitiveKind::Void | pdb::PrimitiveKind::NoType,
+                indirection: None,
+            })) => None,
+            _ => Some(self.id(index)),
+        }
+    }
+
+    /// Describes every type that got an id, including the ones referenced along the way.
+    fn run(&mut self) {
+        while let Some((index, id)) = self.pending.pop() {
+            let tipe = match self.parse(index) {
+                Some(data) => self.convert(index, data),
+                None => Type::Unknown,
+            };
+
+            self.db.types[id] = tipe;
+        }
+    }
+
+    fn convert(&mut self, index: pdb::TypeIndex, data: pdb::TypeData<'t>) -> Type {
+        match data {
+            pdb::TypeData::Primitive(primitive) => match primitive.indirection {
+                Some(indirection) => {
+                    // The lower byte of a primitive's index is the type it points to.
+                    let target = pdb::TypeIndex(index.0 & 0xff);
+                    Type::Pointer {
+                        size: indirection_size(indirection),
+                        target: self.target(target),
+                    }
+                }
+                None => {
+                    let (name, size, encoding) = primitive_kind(primitive.kind);
+                    Type::Base {
+                        name: name.to_string(),
+                        size,
+                        encoding,
+                    }
+                }
+            },
+            pdb::TypeData::Class(class) => Type::Struct {
+                name: Some(class.name.to_string().into_owned()),
+                size: class.size as usize,
+                members: class.fields.map(|fields| self.members(fields)).unwrap_or_default(),
+                is_union: false,
+            },
+            pdb::TypeData::Union(union) => Type::Struct {
+                name: Some(union.name.to_string().into_owned()),
+                size: union.size as usize,
+                members: self.members(union.fields),
+                is_union: true,
+            },
+            pdb::TypeData::Enumeration(enumeration) => Type::Enum {
+                name: Some(enumeration.name.to_string().into_owned()),
+                size: self.size_of(enumeration.underlying_type, 0),
+                variants: self.variants(enumeration.fields),
+            },
+            pdb::TypeData::Pointer(pointer) => Type::Pointer {
+                size: match pointer.attributes.size() {
+                    0 => self.pointer_size,
+                    size => size as usize,
+                },
+                target: self.target(pointer.underlying_type),
+            },
+            pdb::TypeData::Modifier(modifier) => {
+                let mut target = self.target(modifier.underlying_type);
+
+                // Both qualifiers are described by a single record.
+                if modifier.constant && modifier.volatile {
+                    self.db.types.push(Type::Qualified {
+                        qualifier: "volatile",
+                        target,
+                    });
+                    target = Some(self.db.types.len() - 1);
+                }
+
+                let qualifier = match (modifier.constant, modifier.volatile) {
+                    (true, _) => "const",
+                    (false, true) => "volatile",
+                    (false, false) => "__unaligned",
+                };
+
+                Type::Qualified { qualifier, target }
+            }
+            pdb::TypeData::Array(array) => {
+                let elem_size = self.size_of(array.element_type, 0);
+                let size = array.dimensions.last().copied().unwrap_or(0) as usize;
+                Type::Array {
+                    elem: self.id(array.element_type),
+                    count: size.checked_div(elem_size).unwrap_or(0),
+                }
+            }
+            pdb::TypeData::Procedure(procedure) => {
+                let ret = procedure.return_type.and_then(|ret| self.target(ret));
+                let (params, variadic) = self.arguments(procedure.argument_list);
+                Type::Function { ret, params, variadic }
+            }
+            pdb::TypeData::MemberFunction(function) => {
+                let ret = self.target(function.return_type);
+                let (params, variadic) = self.arguments(function.argument_list);
+                Type::Function { ret, params, variadic }
+            }
+            _ => Type::Unknown,
+        }
+    }
+
+    /// Members of a structure, following the field lists it's split into.
+    fn members(&mut self, fields: pdb::TypeIndex) -> Vec<Member> {
+        let mut members = Vec::new();
+        let mut next = Some(fields);
+
+        for _ in 0..MAX_FIELD_LISTS {
+            let list = match next.and_then(|fields| self.parse(fields)) {
+                Some(pdb::TypeData::FieldList(list)) => list,
+                _ => break,
+            };
+
+            for field in list.fields {
+                match field {
+                    pdb::TypeData::Member(member) => {
+                        let name = Some(member.name.to_string().into_owned());
+                        let offset = member.offset as usize;
+
+                        match self.parse(member.field_type) {
+                            Some(pdb::TypeData::Bitfield(bitfield)) => {
+                                let bit_offset = offset * 8 + bitfield.position as usize;
+                                members.push(Member {
+                                    name,
+                                    offset: bit_offset / 8,
+                                    tipe: self.id(bitfield.underlying_type),
+                                    bits: Some((bit_offset % 8, bitfield.length as usize)),
+                                });
+                            }
+                            _ => members.push(Member {
+                                name,
+                                offset,
+                                tipe: self.id(member.field_type),
+                                bits: None,
+                            }),
+                        }
+                    }
+                    pdb::TypeData::BaseClass(base) => members.push(Member {
+                        name: None,
+                        offset: base.offset as usize,
+                        tipe: self.id(base.base_class),
+                        bits: None,
+                    }),
+                    _ => {}
+                }
+            }
+
+            next = list.continuation;
+        }
+
+        members
+    }
+
+    fn variants(&self, fields: pdb::TypeIndex) -> Vec<(i64, String)> {
+        let mut variants = Vec::new();
+        let mut next = Some(fields);
+
+        for _ in 0..MAX_FIELD_LISTS {
+            let list = match next.and_then(|fields| self.parse(fields)) {
+                Some(pdb::TypeData::FieldList(list)) => list,
+                _ => break,
+            };
+
+            for field in list.fields {
+                if let pdb::TypeData::Enumerate(variant) = field {
+                    let value = match variant.value {
+                        pdb::Variant::U8(value) => value as i64,
+                        pdb::Variant::U16(value) => value as i64,
+                        pdb::Variant::U32(value) => value as i64,
+                        pdb::Variant::U64(value) => value as i64,
+                        pdb::Variant::I8(value) => value as i64,
+                        pdb::Variant::I16(value) => value as i64,
+                        pdb::Variant::I32(value) => value as i64,
+                        pdb::Variant::I64(value) => value,
+                    };
+
+                    variants.push((value, variant.name.to_string().into_owned()));
+                }
+            }
+
+            next = list.continuation;
+        }
+
+        variants
+    }
+
+    /// Parameter types of a function and whether it's variadic.
+    fn arguments(&mut self, arguments: pdb::TypeIndex) -> (Vec<TypeId>, bool) {
+        let arguments = match self.parse(arguments) {
+            Some(pdb::TypeData::ArgumentList(list)) => list.arguments,
+            _ => return (Vec::new(), false),
+        };
+
+        let mut params = Vec::new();
+        let mut variadic = false;
+        for argument in arguments {
+            // Variadic functions end in an argument without a type.
+            match self.target(argument) {
+                Some(param) => params.push(param),
+                None if argument.0 == 0 => variadic = true,
+                None => {}
+            }
+        }
+
+        (params, variadic)
+    }
+
+    /// Size of the type at `index`, without describing it.
+    fn size_of(&self, index: pdb::TypeIndex, depth: usize) -> usize {
+        if depth > MAX_DEPTH {
+            return 0;
+        }
+
+        match self.parse(self.definition(index)) {
+            Some(pdb::TypeData::Primitive(primitive)) => match primitive.indirection {
+                Some(indirection) => indirection_size(indirection),
+                None => primitive_kind(primitive.kind).1,
+            },
+            Some(pdb::TypeData::Class(class)) => class.size as usize,
+            Some(pdb::TypeData::Union(union)) => union.size as usize,
+            Some(pdb::TypeData::Enumeration(enumeration)) => {
+                self.size_of(enumeration.underlying_type, depth + 1)
+            }
+            Some(pdb::TypeData::Pointer(pointer)) => match pointer.attributes.size() {
+                0 => self.pointer_size,
+                size => size as usize,
+            },
+            Some(pdb::TypeData::Modifier(modifier)) => {
+                self.size_of(modifier.underlying_type, depth + 1)
+            }
+            Some(pdb::TypeData::Array(array)) => {
+                array.dimensions.last().copied().unwrap_or(0) as usize
+            }
+            _ => 0,
+        }
+    }
+}
+
+fn indirection_size(indirection: pdb::Indirection) -> usize {
+    match indirection {
+        pdb::Indirection::Near16 => 2,
+        pdb::Indirection::Far16 | pdb::Indirection::Huge16 | pdb::Indirection::Near32 => 4,
+        pdb::Indirection::Far32 => 6,
+        pdb::Indirection::Near64 => 8,
+        pdb::Indirection::Near128 => 16,
+    }
+}
+
+/// Name, size and encoding of a primitive type.
+fn primitive_kind(kind: pdb::PrimitiveKind) -> (&'static str, usize, Encoding) {
+    use pdb::PrimitiveKind as Kind;
+
+    match kind {
+        Kind::NoType | Kind::Void => ("void", 0, Encoding::Unsigned),
+        Kind::Char | Kind::RChar => ("char", 1, Encoding::Char),
+        Kind::UChar => ("unsigned char", 1, Encoding::Char),
+        Kind::WChar => ("wchar_t", 2, Encoding::Unsigned),
+        Kind::RChar16 => ("char16_t", 2, Encoding::Unsigned),
+        Kind::RChar32 => ("char32_t", 4, Encoding::Unsigned),
+        Kind::I8 => ("int8_t", 1, Encoding::Signed),
+        Kind::U8 => ("uint8_t", 1, Encoding::Unsigned),
+        Kind::Short => ("short", 2, Encoding::Signed),
+        Kind::UShort => ("unsigned short", 2, Encoding::Unsigned),
+        Kind::I16 => ("int16_t", 2, Encoding::Signed),
+        Kind::U16 => ("uint16_t", 2, Encoding::Unsigned),
+        Kind::Long => ("long", 4, Encoding::Signed),
+        Kind::ULong => ("unsigned long", 4, Encoding::Unsigned),
+        Kind::I32 => ("int", 4, Encoding::Signed),
+        Kind::U32 => ("unsigned int", 4, Encoding::Unsigned),
+        Kind::Quad => ("long long", 8, Encoding::Signed),
+        Kind::UQuad => ("unsigned long long", 8, Encoding::Unsigned),
+        Kind::I64 => ("int64_t", 8, Encoding::Signed),
+        Kind::U64 => ("uint64_t", 8, Encoding::Unsigned),
+        Kind::Octa | Kind::I128 => ("int128_t", 16, Encoding::Signed),
+        Kind::UOcta | Kind::U128 => ("uint128_t", 16, Encoding::Unsigned),
+        Kind::F16 => ("half", 2, Encoding::Float),
+        Kind::F32 | Kind::F32PP => ("float", 4, Encoding::Float),
+        Kind::F48 => ("float48", 6, Encoding::Float),
+        Kind::F64 => ("double", 8, Encoding::Float),
+        Kind::F80 => ("long double", 10, Encoding::Float),
+        Kind::F128 => ("float128", 16, Encoding::Float),
+        Kind::Complex32 => ("complex float", 8, Encoding::Unsigned),
+        Kind::Complex64 => ("complex double", 16, Encoding::Unsigned),
+        Kind::Complex80 => ("complex long double", 20, Encoding::Unsigned),
+        Kind::Complex128 => ("complex float128", 32, Encoding::Unsigned),
+        Kind::Bool8 => ("bool", 1, Encoding::Bool),
+        Kind::Bool16 => ("bool16", 2, Encoding::Bool),
+        Kind::Bool32 => ("bool32", 4, Encoding::Bool),
+        Kind::Bool64 => ("bool64", 8, Encoding::Bool),
+        Kind::HRESULT => ("HRESULT", 4, Encoding::Signed),
+        #[allow(unreachable_patterns)]
+        _ => ("?", 0, Encoding::Unsigned),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `{12345678-9ABC-DEF0-1122-334455667788}` as stored in a CodeView record.
+    const GUID: [u8; 16] = [
+        0x78, 0x56, 0x34, 0x12, 0xbc, 0x9a, 0xf0, 0xde, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+        0x88,
+    ];
+
+    #[test]
+    fn symstore_keys() {
+        assert_eq!(symstore_key(GUID, 1), "123456789ABCDEF011223344556677881");
+        assert_eq!(
+            symstore_key(GUID, 0x2a),
+            "123456789ABCDEF011223344556677882A"
+        );
+        assert_eq!(
+            symstore_key([0; 16], 0),
+            "000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn candidate_order() {
+        let key = symstore_key(GUID, 1);
+        let path = Path::new("/nonexistent/bin/app.exe");
+        let stores = |name: &str| -> Vec<PathBuf> {
+            let dirs = CONFIG.symbol_dirs.iter().map(|dir| expand_home(dir));
+            dirs.map(|dir| dir.join(name).join(&key).join(name)).collect()
+        };
+
+        let mut expected = vec![
+            PathBuf::from("C:\\build\\out\\app.pdb"),
+            PathBuf::from("/nonexistent/bin/app.pdb"),
+        ];
+        expected.extend(stores("app.pdb"));
+        assert_eq!(
+            candidates_by_key(b"C:\\build\\out\\app.pdb", &key, path),
+            expected
+        );
+
+        let mut expected = vec![
+            PathBuf::from("/build/out/lib.pdb"),
+            PathBuf::from("/nonexistent/bin/lib.pdb"),
+        ];
+        expected.extend(stores("lib.pdb"));
+        assert_eq!(
+            candidates_by_key(b"/build/out/lib.pdb", &key, path),
+            expected
+        );
+
+        assert!(candidates_by_key(b"\xffapp.pdb", &key, path).is_empty());
+    }
+
+    /// Record in a type stream, the kind followed by it's fields padded to four bytes.
+    fn record(kind: u16, fields: &[u8]) -> Vec<u8> {
+        let mut record = kind.to_le_bytes().to_vec();
+        record.extend(pad(fields.to_vec()));
+        let mut bytes = (record.len() as u16).to_le_bytes().to_vec();
+        bytes.extend(record);
+        bytes
+    }
+
+    /// Pads with `LF_PAD` bytes counting down to the next four byte boundary.
+    fn pad(mut bytes: Vec<u8>) -> Vec<u8> {
+        // The two bytes of a record's kind have to be accounted for.
+        let padding = (4 - (bytes.len() + 2) % 4) % 4;
+        bytes.extend((1..=padding as u8).rev().map(|n| 0xf0 | n));
+        bytes
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    fn cstr(name: &str) -> Vec<u8> {
+        let mut bytes = name.as_bytes().to_vec();
+        bytes.push(0);
+        bytes
+    }
+
+    fn member(tipe: u32, offset: u16, name: &str) -> Vec<u8> {
+        let mut bytes = 0x150du16.to_le_bytes().to_vec();
+        bytes.extend(3u16.to_le_bytes());
+        bytes.extend(tipe.to_le_bytes());
+        bytes.extend(offset.to_le_bytes());
+        bytes.extend(cstr(name));
+        pad(bytes)
+    }
+
+    fn enumerate(value: &[u8], name: &str) -> Vec<u8> {
+        let mut bytes = 0x1502u16.to_le_bytes().to_vec();
+        bytes.extend(3u16.to_le_bytes());
+        bytes.extend(value);
+        bytes.extend(cstr(name));
+        pad(bytes)
+    }
+
+    fn structure(count: u16, properties: u16, fields: u32, size: u16, name: &str) -> Vec<u8> {
+        let mut bytes = count.to_le_bytes().to_vec();
+        bytes.extend(properties.to_le_bytes());
+        bytes.extend(fields.to_le_bytes());
+        bytes.extend([0; 8]);
+        bytes.extend(size.to_le_bytes());
+        bytes.extend(cstr(name));
+        record(0x1505, &bytes)
+    }
+
+    /// Type records of:
+    ///
+    /// ```c
+    /// struct point { int x; int y; struct point *next; };
+    /// enum color { red, green, none = -1 };
+    /// int draw(struct point *, enum color, ...);
+    /// ```
+    ///
+    /// Where `point` is declared before it's defined, like MSVC does for self-referential types.
+    fn types() -> Vec<u8> {
+        // 0x1000: forward reference to `point`.
+        let mut records = structure(0, 0x80, 0, 0, "point");
+        // 0x1001: 64-bit pointer to the forward reference.
+        records.extend(record(0x1002, &u32s(&[0x1000, 0xc | 8 << 13])));
+        // 0x1002, 0x1003: the definition of `point`.
+        let mut fields = member(0x74, 0, "x");
+        fields.extend(member(0x74, 4, "y"));
+        fields.extend(member(0x1001, 8, "next"));
+        records.extend(record(0x1203, &fields));
+        records.extend(structure(3, 0, 0x1002, 16, "point"));
+        // 0x1004, 0x1005: `color`, with `none` stored as a signed short.
+        let mut fields = enumerate(&0u16.to_le_bytes(), "red");
+        fields.extend(enumerate(&1u16.to_le_bytes(), "green"));
+        fields.extend(enumerate(&[0x01, 0x80, 0xff, 0xff], "none"));
+        records.extend(record(0x1203, &fields));
+        let mut color = 3u16.to_le_bytes().to_vec();
+        color.extend([0, 0]);
+        color.extend(u32s(&[0x74, 0x1004]));
+        color.extend(cstr("color"));
+        records.extend(record(0x1507, &color));
+        // 0x1006, 0x1007: `draw`, variadic functions end in a `0` argument.
+        let mut arguments = 3u32.to_le_bytes().to_vec();
+        arguments.extend(u32s(&[0x1001, 0x1005, 0]));
+        records.extend(record(0x1201, &arguments));
+        let mut procedure = 0x74u32.to_le_bytes().to_vec();
+        procedure.extend([0, 0, 3, 0]);
+        procedure.extend(0x1006u32.to_le_bytes());
+        records.extend(record(0x1008, &procedure));
+
+        let mut tpi = u32s(&[20040203, 56, 0x1000, 0x1008, records.len() as u32]);
+        tpi.extend([0xff; 4]);
+        tpi.extend(u32s(&[4, 0x3ffff]));
+        tpi.extend([0; 24]);
+        tpi.extend(records);
+        tpi
+    }
+
+    /// MSF with 4K pages that only has a type stream, like PDB's older than the id stream.
+    fn msf(tpi: &[u8]) -> Vec<u8> {
+        const PAGE: usize = 4096;
+
+        let mut bytes = vec![0; 5 * PAGE];
+        let mut write = |offset: usize, data: &[u8]| {
+            bytes[offset..][..data.len()].copy_from_slice(data);
+        };
+
+        // The directory is on page 3, the page list of the directory on page 2.
+        write(0, b"Microsoft C/C++ MSF 7.00\r\n\x1a\x44\x53\x00\x00\x00");
+        write(32, &u32s(&[PAGE as u32, 1, 5, 20, 0, 2]));
+        write(2 * PAGE, &u32s(&[3]));
+        write(3 * PAGE, &u32s(&[3, 0, 0, tpi.len() as u32, 4]));
+        write(4 * PAGE, tpi);
+        bytes
+    }
+
+    fn parse_db() -> TypeDb {
+        let mut pdb = pdb::PDB::open(std::io::Cursor::new(msf(&types()))).unwrap();
+
+        let mut typed = AddressMap::default();
+        typed.push(Addressed {
+            addr: 0x3000,
+            item: Typed::Variable("origin".to_string(), pdb::TypeIndex(0x1000)),
+        });
+        typed.push(Addressed {
+            addr: 0x1400,
+            item: Typed::Function(pdb::TypeIndex(0x1007)),
+        });
+
+        parse_types(&mut pdb, typed, 8).unwrap()
+    }
+
+    #[test]
+    fn forward_references() {
+        let db = parse_db();
+
+        let origin = &db.variables[0].item;
+        assert_eq!(origin.name, "origin");
+        let (members, size) = match &db.types[origin.tipe] {
+            Type::Struct {
+                name: Some(name),
+                size,
+                members,
+                is_union: false,
+            } if name == "point" => (members, *size),
+            tipe => panic!("expected the definition of point, got {tipe:?}"),
+        };
+
+        assert_eq!(size, 16);
+        let next = members[2].tipe;
+        let members: Vec<_> = members
+            .iter()
+            .map(|member| {
+                (
+                    member.name.as_deref().unwrap(),
+                    member.offset,
+                    db.name_of(member.tipe),
+                )
+            })
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("x", 0, "int".to_string()),
+                ("y", 4, "int".to_string()),
+                ("next", 8, "point *".to_string())
+            ]
+        );
+
+        // The pointer in the definition refers to the forward reference, so back to itself.
+        assert!(matches!(
+            db.types[next],
+            Type::Pointer { size: 8, target: Some(target) } if target == origin.tipe
+        ));
+    }
+
+    #[test]
+    fn functions() {
+        let db = parse_db();
+
+        let draw = db.functions[0].item;
+        assert_eq!(db.functions[0].addr, 0x1400);
+        let (ret, params) = match &db.types[draw] {
+            Type::Function {
+                ret,
+                params,
+                variadic: true,
+            } => (*ret, params),
+            tipe => panic!("expected a variadic function, got {tipe:?}"),
+        };
+
+        assert_eq!(db.name_of(ret.unwrap()), "int");
+        assert_eq!(params.len(), 2);
+        assert_eq!(db.name_of(params[0]), "point *");
+
+        match &db.types[params[1]] {
+            Type::Enum {
+                name: Some(name),
+                size: 4,
+                variants,
+            } => {
+                assert_eq!(name, "color");
+                let variants: Vec<_> = variants.iter().map(|(v, n)| (*v, n.as_str())).collect();
+                assert_eq!(variants, [(0, "red"), (1, "green"), (-1, "none")]);
+            }
+            tipe => panic!("expected an enum, got {tipe:?}"),
+        }
+    }
+
+    #[test]
+    fn prototypes() {
+        let db = parse_db();
+        let symbol = |name: &'static str| RawSymbol {
+            name,
+            module: None,
+            ordinal: None,
+        };
+
+        let draw = crate::Symbol::parse(0x1400, &symbol("draw"), &db);
+        assert_eq!(draw.prototype(), Some("int draw(point *, color, ...)"));
+
+        // MSVC mangled names already encode the prototype.
+        let mangled =
+            crate::Symbol::parse(0x1400, &symbol("?draw@@YAHPEAUpoint@@W4color@@ZZ"), &db);
+        assert_eq!(mangled.prototype(), None);
+
+        let elsewhere = crate::Symbol::parse(0x1500, &symbol("draw"), &db);
+        assert_eq!(elsewhere.prototype(), None);
+    }
+}
@@ -0,0 +1,364 @@
+//! Shortening demangled names by dropping inline namespaces, eliding default template arguments
+//! and collapsing well-known standard library types.
+
+use std::ops::Range;
+use tokenizing::{Color32, Token};
+
+/// Versioned namespaces libc++, libstdc++ and the NDK put the standard library in.
+const INLINE_NAMESPACES: &[&str] = &["::__1::", "::__cxx11::", "::__ndk1::", "::__u::"];
+
+/// Template arguments that are left out when they're the last ones, as they're the default.
+const DEFAULT_ARGS: &[&str] = &[
+    "std::allocator<",
+    "std::char_traits<",
+    "std::less<",
+    "std::equal_to<",
+    "std::hash<",
+    "std::default_delete<",
+    "alloc::alloc::Global",
+    "std::alloc::Global",
+    "std::collections::hash::map::RandomState",
+    "std::hash::random::RandomState",
+];
+
+/// Types with a shorter name, once their default arguments are gone.
+const ALIASES: &[(&str, &str)] = &[
+    ("std::basic_string<char>", "std::string"),
+    ("std::basic_string<wchar_t>", "std::wstring"),
+    ("std::basic_string<char8_t>", "std::u8string"),
+    ("std::basic_string<char16_t>", "std::u16string"),
+    ("std::basic_string<char32_t>", "std::u32string"),
+    ("std::basic_string_view<char>", "std::string_view"),
+    ("std::basic_string_view<wchar_t>", "std::wstring_view"),
+    ("std::basic_ostream<char>", "std::ostream"),
+    ("std::basic_istream<char>", "std::istream"),
+    ("std::basic_iostream<char>", "std::iostream"),
+    ("std::basic_ostringstream<char>", "std::ostringstream"),
+    ("std::basic_istringstream<char>", "std::istringstream"),
+    ("std::basic_stringstream<char>", "std::stringstream"),
+    ("std::basic_ofstream<char>", "std::ofstream"),
+    ("std::basic_ifstream<char>", "std::ifstream"),
+    ("std::basic_fstream<char>", "std::fstream"),
+    ("std::basic_streambuf<char>", "std::streambuf"),
+    ("alloc::string::String", "String"),
+    ("std::string::String", "String"),
+    ("alloc::vec::Vec<", "Vec<"),
+    ("std::vec::Vec<", "Vec<"),
+    ("alloc::boxed::Box<", "Box<"),
+    ("std::boxed::Box<", "Box<"),
+    ("alloc::rc::Rc<", "Rc<"),
+    ("std::rc::Rc<", "Rc<"),
+    ("alloc::sync::Arc<", "Arc<"),
+    ("std::sync::Arc<", "Arc<"),
+    ("alloc::borrow::Cow<", "Cow<"),
+    ("core::option::Option<", "Option<"),
+    ("core::result::Result<", "Result<"),
+    ("std::collections::hash::map::HashMap<", "HashMap<"),
+    ("core::primitive::str", "str"),
+];
+
+fn is_ident(chr: u8) -> bool {
+    chr.is_ascii_alphanumeric() || chr == b'_' || chr == b'$'
+}
+
+/// Text with the color of each of it's bytes, so it can be edited and turned back into tokens.
+struct Colored {
+    text: String,
+    colors: Vec<Color32>,
+}
+
+impl Colored {
+    fn new(tokens: &[Token]) -> Self {
+        let mut text = String::new();
+        let mut colors = Vec::new();
+
+        for token in tokens {
+            text += &token.text;
+            colors.resize(colors.len() + token.text.len(), token.color);
+        }
+
+        Self { text, colors }
+    }
+
+    /// Replaces a non-empty range, with the new text taking on the color it started with.
+    fn replace(&mut self, range: Range<usize>, with: &str) {
+        let color = self.colors[range.start];
+        self.text.replace_range(range.clone(), with);
+        self.colors.splice(range, vec![color; with.len()]);
+    }
+
+    /// Replaces every occurrence of `from` that isn't part of a longer path or identifier.
+    fn replace_all(&mut self, from: &str, to: &str) {
+        let mut pos = 0;
+
+        while let Some(off) = self.text[pos..].find(from) {
+            let start = pos + off;
+            let end = start + from.len();
+            let bytes = self.text.as_bytes();
+
+            let starts_path = !is_ident(bytes[start])
+                || start == 0
+                || !(is_ident(bytes[start - 1]) || bytes[start - 1] == b':');
+            let ends_path =
+                !is_ident(bytes[end - 1]) || end == bytes.len() || !is_ident(bytes[end]);
+
+            if starts_path && ends_path {
+                self.replace(start..end, to);
+                pos = start + to.len();
+            } else {
+                pos = start + 1;
+            }
+        }
+    }
+
+    fn into_tokens(self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut start = 0;
+
+        for idx in 1..=self.text.len() {
+            let ends_run = idx == self.text.len() || self.colors[idx] != self.colors[start];
+
+            if ends_run && self.text.is_char_boundary(idx) {
+                tokens.push(Token::from_string(
+                    self.text[start..idx].to_string(),
+                    self.colors[start],
+                ));
+                start = idx;
+            }
+        }
+
+        tokens
+    }
+}
+
+/// Ranges of the arguments in the template list opened at `open`, and where it's closed.
+fn template_args(text: &str, open: usize) -> Option<(Vec<Range<usize>>, usize)> {
+    let bytes = text.as_bytes();
+    let mut args = Vec::new();
+    let mut start = open + 1;
+    let mut depth = 0usize;
+
+    for idx in open + 1..bytes.len() {
+        match bytes[idx] {
+            b'<' | b'(' | b'[' => depth += 1,
+            // `operator->` doesn't close anything.
+            b'>' if bytes[idx - 1] == b'-' => {}
+            b'>' | b')' | b']' if depth > 0 => depth -= 1,
+            b'>' => {
+                args.push(start..idx);
+                let args = args
+                    .into_iter()
+                    .map(|range| {
+                        let arg = &text[range.clone()];
+                        let leading = arg.len() - arg.trim_start().len();
+                        range.start + leading..range.start + leading + arg.trim().len()
+                    })
+                    .collect();
+                return Some((args, idx));
+            }
+            b')' | b']' => return None,
+            b',' if depth == 0 => {
+                args.push(start..idx);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Whether `arg` is what the template argument would've been if it were left out.
+fn is_default(arg: &str, first: &str) -> bool {
+    // MSVC names the kind of type an argument is.
+    let arg = arg
+        .strip_prefix("class ")
+        .or_else(|| arg.strip_prefix("struct "))
+        .unwrap_or(arg);
+
+    DEFAULT_ARGS.iter().any(|default| match default.strip_suffix('<') {
+        Some(_) => match arg.strip_prefix(default).and_then(|arg| arg.strip_suffix('>')) {
+            // Maps allocate pairs of their key and value.
+            Some(inner) => inner.trim() == first || inner.starts_with("std::pair<"),
+            None => false,
+        },
+        None => arg == *default,
+    })
+}
+
+/// Removes trailing template arguments that are the same as their default.
+fn elide_defaults(name: &mut Colored) {
+    let mut pos = 0;
+
+    while let Some(off) = name.text[pos..].find('<') {
+        let open = pos + off;
+        pos = open + 1;
+
+        let (args, close) = match template_args(&name.text, open) {
+            Some(args) => args,
+            None => continue,
+        };
+
+        let first = &name.text[args[0].clone()];
+        let mut keep = args.len();
+        while keep > 1 && is_default(&name.text[args[keep - 1].clone()], first) {
+            keep -= 1;
+        }
+
+        if keep != args.len() {
+            name.replace(args[keep - 1].end..close, "");
+        }
+    }
+}
+
+fn simplify(name: &mut Colored) {
+    for namespace in INLINE_NAMESPACES {
+        name.replace_all(namespace, "::");
+    }
+
+    elide_defaults(name);
+
+    for (from, to) in ALIASES {
+        name.replace_all(from, to);
+    }
+}
+
+/// Shortened version of the tokens of a demangled name.
+pub fn tokens(tokens: &[Token]) -> Option<Vec<Token>> {
+    let mut name = Colored::new(tokens);
+    let len = name.text.len();
+
+    simplify(&mut name);
+
+    if name.text.len() == len {
+        return None;
+    }
+
+    Some(name.into_tokens())
+}
+
+/// Shortened version of a name that isn't tokenized, such as a type in a prototype.
+pub fn text(text: &str) -> String {
+    let mut name = Colored {
+        text: text.to_string(),
+        colors: vec![Color32::TRANSPARENT; text.len()],
+    };
+
+    simplify(&mut name);
+    name.text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::text;
+
+    fn demangled(s: &str) -> String {
+        let demangled = crate::demangler::parse(s);
+        let tokens = super::tokens(demangled.tokens()).unwrap_or(demangled.tokens().to_vec());
+        String::from_iter(tokens.iter().map(|t| &t.text[..]))
+    }
+
+    #[test]
+    fn mangled() {
+        assert_eq!(
+            demangled("_ZNSt3__112basic_stringIcNS_11char_traitsIcEENS_9allocatorIcEEE6appendEPKc"),
+            "std::string::append"
+        );
+        assert_eq!(
+            demangled("_ZNSt6vectorINSt7__cxx1112basic_stringIcSt11char_traitsIcESaIcEEESaIS5_EE9push_backEOS5_"),
+            "std::vector<std::string>::push_back"
+        );
+        assert_eq!(
+            demangled("?f@@YAXV?$basic_string@DU?$char_traits@D@std@@V?$allocator@D@2@@std@@@Z"),
+            "void __cdecl f(class std::string)"
+        );
+        assert_eq!(
+            demangled("_RNvMNtCs1234_5alloc3vecINtB2_3VechE4push"),
+            "<Vec<u8>>::push"
+        );
+    }
+
+    #[test]
+    fn inline_namespaces() {
+        assert_eq!(
+            text("std::__1::vector<int>::size() const"),
+            "std::vector<int>::size() const"
+        );
+        assert_eq!(
+            text("std::__cxx11::list<int>::clear()"),
+            "std::list<int>::clear()"
+        );
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(
+            text(
+                "std::__1::basic_string<char, std::__1::char_traits<char>, \
+                  std::__1::allocator<char>>::append(char const*)"
+            ),
+            "std::string::append(char const*)"
+        );
+        assert_eq!(
+            text("f(std::basic_string_view<wchar_t, std::char_traits<wchar_t>>)"),
+            "f(std::wstring_view)"
+        );
+    }
+
+    #[test]
+    fn default_args() {
+        assert_eq!(
+            text(
+                "std::vector<std::basic_string<char, std::char_traits<char>, \
+                  std::allocator<char>>, std::allocator<std::basic_string<char, \
+                  std::char_traits<char>, std::allocator<char>>>>::push_back()"
+            ),
+            "std::vector<std::string>::push_back()"
+        );
+        assert_eq!(
+            text(
+                "std::map<int, float, std::less<int>, std::allocator<std::pair<int const, float>>>"
+            ),
+            "std::map<int, float>"
+        );
+        // Non-default arguments are kept.
+        assert_eq!(
+            text("std::set<int, std::less<void>, std::allocator<int>>"),
+            "std::set<int, std::less<void>>"
+        );
+        assert_eq!(
+            text("std::unique_ptr<Foo, std::default_delete<Foo>>::reset(Foo*)"),
+            "std::unique_ptr<Foo>::reset(Foo*)"
+        );
+    }
+
+    #[test]
+    fn rust() {
+        assert_eq!(
+            text("<alloc::vec::Vec<u8, alloc::alloc::Global> as core::clone::Clone>::clone"),
+            "<Vec<u8> as core::clone::Clone>::clone"
+        );
+        assert_eq!(
+            text("<alloc::string::String as core::convert::From<&core::primitive::str>>::from"),
+            "<String as core::convert::From<&str>>::from"
+        );
+        assert_eq!(
+            text("core::option::Option<alloc::boxed::Box<dyn core::any::Any>>::take"),
+            "Option<Box<dyn core::any::Any>>::take"
+        );
+        // Paths that merely end in a well-known type are left alone.
+        assert_eq!(
+            text("mycrate::alloc::vec::Vec<u8>"),
+            "mycrate::alloc::vec::Vec<u8>"
+        );
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(text("operator<<(int, int)"), "operator<<(int, int)");
+        assert_eq!(
+            text("std::__1::unique_ptr<Foo, std::__1::default_delete<Foo>>::operator->() const"),
+            "std::unique_ptr<Foo>::operator->() const"
+        );
+    }
+}
@@ -0,0 +1,1932 @@
+//! Swift symbol demangler
+//!
+//! Swift mangles symbols in postfix order: operands are pushed on a stack and each operator pops
+//! the nodes it applies to. Demangling therefore happens in two passes, first the symbol is
+//! turned into a tree of nodes which is then printed.
+//!
+//! ```text
+//! <symbol> = ("$s" | "$S" | "$e" | "_T0") <global>
+//!
+//! <global> = <entity> {<function-attribute>}
+//!          | <type> "D"                          // type mangling
+//!          | <type> "N"                          // type metadata
+//!          | <type> "M" <metadata-kind>          // type metadata accessor, descriptors, ...
+//!          | <protocol-conformance> "W" <kind>   // witness tables
+//!          | <entity> "T" ("j" | "q")            // dispatch thunk, method descriptor
+//!          | <protocol-conformance> <entity> "TW" // protocol witness
+//!
+//! <function-attribute> = "TA" // partial apply forwarder
+//!                      | "To" // @objc
+//!                      | "TO" // @nonobjc
+//!                      | "TD" // dynamic
+//!                      | "Td" // super
+//!                      | "Tm" // merged
+//!
+//! <entity> = <context> <decl-name> [<labels>] <function-signature> [<generic-signature>] "F"
+//!          | <context> <decl-name> [<labels>] <type> "v" <accessor>
+//!          | <context> [<labels>] <type> "i" <accessor>   // subscript
+//!          | <context> <type> "fC"                      // allocating init
+//!          | <context> <type> "fc"                      // init
+//!          | <context> ("fD" | "fd" | "fE" | "fe")       // deinit, ivar initializer/destroyer
+//!          | <context> <type> ("fU" | "fu") <index>      // closures
+//!          | <context> "fA" <index>                     // default argument
+//!          | <entity> "Z"                               // static
+//!
+//! <accessor> = "p" // pseudo accessor, the variable itself
+//!            | "g" | "s" | "G" | "m" | "r" | "M" | "w" | "W" | "i" | "au" | "lu"
+//!
+//! <context> = <module> | <entity> | <nominal-type> | <module> <nominal-type> [<generic-signature>] "E"
+//! <module> = <identifier> | "s" | "So" | "SC"
+//!
+//! <decl-name> = <identifier>
+//!             | <identifier> <identifier> "LL"   // private name with it's discriminator
+//!             | <identifier> "L" <index>         // local name
+//!             | <identifier> "o" ("i" | "p" | "P") // operators
+//!
+//! <identifier> = <natural> <chars>
+//!              | "0" {<word-substitution> | <natural> <chars>} ["0"]
+//!              | <substitution>
+//!
+//! <substitution> = "A" {<a-z> | <natural> <a-z>} (<A-Z> | <natural> <A-Z>)
+//!                | "S" [<natural>] <standard-type>
+//!
+//! <type> = <context> <decl-name> ("C" | "V" | "O" | "P" | "a") // nominal types
+//!        | <type> "Sg"                                      // T?
+//!        | <type> "y" {<type> ["_"]} "G"                      // bound generic
+//!        | <return-type> <params> ["Ya"] ["Yb"] ["K"] "c"    // function types
+//!        | <return-type> <params> "X" ("E" | "B" | "C" | "f")
+//!        | "y" | <type> "_" {<type> [<identifier>] ["d"]} "t" // tuples
+//!        | <type> ("m" | "z" | "n" | "h" | "Xp")            // metatype, inout, __owned, __shared
+//!        | "y" | <protocol> "_" {<protocol>} ("p" | "Xl")    // existentials
+//!        | "x" | "q" <generic-param-index>                   // generic parameters
+//!        | "B" <builtin-type>
+//!
+//! <generic-signature> = {<requirement>} ("l" | "r" {<param-count>} "l")
+//! <requirement> = <type> <protocol> "R" ...
+//! ```
+//!
+//! source [Mangling.rst](https://github.com/apple/swift/blob/main/docs/ABI/Mangling.rst)
+mod tests;
+
+use crate::TokenStream;
+use config::CONFIG;
+use tokenizing::{colors, Color32};
+
+/// Max recursion depth.
+const MAX_DEPTH: usize = 256;
+
+/// Max number of words that can be referred to by word substitutions.
+const MAX_WORDS: usize = 26;
+
+/// Try to parse a swift symbol.
+pub fn parse(s: &str) -> Option<TokenStream> {
+    // macOS prefixes symbols with an extra underscore therefore '_$s' is allowed
+    let unprefixed = s.strip_prefix('_').unwrap_or(s);
+    let (src, old) = match unprefixed.get(..2) {
+        Some("$s" | "$S" | "$e") => (&unprefixed[2..], false),
+        _ => match s.strip_prefix("_T0").or(s.strip_prefix("__T0")) {
+            Some(src) => (src, true),
+            None => return None,
+        },
+    };
+
+    // identifiers encoded as punycode aren't supported, so symbols have to be ascii
+    if !src.bytes().all(|c| c.is_ascii()) {
+        return None;
+    }
+
+    let mut parser = Parser::new(src, old);
+    let (attributes, root) = parser.symbol()?;
+
+    let mut printer = Printer {
+        stream: TokenStream::new(s),
+        nodes: &parser.nodes,
+        depth: 0,
+    };
+
+    for attribute in attributes {
+        printer.print(attribute)?;
+    }
+    printer.print(root)?;
+
+    Some(printer.stream)
+}
+
+type NodeId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Identifier,
+    Module,
+    PrivateDeclName,
+    LocalDeclName,
+    InfixOperator,
+    PrefixOperator,
+    PostfixOperator,
+
+    Type,
+    Class,
+    Structure,
+    Enum,
+    Protocol,
+    TypeAlias,
+    Extension,
+    BoundGeneric,
+    TypeList,
+
+    Function,
+    Variable,
+    Subscript,
+    Static,
+    Accessor(&'static str),
+    Allocator,
+    Constructor,
+    Destructor,
+    Deallocator,
+    IVarInitializer,
+    IVarDestroyer,
+    Initializer,
+    ExplicitClosure,
+    ImplicitClosure,
+    DefaultArgumentInitializer,
+
+    FunctionType(&'static str),
+    ArgumentTuple,
+    ReturnType,
+    Tuple,
+    TupleElement,
+    Metatype,
+    ExistentialMetatype,
+    InOut,
+    Owned,
+    Shared,
+    ProtocolList,
+    ProtocolListWithAnyObject,
+    GenericParam,
+    DependentMemberType,
+    Builtin,
+    DependentGenericType,
+    GenericSignature,
+    ParamCount,
+    ConformanceRequirement,
+    SameTypeRequirement,
+    ThrowsAnnotation,
+    AsyncAnnotation,
+    SendableAnnotation,
+
+    EmptyList,
+    FirstElementMarker,
+    VariadicMarker,
+    LabelList,
+    Index,
+
+    TypeMangling,
+    Described(&'static str),
+    ProtocolConformance,
+    ProtocolWitness,
+    LazyWitnessTable(&'static str),
+    Attribute(&'static str),
+}
+
+impl Kind {
+    fn is_decl_name(self) -> bool {
+        matches!(
+            self,
+            Kind::Identifier
+                | Kind::PrivateDeclName
+                | Kind::LocalDeclName
+                | Kind::InfixOperator
+                | Kind::PrefixOperator
+                | Kind::PostfixOperator
+        )
+    }
+
+    fn is_nominal(self) -> bool {
+        matches!(
+            self,
+            Kind::Class | Kind::Structure | Kind::Enum | Kind::Protocol | Kind::TypeAlias
+        )
+    }
+
+    fn is_entity(self) -> bool {
+        matches!(
+            self,
+            Kind::Function
+                | Kind::Variable
+                | Kind::Subscript
+                | Kind::Static
+                | Kind::Accessor(_)
+                | Kind::Allocator
+                | Kind::Constructor
+                | Kind::Destructor
+                | Kind::Deallocator
+                | Kind::IVarInitializer
+                | Kind::IVarDestroyer
+                | Kind::Initializer
+                | Kind::ExplicitClosure
+                | Kind::ImplicitClosure
+                | Kind::DefaultArgumentInitializer
+        )
+    }
+
+    fn is_context(self) -> bool {
+        self == Kind::Module || self == Kind::Extension || self.is_nominal() || self.is_entity()
+    }
+
+    fn is_requirement(self) -> bool {
+        matches!(
+            self,
+            Kind::ConformanceRequirement | Kind::SameTypeRequirement
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    kind: Kind,
+    text: String,
+    index: u64,
+    depth: u64,
+    children: Vec<NodeId>,
+}
+
+/// Arguments that function entities take from the stack.
+enum EntityArgs {
+    None,
+    Index,
+    TypeAndIndex,
+    TypeAndMaybePrivateName,
+}
+
+/// State required to turn a symbol into a tree of nodes.
+struct Parser<'src> {
+    src: &'src str,
+    offset: usize,
+    old: bool,
+    nodes: Vec<Node>,
+    stack: Vec<NodeId>,
+    substitutions: Vec<NodeId>,
+    words: Vec<&'src str>,
+}
+
+impl<'src> Parser<'src> {
+    fn new(src: &'src str, old: bool) -> Self {
+        Self {
+            src,
+            offset: 0,
+            old,
+            nodes: Vec::new(),
+            stack: Vec::new(),
+            substitutions: Vec::new(),
+            words: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.offset).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.offset += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.offset += 1;
+            return true;
+        }
+
+        false
+    }
+
+    /// Parses a decimal number.
+    fn natural(&mut self) -> Option<u64> {
+        let start = self.offset;
+
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.offset += 1;
+        }
+
+        self.src[start..self.offset].parse().ok()
+    }
+
+    /// Parses an index which is either '_' for zero or a number followed by '_' for n + 1.
+    fn index(&mut self) -> Option<u64> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+
+        let n = self.natural()?;
+        if !self.eat(b'_') {
+            return None;
+        }
+
+        n.checked_add(1)
+    }
+
+    fn create(&mut self, kind: Kind, children: Vec<NodeId>) -> NodeId {
+        self.nodes.push(Node {
+            kind,
+            text: String::new(),
+            index: 0,
+            depth: 0,
+            children,
+        });
+
+        self.nodes.len() - 1
+    }
+
+    fn create_text(&mut self, kind: Kind, text: String) -> NodeId {
+        let node = self.create(kind, Vec::new());
+        self.nodes[node].text = text;
+        node
+    }
+
+    fn create_index(&mut self, kind: Kind, index: u64) -> NodeId {
+        let node = self.create(kind, Vec::new());
+        self.nodes[node].index = index;
+        node
+    }
+
+    fn create_type(&mut self, child: NodeId) -> NodeId {
+        self.create(Kind::Type, vec![child])
+    }
+
+    fn kind(&self, node: NodeId) -> Kind {
+        self.nodes[node].kind
+    }
+
+    fn pop(&mut self, pred: impl Fn(Kind) -> bool) -> Option<NodeId> {
+        let top = *self.stack.last()?;
+        if pred(self.kind(top)) {
+            return self.stack.pop();
+        }
+
+        None
+    }
+
+    fn pop_kind(&mut self, kind: Kind) -> Option<NodeId> {
+        self.pop(|k| k == kind)
+    }
+
+    /// Parses the whole symbol, returning the attributes and the entity they apply to.
+    fn symbol(&mut self) -> Option<(Vec<NodeId>, NodeId)> {
+        while self.offset < self.src.len() {
+            let node = self.operator()?;
+            self.stack.push(node);
+
+            // nodes are only ever created by operators, which bounds the size of the input
+            if self.nodes.len() > 4096 {
+                return None;
+            }
+        }
+
+        let mut attributes = Vec::new();
+        while let Some(attribute) = self.pop(|k| matches!(k, Kind::Attribute(_))) {
+            attributes.push(attribute);
+        }
+
+        // anything left over means the symbol wasn't understood
+        if self.stack.len() != 1 {
+            return None;
+        }
+
+        let root = self.stack.pop()?;
+        match self.kind(root) {
+            Kind::TypeMangling
+            | Kind::Described(_)
+            | Kind::ProtocolWitness
+            | Kind::LazyWitnessTable(_) => Some((attributes, root)),
+            kind if kind.is_entity() => Some((attributes, root)),
+            _ => None,
+        }
+    }
+
+    fn operator(&mut self) -> Option<NodeId> {
+        match self.next()? {
+            b'0'..=b'9' => {
+                self.offset -= 1;
+                self.identifier()
+            }
+            b'A' => self.multi_substitution(),
+            b'B' => self.builtin_type(),
+            b'C' => self.nominal_type(Kind::Class),
+            b'D' => {
+                let ty = self.pop_kind(Kind::Type)?;
+                Some(self.create(Kind::TypeMangling, vec![ty]))
+            }
+            b'E' => self.extension(),
+            b'F' => self.plain_function(),
+            b'G' => self.bound_generic_type(),
+            b'K' => Some(self.create(Kind::ThrowsAnnotation, Vec::new())),
+            b'L' => self.local_identifier(),
+            b'M' => self.metadata(),
+            b'N' => {
+                let ty = self.pop_kind(Kind::Type)?;
+                Some(self.create(Kind::Described("type metadata for "), vec![ty]))
+            }
+            b'O' => self.nominal_type(Kind::Enum),
+            b'P' => self.nominal_type(Kind::Protocol),
+            b'Q' => self.associated_type(),
+            b'R' => self.generic_requirement(),
+            b'S' => self.standard_substitution(),
+            b'T' => self.thunk(),
+            b'V' => self.nominal_type(Kind::Structure),
+            b'W' => self.witness(),
+            b'X' => self.special_type(),
+            b'Y' => match self.next()? {
+                b'a' => Some(self.create(Kind::AsyncAnnotation, Vec::new())),
+                b'b' => Some(self.create(Kind::SendableAnnotation, Vec::new())),
+                _ => None,
+            },
+            b'Z' => {
+                let entity = self.pop(Kind::is_entity)?;
+                Some(self.create(Kind::Static, vec![entity]))
+            }
+            b'_' => Some(self.create(Kind::FirstElementMarker, Vec::new())),
+            b'a' => self.nominal_type(Kind::TypeAlias),
+            b'c' => self.pop_function_type(Kind::FunctionType("")),
+            b'd' => Some(self.create(Kind::VariadicMarker, Vec::new())),
+            b'f' => self.function_entity(),
+            b'h' => self.wrap_type(Kind::Shared),
+            b'i' => self.subscript(),
+            b'l' => self.generic_signature(false),
+            b'm' => self.wrap_type(Kind::Metatype),
+            b'n' => self.wrap_type(Kind::Owned),
+            b'o' => self.operator_identifier(),
+            b'p' => {
+                let list = self.protocol_list()?;
+                Some(self.create_type(list))
+            }
+            b'q' => {
+                let param = self.generic_param_index()?;
+                Some(self.create_type(param))
+            }
+            b'r' => self.generic_signature(true),
+            b's' => Some(self.create_text(Kind::Module, "Swift".to_string())),
+            b't' => self.tuple(),
+            b'v' => {
+                let variable = self.entity(Kind::Variable)?;
+                self.accessor(variable)
+            }
+            b'x' => {
+                let param = self.generic_param(0, 0);
+                Some(self.create_type(param))
+            }
+            b'y' => Some(self.create(Kind::EmptyList, Vec::new())),
+            b'z' => self.wrap_type(Kind::InOut),
+            _ => None,
+        }
+    }
+
+    /// Parses an identifier, which might be composed of words from earlier identifiers.
+    fn identifier(&mut self) -> Option<NodeId> {
+        let mut has_word_substitutions = false;
+        if self.eat(b'0') {
+            // punycode
+            if self.eat(b'0') {
+                return None;
+            }
+
+            has_word_substitutions = true;
+        }
+
+        let mut identifier = String::new();
+        loop {
+            while has_word_substitutions {
+                let idx = match self.peek()? {
+                    c @ b'a'..=b'z' => (c - b'a') as usize,
+                    c @ b'A'..=b'Z' => {
+                        has_word_substitutions = false;
+                        (c - b'A') as usize
+                    }
+                    _ => break,
+                };
+
+                self.offset += 1;
+                identifier += self.words.get(idx)?;
+            }
+
+            if self.eat(b'0') {
+                break;
+            }
+
+            let len = self.natural()? as usize;
+            if len == 0 {
+                return None;
+            }
+
+            let start = self.offset;
+            let chunk = self.src.get(start..start.checked_add(len)?)?;
+            self.offset += len;
+            self.collect_words(start, chunk);
+            identifier += chunk;
+
+            if !has_word_substitutions {
+                break;
+            }
+        }
+
+        if identifier.is_empty() {
+            return None;
+        }
+
+        let node = self.create_text(Kind::Identifier, identifier);
+        self.substitutions.push(node);
+        Some(node)
+    }
+
+    /// Remember the words of an identifier so that later identifiers can refer to them.
+    fn collect_words(&mut self, start: usize, chunk: &str) {
+        let bytes = chunk.as_bytes();
+        let is_word_start = |c: u8| !c.is_ascii_digit() && c != b'_';
+        let is_word_end = |c: u8, prev: u8| {
+            c == b'_' || c.is_ascii_digit() || (prev.is_ascii_lowercase() && c.is_ascii_uppercase())
+        };
+
+        let mut word_start = None;
+        for idx in 0..=bytes.len() {
+            let c = bytes.get(idx).copied().unwrap_or(0);
+
+            if let Some(first) = word_start {
+                if idx == bytes.len() || is_word_end(c, bytes[idx - 1]) {
+                    if idx - first >= 2 && self.words.len() < MAX_WORDS {
+                        self.words.push(&self.src[start + first..start + idx]);
+                    }
+                    word_start = None;
+                }
+            }
+
+            if word_start.is_none() && idx < bytes.len() && is_word_start(c) {
+                word_start = Some(idx);
+            }
+        }
+    }
+
+    /// Parses substitutions of the form 'A' {[count] <a-z>} [count] <A-Z> or 'A' <index>.
+    fn multi_substitution(&mut self) -> Option<NodeId> {
+        let mut count = 1;
+
+        loop {
+            let c = self.next()?;
+            match c {
+                b'a'..=b'z' => {
+                    let node = *self.substitutions.get((c - b'a') as usize)?;
+                    for _ in 0..count {
+                        self.stack.push(node);
+                    }
+                    count = 1;
+                }
+                b'A'..=b'Z' => {
+                    let node = *self.substitutions.get((c - b'A') as usize)?;
+                    for _ in 1..count {
+                        self.stack.push(node);
+                    }
+                    return Some(node);
+                }
+                b'_' => {
+                    return self.substitutions.get(27).copied();
+                }
+                _ => {
+                    self.offset -= 1;
+                    count = self.natural()?;
+
+                    // an index past the 26 that fit in a letter
+                    if self.eat(b'_') {
+                        let idx = usize::try_from(count).ok()?.checked_add(27)?;
+                        return self.substitutions.get(idx).copied();
+                    }
+
+                    // don't let a repeat count blow up the stack
+                    if count > 256 {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parses a type or module from the standard library.
+    fn standard_substitution(&mut self) -> Option<NodeId> {
+        if self.eat(b'o') {
+            return Some(self.create_text(Kind::Module, "__C".to_string()));
+        }
+
+        if self.eat(b'C') {
+            return Some(self.create_text(Kind::Module, "__C_Synthesized".to_string()));
+        }
+
+        if self.eat(b'g') {
+            let ty = self.pop_kind(Kind::Type)?;
+            let optional = self.standard_type(Kind::Enum, "Optional");
+            let args = self.create(Kind::TypeList, vec![ty]);
+            let bound = self.create(Kind::BoundGeneric, vec![optional, args]);
+            let bound = self.create_type(bound);
+            self.substitutions.push(bound);
+            return Some(bound);
+        }
+
+        let count = match self.peek()? {
+            b'0'..=b'9' => self.natural()?,
+            _ => 1,
+        };
+
+        if count > 256 {
+            return None;
+        }
+
+        let (kind, name) = match self.next()? {
+            b'A' => (Kind::Structure, "AutoreleasingUnsafeMutablePointer"),
+            b'a' => (Kind::Structure, "Array"),
+            b'b' => (Kind::Structure, "Bool"),
+            b'D' => (Kind::Structure, "Dictionary"),
+            b'd' => (Kind::Structure, "Double"),
+            b'f' => (Kind::Structure, "Float"),
+            b'h' => (Kind::Structure, "Set"),
+            b'I' => (Kind::Structure, "DefaultIndices"),
+            b'i' => (Kind::Structure, "Int"),
+            b'J' => (Kind::Structure, "Character"),
+            b'N' => (Kind::Structure, "ClosedRange"),
+            b'n' => (Kind::Structure, "Range"),
+            b'O' => (Kind::Structure, "ObjectIdentifier"),
+            b'P' => (Kind::Structure, "UnsafePointer"),
+            b'p' => (Kind::Structure, "UnsafeMutablePointer"),
+            b'R' => (Kind::Structure, "UnsafeBufferPointer"),
+            b'r' => (Kind::Structure, "UnsafeMutableBufferPointer"),
+            b'S' => (Kind::Structure, "String"),
+            b's' => (Kind::Structure, "Substring"),
+            b'u' => (Kind::Structure, "UInt"),
+            b'V' => (Kind::Structure, "UnsafeRawPointer"),
+            b'v' => (Kind::Structure, "UnsafeMutableRawPointer"),
+            b'W' => (Kind::Structure, "UnsafeRawBufferPointer"),
+            b'w' => (Kind::Structure, "UnsafeMutableRawBufferPointer"),
+            b'q' => (Kind::Enum, "Optional"),
+            b'B' => (Kind::Protocol, "BinaryFloatingPoint"),
+            b'E' => (Kind::Protocol, "Encodable"),
+            b'e' => (Kind::Protocol, "Decodable"),
+            b'F' => (Kind::Protocol, "FloatingPoint"),
+            b'G' => (Kind::Protocol, "RandomNumberGenerator"),
+            b'H' => (Kind::Protocol, "Hashable"),
+            b'j' => (Kind::Protocol, "Numeric"),
+            b'K' => (Kind::Protocol, "BidirectionalCollection"),
+            b'k' => (Kind::Protocol, "RandomAccessCollection"),
+            b'L' => (Kind::Protocol, "Comparable"),
+            b'l' => (Kind::Protocol, "Collection"),
+            b'M' => (Kind::Protocol, "MutableCollection"),
+            b'm' => (Kind::Protocol, "RangeReplaceableCollection"),
+            b'Q' => (Kind::Protocol, "Equatable"),
+            b'T' => (Kind::Protocol, "Sequence"),
+            b't' => (Kind::Protocol, "IteratorProtocol"),
+            b'U' => (Kind::Protocol, "UnsignedInteger"),
+            b'X' => (Kind::Protocol, "RangeExpression"),
+            b'x' => (Kind::Protocol, "Strideable"),
+            b'Y' => (Kind::Protocol, "RawRepresentable"),
+            b'y' => (Kind::Protocol, "StringProtocol"),
+            b'Z' => (Kind::Protocol, "SignedInteger"),
+            b'z' => (Kind::Protocol, "BinaryInteger"),
+            b'c' => match self.next()? {
+                b'A' => (Kind::Protocol, "Actor"),
+                b'C' => (Kind::Structure, "CheckedContinuation"),
+                b'c' => (Kind::Structure, "UnsafeContinuation"),
+                b'E' => (Kind::Structure, "CancellationError"),
+                b'e' => (Kind::Structure, "UnownedSerialExecutor"),
+                b'F' => (Kind::Protocol, "Executor"),
+                b'f' => (Kind::Protocol, "SerialExecutor"),
+                b'G' => (Kind::Structure, "TaskGroup"),
+                b'g' => (Kind::Structure, "ThrowingTaskGroup"),
+                b'I' => (Kind::Protocol, "AsyncIteratorProtocol"),
+                b'i' => (Kind::Protocol, "AsyncSequence"),
+                b'J' => (Kind::Structure, "UnownedJob"),
+                b'M' => (Kind::Class, "MainActor"),
+                b'P' => (Kind::Structure, "TaskPriority"),
+                b'S' => (Kind::Structure, "AsyncStream"),
+                b's' => (Kind::Structure, "AsyncThrowingStream"),
+                b'T' => (Kind::Structure, "Task"),
+                b't' => (Kind::Structure, "UnsafeCurrentTask"),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        let node = self.standard_type(kind, name);
+        for _ in 1..count {
+            self.stack.push(node);
+        }
+
+        Some(node)
+    }
+
+    /// Creates a nominal type that's part of the standard library.
+    fn standard_type(&mut self, kind: Kind, name: &str) -> NodeId {
+        let module = self.create_text(Kind::Module, "Swift".to_string());
+        let name = self.create_text(Kind::Identifier, name.to_string());
+        let nominal = self.create(kind, vec![module, name]);
+        self.create_type(nominal)
+    }
+
+    fn builtin_type(&mut self) -> Option<NodeId> {
+        let name = match self.next()? {
+            b'b' => "Builtin.BridgeObject".to_string(),
+            b'B' => "Builtin.UnsafeValueBuffer".to_string(),
+            b'c' => "Builtin.RawUnsafeContinuation".to_string(),
+            b'D' => "Builtin.DefaultActorStorage".to_string(),
+            b'e' => "Builtin.Executor".to_string(),
+            b'f' => format!("Builtin.FPIEEE{}", self.index()?.checked_sub(1)?),
+            b'i' => format!("Builtin.Int{}", self.index()?.checked_sub(1)?),
+            b'I' => "Builtin.IntLiteral".to_string(),
+            b'j' => "Builtin.Job".to_string(),
+            b'O' => "Builtin.UnknownObject".to_string(),
+            b'o' => "Builtin.NativeObject".to_string(),
+            b'p' => "Builtin.RawPointer".to_string(),
+            b't' => "Builtin.SILToken".to_string(),
+            b'w' => "Builtin.Word".to_string(),
+            _ => return None,
+        };
+
+        let builtin = self.create_text(Kind::Builtin, name);
+        Some(self.create_type(builtin))
+    }
+
+    fn wrap_type(&mut self, kind: Kind) -> Option<NodeId> {
+        let ty = self.pop_kind(Kind::Type)?;
+        let node = self.create(kind, vec![ty]);
+        Some(self.create_type(node))
+    }
+
+    fn pop_module(&mut self) -> Option<NodeId> {
+        if let Some(ident) = self.pop_kind(Kind::Identifier) {
+            let text = self.nodes[ident].text.clone();
+            return Some(self.create_text(Kind::Module, text));
+        }
+
+        self.pop_kind(Kind::Module)
+    }
+
+    fn pop_context(&mut self) -> Option<NodeId> {
+        if let Some(module) = self.pop_module() {
+            return Some(module);
+        }
+
+        if let Some(ty) = self.pop_kind(Kind::Type) {
+            let child = *self.nodes[ty].children.first()?;
+            return match self.kind(child).is_context() {
+                true => Some(child),
+                false => None,
+            };
+        }
+
+        self.pop(Kind::is_context)
+    }
+
+    /// Pops a type, returning the nominal type it wraps.
+    fn pop_nominal(&mut self) -> Option<NodeId> {
+        let ty = self.pop_kind(Kind::Type)?;
+        let child = *self.nodes[ty].children.first()?;
+        match self.kind(child).is_nominal() {
+            true => Some(child),
+            false => None,
+        }
+    }
+
+    fn pop_protocol(&mut self) -> Option<NodeId> {
+        if let Some(ty) = self.pop_kind(Kind::Type) {
+            let child = *self.nodes[ty].children.first()?;
+            return match self.kind(child) {
+                Kind::Protocol => Some(ty),
+                _ => None,
+            };
+        }
+
+        // protocols in conformances and existentials are mangled without the 'P' operator
+        let name = self.pop(Kind::is_decl_name)?;
+        let ctx = self.pop_context()?;
+        let protocol = self.create(Kind::Protocol, vec![ctx, name]);
+        Some(self.create_type(protocol))
+    }
+
+    fn pop_conformance(&mut self) -> Option<NodeId> {
+        let signature = self.pop_kind(Kind::GenericSignature);
+        let module = self.pop_module()?;
+        let protocol = self.pop_protocol()?;
+        let mut ty = self.pop_kind(Kind::Type)?;
+
+        if let Some(signature) = signature {
+            let generic = self.create(Kind::DependentGenericType, vec![signature, ty]);
+            ty = self.create_type(generic);
+        }
+
+        Some(self.create(Kind::ProtocolConformance, vec![ty, protocol, module]))
+    }
+
+    fn nominal_type(&mut self, kind: Kind) -> Option<NodeId> {
+        let name = self.pop(Kind::is_decl_name)?;
+        let ctx = self.pop_context()?;
+        let nominal = self.create(kind, vec![ctx, name]);
+        let ty = self.create_type(nominal);
+        self.substitutions.push(ty);
+        Some(ty)
+    }
+
+    fn extension(&mut self) -> Option<NodeId> {
+        let signature = self.pop_kind(Kind::GenericSignature);
+        let module = self.pop_module()?;
+        let ty = self.pop_kind(Kind::Type)?;
+
+        let mut children = vec![module, ty];
+        children.extend(signature);
+        Some(self.create(Kind::Extension, children))
+    }
+
+    fn bound_generic_type(&mut self) -> Option<NodeId> {
+        let mut lists = Vec::new();
+
+        loop {
+            let mut args = Vec::new();
+            while let Some(ty) = self.pop_kind(Kind::Type) {
+                args.push(ty);
+            }
+            args.reverse();
+            lists.push(self.create(Kind::TypeList, args));
+
+            if self.pop_kind(Kind::EmptyList).is_some() {
+                break;
+            }
+
+            self.pop_kind(Kind::FirstElementMarker)?;
+        }
+
+        let nominal = self.pop_nominal()?;
+        let bound = self.bind_generic_args(nominal, &lists, 0)?;
+        let ty = self.create_type(bound);
+        self.substitutions.push(ty);
+        Some(ty)
+    }
+
+    /// Applies the innermost list of generic arguments to a type and the rest to it's parents.
+    fn bind_generic_args(
+        &mut self,
+        nominal: NodeId,
+        lists: &[NodeId],
+        idx: usize,
+    ) -> Option<NodeId> {
+        let args = *lists.get(idx)?;
+        let mut nominal = nominal;
+
+        if idx + 1 < lists.len() {
+            let ctx = *self.nodes[nominal].children.first()?;
+            let bound_ctx = match self.kind(ctx) {
+                Kind::Extension => {
+                    let extended = *self.nodes[ctx].children.get(1)?;
+                    let extended = *self.nodes[extended].children.first()?;
+                    let bound = self.bind_generic_args(extended, lists, idx + 1)?;
+                    let bound = self.create_type(bound);
+                    let mut children = self.nodes[ctx].children.clone();
+                    children[1] = bound;
+                    self.create(Kind::Extension, children)
+                }
+                kind if kind.is_nominal() => self.bind_generic_args(ctx, lists, idx + 1)?,
+                _ => return None,
+            };
+
+            let mut children = self.nodes[nominal].children.clone();
+            children[0] = bound_ctx;
+            nominal = self.create(self.kind(nominal), children);
+        }
+
+        // parents that aren't generic have an empty argument list
+        if self.nodes[args].children.is_empty() {
+            return Some(nominal);
+        }
+
+        let ty = self.create_type(nominal);
+        Some(self.create(Kind::BoundGeneric, vec![ty, args]))
+    }
+
+    fn tuple(&mut self) -> Option<NodeId> {
+        let mut elements = Vec::new();
+
+        if self.pop_kind(Kind::EmptyList).is_none() {
+            loop {
+                let first = self.pop_kind(Kind::FirstElementMarker).is_some();
+                let variadic = self.pop_kind(Kind::VariadicMarker);
+                let name = self.pop_kind(Kind::Identifier);
+                let ty = self.pop_kind(Kind::Type)?;
+
+                let mut children = Vec::new();
+                children.extend(variadic);
+                children.extend(name);
+                children.push(ty);
+                elements.push(self.create(Kind::TupleElement, children));
+
+                if first {
+                    break;
+                }
+            }
+            elements.reverse();
+        }
+
+        let tuple = self.create(Kind::Tuple, elements);
+        Some(self.create_type(tuple))
+    }
+
+    fn protocol_list(&mut self) -> Option<NodeId> {
+        let mut protocols = Vec::new();
+
+        if self.pop_kind(Kind::EmptyList).is_none() {
+            loop {
+                let first = self.pop_kind(Kind::FirstElementMarker).is_some();
+                protocols.push(self.pop_protocol()?);
+
+                if first {
+                    break;
+                }
+            }
+            protocols.reverse();
+        }
+
+        let list = self.create(Kind::TypeList, protocols);
+        Some(self.create(Kind::ProtocolList, vec![list]))
+    }
+
+    fn special_type(&mut self) -> Option<NodeId> {
+        match self.next()? {
+            b'E' => self.pop_function_type(Kind::FunctionType("")),
+            b'B' => self.pop_function_type(Kind::FunctionType("@convention(block) ")),
+            b'C' => self.pop_function_type(Kind::FunctionType("@convention(c) ")),
+            b'f' => self.pop_function_type(Kind::FunctionType("@convention(thin) ")),
+            b'l' => {
+                let list = self.protocol_list()?;
+                let list = self.create(Kind::ProtocolListWithAnyObject, vec![list]);
+                Some(self.create_type(list))
+            }
+            b'p' => self.wrap_type(Kind::ExistentialMetatype),
+            _ => None,
+        }
+    }
+
+    fn pop_function_params(&mut self, kind: Kind) -> Option<NodeId> {
+        let ty = match self.pop_kind(Kind::EmptyList) {
+            Some(_) => {
+                let tuple = self.create(Kind::Tuple, Vec::new());
+                self.create_type(tuple)
+            }
+            None => self.pop_kind(Kind::Type)?,
+        };
+
+        Some(self.create(kind, vec![ty]))
+    }
+
+    fn pop_function_type(&mut self, kind: Kind) -> Option<NodeId> {
+        let mut children = Vec::new();
+        children.extend(self.pop_kind(Kind::ThrowsAnnotation));
+        children.extend(self.pop_kind(Kind::SendableAnnotation));
+        children.extend(self.pop_kind(Kind::AsyncAnnotation));
+        children.push(self.pop_function_params(Kind::ArgumentTuple)?);
+        children.push(self.pop_function_params(Kind::ReturnType)?);
+
+        let function = self.create(kind, children);
+        Some(self.create_type(function))
+    }
+
+    /// Number of parameters of a function type, used to pop it's argument labels.
+    fn param_count(&self, ty: NodeId) -> Option<usize> {
+        let mut node = *self.nodes[ty].children.first()?;
+
+        if self.kind(node) == Kind::DependentGenericType {
+            node = *self.nodes[node].children.get(1)?;
+            node = *self.nodes[node].children.first()?;
+        }
+
+        if !matches!(self.kind(node), Kind::FunctionType(_)) {
+            return None;
+        }
+
+        let args = *self.nodes[node]
+            .children
+            .iter()
+            .find(|&&c| self.kind(c) == Kind::ArgumentTuple)?;
+        let args = *self.nodes[args].children.first()?;
+        let args = *self.nodes[args].children.first()?;
+
+        match self.kind(args) {
+            Kind::Tuple => Some(self.nodes[args].children.len()),
+            _ => Some(1),
+        }
+    }
+
+    fn pop_function_param_labels(&mut self, ty: NodeId) -> Option<NodeId> {
+        // labels are part of the argument tuple in the old mangling
+        if self.old {
+            return None;
+        }
+
+        if self.pop_kind(Kind::EmptyList).is_some() {
+            return Some(self.create(Kind::LabelList, Vec::new()));
+        }
+
+        let count = self.param_count(ty)?;
+        if count == 0 {
+            return None;
+        }
+
+        let mut labels = Vec::new();
+        for _ in 0..count {
+            labels.push(self.pop(|k| k == Kind::Identifier || k == Kind::FirstElementMarker)?);
+        }
+        labels.reverse();
+
+        Some(self.create(Kind::LabelList, labels))
+    }
+
+    fn plain_function(&mut self) -> Option<NodeId> {
+        let signature = self.pop_kind(Kind::GenericSignature);
+        let mut ty = self.pop_function_type(Kind::FunctionType(""))?;
+        let labels = self.pop_function_param_labels(ty);
+
+        if let Some(signature) = signature {
+            let generic = self.create(Kind::DependentGenericType, vec![signature, ty]);
+            ty = self.create_type(generic);
+        }
+
+        let name = self.pop(Kind::is_decl_name)?;
+        let ctx = self.pop_context()?;
+
+        let mut children = vec![ctx, name];
+        children.extend(labels);
+        children.push(ty);
+        Some(self.create(Kind::Function, children))
+    }
+
+    fn entity(&mut self, kind: Kind) -> Option<NodeId> {
+        let ty = self.pop_kind(Kind::Type)?;
+        let labels = self.pop_function_param_labels(ty);
+        let name = self.pop(Kind::is_decl_name)?;
+        let ctx = self.pop_context()?;
+
+        let mut children = vec![ctx, name];
+        children.extend(labels);
+        children.push(ty);
+        Some(self.create(kind, children))
+    }
+
+    fn subscript(&mut self) -> Option<NodeId> {
+        self.pop_kind(Kind::PrivateDeclName);
+        let ty = self.pop_kind(Kind::Type)?;
+        let labels = self.pop_function_param_labels(ty);
+        let ctx = self.pop_context()?;
+
+        let mut children = vec![ctx];
+        children.extend(labels);
+        children.push(ty);
+        let subscript = self.create(Kind::Subscript, children);
+        self.accessor(subscript)
+    }
+
+    fn accessor(&mut self, entity: NodeId) -> Option<NodeId> {
+        let suffix = match self.next()? {
+            b'p' => return Some(entity),
+            b'g' => ".getter",
+            b'G' => ".getter",
+            b's' => ".setter",
+            b'm' => ".materializeForSet",
+            b'M' => ".modify",
+            b'r' => ".read",
+            b'w' => ".willset",
+            b'W' => ".didset",
+            b'i' => ".init",
+            b'a' => match self.next()? {
+                b'u' => ".unsafeMutableAddressor",
+                _ => return None,
+            },
+            b'l' => match self.next()? {
+                b'u' => ".unsafeAddressor",
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Some(self.create(Kind::Accessor(suffix), vec![entity]))
+    }
+
+    fn function_entity(&mut self) -> Option<NodeId> {
+        let (kind, args) = match self.next()? {
+            b'D' => (Kind::Deallocator, EntityArgs::None),
+            b'd' => (Kind::Destructor, EntityArgs::None),
+            b'E' => (Kind::IVarDestroyer, EntityArgs::None),
+            b'e' => (Kind::IVarInitializer, EntityArgs::None),
+            b'i' => (Kind::Initializer, EntityArgs::None),
+            b'C' => (Kind::Allocator, EntityArgs::TypeAndMaybePrivateName),
+            b'c' => (Kind::Constructor, EntityArgs::TypeAndMaybePrivateName),
+            b'U' => (Kind::ExplicitClosure, EntityArgs::TypeAndIndex),
+            b'u' => (Kind::ImplicitClosure, EntityArgs::TypeAndIndex),
+            b'A' => (Kind::DefaultArgumentInitializer, EntityArgs::Index),
+            _ => return None,
+        };
+
+        let mut children = Vec::new();
+        match args {
+            EntityArgs::None => {}
+            EntityArgs::Index => {
+                let index = self.index()?;
+                children.push(self.create_index(Kind::Index, index));
+            }
+            EntityArgs::TypeAndIndex => {
+                let index = self.index()?;
+                children.push(self.create_index(Kind::Index, index));
+                children.push(self.pop_kind(Kind::Type)?);
+            }
+            EntityArgs::TypeAndMaybePrivateName => {
+                self.pop_kind(Kind::PrivateDeclName);
+                let ty = self.pop_kind(Kind::Type)?;
+                children.extend(self.pop_function_param_labels(ty));
+                children.push(ty);
+            }
+        }
+
+        children.insert(0, self.pop_context()?);
+        Some(self.create(kind, children))
+    }
+
+    fn local_identifier(&mut self) -> Option<NodeId> {
+        if self.eat(b'L') {
+            let discriminator = self.pop_kind(Kind::Identifier)?;
+            let name = self.pop(Kind::is_decl_name)?;
+            return Some(self.create(Kind::PrivateDeclName, vec![discriminator, name]));
+        }
+
+        if self.eat(b'l') {
+            let discriminator = self.pop_kind(Kind::Identifier)?;
+            return Some(self.create(Kind::PrivateDeclName, vec![discriminator]));
+        }
+
+        let index = self.index()?;
+        let index = self.create_index(Kind::Index, index);
+        let name = self.pop(Kind::is_decl_name)?;
+        Some(self.create(Kind::LocalDeclName, vec![index, name]))
+    }
+
+    fn operator_identifier(&mut self) -> Option<NodeId> {
+        const OPERATORS: &[u8; 26] = b"& @/= >    <*!|+?%-~   ^ .";
+
+        let ident = self.pop_kind(Kind::Identifier)?;
+        let mut text = String::new();
+        for c in self.nodes[ident].text.bytes() {
+            if !c.is_ascii_lowercase() || OPERATORS[(c - b'a') as usize] == b' ' {
+                return None;
+            }
+
+            text.push(OPERATORS[(c - b'a') as usize] as char);
+        }
+
+        let kind = match self.next()? {
+            b'i' => Kind::InfixOperator,
+            b'p' => Kind::PrefixOperator,
+            b'P' => Kind::PostfixOperator,
+            _ => return None,
+        };
+
+        Some(self.create_text(kind, text))
+    }
+
+    fn generic_param(&mut self, depth: u64, index: u64) -> NodeId {
+        let param = self.create_index(Kind::GenericParam, index);
+        self.nodes[param].depth = depth;
+        param
+    }
+
+    fn generic_param_index(&mut self) -> Option<NodeId> {
+        if self.eat(b'd') {
+            let depth = self.index()?.checked_add(1)?;
+            let index = self.index()?;
+            return Some(self.generic_param(depth, index));
+        }
+
+        if self.eat(b'z') {
+            return Some(self.generic_param(0, 0));
+        }
+
+        let index = self.index()?.checked_add(1)?;
+        Some(self.generic_param(0, index))
+    }
+
+    /// Parses an associated type of a generic parameter, such as `A.Element`.
+    fn associated_type(&mut self) -> Option<NodeId> {
+        let base = match self.next()? {
+            b'z' => self.generic_param(0, 0),
+            b'y' => self.generic_param_index()?,
+            _ => return None,
+        };
+
+        let base = self.create_type(base);
+        let name = self.pop_kind(Kind::Identifier)?;
+        let member = self.create(Kind::DependentMemberType, vec![base, name]);
+        let ty = self.create_type(member);
+        self.substitutions.push(ty);
+        Some(ty)
+    }
+
+    fn generic_signature(&mut self, has_param_counts: bool) -> Option<NodeId> {
+        let mut children = Vec::new();
+
+        if has_param_counts {
+            while !self.eat(b'l') {
+                let count = match self.eat(b'z') {
+                    true => 0,
+                    false => self.index()?.checked_add(1)?,
+                };
+                children.push(self.create_index(Kind::ParamCount, count));
+            }
+        } else {
+            children.push(self.create_index(Kind::ParamCount, 1));
+        }
+
+        let mut requirements = Vec::new();
+        while let Some(requirement) = self.pop(Kind::is_requirement) {
+            requirements.push(requirement);
+        }
+        requirements.reverse();
+        children.extend(requirements);
+
+        Some(self.create(Kind::GenericSignature, children))
+    }
+
+    fn generic_requirement(&mut self) -> Option<NodeId> {
+        // only requirements on generic parameters are supported, not on associated types
+        let (kind, substitution) = match self.next()? {
+            b'b' => (Kind::ConformanceRequirement, false),
+            b'B' => (Kind::ConformanceRequirement, true),
+            b's' => (Kind::SameTypeRequirement, false),
+            b'S' => (Kind::SameTypeRequirement, true),
+            b'Q' => {
+                let subject = self.pop_kind(Kind::Type)?;
+                let protocol = self.pop_protocol()?;
+                return Some(self.create(Kind::ConformanceRequirement, vec![subject, protocol]));
+            }
+            b'c' | b'C' | b't' | b'T' | b'p' | b'P' | b'm' | b'M' | b'l' | b'L' => return None,
+            _ => {
+                self.offset -= 1;
+                let param = self.generic_param_index()?;
+                let subject = self.create_type(param);
+                let protocol = self.pop_protocol()?;
+                return Some(self.create(Kind::ConformanceRequirement, vec![subject, protocol]));
+            }
+        };
+
+        let subject = match substitution {
+            true => self.pop_kind(Kind::Type)?,
+            false => {
+                let param = self.generic_param_index()?;
+                self.create_type(param)
+            }
+        };
+
+        let constraint = self.pop_kind(Kind::Type)?;
+        Some(self.create(kind, vec![subject, constraint]))
+    }
+
+    fn metadata(&mut self) -> Option<NodeId> {
+        let (description, child) = match self.next()? {
+            b'a' => ("type metadata accessor for ", self.pop_kind(Kind::Type)?),
+            b'n' => ("nominal type descriptor for ", self.pop_kind(Kind::Type)?),
+            b'f' => ("full type metadata for ", self.pop_kind(Kind::Type)?),
+            b'm' => ("metaclass for ", self.pop_kind(Kind::Type)?),
+            b'L' => (
+                "lazy cache variable for type metadata for ",
+                self.pop_kind(Kind::Type)?,
+            ),
+            b'l' => (
+                "type metadata singleton initialization cache for ",
+                self.pop_kind(Kind::Type)?,
+            ),
+            b'o' => (
+                "class metadata base offset for ",
+                self.pop_kind(Kind::Type)?,
+            ),
+            b'u' => ("method lookup function for ", self.pop_kind(Kind::Type)?),
+            b'r' => (
+                "type metadata completion function for ",
+                self.pop_kind(Kind::Type)?,
+            ),
+            b'i' => (
+                "type metadata instantiation function for ",
+                self.pop_kind(Kind::Type)?,
+            ),
+            b'I' => (
+                "type metadata instantiation cache for ",
+                self.pop_kind(Kind::Type)?,
+            ),
+            b'P' => (
+                "generic type metadata pattern for ",
+                self.pop_kind(Kind::Type)?,
+            ),
+            b'F' => (
+                "reflection metadata field descriptor ",
+                self.pop_kind(Kind::Type)?,
+            ),
+            b'D' => (
+                "demangling cache variable for type metadata for ",
+                self.pop_kind(Kind::Type)?,
+            ),
+            b'X' => match self.next()? {
+                b'M' => ("module descriptor ", self.pop_module()?),
+                _ => return None,
+            },
+            b'p' => ("protocol descriptor for ", self.pop_protocol()?),
+            b'c' => (
+                "protocol conformance descriptor for ",
+                self.pop_conformance()?,
+            ),
+            b'V' => ("property descriptor for ", self.pop(Kind::is_entity)?),
+            _ => return None,
+        };
+
+        Some(self.create(Kind::Described(description), vec![child]))
+    }
+
+    fn witness(&mut self) -> Option<NodeId> {
+        let (description, child) = match self.next()? {
+            b'V' => ("value witness table for ", self.pop_kind(Kind::Type)?),
+            b'P' => ("protocol witness table for ", self.pop_conformance()?),
+            b'p' => (
+                "protocol witness table pattern for ",
+                self.pop_conformance()?,
+            ),
+            b'a' => (
+                "protocol witness table accessor for ",
+                self.pop_conformance()?,
+            ),
+            b'v' => {
+                let description = match self.next()? {
+                    b'd' => "direct field offset for ",
+                    b'i' => "indirect field offset for ",
+                    _ => return None,
+                };
+                (description, self.pop(Kind::is_entity)?)
+            }
+            c @ (b'L' | b'l') => {
+                let description = match c {
+                    b'L' => "lazy protocol witness table cache variable for type ",
+                    _ => "lazy protocol witness table accessor for type ",
+                };
+                let conformance = self.pop_conformance()?;
+                let ty = self.pop_kind(Kind::Type)?;
+                return Some(
+                    self.create(Kind::LazyWitnessTable(description), vec![ty, conformance]),
+                );
+            }
+            _ => return None,
+        };
+
+        Some(self.create(Kind::Described(description), vec![child]))
+    }
+
+    fn thunk(&mut self) -> Option<NodeId> {
+        let attribute = match self.next()? {
+            b'A' => "partial apply forwarder for ",
+            b'a' => "partial apply ObjC forwarder for ",
+            b'o' => "@objc ",
+            b'O' => "@nonobjc ",
+            b'D' => "dynamic ",
+            b'd' => "super ",
+            b'm' => "merged ",
+            b'j' => {
+                let entity = self.pop(Kind::is_entity)?;
+                return Some(self.create(Kind::Described("dispatch thunk of "), vec![entity]));
+            }
+            b'q' => {
+                let entity = self.pop(Kind::is_entity)?;
+                return Some(self.create(Kind::Described("method descriptor for "), vec![entity]));
+            }
+            b'W' => {
+                let entity = self.pop(Kind::is_entity)?;
+                let conformance = self.pop_conformance()?;
+                return Some(self.create(Kind::ProtocolWitness, vec![entity, conformance]));
+            }
+            _ => return None,
+        };
+
+        Some(self.create(Kind::Attribute(attribute), Vec::new()))
+    }
+}
+
+/// Generic parameters are named A, B, .., Z, BA, .. with their depth appended.
+fn generic_param_name(depth: u64, mut index: u64) -> String {
+    let mut name = String::new();
+
+    loop {
+        name.insert(0, (b'A' + (index % 26) as u8) as char);
+        index /= 26;
+        if index == 0 {
+            break;
+        }
+    }
+
+    if depth != 0 {
+        name += &depth.to_string();
+    }
+
+    name
+}
+
+/// State required to print a tree of nodes.
+struct Printer<'a> {
+    stream: TokenStream,
+    nodes: &'a [Node],
+    depth: usize,
+}
+
+impl Printer<'_> {
+    #[inline]
+    fn push(&mut self, text: &'static str, color: Color32) {
+        self.stream.push(text, color);
+    }
+
+    fn kind(&self, node: NodeId) -> Kind {
+        self.nodes[node].kind
+    }
+
+    fn child(&self, node: NodeId, idx: usize) -> Option<NodeId> {
+        self.nodes[node].children.get(idx).copied()
+    }
+
+    fn find(&self, node: NodeId, kind: Kind) -> Option<NodeId> {
+        self.nodes[node].children.iter().copied().find(|&c| self.kind(c) == kind)
+    }
+
+    /// The type of an entity is always it's last child.
+    fn entity_type(&self, node: NodeId) -> Option<NodeId> {
+        self.nodes[node]
+            .children
+            .last()
+            .copied()
+            .filter(|&c| self.kind(c) == Kind::Type)
+    }
+
+    /// Unwraps [Kind::Type] nodes.
+    fn inner(&self, mut node: NodeId) -> NodeId {
+        while self.kind(node) == Kind::Type {
+            match self.child(node, 0) {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+
+        node
+    }
+
+    fn print(&mut self, node: NodeId) -> Option<()> {
+        if self.depth == MAX_DEPTH || self.stream.tokens().len() > 4096 {
+            return None;
+        }
+
+        self.depth += 1;
+        let result = self.print_node(node);
+        self.depth -= 1;
+        result
+    }
+
+    fn print_node(&mut self, node: NodeId) -> Option<()> {
+        let nodes = self.nodes;
+        let text = &nodes[node].text;
+
+        match self.kind(node) {
+            Kind::Identifier | Kind::Module => {
+                self.stream.push_string(text.clone(), CONFIG.colors.asm.component);
+            }
+            Kind::InfixOperator => {
+                self.stream.push_string(text.clone(), CONFIG.colors.asm.component);
+                self.push(" infix", CONFIG.colors.asm.annotation);
+            }
+            Kind::PrefixOperator => {
+                self.stream.push_string(text.clone(), CONFIG.colors.asm.component);
+                self.push(" prefix", CONFIG.colors.asm.annotation);
+            }
+            Kind::PostfixOperator => {
+                self.stream.push_string(text.clone(), CONFIG.colors.asm.component);
+                self.push(" postfix", CONFIG.colors.asm.annotation);
+            }
+            Kind::PrivateDeclName => {
+                let discriminator = self.nodes[self.child(node, 0)?].text.clone();
+                self.push("(", CONFIG.colors.brackets);
+                self.print(self.child(node, 1)?)?;
+                self.push(" in ", CONFIG.colors.brackets);
+                self.stream.push_string(discriminator, colors::GRAYAA);
+                self.push(")", CONFIG.colors.brackets);
+            }
+            Kind::LocalDeclName => {
+                let index = self.nodes[self.child(node, 0)?].index;
+                self.print(self.child(node, 1)?)?;
+                self.stream.push_string(format!(" #{}", index + 1), CONFIG.colors.asm.primitive);
+            }
+            Kind::Type | Kind::ArgumentTuple | Kind::ReturnType | Kind::TypeMangling => {
+                self.print(self.child(node, 0)?)?;
+            }
+            kind if kind.is_nominal() => {
+                let ctx = self.child(node, 0)?;
+                let name = self.child(node, 1)?;
+
+                if self.kind(name) == Kind::LocalDeclName {
+                    self.print(name)?;
+                    self.push(" in ", CONFIG.colors.brackets);
+                    self.print(ctx)?;
+                } else {
+                    self.print(ctx)?;
+                    self.push(".", CONFIG.colors.delimiter);
+                    self.print(name)?;
+                }
+            }
+            Kind::Extension => {
+                self.push("(extension in ", CONFIG.colors.brackets);
+                self.print(self.child(node, 0)?)?;
+                self.push("):", CONFIG.colors.brackets);
+                self.print(self.child(node, 1)?)?;
+            }
+            Kind::BoundGeneric => self.print_bound_generic(node)?,
+            Kind::TypeList => self.print_list(node, ", ")?,
+            Kind::Function
+            | Kind::Variable
+            | Kind::Subscript
+            | Kind::Allocator
+            | Kind::Constructor
+            | Kind::Destructor
+            | Kind::Deallocator
+            | Kind::IVarInitializer
+            | Kind::IVarDestroyer => self.print_entity(node, None)?,
+            Kind::Accessor(suffix) => self.print_entity(self.child(node, 0)?, Some(suffix))?,
+            Kind::Static => {
+                self.push("static ", CONFIG.colors.asm.annotation);
+                self.print(self.child(node, 0)?)?;
+            }
+            Kind::Initializer => {
+                self.push(
+                    "variable initialization expression of ",
+                    CONFIG.colors.asm.primitive,
+                );
+                self.print(self.child(node, 0)?)?;
+            }
+            Kind::ExplicitClosure | Kind::ImplicitClosure => {
+                let index = self.nodes[self.child(node, 1)?].index;
+                let closure = match self.kind(node) {
+                    Kind::ExplicitClosure => "closure",
+                    _ => "implicit closure",
+                };
+
+                self.stream.push_string(
+                    format!("{closure} #{}", index + 1),
+                    CONFIG.colors.asm.primitive,
+                );
+                if let Some(ty) = self.child(node, 2) {
+                    self.push(" ", colors::WHITE);
+                    self.print(ty)?;
+                }
+                self.push(" in ", CONFIG.colors.brackets);
+                self.print(self.child(node, 0)?)?;
+            }
+            Kind::DefaultArgumentInitializer => {
+                let index = self.nodes[self.child(node, 1)?].index;
+                self.stream.push_string(
+                    format!("default argument {index}"),
+                    CONFIG.colors.asm.primitive,
+                );
+                self.push(" of ", CONFIG.colors.brackets);
+                self.print(self.child(node, 0)?)?;
+            }
+            Kind::FunctionType(_) => self.print_function_type(node, None)?,
+            Kind::Tuple => {
+                self.push("(", CONFIG.colors.brackets);
+                self.print_list(node, ", ")?;
+                self.push(")", CONFIG.colors.brackets);
+            }
+            Kind::TupleElement => {
+                if let Some(name) = self.find(node, Kind::Identifier) {
+                    self.print(name)?;
+                    self.push(": ", CONFIG.colors.delimiter);
+                }
+                self.print(*self.nodes[node].children.last()?)?;
+                if self.find(node, Kind::VariadicMarker).is_some() {
+                    self.push("...", CONFIG.colors.delimiter);
+                }
+            }
+            Kind::Metatype | Kind::ExistentialMetatype => {
+                self.print(self.child(node, 0)?)?;
+                self.push(".Type", CONFIG.colors.asm.primitive);
+            }
+            Kind::InOut => {
+                self.push("inout ", CONFIG.colors.asm.annotation);
+                self.print(self.child(node, 0)?)?;
+            }
+            Kind::Owned => {
+                self.push("__owned ", CONFIG.colors.asm.annotation);
+                self.print(self.child(node, 0)?)?;
+            }
+            Kind::Shared => {
+                self.push("__shared ", CONFIG.colors.asm.annotation);
+                self.print(self.child(node, 0)?)?;
+            }
+            Kind::ProtocolList => {
+                let list = self.child(node, 0)?;
+                match self.nodes[list].children.is_empty() {
+                    true => self.push("Any", CONFIG.colors.asm.primitive),
+                    false => self.print_list(list, " & ")?,
+                }
+            }
+            Kind::ProtocolListWithAnyObject => {
+                let list = self.child(self.child(node, 0)?, 0)?;
+                if !self.nodes[list].children.is_empty() {
+                    self.print_list(list, " & ")?;
+                    self.push(" & ", CONFIG.colors.delimiter);
+                }
+                self.push("Swift.AnyObject", CONFIG.colors.asm.component);
+            }
+            Kind::GenericParam => {
+                let name = generic_param_name(self.nodes[node].depth, self.nodes[node].index);
+                self.stream.push_string(name, CONFIG.colors.asm.primitive);
+            }
+            Kind::DependentMemberType => {
+                self.print(self.child(node, 0)?)?;
+                self.push(".", CONFIG.colors.delimiter);
+                self.print(self.child(node, 1)?)?;
+            }
+            Kind::Builtin => {
+                self.stream.push_string(text.clone(), CONFIG.colors.asm.primitive);
+            }
+            Kind::DependentGenericType => {
+                self.print(self.child(node, 0)?)?;
+                self.push(" ", colors::WHITE);
+                self.print(self.child(node, 1)?)?;
+            }
+            Kind::GenericSignature => self.print_generic_signature(node)?,
+            Kind::ConformanceRequirement => {
+                self.print(self.child(node, 0)?)?;
+                self.push(": ", CONFIG.colors.delimiter);
+                self.print(self.child(node, 1)?)?;
+            }
+            Kind::SameTypeRequirement => {
+                self.print(self.child(node, 0)?)?;
+                self.push(" == ", CONFIG.colors.delimiter);
+                self.print(self.child(node, 1)?)?;
+            }
+            Kind::Described(description) => {
+                self.push(description, CONFIG.colors.asm.primitive);
+                self.print(self.child(node, 0)?)?;
+            }
+            Kind::ProtocolConformance => {
+                self.print(self.child(node, 0)?)?;
+                self.push(" : ", CONFIG.colors.delimiter);
+                self.print(self.child(node, 1)?)?;
+                self.push(" in ", CONFIG.colors.brackets);
+                self.print(self.child(node, 2)?)?;
+            }
+            Kind::ProtocolWitness => {
+                self.push("protocol witness for ", CONFIG.colors.asm.primitive);
+                self.print(self.child(node, 0)?)?;
+                self.push(" in conformance ", CONFIG.colors.asm.primitive);
+                self.print(self.child(node, 1)?)?;
+            }
+            Kind::LazyWitnessTable(description) => {
+                self.push(description, CONFIG.colors.asm.primitive);
+                self.print(self.child(node, 0)?)?;
+                self.push(" and conformance ", CONFIG.colors.asm.primitive);
+                self.print(self.child(node, 1)?)?;
+            }
+            Kind::Attribute(attribute) => self.push(attribute, CONFIG.colors.asm.annotation),
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    fn print_list(&mut self, node: NodeId, separator: &'static str) -> Option<()> {
+        let nodes = self.nodes;
+        for (idx, &child) in nodes[node].children.iter().enumerate() {
+            if idx != 0 {
+                self.push(separator, CONFIG.colors.delimiter);
+            }
+            self.print(child)?;
+        }
+
+        Some(())
+    }
+
+    /// Prints generic types, using sugar for arrays, dictionaries and optionals.
+    fn print_bound_generic(&mut self, node: NodeId) -> Option<()> {
+        let nominal = self.inner(self.child(node, 0)?);
+        let args = self.child(node, 1)?;
+        let arg_count = self.nodes[args].children.len();
+
+        let ctx = self.child(nominal, 0)?;
+        let name = self.child(nominal, 1)?;
+        let is_swift = self.kind(ctx) == Kind::Module && self.nodes[ctx].text == "Swift";
+
+        match (is_swift, &self.nodes[name].text[..], arg_count) {
+            (true, "Optional", 1) => {
+                let wrapped = self.child(args, 0)?;
+                let needs_parens = matches!(self.kind(self.inner(wrapped)), Kind::FunctionType(_));
+
+                if needs_parens {
+                    self.push("(", CONFIG.colors.brackets);
+                }
+                self.print(wrapped)?;
+                if needs_parens {
+                    self.push(")", CONFIG.colors.brackets);
+                }
+                self.push("?", CONFIG.colors.delimiter);
+            }
+            (true, "Array", 1) => {
+                self.push("[", CONFIG.colors.brackets);
+                self.print(self.child(args, 0)?)?;
+                self.push("]", CONFIG.colors.brackets);
+            }
+            (true, "Dictionary", 2) => {
+                self.push("[", CONFIG.colors.brackets);
+                self.print(self.child(args, 0)?)?;
+                self.push(" : ", CONFIG.colors.delimiter);
+                self.print(self.child(args, 1)?)?;
+                self.push("]", CONFIG.colors.brackets);
+            }
+            _ => {
+                self.print(nominal)?;
+                self.push("<", CONFIG.colors.brackets);
+                self.print_list(args, ", ")?;
+                self.push(">", CONFIG.colors.brackets);
+            }
+        }
+
+        Some(())
+    }
+
+    fn print_generic_signature(&mut self, node: NodeId) -> Option<()> {
+        let nodes = self.nodes;
+        let children = &nodes[node].children;
+        let counts: Vec<u64> = children
+            .iter()
+            .filter(|&&c| self.kind(c) == Kind::ParamCount)
+            .map(|&c| self.nodes[c].index)
+            .collect();
+        let requirements: Vec<NodeId> =
+            children.iter().copied().filter(|&c| self.kind(c).is_requirement()).collect();
+
+        let last_depth = counts.iter().rposition(|&count| count != 0);
+        for (depth, &count) in counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+
+            self.push("<", CONFIG.colors.brackets);
+            for index in 0..count {
+                if index != 0 {
+                    self.push(", ", CONFIG.colors.delimiter);
+                }
+                let name = generic_param_name(depth as u64, index);
+                self.stream.push_string(name, CONFIG.colors.asm.primitive);
+            }
+
+            if Some(depth) == last_depth && !requirements.is_empty() {
+                self.push(" where ", CONFIG.colors.asm.annotation);
+                for (idx, &requirement) in requirements.iter().enumerate() {
+                    if idx != 0 {
+                        self.push(", ", CONFIG.colors.delimiter);
+                    }
+                    self.print(requirement)?;
+                }
+            }
+            self.push(">", CONFIG.colors.brackets);
+        }
+
+        Some(())
+    }
+
+    /// Prints an entity with it's context, optionally as the accessor of a variable or subscript.
+    fn print_entity(&mut self, node: NodeId, accessor: Option<&'static str>) -> Option<()> {
+        let kind = self.kind(node);
+        let ctx = self.child(node, 0)?;
+
+        let name = match kind {
+            Kind::Function | Kind::Variable => Some(self.child(node, 1)?),
+            _ => None,
+        };
+
+        // local declarations are printed as 'name #1 in context'
+        let is_local = name.is_some_and(|name| self.kind(name) == Kind::LocalDeclName);
+        if !is_local {
+            self.print(ctx)?;
+            self.push(".", CONFIG.colors.delimiter);
+        }
+
+        match kind {
+            Kind::Function | Kind::Variable => self.print(name?)?,
+            Kind::Subscript => self.push("subscript", CONFIG.colors.asm.component),
+            Kind::Allocator => self.push("__allocating_init", CONFIG.colors.asm.component),
+            Kind::Constructor => self.push("init", CONFIG.colors.asm.component),
+            Kind::Destructor => self.push("deinit", CONFIG.colors.asm.component),
+            Kind::Deallocator => self.push("__deallocating_deinit", CONFIG.colors.asm.component),
+            Kind::IVarInitializer => self.push("__ivar_initializer", CONFIG.colors.asm.component),
+            Kind::IVarDestroyer => self.push("__ivar_destroyer", CONFIG.colors.asm.component),
+            _ => return None,
+        }
+
+        if let Some(accessor) = accessor {
+            self.push(accessor, CONFIG.colors.asm.component);
+        }
+
+        if let Some(ty) = self.entity_type(node) {
+            let labels = self.find(node, Kind::LabelList);
+
+            match (kind, accessor) {
+                (Kind::Variable, _) | (_, Some(_)) => {
+                    self.push(" : ", CONFIG.colors.delimiter);
+                    self.print(ty)?;
+                }
+                _ => self.print_signature(ty, labels)?,
+            }
+        }
+
+        if is_local {
+            self.push(" in ", CONFIG.colors.brackets);
+            self.print(ctx)?;
+        }
+
+        Some(())
+    }
+
+    /// Prints the generic signature and function type that follow a function's name.
+    fn print_signature(&mut self, ty: NodeId, labels: Option<NodeId>) -> Option<()> {
+        let mut function = self.inner(ty);
+
+        if self.kind(function) == Kind::DependentGenericType {
+            self.print(self.child(function, 0)?)?;
+            function = self.inner(self.child(function, 1)?);
+        }
+
+        match self.kind(function) {
+            Kind::FunctionType(_) => self.print_function_type(function, labels),
+            _ => {
+                self.push(" : ", CONFIG.colors.delimiter);
+                self.print(function)
+            }
+        }
+    }
+
+    fn print_function_type(&mut self, node: NodeId, labels: Option<NodeId>) -> Option<()> {
+        if let Kind::FunctionType(convention) = self.kind(node) {
+            if !convention.is_empty() {
+                self.push(convention, CONFIG.colors.asm.annotation);
+            }
+        }
+
+        if self.find(node, Kind::SendableAnnotation).is_some() {
+            self.push("@Sendable ", CONFIG.colors.asm.annotation);
+        }
+
+        let args = self.find(node, Kind::ArgumentTuple)?;
+        let args = self.inner(self.child(args, 0)?);
+        let ret = self.find(node, Kind::ReturnType)?;
+
+        let params: Vec<NodeId> = match self.kind(args) {
+            Kind::Tuple => self.nodes[args].children.clone(),
+            _ => vec![args],
+        };
+
+        let labels: Vec<NodeId> =
+            labels.map(|l| self.nodes[l].children.clone()).unwrap_or_default();
+        if !labels.is_empty() && labels.len() != params.len() {
+            return None;
+        }
+
+        self.push("(", CONFIG.colors.brackets);
+        for (idx, &param) in params.iter().enumerate() {
+            if idx != 0 {
+                self.push(", ", CONFIG.colors.delimiter);
+            }
+
+            match labels.get(idx) {
+                Some(&label) if self.kind(label) == Kind::Identifier => {
+                    self.print(label)?;
+                    self.push(": ", CONFIG.colors.delimiter);
+                }
+                Some(_) => self.push("_: ", CONFIG.colors.delimiter),
+                None => {}
+            }
+
+            // labels replace the names of tuple elements
+            match (self.kind(param), labels.is_empty()) {
+                (Kind::TupleElement, false) => self.print(*self.nodes[param].children.last()?)?,
+                _ => self.print(param)?,
+            }
+        }
+        self.push(")", CONFIG.colors.brackets);
+
+        if self.find(node, Kind::AsyncAnnotation).is_some() {
+            self.push(" async", CONFIG.colors.asm.annotation);
+        }
+
+        if self.find(node, Kind::ThrowsAnnotation).is_some() {
+            self.push(" throws", CONFIG.colors.asm.annotation);
+        }
+
+        self.push(" -> ", CONFIG.colors.brackets);
+        self.print(ret)
+    }
+}
@@ -0,0 +1,165 @@
+#![cfg(test)]
+
+use super::*;
+
+macro_rules! eq {
+    ($mangled:literal => $demangled:literal) => {
+        let symbol = parse($mangled).expect(&format!("Formatting '{}' failed.", $mangled));
+
+        assert_eq!(
+            String::from_iter(symbol.tokens().iter().map(|t| &t.text[..])),
+            $demangled
+        );
+    };
+}
+
+#[test]
+fn functions() {
+    eq!("$s4main3fooyyF" => "main.foo() -> ()");
+    eq!("_$s4main3fooyyF" => "main.foo() -> ()");
+    eq!("$s4test3fooyySiF" => "test.foo(Swift.Int) -> ()");
+    eq!("$s4main3add1a1bS2i_SitF" => "main.add(a: Swift.Int, b: Swift.Int) -> Swift.Int");
+    eq!("$s4main1f_1yySi_SitF" => "main.f(_: Swift.Int, y: Swift.Int) -> ()");
+    eq!("$s4main3fooSSyF" => "main.foo() -> Swift.String");
+    eq!("$s4main3fooyyYaKF" => "main.foo() async throws -> ()");
+}
+
+#[test]
+fn old_mangling() {
+    eq!("_T04main3fooyyF" => "main.foo() -> ()");
+    eq!("__T04main3fooyyF" => "main.foo() -> ()");
+}
+
+#[test]
+fn types() {
+    eq!("$sSiD" => "Swift.Int");
+    eq!("$sSiSgD" => "Swift.Int?");
+    eq!("$sSaySiGD" => "[Swift.Int]");
+    eq!("$sSDySSSiGD" => "[Swift.String : Swift.Int]");
+    eq!("$sSi_SStD" => "(Swift.Int, Swift.String)");
+    eq!("$sSimD" => "Swift.Int.Type");
+    eq!("$sypD" => "Any");
+    eq!("$syXlD" => "Swift.AnyObject");
+    eq!("$ss5Error_pSgD" => "Swift.Error?");
+    eq!("$sSo8NSObjectCD" => "__C.NSObject");
+    eq!("$sBoD" => "Builtin.NativeObject");
+    eq!("$sBi64_D" => "Builtin.Int64");
+}
+
+#[test]
+fn function_types() {
+    eq!("$syycD" => "() -> ()");
+    eq!("$sSiSScD" => "(Swift.String) -> Swift.Int");
+    eq!("$sSiSSKcD" => "(Swift.String) throws -> Swift.Int");
+    eq!("$syyXBD" => "@convention(block) () -> ()");
+    eq!("$s4main3fooyyyyXEF" => "main.foo(() -> ()) -> ()");
+    eq!("$s4main3foo8callbackyyyc_tF" => "main.foo(callback: () -> ()) -> ()");
+}
+
+#[test]
+fn variables() {
+    eq!("$s4main1xSivp" => "main.x : Swift.Int");
+    eq!("$s4main3FooC1xSivg" => "main.Foo.x.getter : Swift.Int");
+    eq!("$s4main3FooC1xSivs" => "main.Foo.x.setter : Swift.Int");
+    eq!("$s4main3FooC1xSivM" => "main.Foo.x.modify : Swift.Int");
+    eq!("$s4main3FooV1xSivgZ" => "static main.Foo.x.getter : Swift.Int");
+    eq!("$s4main1xSivau" => "main.x.unsafeMutableAddressor : Swift.Int");
+    eq!("$s4main1xSivpfi" => "variable initialization expression of main.x : Swift.Int");
+}
+
+#[test]
+fn methods() {
+    eq!("$s4main3FooV3baryyF" => "main.Foo.bar() -> ()");
+    eq!("$s4main3FooC3baryyFZ" => "static main.Foo.bar() -> ()");
+    eq!("$s4main3FooCACycfC" => "main.Foo.__allocating_init() -> main.Foo");
+    eq!("$s4main3FooCACycfc" => "main.Foo.init() -> main.Foo");
+    eq!("$s4main3FooC1xACSi_tcfC" => "main.Foo.__allocating_init(x: Swift.Int) -> main.Foo");
+    eq!("$s4main3FooCfD" => "main.Foo.__deallocating_deinit");
+    eq!("$s4main3FooCfd" => "main.Foo.deinit");
+    eq!("$s4main3FooCyS2icip" => "main.Foo.subscript(Swift.Int) -> Swift.Int");
+    eq!("$s4main3FooCyS2icig" => "main.Foo.subscript.getter : (Swift.Int) -> Swift.Int");
+    eq!("$s4main3FooV2eeoiySbAC_ACtFZ" => "static main.Foo.== infix(main.Foo, main.Foo) -> Swift.Bool");
+}
+
+#[test]
+fn nested_and_private() {
+    eq!("$s4main5OuterV5InnerO3bazyyF" => "main.Outer.Inner.baz() -> ()");
+    eq!("$s4main3Foo33_0123456789ABCDEF0123456789ABCDEFLLV3baryyF"
+        => "main.(Foo in _0123456789ABCDEF0123456789ABCDEF).bar() -> ()");
+    eq!("$s4main3fooyyF3BarL_VD" => "Bar #1 in main.foo() -> ()");
+}
+
+#[test]
+fn extensions() {
+    eq!("$sSi4mainE3fooyyF" => "(extension in main):Swift.Int.foo() -> ()");
+    eq!("$sSo8NSObjectC4mainE3baryyF" => "(extension in main):__C.NSObject.bar() -> ()");
+}
+
+#[test]
+fn closures() {
+    eq!("$s4main3fooyyFyycfU_" => "closure #1 () -> () in main.foo() -> ()");
+    eq!("$s4main3fooyyFyycfU0_" => "closure #2 () -> () in main.foo() -> ()");
+    eq!("$s4main3fooyyFyycfu_" => "implicit closure #1 () -> () in main.foo() -> ()");
+    eq!("$s4main3foo1xySi_tFfA_" => "default argument 0 of main.foo(x: Swift.Int) -> ()");
+}
+
+#[test]
+fn generics() {
+    eq!("$s4main3fooyyxlF" => "main.foo<A>(A) -> ()");
+    eq!("$s4main3fooyyx_q_tr0_lF" => "main.foo<A, B>(A, B) -> ()");
+    eq!("$s4main3fooyyxSQRzlF" => "main.foo<A where A: Swift.Equatable>(A) -> ()");
+    eq!("$s4main3fooySayxGxlF" => "main.foo<A>(A) -> [A]");
+    eq!("$s4main3BoxVySiGD" => "main.Box<Swift.Int>");
+    eq!("$s4main3BoxVySiGMa" => "type metadata accessor for main.Box<Swift.Int>");
+    eq!("$s4main3fooy7ElementQzxSTRzlF" => "main.foo<A where A: Swift.Sequence>(A) -> A.Element");
+}
+
+#[test]
+fn substitutions() {
+    eq!("$s4main3FooV3bar1xA2C_tF" => "main.Foo.bar(x: main.Foo) -> main.Foo");
+    eq!("$s4main3FooV3baryA2CF" => "main.Foo.bar(main.Foo) -> main.Foo");
+    eq!("$s4test8SomeTypeV0aB4LongVD" => "test.SomeType.testSomeLong");
+}
+
+#[test]
+fn metadata() {
+    eq!("$s4main3FooVN" => "type metadata for main.Foo");
+    eq!("$s4main3FooCMa" => "type metadata accessor for main.Foo");
+    eq!("$s4main3FooCMn" => "nominal type descriptor for main.Foo");
+    eq!("$s4main3FooCMm" => "metaclass for main.Foo");
+    eq!("$s4main3FooCMo" => "class metadata base offset for main.Foo");
+    eq!("$s4main3FooCMf" => "full type metadata for main.Foo");
+    eq!("$s4main3FooVMF" => "reflection metadata field descriptor main.Foo");
+    eq!("$s4main1PMp" => "protocol descriptor for main.P");
+    eq!("$s4main3FooVAA1PAAMc" => "protocol conformance descriptor for main.Foo : main.P in main");
+    eq!("$s10Foundation3URLVSgMD" => "demangling cache variable for type metadata for Foundation.URL?");
+    eq!("$s4mainMXM" => "module descriptor main");
+    eq!("$s4main3FooVWV" => "value witness table for main.Foo");
+    eq!("$s4main3FooVAA1PAAWP" => "protocol witness table for main.Foo : main.P in main");
+    eq!("$s4main3FooC1xSivpWvd" => "direct field offset for main.Foo.x : Swift.Int");
+    eq!("$s4main3FooVAcA1PAAWl"
+        => "lazy protocol witness table accessor for type main.Foo and conformance main.Foo : main.P in main");
+}
+
+#[test]
+fn thunks() {
+    eq!("$s4main3FooC3baryyFTo" => "@objc main.Foo.bar() -> ()");
+    eq!("$s4main3FooC3baryyFTj" => "dispatch thunk of main.Foo.bar() -> ()");
+    eq!("$s4main3FooC3baryyFTq" => "method descriptor for main.Foo.bar() -> ()");
+    eq!("$s4main3FooC3baryyFTA" => "partial apply forwarder for main.Foo.bar() -> ()");
+    eq!("$sSo33NSWorkspaceLaunchConfigurationKeyas21_ObjectiveCBridgeableSCsACP09_bridgeToE1C01_E5CTypeQzyFTW"
+        => "protocol witness for Swift._ObjectiveCBridgeable._bridgeToObjectiveC() -> A._ObjectiveCType in \
+            conformance __C.NSWorkspaceLaunchConfigurationKey : Swift._ObjectiveCBridgeable in __C_Synthesized");
+    eq!("$s4main3FooVAA1PA2aDP3baryyFTW"
+        => "protocol witness for main.P.bar() -> () in conformance main.Foo : main.P in main");
+}
+
+#[test]
+fn invalid() {
+    assert!(parse("$s").is_none());
+    assert!(parse("$s4main").is_none());
+    assert!(parse("$s4main3fooyyFyyF").is_none());
+    assert!(parse("$s99main").is_none());
+    assert!(parse("_ZN4main3fooEv").is_none());
+    assert!(parse("$sAZ").is_none());
+}
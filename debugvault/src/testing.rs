@@ -0,0 +1,214 @@
+//! DWARF written with gimli and wrapped in a tiny little-endian ELF64 for the tests.
+#![cfg(test)]
+
+use gimli::write::{
+    Address, AttributeValue, DwarfUnit, EndianVec, LineProgram, LineString, Sections,
+};
+use gimli::{LineEncoding, LittleEndian};
+use object::elf;
+use object::endian::{LittleEndian as LE, U16, U32, U64};
+use object::pod::{bytes_of, Pod};
+use std::mem::size_of;
+
+/// All zero header, to be filled in field by field.
+fn zeroed<T: Pod>() -> T {
+    *object::pod::from_bytes(&vec![0; size_of::<T>()]).unwrap().0
+}
+
+/// An x86-64 executable holding nothing but the given sections.
+pub fn elf(sections: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let header_size = size_of::<elf::FileHeader64<LE>>();
+
+    let mut shstrtab = vec![0];
+    let mut names = Vec::new();
+    for name in sections.iter().map(|(name, _)| *name).chain([".shstrtab"]) {
+        names.push(shstrtab.len() as u32);
+        shstrtab.extend(name.as_bytes());
+        shstrtab.push(0);
+    }
+
+    let contents = sections.iter().map(|(_, bytes)| (elf::SHT_PROGBITS, bytes));
+    let contents = contents.chain([(elf::SHT_STRTAB, &shstrtab)]);
+
+    let mut data = Vec::new();
+    let mut headers = vec![zeroed::<elf::SectionHeader64<LE>>()];
+    for (name, (sh_type, bytes)) in names.into_iter().zip(contents) {
+        let mut header: elf::SectionHeader64<LE> = zeroed();
+        header.sh_name = U32::new(LE, name);
+        header.sh_type = U32::new(LE, sh_type);
+        header.sh_offset = U64::new(LE, (header_size + data.len()) as u64);
+        header.sh_size = U64::new(LE, bytes.len() as u64);
+        header.sh_addralign = U64::new(LE, 1);
+        data.extend_from_slice(bytes);
+        headers.push(header);
+    }
+
+    let shoff = (header_size + data.len()).next_multiple_of(8);
+    let mut header: elf::FileHeader64<LE> = zeroed();
+    header.e_ident.magic = elf::ELFMAG;
+    header.e_ident.class = elf::ELFCLASS64;
+    header.e_ident.data = elf::ELFDATA2LSB;
+    header.e_ident.version = elf::EV_CURRENT;
+    header.e_type = U16::new(LE, elf::ET_EXEC);
+    header.e_machine = U16::new(LE, elf::EM_X86_64);
+    header.e_version = U32::new(LE, elf::EV_CURRENT as u32);
+    header.e_shoff = U64::new(LE, shoff as u64);
+    header.e_ehsize = U16::new(LE, header_size as u16);
+    header.e_shentsize = U16::new(LE, size_of::<elf::SectionHeader64<LE>>() as u16);
+    header.e_shnum = U16::new(LE, headers.len() as u16);
+    header.e_shstrndx = U16::new(LE, headers.len() as u16 - 1);
+
+    let mut bytes = bytes_of(&header).to_vec();
+    bytes.extend(data);
+    bytes.resize(shoff, 0);
+    for header in headers {
+        bytes.extend(bytes_of(&header));
+    }
+    bytes
+}
+
+/// A unit in `/src/bar.rs` where `bar` at `0x1000..0x1040` has `foo` inlined into it at
+/// `0x1010..0x1020`, which in turn has `baz` inlined into it at `0x1014..0x1018`.
+///
+/// The static function `helper` follows at `0x1040..0x1050`. `unused` got discarded by the
+/// linker along with a call inlined into it, and `external` is only declared.
+pub fn inlined_calls() -> Vec<u8> {
+    let encoding = gimli::Encoding {
+        format: gimli::Format::Dwarf32,
+        version: 4,
+        address_size: 8,
+    };
+    let string = |string: &str| LineString::String(string.as_bytes().to_vec());
+
+    let mut program = LineProgram::new(
+        encoding,
+        LineEncoding::default(),
+        string("/src"),
+        string("bar.rs"),
+        None,
+    );
+    let dir = program.default_directory();
+    let bar_rs = program.add_file(string("bar.rs"), dir, None);
+    let foo_rs = program.add_file(string("foo.rs"), dir, None);
+
+    program.begin_sequence(Some(Address::Constant(0x1000)));
+    for (offset, file, line) in [
+        (0x00, bar_rs, 5),
+        (0x10, foo_rs, 40),
+        (0x14, foo_rs, 60),
+        (0x18, foo_rs, 41),
+        (0x20, bar_rs, 8),
+        (0x40, bar_rs, 20),
+    ] {
+        let row = program.row();
+        row.address_offset = offset;
+        row.file = file;
+        row.line = line;
+        program.generate_row();
+    }
+    program.end_sequence(0x50);
+
+    let mut dwarf = DwarfUnit::new(encoding);
+    dwarf.unit.line_program = program;
+    let unit = &mut dwarf.unit;
+    let root = unit.root();
+    let string = |string: &str| AttributeValue::String(string.as_bytes().to_vec());
+    unit.get_mut(root).set(gimli::DW_AT_name, string("bar.rs"));
+    unit.get_mut(root).set(gimli::DW_AT_comp_dir, string("/src"));
+
+    let mut add = |parent, tag, attrs: Vec<(gimli::DwAt, AttributeValue)>| {
+        let id = unit.add(parent, tag);
+        for (name, value) in attrs {
+            unit.get_mut(id).set(name, value);
+        }
+        id
+    };
+    let range = |start: u64, len: u64| {
+        vec![
+            (
+                gimli::DW_AT_low_pc,
+                AttributeValue::Address(Address::Constant(start)),
+            ),
+            (gimli::DW_AT_high_pc, AttributeValue::Udata(len)),
+        ]
+    };
+    let inline = (
+        gimli::DW_AT_inline,
+        AttributeValue::Inline(gimli::DW_INL_inlined),
+    );
+
+    let subprogram = gimli::DW_TAG_subprogram;
+    let inlined_subroutine = gimli::DW_TAG_inlined_subroutine;
+    let foo = add(
+        root,
+        subprogram,
+        vec![(gimli::DW_AT_name, string("foo")), inline.clone()],
+    );
+    let baz = add(
+        root,
+        subprogram,
+        vec![(gimli::DW_AT_name, string("baz")), inline],
+    );
+
+    let mut attrs = vec![(gimli::DW_AT_name, string("bar"))];
+    attrs.extend(range(0x1000, 0x40));
+    let bar = add(root, subprogram, attrs);
+
+    let mut attrs = vec![
+        (gimli::DW_AT_abstract_origin, AttributeValue::UnitRef(foo)),
+        (
+            gimli::DW_AT_call_file,
+            AttributeValue::FileIndex(Some(bar_rs)),
+        ),
+        (gimli::DW_AT_call_line, AttributeValue::Udata(7)),
+    ];
+    attrs.extend(range(0x1010, 0x10));
+    let foo_call = add(bar, inlined_subroutine, attrs);
+
+    let mut attrs = vec![
+        (gimli::DW_AT_abstract_origin, AttributeValue::UnitRef(baz)),
+        (
+            gimli::DW_AT_call_file,
+            AttributeValue::FileIndex(Some(foo_rs)),
+        ),
+        (gimli::DW_AT_call_line, AttributeValue::Udata(42)),
+    ];
+    attrs.extend(range(0x1014, 0x4));
+    add(foo_call, inlined_subroutine, attrs);
+
+    let mut attrs = vec![
+        (gimli::DW_AT_name, string("helper")),
+        (gimli::DW_AT_linkage_name, string("_ZL6helperv")),
+    ];
+    attrs.extend(range(0x1040, 0x10));
+    add(root, subprogram, attrs);
+
+    let mut attrs = vec![(gimli::DW_AT_name, string("unused"))];
+    attrs.extend(range(0, 0x20));
+    let unused = add(root, subprogram, attrs);
+
+    let mut attrs = vec![(gimli::DW_AT_abstract_origin, AttributeValue::UnitRef(foo))];
+    attrs.extend(range(0x8, 0x4));
+    add(unused, inlined_subroutine, attrs);
+
+    let attrs = vec![
+        (gimli::DW_AT_name, string("external")),
+        (gimli::DW_AT_declaration, AttributeValue::Flag(true)),
+    ];
+    add(root, subprogram, attrs);
+
+    let mut sections = Sections::new(EndianVec::new(LittleEndian));
+    dwarf.write(&mut sections).unwrap();
+
+    let mut debug_sections = Vec::new();
+    sections
+        .for_each(|id, data| {
+            if !data.slice().is_empty() {
+                debug_sections.push((id.name(), data.slice().to_vec()));
+            }
+            Ok::<_, gimli::write::Error>(())
+        })
+        .unwrap();
+
+    elf(&debug_sections)
+}
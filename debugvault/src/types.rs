@@ -0,0 +1,653 @@
+//! Types described by debug info, used to render global variables field by field.
+
+use crate::{AddressMap, Addressed};
+use object::Endianness;
+
+/// Index into [`TypeDb::types`].
+pub type TypeId = usize;
+
+/// Most fields a single variable is expanded into, anything past it is left as bytes.
+const MAX_FIELDS: usize = 512;
+
+/// How deeply types are followed, in case the debug info is cyclic.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Signed,
+    Unsigned,
+    Float,
+    Bool,
+    Char,
+}
+
+#[derive(Debug, Clone)]
+pub enum Type {
+    Base {
+        name: String,
+        size: usize,
+        encoding: Encoding,
+    },
+    Struct {
+        name: Option<String>,
+        size: usize,
+        members: Vec<Member>,
+        is_union: bool,
+    },
+    Enum {
+        name: Option<String>,
+        size: usize,
+        variants: Vec<(i64, String)>,
+    },
+    Pointer {
+        size: usize,
+        target: Option<TypeId>,
+    },
+    Array {
+        elem: TypeId,
+        /// Number of elements, multi-dimensional arrays are flattened.
+        count: usize,
+    },
+    Typedef {
+        name: String,
+        target: Option<TypeId>,
+    },
+    /// `const` or `volatile`.
+    Qualified {
+        qualifier: &'static str,
+        target: Option<TypeId>,
+    },
+    Function {
+        /// Returned type, `None` for `void`.
+        ret: Option<TypeId>,
+        params: Vec<TypeId>,
+        variadic: bool,
+    },
+    /// Referenced but either not described or described in a way that isn't supported.
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: Option<String>,
+    pub offset: usize,
+    pub tipe: TypeId,
+    /// Bit offset from the start of the containing type and width of a bitfield.
+    pub bits: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub tipe: TypeId,
+}
+
+/// A scalar part of a variable.
+#[derive(Debug, Clone)]
+pub struct Field {
+    /// Offset from the start of the variable.
+    pub offset: usize,
+    pub name: String,
+    pub tipe: String,
+    pub value: String,
+    /// Address the field points to, if it's a pointer.
+    pub pointer: Option<usize>,
+}
+
+/// Fields of a variable and how many bytes they cover.
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    pub fields: Vec<Field>,
+    pub size: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct TypeDb {
+    pub types: Vec<Type>,
+    /// Variables with a fixed address.
+    pub variables: AddressMap<Variable>,
+    /// Type of the function at an address.
+    pub functions: AddressMap<TypeId>,
+}
+
+impl TypeDb {
+    pub fn extend(&mut self, other: Self) {
+        // Type id's are indices, so every reference in `other` has to be moved past ours.
+        let base = self.types.len();
+        let shift = |id: &mut Option<TypeId>| {
+            if let Some(id) = id {
+                *id += base;
+            }
+        };
+
+        for mut tipe in other.types {
+            match &mut tipe {
+                Type::Struct { members, .. } => {
+                    for member in members.iter_mut() {
+                        member.tipe += base;
+                    }
+                }
+                Type::Pointer { target, .. } => shift(target),
+                Type::Array { elem, .. } => *elem += base,
+                Type::Typedef { target, .. } => shift(target),
+                Type::Qualified { target, .. } => shift(target),
+                Type::Function { ret, params, .. } => {
+                    shift(ret);
+                    for param in params.iter_mut() {
+                        *param += base;
+                    }
+                }
+                Type::Base { .. } | Type::Enum { .. } | Type::Unknown => {}
+            }
+            self.types.push(tipe);
+        }
+
+        for Addressed { addr, mut item } in other.variables.mapping {
+            item.tipe += base;
+            self.variables.push(Addressed { addr, item });
+        }
+
+        for Addressed { addr, item } in other.functions.mapping {
+            self.functions.push(Addressed {
+                addr,
+                item: item + base,
+            });
+        }
+    }
+
+    pub fn size_of(&self, tipe: TypeId) -> Option<usize> {
+        self.size_of_inner(tipe, 0)
+    }
+
+    fn size_of_inner(&self, tipe: TypeId, depth: usize) -> Option<usize> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        match self.types.get(tipe)? {
+            Type::Base { size, .. } | Type::Struct { size, .. } | Type::Enum { size, .. } => {
+                Some(*size)
+            }
+            Type::Pointer { size, .. } => Some(*size),
+            Type::Array { elem, count } => {
+                self.size_of_inner(*elem, depth + 1)?.checked_mul(*count)
+            }
+            Type::Typedef { target, .. } | Type::Qualified { target, .. } => {
+                self.size_of_inner((*target)?, depth + 1)
+            }
+            Type::Function { .. } | Type::Unknown => None,
+        }
+    }
+
+    /// C-like name of a type.
+    pub fn name_of(&self, tipe: TypeId) -> String {
+        self.name_of_inner(Some(tipe), 0)
+    }
+
+    fn name_of_inner(&self, tipe: Option<TypeId>, depth: usize) -> String {
+        if depth > MAX_DEPTH {
+            return "?".to_string();
+        }
+
+        let tipe = match tipe.and_then(|tipe| self.types.get(tipe)) {
+            Some(tipe) => tipe,
+            None => return "void".to_string(),
+        };
+
+        match tipe {
+            Type::Base { name, .. } => name.clone(),
+            Type::Struct {
+                name: Some(name), ..
+            } => name.clone(),
+            Type::Struct { is_union, .. } => {
+                if *is_union {
+                    "union".to_string()
+                } else {
+                    "struct".to_string()
+                }
+            }
+            Type::Enum { name, .. } => name.clone().unwrap_or_else(|| "enum".to_string()),
+            Type::Pointer { target, .. } => format!("{} *", self.name_of_inner(*target, depth + 1)),
+            Type::Array { elem, count } => {
+                format!("{}[{count}]", self.name_of_inner(Some(*elem), depth + 1))
+            }
+            Type::Typedef { name, .. } => name.clone(),
+            Type::Qualified { qualifier, target } => {
+                let name = self.name_of_inner(*target, depth + 1);
+
+                // Arrays of qualified elements are often qualified themselves.
+                if name.starts_with(qualifier) {
+                    name
+                } else {
+                    format!("{qualifier} {name}")
+                }
+            }
+            Type::Function { ret, params, variadic } => {
+                let ret = self.name_of_inner(*ret, depth + 1);
+                let params = self.params_of(params, *variadic, depth + 1);
+                format!("{ret} ({params})")
+            }
+            Type::Unknown => "?".to_string(),
+        }
+    }
+
+    fn params_of(&self, params: &[TypeId], variadic: bool, depth: usize) -> String {
+        let mut names: Vec<String> =
+            params.iter().map(|param| self.name_of_inner(Some(*param), depth)).collect();
+
+        if variadic {
+            names.push("...".to_string());
+        }
+
+        names.join(", ")
+    }
+
+    /// C-like prototype of a function named `name`, if `tipe` is a function.
+    pub fn prototype(&self, tipe: TypeId, name: &str) -> Option<String> {
+        let (ret, params, variadic) = match self.types.get(tipe)? {
+            Type::Function { ret, params, variadic } => (ret, params, variadic),
+            _ => return None,
+        };
+
+        let ret = self.name_of_inner(*ret, 0);
+
+        // Demangled C++ names already list their parameters.
+        if name.contains('(') {
+            return Some(format!("{ret} {name}"));
+        }
+
+        Some(format!("{ret} {name}({})", self.params_of(params, *variadic, 0)))
+    }
+
+    /// Split the bytes of a variable into it's fields.
+    pub fn layout(&self, var: &Variable, bytes: &[u8], endian: Endianness) -> Layout {
+        let mut layout = Layout::default();
+        let mut cx = LayoutContext {
+            bytes,
+            endian,
+            layout: &mut layout,
+        };
+        self.layout_inner(&mut cx, var.tipe, var.name.clone(), 0, None, 0);
+        layout
+    }
+
+    fn layout_inner(
+        &self,
+        cx: &mut LayoutContext,
+        tipe: TypeId,
+        name: String,
+        offset: usize,
+        bits: Option<(usize, usize)>,
+        depth: usize,
+    ) {
+        if depth > MAX_DEPTH || cx.layout.fields.len() >= MAX_FIELDS {
+            return;
+        }
+
+        let (resolved, encoding) = self.resolve(tipe);
+        match self.types.get(resolved) {
+            Some(Type::Struct { members, .. }) => {
+                for member in members {
+                    if cx.layout.fields.len() >= MAX_FIELDS {
+                        break;
+                    }
+
+                    let name = match &member.name {
+                        Some(member) => format!("{name}.{member}"),
+                        // Anonymous structs and unions share the name of their parent.
+                        None => name.clone(),
+                    };
+                    let offset = offset.saturating_add(member.offset);
+                    self.layout_inner(cx, member.tipe, name, offset, member.bits, depth + 1);
+                }
+            }
+            Some(Type::Array { elem, count }) => {
+                let elem_size = match self.size_of(*elem) {
+                    Some(size) if size > 0 => size,
+                    _ => return,
+                };
+
+                if self.resolve(*elem).1 == Some(Encoding::Char) && elem_size == 1 {
+                    let bytes = cx.bytes(offset, *count);
+                    let end = bytes.iter().position(|&b| b == b'\0').unwrap_or(bytes.len());
+                    let value = format!(
+                        "\"{}\"",
+                        String::from_utf8_lossy(&bytes[..end]).escape_debug()
+                    );
+                    cx.push(self, tipe, name, offset, *count, value, None);
+                    return;
+                }
+
+                for idx in 0..*count {
+                    // Elements past the end of the bytes can't be shown either.
+                    let elem_offset = idx.checked_mul(elem_size);
+                    let offset = match elem_offset.and_then(|elem| offset.checked_add(elem)) {
+                        Some(offset) if offset < cx.bytes.len() => offset,
+                        _ => break,
+                    };
+
+                    if cx.layout.fields.len() >= MAX_FIELDS {
+                        break;
+                    }
+
+                    let name = format!("{name}[{idx}]");
+                    self.layout_inner(cx, *elem, name, offset, None, depth + 1);
+                }
+            }
+            Some(Type::Enum { size, variants, .. }) => {
+                let value = cx.read(offset, *size, true);
+                let value = match variants.iter().find(|(variant, _)| *variant == value) {
+                    Some((_, variant)) => variant.clone(),
+                    None => format!("{value:#x}"),
+                };
+                cx.push(self, tipe, name, offset, *size, value, None);
+            }
+            Some(Type::Pointer { size, .. }) => {
+                let value = cx.read(offset, *size, false) as u64;
+                let pointer = Some(value as usize).filter(|&addr| addr != 0);
+                cx.push(
+                    self,
+                    tipe,
+                    name,
+                    offset,
+                    *size,
+                    format!("{value:#x}"),
+                    pointer,
+                );
+            }
+            Some(Type::Base { size, .. }) => {
+                let size = match bits {
+                    Some((bit_offset, width)) => (bit_offset + width).div_ceil(8),
+                    None => *size,
+                };
+                let value = match bits {
+                    Some((bit_offset, width)) => {
+                        let value = cx.read_bits(offset, bit_offset, width);
+                        match encoding {
+                            Some(Encoding::Signed) if width > 0 && width < 64 => {
+                                let shift = 64 - width;
+                                (((value as i64) << shift) >> shift).to_string()
+                            }
+                            Some(Encoding::Bool) => (value != 0).to_string(),
+                            _ => format!("{value:#x}"),
+                        }
+                    }
+                    None => format_scalar(cx, offset, size, encoding),
+                };
+                cx.push(self, tipe, name, offset, size, value, None);
+            }
+            _ => {
+                // Nothing is known about the type, show whatever bytes it might cover.
+                let size = self.size_of(tipe).unwrap_or(0);
+                let bytes = cx.bytes(offset, size);
+                let value = bytes.iter().map(|b| format!("{b:02x}")).collect::<Vec<_>>().join(" ");
+                cx.push(self, tipe, name, offset, size, value, None);
+            }
+        }
+    }
+
+    /// Follow typedefs and qualifiers to the underlying type, and it's encoding if it's
+    /// a base type.
+    fn resolve(&self, mut tipe: TypeId) -> (TypeId, Option<Encoding>) {
+        for _ in 0..MAX_DEPTH {
+            match self.types.get(tipe) {
+                Some(Type::Typedef {
+                    target: Some(target),
+                    ..
+                })
+                | Some(Type::Qualified {
+                    target: Some(target),
+                    ..
+                }) => tipe = *target,
+                Some(Type::Base { encoding, .. }) => return (tipe, Some(*encoding)),
+                _ => break,
+            }
+        }
+
+        (tipe, None)
+    }
+}
+
+struct LayoutContext<'a> {
+    bytes: &'a [u8],
+    endian: Endianness,
+    layout: &'a mut Layout,
+}
+
+impl LayoutContext<'_> {
+    fn bytes(&self, offset: usize, len: usize) -> &[u8] {
+        let start = std::cmp::min(offset, self.bytes.len());
+        let end = std::cmp::min(offset.saturating_add(len), self.bytes.len());
+        &self.bytes[start..end]
+    }
+
+    /// Read an integer of up to 8 bytes.
+    fn read(&self, offset: usize, size: usize, signed: bool) -> i64 {
+        let bytes = self.bytes(offset, std::cmp::min(size, 8));
+        let value = read_uint(bytes, self.endian);
+
+        let width = bytes.len() * 8;
+        if signed && width > 0 && width < 64 {
+            let shift = 64 - width;
+            ((value as i64) << shift) >> shift
+        } else {
+            value as i64
+        }
+    }
+
+    fn read_bits(&self, offset: usize, bit_offset: usize, width: usize) -> u64 {
+        let start = offset + bit_offset / 8;
+        let shift = bit_offset % 8;
+        let len = std::cmp::min((shift + width).div_ceil(8), 8);
+        let value = read_uint(self.bytes(start, len), self.endian);
+
+        // Bits are numbered from the least significant end on little endian and from the most
+        // significant end on big endian.
+        let value = match self.endian {
+            Endianness::Little => value >> shift,
+            Endianness::Big => value >> (len * 8).saturating_sub(shift + width),
+        };
+
+        if width >= 64 {
+            value
+        } else {
+            value & ((1 << width) - 1)
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push(
+        &mut self,
+        db: &TypeDb,
+        tipe: TypeId,
+        name: String,
+        offset: usize,
+        size: usize,
+        value: String,
+        pointer: Option<usize>,
+    ) {
+        // Fields past the end of the bytes can't be shown.
+        let end = offset.saturating_add(size);
+        if end > self.bytes.len() || self.layout.fields.len() >= MAX_FIELDS {
+            return;
+        }
+
+        self.layout.fields.push(Field {
+            offset,
+            name,
+            tipe: db.name_of(tipe),
+            value,
+            pointer,
+        });
+        self.layout.size = std::cmp::max(self.layout.size, end);
+    }
+}
+
+fn format_scalar(
+    cx: &LayoutContext,
+    offset: usize,
+    size: usize,
+    encoding: Option<Encoding>,
+) -> String {
+    match encoding {
+        Some(Encoding::Signed) => cx.read(offset, size, true).to_string(),
+        Some(Encoding::Bool) => (cx.read(offset, size, false) != 0).to_string(),
+        Some(Encoding::Float) if size == 4 => {
+            f32::from_bits(cx.read(offset, size, false) as u32).to_string()
+        }
+        Some(Encoding::Float) if size == 8 => {
+            f64::from_bits(cx.read(offset, size, false) as u64).to_string()
+        }
+        Some(Encoding::Char) if size == 1 => {
+            let value = cx.read(offset, size, false) as u8;
+            if value.is_ascii_graphic() || value == b' ' {
+                format!("'{}'", value as char)
+            } else {
+                format!("{value:#x}")
+            }
+        }
+        _ => format!("{:#x}", cx.read(offset, size, false)),
+    }
+}
+
+fn read_uint(bytes: &[u8], endian: Endianness) -> u64 {
+    match endian {
+        Endianness::Little => bytes.iter().rev().fold(0, |acc, &b| acc << 8 | b as u64),
+        Endianness::Big => bytes.iter().fold(0, |acc, &b| acc << 8 | b as u64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INT: TypeId = 0;
+    const CHAR: TypeId = 1;
+    const POINT: TypeId = 4;
+    const HUGE: TypeId = 5;
+
+    fn db() -> TypeDb {
+        let member = |name: &str, offset, tipe, bits| Member {
+            name: Some(name.to_string()),
+            offset,
+            tipe,
+            bits,
+        };
+
+        let types = vec![
+            Type::Base {
+                name: "int".to_string(),
+                size: 4,
+                encoding: Encoding::Signed,
+            },
+            Type::Base {
+                name: "char".to_string(),
+                size: 1,
+                encoding: Encoding::Char,
+            },
+            Type::Array {
+                elem: CHAR,
+                count: 8,
+            },
+            Type::Pointer {
+                size: 8,
+                target: Some(POINT),
+            },
+            Type::Struct {
+                name: Some("point".to_string()),
+                size: 24,
+                members: vec![
+                    member("x", 0, INT, None),
+                    member("low", 4, INT, Some((0, 3))),
+                    member("high", 4, INT, Some((3, 5))),
+                    member("name", 8, 2, None),
+                    member("next", 16, 3, None),
+                ],
+                is_union: false,
+            },
+            Type::Array {
+                elem: INT,
+                count: usize::MAX / 2,
+            },
+            Type::Array {
+                elem: HUGE,
+                count: 4,
+            },
+        ];
+
+        TypeDb {
+            types,
+            ..Default::default()
+        }
+    }
+
+    fn expand(db: &TypeDb, tipe: TypeId, bytes: &[u8]) -> Layout {
+        let var = Variable {
+            name: "var".to_string(),
+            tipe,
+        };
+        db.layout(&var, bytes, Endianness::Little)
+    }
+
+    #[test]
+    fn sizes() {
+        let db = db();
+        assert_eq!(db.size_of(POINT), Some(24));
+        assert_eq!(db.size_of(2), Some(8));
+        assert_eq!(db.size_of(HUGE), None);
+        assert_eq!(db.size_of(6), None);
+    }
+
+    #[test]
+    fn struct_fields() {
+        let mut bytes = (-5i32).to_le_bytes().to_vec();
+        bytes.extend(0b1011_0101u32.to_le_bytes());
+        bytes.extend(b"ab\0cdefg");
+        bytes.extend(0x1000u64.to_le_bytes());
+
+        let layout = expand(&db(), POINT, &bytes);
+        let fields: Vec<_> = layout
+            .fields
+            .iter()
+            .map(|field| {
+                (
+                    field.offset,
+                    field.name.as_str(),
+                    field.value.as_str(),
+                    field.pointer,
+                )
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (0, "var.x", "-5", None),
+                (4, "var.low", "-3", None),
+                (4, "var.high", "-10", None),
+                (8, "var.name", "\"ab\"", None),
+                (16, "var.next", "0x1000", Some(0x1000)),
+            ]
+        );
+        assert_eq!(layout.size, 24);
+    }
+
+    #[test]
+    fn huge_arrays() {
+        // Only the elements the bytes cover are expanded.
+        let layout = expand(&db(), HUGE, &[1, 0, 0, 0, 2, 0, 0, 0, 3, 0]);
+        let fields: Vec<_> = layout.fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(fields, ["var[0]", "var[1]"]);
+        assert_eq!(layout.size, 8);
+
+        // Nested arrays whose size overflows are left as bytes.
+        let layout = expand(&db(), 6, &[0; 16]);
+        assert!(layout.fields.is_empty());
+    }
+
+    #[test]
+    fn field_limit() {
+        let layout = expand(&db(), HUGE, &[0; 4 * (MAX_FIELDS + 100)]);
+        assert_eq!(layout.fields.len(), MAX_FIELDS);
+        assert_eq!(layout.fields[MAX_FIELDS - 1].offset, 4 * (MAX_FIELDS - 1));
+    }
+}
@@ -9,11 +9,14 @@ impl super::UI {
 
     /// Runs a singular commands, returning if it should exit the process.
     fn process_cmd(&mut self, cmd: &str) -> bool {
-        let empty_index = debugvault::Index::default();
-        let index = self.panels.processor().map(|proc| &proc.index).unwrap_or(&empty_index);
+        let command = {
+            let empty_index = debugvault::Index::default();
+            let index = self.panels.processor().map(|proc| proc.index());
+            Command::parse(index.as_deref().unwrap_or(&empty_index), cmd, 0)
+        };
 
-        match Command::parse(index, cmd, 0) {
-            Ok(Command::Load(path)) => self.offload_binary_processing(path),
+        match command {
+            Ok(Command::Load(path)) => self.offload_binary_processing(path, Vec::new()),
             Ok(Command::PrintPath) => match std::env::current_dir() {
                 Ok(path) => tprint!(
                     self.panels.terminal(),
@@ -54,6 +57,25 @@ impl super::UI {
 
                 self.panels.load_src(addr);
             }
+            Ok(Command::ImportSymbols(path)) => self.offload_symbol_import(path),
+            Ok(Command::ExportSymbols(path)) => {
+                let processor = match self.panels.processor() {
+                    Some(processor) => processor,
+                    None => {
+                        tprint!(self.panels.terminal(), "No targets loaded.");
+                        return true;
+                    }
+                };
+
+                match processor.export_symbols(&path) {
+                    Ok(count) => tprint!(
+                        self.panels.terminal(),
+                        "Exported {count} symbols to {}.",
+                        path.display()
+                    ),
+                    Err(err) => tprint!(self.panels.terminal(), "Failed to export symbols: {err}."),
+                }
+            }
             Ok(Command::Quit) => return false,
             Ok(Command::Clear) => {
                 log::LOGGER.write().unwrap().clear();
@@ -57,6 +57,7 @@ pub enum UIEvent {
     BinaryRequested(std::path::PathBuf),
     BinaryFailed(processor::Error),
     BinaryLoaded(processor::Processor),
+    SymbolsImported,
     GotoAddr(usize),
 }
 
@@ -137,11 +138,16 @@ impl UI {
 
     pub fn process_args(&mut self) {
         if let Some(path) = commands::ARGS.path.as_ref().cloned() {
-            self.offload_binary_processing(path);
+            self.offload_binary_processing(path, commands::ARGS.imports.clone());
         }
     }
 
-    fn offload_binary_processing(&mut self, path: std::path::PathBuf) {
+    /// Parses the binary at `path` on a separate thread, merging in the symbols at `imports`.
+    fn offload_binary_processing(
+        &mut self,
+        path: std::path::PathBuf,
+        imports: Vec<std::path::PathBuf>,
+    ) {
         // don't load multiple binaries at a time
         if self.panels.is_loading() {
             return;
@@ -152,12 +158,41 @@ impl UI {
 
         std::thread::spawn(move || {
             match processor::Processor::parse(&path) {
-                Ok(diss) => ui_queue.push(UIEvent::BinaryLoaded(diss)),
+                Ok(diss) => {
+                    for path in imports {
+                        import_symbols(&diss, &path);
+                    }
+
+                    ui_queue.push(UIEvent::BinaryLoaded(diss));
+                }
                 Err(err) => ui_queue.push(UIEvent::BinaryFailed(err)),
             };
         });
     }
 
+    /// Merges the symbols at `path` into the loaded binary on a separate thread.
+    fn offload_symbol_import(&mut self, path: std::path::PathBuf) {
+        if self.panels.is_loading() {
+            return;
+        }
+
+        let processor = match self.panels.processor() {
+            Some(processor) => Arc::clone(processor),
+            None => {
+                tprint!(self.panels.terminal(), "No targets loaded.");
+                return;
+            }
+        };
+
+        self.panels.start_loading();
+        let ui_queue = self.ui_queue.clone();
+
+        std::thread::spawn(move || {
+            import_symbols(&processor, &path);
+            ui_queue.push(UIEvent::SymbolsImported);
+        });
+    }
+
     fn handle_ui_events(&mut self) {
         #[cfg(target_os = "macos")]
         while let Ok(event) = self.arch.menu_channel.try_recv() {
@@ -175,6 +210,14 @@ impl UI {
                     self.panels.goto_window(panes::FUNCTIONS);
                     self.arch.bar.set_checked(panes::FUNCTIONS);
                 }
+                panes::FILE_INFO => {
+                    self.panels.goto_window(panes::FILE_INFO);
+                    self.arch.bar.set_checked(panes::FILE_INFO);
+                }
+                panes::CLASSES => {
+                    self.panels.goto_window(panes::CLASSES);
+                    self.arch.bar.set_checked(panes::CLASSES);
+                }
                 panes::LOGGING => {
                     self.panels.goto_window(panes::LOGGING);
                     self.arch.bar.set_checked(panes::LOGGING);
@@ -190,14 +233,18 @@ impl UI {
                     log::warning!("{err:?}");
                 }
                 UIEvent::BinaryRequested(path) => {
-                    self.offload_binary_processing(path);
+                    self.offload_binary_processing(path, Vec::new());
                 }
                 UIEvent::BinaryLoaded(disassembly) => {
                     #[cfg(target_os = "macos")]
                     self.arch.bar.set_path(&disassembly.path);
 
                     self.panels.stop_loading();
-                    self.panels.load_binary(disassembly);
+                    self.panels.load_binary(Arc::new(disassembly));
+                }
+                UIEvent::SymbolsImported => {
+                    self.panels.stop_loading();
+                    self.panels.refresh_functions();
                 }
                 UIEvent::GotoAddr(addr) => {
                     if let Some(listing) = self.panels.listing() {
@@ -250,7 +297,9 @@ impl UI {
                         self.instance.resize(size.width, size.height);
                         self.window.request_redraw();
                     }
-                    WindowEvent::DroppedFile(path) => self.offload_binary_processing(path),
+                    WindowEvent::DroppedFile(path) => {
+                        self.offload_binary_processing(path, Vec::new())
+                    }
                     WindowEvent::CloseRequested => target.exit(),
                     _ => {}
                 },
@@ -268,3 +317,14 @@ impl UI {
         });
     }
 }
+
+fn import_symbols(diss: &processor::Processor, path: &std::path::Path) {
+    if let Err(err) = diss.import(path) {
+        log::complex!(
+            w "[ui::import_symbols] failed to read ",
+            g path.display().to_string(),
+            w ": ",
+            r format!("{err}."),
+        );
+    }
+}
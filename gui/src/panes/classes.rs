@@ -0,0 +1,150 @@
+use crate::common::*;
+use crate::{UIEvent, UiQueue};
+use config::CONFIG;
+use debugvault::TokenStream;
+use processor::Processor;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokenizing::{colors, Token};
+
+/// How deep a hierarchy gets rendered, in case the type information is cyclic.
+const MAX_DEPTH: usize = 64;
+
+pub struct Classes {
+    processor: Arc<Processor>,
+    ui_queue: Arc<UiQueue>,
+    /// Demangled name of every class.
+    names: Vec<TokenStream>,
+    /// Demangled names of the direct bases of every class.
+    bases: Vec<Vec<TokenStream>>,
+    /// Classes directly deriving from every class, by index.
+    derived: Vec<Vec<usize>>,
+    /// Classes without a base that has type information of it's own.
+    roots: Vec<usize>,
+}
+
+impl Classes {
+    pub fn new(processor: Arc<Processor>, ui_queue: Arc<UiQueue>) -> Self {
+        let classes = &processor.classes;
+        let names: Vec<TokenStream> =
+            classes.iter().map(|class| debugvault::demangle(&class.name)).collect();
+        let bases = classes
+            .iter()
+            .map(|class| class.bases.iter().map(|base| debugvault::demangle(base)).collect())
+            .collect();
+
+        let by_name: HashMap<&str, usize> =
+            classes.iter().enumerate().map(|(idx, class)| (&class.name[..], idx)).collect();
+
+        let mut derived = vec![Vec::new(); classes.len()];
+        let mut roots = Vec::new();
+        for (idx, class) in classes.iter().enumerate() {
+            let mut has_base = false;
+            for base in class.bases.iter() {
+                if let Some(&base) = by_name.get(&base[..]) {
+                    derived[base].push(idx);
+                    has_base = true;
+                }
+            }
+
+            if !has_base {
+                roots.push(idx);
+            }
+        }
+
+        let sorted: Vec<String> = names
+            .iter()
+            .map(|name| String::from_iter(name.tokens().iter().map(|t| &t.text[..])))
+            .collect();
+        roots.sort_unstable_by(|a, b| sorted[*a].cmp(&sorted[*b]));
+        for derived in derived.iter_mut() {
+            derived.sort_unstable_by(|a, b| sorted[*a].cmp(&sorted[*b]));
+        }
+
+        Self {
+            processor,
+            ui_queue,
+            names,
+            bases,
+            derived,
+            roots,
+        }
+    }
+
+    fn show_class(&self, ui: &mut egui::Ui, idx: usize, depth: usize) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+
+        let class = &self.processor.classes[idx];
+        let header =
+            egui::CollapsingHeader::new(tokens_to_layoutjob(self.names[idx].tokens().to_vec()))
+                .id_source(idx)
+                .default_open(depth == 0 && self.roots.len() == 1);
+
+        header.show(ui, |ui| {
+            if !self.bases[idx].is_empty() {
+                let mut tokens = vec![Token::from_str("bases: ", CONFIG.colors.src.field)];
+                for (base_idx, base) in self.bases[idx].iter().enumerate() {
+                    if base_idx != 0 {
+                        tokens.push(Token::from_str(", ", CONFIG.colors.delimiter));
+                    }
+                    tokens.extend(base.tokens().iter().cloned());
+                }
+                ui.label(tokens_to_layoutjob(tokens));
+            }
+
+            let typeinfo = vec![
+                Token::from_str("type info: ", CONFIG.colors.src.field),
+                Token::from_string(format!("{:#x}", class.typeinfo), colors::WHITE),
+            ];
+
+            if ui.link(tokens_to_layoutjob(typeinfo)).clicked() {
+                self.ui_queue.push(UIEvent::GotoAddr(class.typeinfo));
+            }
+
+            for (vtable_idx, (vtable, slots)) in class.vtables.iter().enumerate() {
+                let title = vec![
+                    Token::from_str("vtable ", CONFIG.colors.asm.section),
+                    Token::from_string(format!("{vtable:#x}"), colors::WHITE),
+                ];
+
+                let header = egui::CollapsingHeader::new(tokens_to_layoutjob(title))
+                    .id_source(("vtable", vtable_idx));
+
+                header.show(ui, |ui| {
+                    for (slot_idx, &addr) in slots.iter().enumerate() {
+                        let mut tokens = vec![
+                            Token::from_string(format!("[{slot_idx}] "), CONFIG.colors.delimiter),
+                            Token::from_string(format!("{addr:0>10X} "), colors::WHITE),
+                        ];
+
+                        if let Some(sym) = self.processor.index().get_sym_by_addr(addr) {
+                            tokens.extend(sym.name().iter().cloned());
+                        }
+
+                        if ui.link(tokens_to_layoutjob(tokens)).clicked() {
+                            self.ui_queue.push(UIEvent::GotoAddr(addr));
+                        }
+                    }
+                });
+            }
+
+            for &derived in self.derived[idx].iter() {
+                self.show_class(ui, derived, depth + 1);
+            }
+        });
+    }
+}
+
+impl Display for Classes {
+    fn show(&mut self, ui: &mut egui::Ui) {
+        let area = egui::ScrollArea::both().auto_shrink([false, false]).drag_to_scroll(false);
+
+        area.show(ui, |ui| {
+            for &idx in self.roots.iter() {
+                self.show_class(ui, idx, 0);
+            }
+        });
+    }
+}
@@ -0,0 +1,43 @@
+use crate::common::*;
+use config::CONFIG;
+use processor::Processor;
+use std::sync::Arc;
+use tokenizing::{colors, Token};
+
+pub struct FileInfo {
+    processor: Arc<Processor>,
+}
+
+impl FileInfo {
+    pub fn new(processor: Arc<Processor>) -> Self {
+        Self { processor }
+    }
+}
+
+impl Display for FileInfo {
+    fn show(&mut self, ui: &mut egui::Ui) {
+        let area = egui::ScrollArea::both().auto_shrink([false, false]).drag_to_scroll(false);
+
+        area.show(ui, |ui| {
+            for (idx, info) in self.processor.file_info.iter().enumerate() {
+                let title = Token::from_str(info.title, CONFIG.colors.asm.section);
+                let header = egui::CollapsingHeader::new(tokens_to_layoutjob(vec![title]))
+                    .id_source(idx)
+                    .default_open(true);
+
+                header.show(ui, |ui| {
+                    egui::Grid::new(idx).num_columns(2).spacing([20.0, 2.0]).show(ui, |ui| {
+                        for (key, value) in info.entries.iter() {
+                            let key = Token::from_string(key.clone(), CONFIG.colors.src.field);
+                            let value = Token::from_string(value.clone(), colors::WHITE);
+
+                            ui.label(tokens_to_layoutjob(vec![key]));
+                            ui.label(tokens_to_layoutjob(vec![value]));
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+        });
+    }
+}
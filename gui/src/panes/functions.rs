@@ -17,7 +17,7 @@ pub struct Functions {
 
 impl Functions {
     pub fn new(processor: Arc<Processor>, ui_queue: Arc<UiQueue>) -> Self {
-        let function_count = processor.index.named_funcs_count();
+        let function_count = processor.index().named_funcs_count();
 
         Self {
             processor,
@@ -28,6 +28,13 @@ impl Functions {
             max_row: 0,
         }
     }
+
+    /// Re-reads the functions on the next frame, after symbols got added to the index.
+    pub fn refresh(&mut self) {
+        self.lines_count = self.processor.index().named_funcs_count();
+        self.min_row = 0;
+        self.max_row = 0;
+    }
 }
 
 fn tokenize_functions(index: &debugvault::Index, range: std::ops::Range<usize>) -> Vec<(usize, Vec<Token>)> {
@@ -53,6 +60,12 @@ fn tokenize_functions(index: &debugvault::Index, range: std::ops::Range<usize>)
             tokens.push(token.clone());
         }
 
+        // Written the way module definition files list exports by ordinal.
+        if let Some(ordinal) = item.ordinal() {
+            tokens.push(Token::from_str(" @", CONFIG.colors.delimiter));
+            tokens.push(Token::from_string(ordinal.to_string(), CONFIG.colors.asm.immediate));
+        }
+
         functions.push((*addr, tokens));
     }
 
@@ -65,8 +78,9 @@ impl Display for Functions {
 
         area.show_rows(ui, FONT.size, self.lines_count, |ui, row_range| {
             if row_range != (self.min_row..self.max_row) {
-                self.lines = tokenize_functions(&self.processor.index, row_range.clone());
-                self.lines_count = self.processor.index.named_funcs_count();
+                let index = self.processor.index();
+                self.lines = tokenize_functions(&index, row_range.clone());
+                self.lines_count = index.named_funcs_count();
                 self.min_row = row_range.start;
                 self.max_row = row_range.end;
             }
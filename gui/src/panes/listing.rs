@@ -247,7 +247,7 @@ impl Display for Listing {
 
                 match block.content {
                     BlockContent::Instruction { .. } => {
-                        draw_instruction(ui, stream.inner, &self.processor.index, &self.ui_queue);
+                        draw_instruction(ui, stream.inner, &self.processor.index(), &self.ui_queue);
                     }
                     BlockContent::Label { .. } => {
                         if ui.link(tokens_to_layoutjob(stream.inner)).clicked() {
@@ -1,3 +1,5 @@
+mod classes;
+mod file_info;
 mod functions;
 mod listing;
 mod source_code;
@@ -18,11 +20,15 @@ pub type Identifier = &'static str;
 pub const SOURCE: Identifier = crate::icon!(EMBED2, " Source");
 pub const DISASSEMBLY: Identifier = crate::icon!(PARAGRAPH_LEFT, " Disassembly");
 pub const FUNCTIONS: Identifier = crate::icon!(LIGATURE, " Functions");
+pub const FILE_INFO: Identifier = crate::icon!(INFO, " File info");
+pub const CLASSES: Identifier = crate::icon!(TREE, " Classes");
 pub const LOGGING: Identifier = crate::icon!(TERMINAL, " Logs");
 
 enum PanelKind {
     Disassembly(listing::Listing),
     Functions(functions::Functions),
+    FileInfo(file_info::FileInfo),
+    Classes(classes::Classes),
     Source(source_code::Source),
     Logging,
 }
@@ -108,6 +114,8 @@ impl egui_tiles::Behavior<Identifier> for Tabs {
             match self.mapping.get_mut(pane) {
                 Some(PanelKind::Disassembly(disassembly)) => disassembly.show(ui),
                 Some(PanelKind::Functions(functions)) => functions.show(ui),
+                Some(PanelKind::FileInfo(file_info)) => file_info.show(ui),
+                Some(PanelKind::Classes(classes)) => classes.show(ui),
                 Some(PanelKind::Source(src)) => src.show(ui),
                 Some(PanelKind::Logging) => {
                     let area = egui::ScrollArea::vertical()
@@ -190,20 +198,25 @@ impl Panels {
 
     /// Jump to both the source and the assembly.
     pub fn load_src(&mut self, addr: usize) {
-        let file_attr = match self.processor().and_then(|proc| proc.index.get_file_by_addr(addr)) {
+        let processor = match self.panes.processor.as_ref() {
+            Some(processor) => processor,
+            None => return,
+        };
+
+        let index = processor.index();
+        let file_attr = match index.get_file_by_addr(addr) {
             Some(file_attr) => file_attr,
             None => return,
         };
 
         if let Ok(src) = std::fs::read_to_string(&file_attr.path) {
-            let src = source_code::Source::new(&src, file_attr);
+            let frames = index.inline_frames(addr);
+            let src = source_code::Source::new(&src, file_attr, &frames);
             self.panes.mapping.insert(SOURCE, PanelKind::Source(src));
         }
     }
 
-    pub fn load_binary(&mut self, processor: Processor) {
-        let processor = Arc::new(processor);
-
+    pub fn load_binary(&mut self, processor: Arc<Processor>) {
         self.panes.mapping.insert(
             DISASSEMBLY,
             PanelKind::Disassembly(listing::Listing::new(
@@ -220,9 +233,27 @@ impl Panels {
             )),
         );
 
+        self.panes.mapping.insert(
+            FILE_INFO,
+            PanelKind::FileInfo(file_info::FileInfo::new(processor.clone())),
+        );
+
+        self.panes.mapping.insert(
+            CLASSES,
+            PanelKind::Classes(classes::Classes::new(processor.clone(), self.ui_queue.clone())),
+        );
+
         self.panes.processor = Some(processor);
     }
 
+    /// Closes the panes showing the binary, handing it back.
+    /// Shows symbols that got imported into the loaded binary, leaving the other panes as is.
+    pub fn refresh_functions(&mut self) {
+        if let Some(PanelKind::Functions(functions)) = self.panes.mapping.get_mut(FUNCTIONS) {
+            functions.refresh();
+        }
+    }
+
     pub fn ask_for_binary(&self) {
         if let Some(path) = rfd::FileDialog::new().pick_file() {
             self.ui_queue.push(crate::UIEvent::BinaryRequested(path));
@@ -235,8 +266,8 @@ impl Panels {
         }
 
         let empty_index = debugvault::Index::default();
-        let index = self.panes.processor.as_ref().map(|proc| &proc.index).unwrap_or(&empty_index);
-        self.panes.terminal.record_input(events, index);
+        let index = self.panes.processor.as_ref().map(|proc| proc.index());
+        self.panes.terminal.record_input(events, index.as_deref().unwrap_or(&empty_index));
     }
 
     pub fn goto_window(&mut self, tile: Identifier) {
@@ -331,6 +362,16 @@ impl Panels {
                     ui.close_menu();
                 }
 
+                if ui.button(FILE_INFO).clicked() {
+                    self.goto_window(FILE_INFO);
+                    ui.close_menu();
+                }
+
+                if ui.button(CLASSES).clicked() {
+                    self.goto_window(CLASSES);
+                    ui.close_menu();
+                }
+
                 if ui.button(SOURCE).clicked() {
                     self.goto_window(SOURCE);
                     ui.close_menu();
@@ -10,7 +10,7 @@ use tree_sitter_highlight::{Highlight, HighlightConfiguration, HighlightEvent, H
 
 use crate::common::*;
 use config::CONFIG;
-use debugvault::FileAttr;
+use debugvault::{FileAttr, InlineFrame};
 use tokenizing::colors;
 
 pub struct Source {
@@ -19,6 +19,8 @@ pub struct Source {
     max_number_width: usize,
     scroll: Option<usize>,
     cache: (Range<usize>, Arc<Galley>),
+    /// Calls the shown line got inlined through, innermost first.
+    inlined: Vec<String>,
 }
 
 struct Line {
@@ -134,7 +136,7 @@ fn find_matching_sections(
 }
 
 impl Source {
-    pub fn new(src: &str, file_attr: &FileAttr) -> Self {
+    pub fn new(src: &str, file_attr: &FileAttr, frames: &[InlineFrame]) -> Self {
         let max_width = (src.lines().count().ilog10() + 1) as usize;
         let mut lines = Vec::new();
         let sections = compute_sections(&file_attr.path, &src);
@@ -173,12 +175,15 @@ impl Source {
             }),
         );
 
+        let inlined = frames.iter().map(|frame| frame.to_string()).collect();
+
         Self {
             src: src.to_string(),
             lines,
             max_number_width: max_width,
             scroll: Some(file_attr.line.saturating_sub(1)),
             cache,
+            inlined,
         }
     }
 }
@@ -220,6 +225,10 @@ impl Source {
     }
 
     pub fn show(&mut self, ui: &mut egui::Ui) {
+        for inlined in self.inlined.iter() {
+            ui.label(egui::RichText::new(inlined).font(FONT).color(CONFIG.colors.comment));
+        }
+
         let mut area = egui::ScrollArea::vertical().auto_shrink(false).drag_to_scroll(false);
 
         if let Some(scroll) = self.scroll.take() {
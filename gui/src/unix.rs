@@ -90,6 +90,20 @@ mod macos {
                 false,
                 None,
             ));
+            windows.push(CheckMenuItem::with_id(
+                panes::FILE_INFO,
+                "File info",
+                true,
+                false,
+                None,
+            ));
+            windows.push(CheckMenuItem::with_id(
+                panes::CLASSES,
+                "Classes",
+                true,
+                false,
+                None,
+            ));
             windows.push(CheckMenuItem::with_id(
                 panes::SOURCE,
                 "Source",
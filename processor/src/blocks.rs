@@ -1,17 +1,27 @@
 use crate::Processor;
-use binformat::elf::{Elf32Dyn, Elf32Sym, Elf64Dyn, Elf64Sym};
+use binformat::elf::{
+    self, Elf32Dyn, Elf32Rel, Elf32Rela, Elf32Sym, Elf64Dyn, Elf64Rel, Elf64Rela, Elf64Sym,
+    ElfGnuHashHeader, ElfVerdaux, ElfVerdef, ElfVernaux, ElfVerneed, ElfVersym, GnuHashLayout,
+    VersionRecord,
+};
 use binformat::pe::ExceptionDirectoryEntry;
-use binformat::ToData;
+use binformat::{Datastructure, Reference, ToData};
 use config::CONFIG;
 use debugvault::Symbol;
-use object::Endian;
-use processor_shared::{encode_hex_bytes_truncated, Section, SectionKind};
+use object::{AddressSize, Endian};
+use processor_shared::{encode_hex_bytes_truncated, Section, SectionKind, SwapBytes};
 use std::mem::size_of;
 use std::sync::Arc;
 use tokenizing::{colors, Token, TokenStream};
 
 const BYTES_BLOCK_SIZE: usize = 256;
 
+/// Shortest run of printable characters shown as a string in between datastructures.
+const MIN_STRING_LEN: usize = 4;
+
+/// Number of words shown per block in tables like the buckets of a `.gnu.hash` section.
+const WORDS_BLOCK_SIZE: usize = 16;
+
 #[derive(Debug)]
 pub enum BlockContent {
     SectionStart {
@@ -52,6 +62,13 @@ pub enum BlockContent {
     },
 }
 
+/// Contents of a [`SectionKind::Structured`] section.
+enum Structured<'a> {
+    Datastructure(&'a Datastructure),
+    String(&'a [u8]),
+    Bytes(&'a [u8]),
+}
+
 #[derive(Debug)]
 pub struct Block {
     pub addr: usize,
@@ -81,6 +98,9 @@ impl Block {
                 stream.push("\n<", CONFIG.colors.asm.label);
                 stream.inner.extend_from_slice(symbol.name());
                 stream.push(">", CONFIG.colors.asm.label);
+                if let Some(prototype) = symbol.prototype() {
+                    stream.push_owned(format!("  ; {prototype}"), CONFIG.colors.comment);
+                }
             }
             BlockContent::SectionStart { section } => {
                 stream.push("section started", colors::WHITE);
@@ -192,7 +212,7 @@ impl Processor {
             return None;
         }
 
-        self.index.get_sym_by_addr(addr)
+        self.index().get_sym_by_addr(addr)
     }
 
     /// Parse blocks given an address boundary.
@@ -273,39 +293,269 @@ impl Processor {
             SectionKind::Elf64Dyn => {
                 self.parse_datastructure::<Elf64Dyn>(addr, section, &mut blocks);
             }
-            // For any other section kinds just assume they're made of bytes.
+            SectionKind::Elf32Rel => {
+                self.parse_datastructure::<Elf32Rel>(addr, section, &mut blocks);
+            }
+            SectionKind::Elf64Rel => {
+                self.parse_datastructure::<Elf64Rel>(addr, section, &mut blocks);
+            }
+            SectionKind::Elf32Rela => {
+                self.parse_datastructure::<Elf32Rela>(addr, section, &mut blocks);
+            }
+            SectionKind::Elf64Rela => {
+                self.parse_datastructure::<Elf64Rela>(addr, section, &mut blocks);
+            }
+            SectionKind::ElfVersym => {
+                self.parse_datastructure::<ElfVersym>(addr, section, &mut blocks);
+            }
+            SectionKind::ElfVerneed | SectionKind::ElfVerdef => {
+                self.parse_version_record(addr, section, &mut blocks);
+            }
+            SectionKind::ElfNote => self.parse_note(addr, section, &mut blocks),
+            SectionKind::ElfGnuHash => self.parse_gnu_hash(addr, section, &mut blocks),
+            SectionKind::Structured => self.parse_structured(addr, section, &mut blocks),
+            // For any other section kinds just assume they're made of bytes, apart from any
+            // variables we know the type of.
             // As a note, we calculate the byte boundaries in blocks of [`BYTES_BLOCK_SIZE`],
             // so this block can be up to [`BYTES_BLOCK_SIZE`] bytes.
             _ => {
-                let bytes = section.bytes_by_addr(addr, BYTES_BLOCK_SIZE).to_vec();
-                blocks.push(Block {
-                    addr,
-                    content: BlockContent::Bytes { bytes },
-                });
+                let content = match self.typed_block(addr, section) {
+                    (Some(datastructure), _) => {
+                        self.resolve_references(datastructure.clone(), section)
+                    }
+                    (None, len) => BlockContent::Bytes {
+                        bytes: section.bytes_by_addr(addr, len).to_vec(),
+                    },
+                };
+
+                blocks.push(Block { addr, content });
             }
         }
 
         blocks
     }
 
-    fn parse_datastructure<T: ToData + object::Pod>(
+    fn parse_datastructure<T: ToData + SwapBytes + object::Pod>(
         &self,
         addr: usize,
         section: &Section,
         blocks: &mut Vec<Block>,
     ) {
-        if let Ok(datastructure) = section.read_at::<T>(addr) {
+        if let Ok(datastructure) = section.read_at::<T>(addr, self.endianness) {
             let datastructure = datastructure.to_fields(addr);
             blocks.push(Block {
                 addr,
-                content: BlockContent::DataStructure {
-                    ident: datastructure.ident,
-                    fields: datastructure.fields,
-                },
+                content: self.resolve_references(datastructure, section),
             })
         }
     }
 
+    /// Append the names of any addresses or strings a datastructure refers to.
+    fn resolve_references(&self, datastructure: Datastructure, section: &Section) -> BlockContent {
+        let mut fields = datastructure.fields;
+
+        for (idx, reference) in datastructure.references {
+            let name = match reference {
+                Reference::Address(addr) => {
+                    self.index().get_sym_by_addr(addr).map(|sym| sym.as_str().to_string())
+                }
+                Reference::String(offset) => self.string(section, offset),
+                Reference::Relocation { info, is_64 } => {
+                    let (sym, r_type) = elf::relocation_info(info, is_64);
+                    let name = self.relocation_symbol(section, sym, is_64);
+                    fields[idx].3 =
+                        elf::describe_relocation(self.arch, r_type, sym, name.as_deref());
+                    continue;
+                }
+            };
+
+            if let Some(name) = name.filter(|name| !name.is_empty()) {
+                fields[idx].3 += &format!(" <{name}>");
+            }
+        }
+
+        BlockContent::DataStructure {
+            ident: datastructure.ident,
+            fields,
+        }
+    }
+
+    /// Read a string from the string table `section` is linked to, or `.dynstr` if it has none.
+    fn string(&self, section: &Section, offset: usize) -> Option<String> {
+        let name = section.strings.as_deref().unwrap_or(".dynstr");
+        let strtab = self.sections().find(|section| section.name == name)?;
+        let bytes = strtab.bytes().get(offset..)?;
+        let end = bytes.iter().position(|&b| b == b'\0')?;
+        Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+
+    /// Name of symbol `index` in the symbol table a relocation `section` is linked to.
+    fn relocation_symbol(&self, section: &Section, index: u32, is_64: bool) -> Option<String> {
+        let name = section.symbols.as_deref()?;
+        let symtab = self.sections().find(|section| section.name == name)?;
+        let offset = elf::symbol_name_offset(symtab.bytes(), index, is_64, self.endianness)?;
+        self.string(symtab, offset)
+    }
+
+    fn parse_version_record(&self, addr: usize, section: &Section, blocks: &mut Vec<Block>) {
+        let definitions = section.kind == SectionKind::ElfVerdef;
+        let records = elf::version_records(section.bytes(), definitions, self.endianness);
+        let rva = addr - section.start;
+
+        match records.iter().find(|(offset, _)| *offset == rva) {
+            Some((_, VersionRecord::Verneed)) => {
+                self.parse_datastructure::<ElfVerneed>(addr, section, blocks)
+            }
+            Some((_, VersionRecord::Vernaux)) => {
+                self.parse_datastructure::<ElfVernaux>(addr, section, blocks)
+            }
+            Some((_, VersionRecord::Verdef)) => {
+                self.parse_datastructure::<ElfVerdef>(addr, section, blocks)
+            }
+            Some((_, VersionRecord::Verdaux)) => {
+                self.parse_datastructure::<ElfVerdaux>(addr, section, blocks)
+            }
+            None => self.parse_bytes(addr, section, blocks),
+        }
+    }
+
+    fn parse_note(&self, addr: usize, section: &Section, blocks: &mut Vec<Block>) {
+        let bytes = section.bytes_by_addr(addr, usize::MAX);
+        let is_64 = self.arch.address_size() != Some(AddressSize::U32);
+
+        match elf::parse_note(bytes, addr, is_64, self.endianness) {
+            Some(datastructure) => blocks.push(Block {
+                addr,
+                content: self.resolve_references(datastructure, section),
+            }),
+            None => self.parse_bytes(addr, section, blocks),
+        }
+    }
+
+    fn parse_gnu_hash(&self, addr: usize, section: &Section, blocks: &mut Vec<Block>) {
+        let is_64 = self.arch.address_size() != Some(AddressSize::U32);
+        let layout = match GnuHashLayout::parse(section.bytes(), is_64, self.endianness) {
+            Some(layout) => layout,
+            None => return self.parse_bytes(addr, section, blocks),
+        };
+
+        let rva = addr - section.start;
+        if rva < layout.bloom {
+            return self.parse_datastructure::<ElfGnuHashHeader>(addr, section, blocks);
+        }
+
+        if rva < layout.buckets {
+            let bytes = section.bytes_by_addr(addr, layout.buckets - rva).to_vec();
+            blocks.push(Block {
+                addr,
+                content: BlockContent::Bytes { bytes },
+            });
+            return;
+        }
+
+        let (ident, name, table_start, table_end) = if rva < layout.chains {
+            ("buckets", "bucket", layout.buckets, layout.chains)
+        } else {
+            ("chains", "chain", layout.chains, section.bytes().len())
+        };
+
+        let block_size = WORDS_BLOCK_SIZE * 4;
+        let block_idx = (rva - table_start) / block_size;
+        let block_end = (table_start + (block_idx + 1) * block_size).min(table_end);
+
+        let mut fields = Vec::new();
+        let mut offset = rva;
+        while offset + 4 <= block_end {
+            let word = section.bytes_by_addr(section.start + offset, 4);
+            let word = self.endianness.read_u32_bytes(word.try_into().unwrap());
+            fields.push((section.start + offset, name, "u32", format!("{word:#x}")));
+            offset += 4;
+        }
+
+        if !fields.is_empty() {
+            blocks.push(Block {
+                addr,
+                content: BlockContent::DataStructure { ident, fields },
+            });
+        }
+    }
+
+    fn parse_structured(&self, addr: usize, section: &Section, blocks: &mut Vec<Block>) {
+        let content = match self.structured_block(addr, section).0 {
+            Structured::Datastructure(datastructure) => {
+                self.resolve_references(datastructure.clone(), section)
+            }
+            Structured::String(bytes) => BlockContent::CString { bytes: bytes.to_vec() },
+            Structured::Bytes(bytes) => BlockContent::Bytes { bytes: bytes.to_vec() },
+        };
+
+        blocks.push(Block { addr, content });
+    }
+
+    /// Find what's at `addr` in a [`SectionKind::Structured`] section and how many bytes it
+    /// spans.
+    ///
+    /// Gaps between datastructures are mostly the strings they point to, so any printable
+    /// null terminated strings of at least [`MIN_STRING_LEN`] are shown as such.
+    fn structured_block<'a>(&'a self, addr: usize, section: &'a Section) -> (Structured<'a>, usize) {
+        let (idx, datastructure) = match self.datastructures.search(addr) {
+            Ok(idx) => (idx + 1, Some(&self.datastructures[idx].item)),
+            Err(idx) => (idx, None),
+        };
+
+        let next = self.datastructures.get(idx).map_or(section.end, |ds| ds.addr);
+        let next = std::cmp::min(next, section.end);
+
+        if let Some(datastructure) = datastructure {
+            let len = std::cmp::min(datastructure.size, next - addr);
+            return (Structured::Datastructure(datastructure), std::cmp::max(len, 1));
+        }
+
+        let bytes = section.bytes_by_addr(addr, next - addr);
+        if bytes.is_empty() {
+            return (Structured::Bytes(bytes), next - addr);
+        }
+
+        let printable = |b: &u8| b.is_ascii_graphic() || *b == b' ';
+        let string_len = bytes.iter().take_while(|b| printable(b)).count();
+        if string_len >= MIN_STRING_LEN && bytes.get(string_len) == Some(&b'\0') {
+            // Include any padding after the string.
+            let padding = bytes[string_len..].iter().take_while(|&&b| b == b'\0').count();
+            return (Structured::String(&bytes[..string_len]), string_len + padding);
+        }
+
+        // Stop at whatever looks like the start of a string.
+        let mut len = 1;
+        while len < bytes.len() && len < BYTES_BLOCK_SIZE {
+            if bytes[len - 1] == b'\0' && printable(&bytes[len]) {
+                break;
+            }
+            len += 1;
+        }
+
+        (Structured::Bytes(&bytes[..len]), len)
+    }
+
+    /// Find the variable at `addr` in a section that's otherwise shown as bytes and how many
+    /// bytes it spans, or how many bytes there are until the next variable.
+    fn typed_block(&self, addr: usize, section: &Section) -> (Option<&Datastructure>, usize) {
+        let (idx, datastructure) = match self.datastructures.search(addr) {
+            Ok(idx) => (idx + 1, Some(&self.datastructures[idx].item)),
+            Err(idx) => (idx, None),
+        };
+
+        let next = self.datastructures.get(idx).map_or(section.end, |ds| ds.addr);
+        let next = std::cmp::min(next, section.end);
+
+        match datastructure {
+            Some(datastructure) => {
+                let len = std::cmp::min(datastructure.size, next - addr);
+                (Some(datastructure), std::cmp::max(len, 1))
+            }
+            None => (None, std::cmp::min(BYTES_BLOCK_SIZE, next - addr)),
+        }
+    }
+
     fn parse_got(&self, addr: usize, size: usize, section: &Section, blocks: &mut Vec<Block>) {
         let symbol = self.get_symbol_by_addr(addr, section).unwrap_or_default();
         blocks.push(Block {
@@ -322,7 +572,10 @@ impl Processor {
             self.endianness.read_u64_bytes(bytes.try_into().unwrap())
         };
 
-        let symbol = self.get_symbol_by_addr(addr, section);
+        // Either the pointer itself is named (e.g. an import) or what it points to is.
+        let symbol = self
+            .get_symbol_by_addr(addr, section)
+            .or_else(|| self.index().get_sym_by_addr(value as usize));
 
         blocks.push(Block {
             addr,
@@ -356,7 +609,21 @@ impl Processor {
 
         if let Some(inst) = opt_inst {
             let width = self.instruction_width(&inst);
-            let inst = self.instruction_tokens(&inst, &self.index);
+            let mut inst = self.instruction_tokens(&inst, &self.index());
+            if let Some(comment) = self.comment_by_addr(addr) {
+                inst.push(Token::from_string(format!("  ; {comment}"), CONFIG.colors.comment));
+            }
+            let locals = self.index().locals(addr);
+            if !locals.is_empty() {
+                let names = crate::locals::referenced(self.arch, &inst, &locals);
+                if !names.is_empty() {
+                    let names = names.join(", ");
+                    inst.push(Token::from_string(format!("  ; {names}"), CONFIG.colors.comment));
+                }
+            }
+            for frame in self.index().inline_frames_starting(addr) {
+                inst.push(Token::from_string(format!("  ; {frame}"), CONFIG.colors.comment));
+            }
             let bytes = section.bytes_by_addr(addr, width);
             let bytes =
                 encode_hex_bytes_truncated(&bytes, self.max_instruction_width * 3 + 1, true);
@@ -473,47 +740,63 @@ impl Processor {
                 }
             }
             SectionKind::ExceptionDirEntry => {
-                let mut addr = section.start;
-                while addr < section.end {
-                    boundaries.push(addr);
-                    addr += size_of::<ExceptionDirectoryEntry>();
-                }
+                let stride = size_of::<ExceptionDirectoryEntry>();
+                self.compute_stride_boundaries(section, stride, &mut boundaries)
             }
             SectionKind::Elf32Sym => {
-                let mut addr = section.start;
-                while addr < section.end {
-                    boundaries.push(addr);
-                    addr += size_of::<Elf32Sym>();
-                }
+                self.compute_stride_boundaries(section, size_of::<Elf32Sym>(), &mut boundaries)
             }
             SectionKind::Elf64Sym => {
-                let mut addr = section.start;
-                while addr < section.end {
-                    boundaries.push(addr);
-                    addr += size_of::<Elf64Sym>();
-                }
+                self.compute_stride_boundaries(section, size_of::<Elf64Sym>(), &mut boundaries)
             }
             SectionKind::Elf32Dyn => {
-                let mut addr = section.start;
-                while addr < section.end {
-                    boundaries.push(addr);
-                    addr += size_of::<Elf32Dyn>();
-                }
+                self.compute_stride_boundaries(section, size_of::<Elf32Dyn>(), &mut boundaries)
             }
             SectionKind::Elf64Dyn => {
+                self.compute_stride_boundaries(section, size_of::<Elf64Dyn>(), &mut boundaries)
+            }
+            SectionKind::Elf32Rel => {
+                self.compute_stride_boundaries(section, size_of::<Elf32Rel>(), &mut boundaries)
+            }
+            SectionKind::Elf64Rel => {
+                self.compute_stride_boundaries(section, size_of::<Elf64Rel>(), &mut boundaries)
+            }
+            SectionKind::Elf32Rela => {
+                self.compute_stride_boundaries(section, size_of::<Elf32Rela>(), &mut boundaries)
+            }
+            SectionKind::Elf64Rela => {
+                self.compute_stride_boundaries(section, size_of::<Elf64Rela>(), &mut boundaries)
+            }
+            SectionKind::ElfVersym => {
+                self.compute_stride_boundaries(section, size_of::<ElfVersym>(), &mut boundaries)
+            }
+            SectionKind::ElfVerneed | SectionKind::ElfVerdef => {
+                let definitions = section.kind == SectionKind::ElfVerdef;
+                let records = elf::version_records(section.bytes(), definitions, self.endianness);
+                for (offset, _) in records {
+                    boundaries.push(section.start + offset);
+                }
+            }
+            SectionKind::ElfNote => {
+                for offset in elf::note_offsets(section.bytes(), self.endianness) {
+                    boundaries.push(section.start + offset);
+                }
+            }
+            SectionKind::ElfGnuHash => self.compute_gnu_hash_boundaries(section, &mut boundaries),
+            SectionKind::Structured => {
                 let mut addr = section.start;
                 while addr < section.end {
                     boundaries.push(addr);
-                    addr += size_of::<Elf64Dyn>();
+                    addr += self.structured_block(addr, section).1;
                 }
             }
             // For any other section kinds just assume they evenly
-            // split in blocks of [`BYTES_BLOCK_SIZE`].
+            // split in blocks of [`BYTES_BLOCK_SIZE`] in between typed variables.
             _ => {
                 let mut addr = section.start;
                 while addr < section.end {
                     boundaries.push(addr);
-                    addr += BYTES_BLOCK_SIZE;
+                    addr += self.typed_block(addr, section).1;
                 }
             }
         }
@@ -521,6 +804,41 @@ impl Processor {
         boundaries
     }
 
+    fn compute_stride_boundaries(
+        &self,
+        section: &Section,
+        stride: usize,
+        boundaries: &mut Vec<usize>,
+    ) {
+        let mut addr = section.start;
+        while addr < section.end {
+            boundaries.push(addr);
+            addr += stride;
+        }
+    }
+
+    fn compute_gnu_hash_boundaries(&self, section: &Section, boundaries: &mut Vec<usize>) {
+        let is_64 = self.arch.address_size() != Some(AddressSize::U32);
+        let layout = match GnuHashLayout::parse(section.bytes(), is_64, self.endianness) {
+            Some(layout) => layout,
+            None => return,
+        };
+
+        boundaries.push(section.start + layout.bloom);
+
+        let mut addr = section.start + layout.buckets;
+        while addr < section.end {
+            boundaries.push(addr);
+            addr += WORDS_BLOCK_SIZE * 4;
+
+            // Chains start on their own block.
+            let chains = section.start + layout.chains;
+            if addr > chains && addr - WORDS_BLOCK_SIZE * 4 < chains {
+                addr = chains;
+            }
+        }
+    }
+
     fn compute_code_boundaries(&self, section: &Section, boundaries: &mut Vec<usize>) {
         let mut addr = section.start;
 
@@ -529,7 +847,7 @@ impl Processor {
                 break;
             }
 
-            if self.index.get_sym_by_addr(addr).is_some() {
+            if self.index().get_sym_by_addr(addr).is_some() {
                 boundaries.push(addr);
             }
 
@@ -560,7 +878,7 @@ impl Processor {
                 }
 
                 // We found some labelled bytes, so those would have to be in a different block.
-                if addr != baddr && self.index.get_sym_by_addr(baddr).is_some() {
+                if addr != baddr && self.index().get_sym_by_addr(baddr).is_some() {
                     break;
                 }
 
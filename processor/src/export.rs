@@ -0,0 +1,598 @@
+//! Writing the symbols of a binary out for other tools, as JSON, CSV or as an ELF file with only
+//! a `.symtab` that gdb can load with `add-symbol-file`.
+
+use crate::Processor;
+use debugvault::Index;
+use object::elf;
+use object::endian::{Endian, Endianness, U16, U32, U64};
+use object::pod::bytes_of;
+use object::{AddressSize, Architecture};
+use processor_shared::{Section, SectionKind};
+use std::fmt::Write;
+use std::path::Path;
+
+/// A symbol with it's size guessed from where the next one starts.
+struct SizedSymbol<'a> {
+    addr: usize,
+    size: usize,
+    name: &'a str,
+    /// Name as it's stored in the binary, which is what other tools expect to find.
+    mangled: &'a str,
+    module: Option<&'a str>,
+    /// Index of the section it's in, starting at 1.
+    section: Option<usize>,
+}
+
+/// Escapes a string for use inside of a JSON string.
+fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for chr in s.chars() {
+        match chr {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            chr if (chr as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", chr as u32);
+            }
+            chr => escaped.push(chr),
+        }
+    }
+    escaped
+}
+
+/// Quotes a CSV field if it contains a delimiter, C++ names often do.
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Symbols of `index` with their sizes, as found in `sections`.
+fn sized_symbols<'a>(sections: &[&Section], index: &'a Index) -> Vec<SizedSymbol<'a>> {
+    // Sections are labeled in the listing, but they aren't symbols to other tools.
+    let syms: Vec<_> = index
+        .functions()
+        .filter(|sym| !sections.iter().any(|s| s.start == sym.addr && s.name == sym.item.as_str()))
+        .collect();
+    let mut sized = Vec::with_capacity(syms.len());
+
+    for (idx, sym) in syms.iter().enumerate() {
+        let section = sections
+            .iter()
+            .position(|section| (section.start..section.end).contains(&sym.addr));
+
+        // Symbols don't record their size, so they're assumed to end where the next one starts
+        // and the last one in a section at the end of it.
+        let next = syms.get(idx + 1).map(|next| next.addr);
+        let end = match section.map(|idx| sections[idx]) {
+            Some(section) => next.unwrap_or(section.end).clamp(sym.addr, section.end),
+            None => next.unwrap_or(sym.addr),
+        };
+
+        sized.push(SizedSymbol {
+            addr: sym.addr,
+            size: end - sym.addr,
+            name: sym.item.as_str(),
+            mangled: sym.item.mangled(),
+            module: sym.item.module(),
+            section: section.map(|idx| idx + 1),
+        });
+    }
+
+    sized
+}
+
+impl Processor {
+    /// Sections that are loaded at an address.
+    fn loaded_sections(&self) -> Vec<&Section> {
+        self.sections().filter(|section| section.start != 0 && section.end > section.start).collect()
+    }
+
+    fn export_json(&self, syms: &[SizedSymbol]) -> String {
+        let mut json = String::from("[\n");
+
+        for (idx, sym) in syms.iter().enumerate() {
+            let _ = write!(
+                json,
+                "  {{\"name\": \"{}\", \"address\": \"{:#x}\", \"size\": {}",
+                json_escape(sym.mangled),
+                sym.addr,
+                sym.size
+            );
+
+            if sym.name != sym.mangled {
+                let _ = write!(json, ", \"demangled\": \"{}\"", json_escape(sym.name));
+            }
+
+            if let Some(module) = sym.module {
+                let _ = write!(json, ", \"module\": \"{}\"", json_escape(module));
+            }
+
+            json += if idx + 1 == syms.len() { "}\n" } else { "},\n" };
+        }
+
+        json += "]\n";
+        json
+    }
+
+    fn export_csv(&self, syms: &[SizedSymbol]) -> String {
+        let mut csv = String::from("address,size,name,demangled,module\n");
+
+        for sym in syms {
+            let demangled = if sym.name != sym.mangled { sym.name } else { "" };
+            let _ = writeln!(
+                csv,
+                "{:#x},{},{},{},{}",
+                sym.addr,
+                sym.size,
+                csv_escape(sym.mangled),
+                csv_escape(demangled),
+                csv_escape(sym.module.unwrap_or_default())
+            );
+        }
+
+        csv
+    }
+
+    fn export_elf(&self, syms: &[SizedSymbol]) -> std::io::Result<Vec<u8>> {
+        elf_file(self.arch, self.endianness, &self.loaded_sections(), syms)
+    }
+
+    /// Writes every symbol to `path`, as JSON or CSV depending on it's extension and otherwise
+    /// as an ELF file. Returns the number of symbols written.
+    pub fn export_symbols(&self, path: &Path) -> std::io::Result<usize> {
+        let index = self.index();
+        let syms = sized_symbols(&self.loaded_sections(), &index);
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        let bytes = match extension.to_ascii_lowercase().as_str() {
+            "json" => self.export_json(&syms).into_bytes(),
+            "csv" => self.export_csv(&syms).into_bytes(),
+            _ => self.export_elf(&syms)?,
+        };
+
+        std::fs::write(path, bytes)?;
+
+        log::complex!(
+            w "[processor::export_symbols] exported ",
+            g syms.len().to_string(),
+            w " symbols to ",
+            g path.display().to_string(),
+            w "."
+        );
+
+        Ok(syms.len())
+    }
+}
+
+/// ELF file with a section header for each of `sections` and a `.symtab` holding `syms`.
+fn elf_file(
+    arch: Architecture,
+    endian: Endianness,
+    sections: &[&Section],
+    syms: &[SizedSymbol],
+) -> std::io::Result<Vec<u8>> {
+    // Indices from `SHN_LORESERVE` up are reserved, the null section and the tables describing
+    // the symbols have to fit below it too.
+    if sections.len() + 4 > elf::SHN_LORESERVE as usize {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} sections don't fit in an ELF file", sections.len()),
+        ));
+    }
+
+    let is_64 = arch.address_size() != Some(AddressSize::U32);
+    let mut writer = ElfWriter {
+        endian,
+        is_64,
+        bytes: Vec::new(),
+    };
+
+    let machine = match arch {
+        Architecture::X86_64 | Architecture::X86_64_X32 => elf::EM_X86_64,
+        Architecture::I386 => elf::EM_386,
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => elf::EM_AARCH64,
+        Architecture::Arm => elf::EM_ARM,
+        Architecture::Riscv32 | Architecture::Riscv64 => elf::EM_RISCV,
+        Architecture::Mips | Architecture::Mips64 => elf::EM_MIPS,
+        _ => elf::EM_NONE,
+    };
+
+    // Names of the mirrored sections, followed by the ones describing the symbols.
+    let mut shstrtab = vec![0];
+    let mut section_names = Vec::new();
+    for section in sections.iter() {
+        section_names.push(shstrtab.len() as u32);
+        shstrtab.extend_from_slice(section.name.as_bytes());
+        shstrtab.push(0);
+    }
+
+    let mut table_names = [0; 3];
+    for (name, offset) in [".symtab", ".strtab", ".shstrtab"].iter().zip(&mut table_names) {
+        *offset = shstrtab.len() as u32;
+        shstrtab.extend_from_slice(name.as_bytes());
+        shstrtab.push(0);
+    }
+
+    let symtab_idx = sections.len() as u32 + 1;
+    let mut strtab = vec![0];
+    let mut symtab = Vec::new();
+
+    writer.symbol(&mut symtab, 0, 0, 0, 0, 0);
+    for sym in syms.iter() {
+        let name = strtab.len() as u32;
+        strtab.extend_from_slice(sym.mangled.as_bytes());
+        strtab.push(0);
+
+        let (kind, shndx) = match sym.section {
+            Some(idx) => {
+                let kind = match sections[idx - 1].kind {
+                    SectionKind::Code => elf::STT_FUNC,
+                    _ => elf::STT_OBJECT,
+                };
+                (kind, idx as u16)
+            }
+            None => (elf::STT_NOTYPE, elf::SHN_ABS),
+        };
+
+        let info = (elf::STB_GLOBAL << 4) | kind;
+        writer.symbol(
+            &mut symtab,
+            name,
+            info,
+            shndx,
+            sym.addr as u64,
+            sym.size as u64,
+        );
+    }
+
+    let header_size = if is_64 { 64 } else { 52 };
+    let symtab_offset = header_size;
+    let strtab_offset = symtab_offset + symtab.len();
+    let shstrtab_offset = strtab_offset + strtab.len();
+    let section_headers_offset = (shstrtab_offset + shstrtab.len()).next_multiple_of(8);
+    let section_count = symtab_idx as u16 + 3;
+
+    writer.header(machine, section_headers_offset as u64, section_count);
+    writer.bytes.extend_from_slice(&symtab);
+    writer.bytes.extend_from_slice(&strtab);
+    writer.bytes.extend_from_slice(&shstrtab);
+    writer.bytes.resize(section_headers_offset, 0);
+
+    writer.section(SectionHeader::default());
+    for (section, name) in sections.iter().zip(section_names) {
+        let mut flags = elf::SHF_ALLOC;
+        if section.kind == SectionKind::Code {
+            flags |= elf::SHF_EXECINSTR;
+        }
+
+        // Only the addresses are kept, the contents are in the binary.
+        writer.section(SectionHeader {
+            name,
+            kind: elf::SHT_NOBITS,
+            flags: flags as u64,
+            addr: section.start as u64,
+            size: (section.end - section.start) as u64,
+            align: 1,
+            ..SectionHeader::default()
+        });
+    }
+
+    writer.section(SectionHeader {
+        name: table_names[0],
+        kind: elf::SHT_SYMTAB,
+        offset: symtab_offset as u64,
+        size: symtab.len() as u64,
+        link: symtab_idx + 1,
+        // Index of the first global symbol, which is every symbol but the null one.
+        info: 1,
+        align: if is_64 { 8 } else { 4 },
+        entsize: if is_64 { 24 } else { 16 },
+        ..SectionHeader::default()
+    });
+
+    writer.section(SectionHeader {
+        name: table_names[1],
+        kind: elf::SHT_STRTAB,
+        offset: strtab_offset as u64,
+        size: strtab.len() as u64,
+        align: 1,
+        ..SectionHeader::default()
+    });
+
+    writer.section(SectionHeader {
+        name: table_names[2],
+        kind: elf::SHT_STRTAB,
+        offset: shstrtab_offset as u64,
+        size: shstrtab.len() as u64,
+        align: 1,
+        ..SectionHeader::default()
+    });
+
+    Ok(writer.bytes)
+}
+
+#[derive(Default)]
+struct SectionHeader {
+    name: u32,
+    kind: u32,
+    flags: u64,
+    addr: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+    info: u32,
+    align: u64,
+    entsize: u64,
+}
+
+/// Serializes ELF structures in the class and byte order of the binary.
+struct ElfWriter {
+    endian: Endianness,
+    is_64: bool,
+    bytes: Vec<u8>,
+}
+
+impl ElfWriter {
+    fn header(&mut self, machine: u16, section_headers_offset: u64, section_count: u16) {
+        let e = self.endian;
+        let ident = elf::Ident {
+            magic: elf::ELFMAG,
+            class: if self.is_64 {
+                elf::ELFCLASS64
+            } else {
+                elf::ELFCLASS32
+            },
+            data: if e.is_big_endian() {
+                elf::ELFDATA2MSB
+            } else {
+                elf::ELFDATA2LSB
+            },
+            version: elf::EV_CURRENT,
+            os_abi: elf::ELFOSABI_NONE,
+            abi_version: 0,
+            padding: [0; 7],
+        };
+
+        if self.is_64 {
+            self.bytes.extend_from_slice(bytes_of(&elf::FileHeader64 {
+                e_ident: ident,
+                e_type: U16::new(e, elf::ET_EXEC),
+                e_machine: U16::new(e, machine),
+                e_version: U32::new(e, elf::EV_CURRENT as u32),
+                e_entry: U64::new(e, 0),
+                e_phoff: U64::new(e, 0),
+                e_shoff: U64::new(e, section_headers_offset),
+                e_flags: U32::new(e, 0),
+                e_ehsize: U16::new(e, 64),
+                e_phentsize: U16::new(e, 0),
+                e_phnum: U16::new(e, 0),
+                e_shentsize: U16::new(e, 64),
+                e_shnum: U16::new(e, section_count),
+                e_shstrndx: U16::new(e, section_count - 1),
+            }));
+        } else {
+            self.bytes.extend_from_slice(bytes_of(&elf::FileHeader32 {
+                e_ident: ident,
+                e_type: U16::new(e, elf::ET_EXEC),
+                e_machine: U16::new(e, machine),
+                e_version: U32::new(e, elf::EV_CURRENT as u32),
+                e_entry: U32::new(e, 0),
+                e_phoff: U32::new(e, 0),
+                e_shoff: U32::new(e, section_headers_offset as u32),
+                e_flags: U32::new(e, 0),
+                e_ehsize: U16::new(e, 52),
+                e_phentsize: U16::new(e, 0),
+                e_phnum: U16::new(e, 0),
+                e_shentsize: U16::new(e, 40),
+                e_shnum: U16::new(e, section_count),
+                e_shstrndx: U16::new(e, section_count - 1),
+            }));
+        }
+    }
+
+    fn symbol(&self, out: &mut Vec<u8>, name: u32, info: u8, shndx: u16, value: u64, size: u64) {
+        let e = self.endian;
+        if self.is_64 {
+            out.extend_from_slice(bytes_of(&elf::Sym64 {
+                st_name: U32::new(e, name),
+                st_info: info,
+                st_other: elf::STV_DEFAULT,
+                st_shndx: U16::new(e, shndx),
+                st_value: U64::new(e, value),
+                st_size: U64::new(e, size),
+            }));
+        } else {
+            out.extend_from_slice(bytes_of(&elf::Sym32 {
+                st_name: U32::new(e, name),
+                st_value: U32::new(e, value as u32),
+                st_size: U32::new(e, size as u32),
+                st_info: info,
+                st_other: elf::STV_DEFAULT,
+                st_shndx: U16::new(e, shndx),
+            }));
+        }
+    }
+
+    fn section(&mut self, header: SectionHeader) {
+        let e = self.endian;
+        if self.is_64 {
+            self.bytes.extend_from_slice(bytes_of(&elf::SectionHeader64 {
+                sh_name: U32::new(e, header.name),
+                sh_type: U32::new(e, header.kind),
+                sh_flags: U64::new(e, header.flags),
+                sh_addr: U64::new(e, header.addr),
+                sh_offset: U64::new(e, header.offset),
+                sh_size: U64::new(e, header.size),
+                sh_link: U32::new(e, header.link),
+                sh_info: U32::new(e, header.info),
+                sh_addralign: U64::new(e, header.align),
+                sh_entsize: U64::new(e, header.entsize),
+            }));
+        } else {
+            self.bytes.extend_from_slice(bytes_of(&elf::SectionHeader32 {
+                sh_name: U32::new(e, header.name),
+                sh_type: U32::new(e, header.kind),
+                sh_flags: U32::new(e, header.flags as u32),
+                sh_addr: U32::new(e, header.addr as u32),
+                sh_offset: U32::new(e, header.offset as u32),
+                sh_size: U32::new(e, header.size as u32),
+                sh_link: U32::new(e, header.link),
+                sh_info: U32::new(e, header.info),
+                sh_addralign: U32::new(e, header.align as u32),
+                sh_entsize: U32::new(e, header.entsize as u32),
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object::{Object, ObjectSection, ObjectSymbol, SectionIndex, SymbolKind, SymbolSection};
+
+    fn sections() -> Vec<Section> {
+        vec![
+            Section::new(
+                ".text".into(),
+                "Text",
+                SectionKind::Code,
+                &[],
+                0x1000,
+                0x1100,
+            ),
+            Section::new(
+                ".data".into(),
+                "Data",
+                SectionKind::Raw,
+                &[],
+                0x2000,
+                0x2040,
+            ),
+        ]
+    }
+
+    fn index() -> Index {
+        let mut index = Index::default();
+        index.insert_func(0x1000, "main");
+        index.insert_func(0x1040, "_ZN5queue4pushEi");
+        index.insert_func(0x2000, "table");
+        index
+    }
+
+    #[test]
+    fn symbol_sizes() {
+        let sections = sections();
+        let sections: Vec<&Section> = sections.iter().collect();
+        let index = index();
+        let syms = sized_symbols(&sections, &index);
+
+        let sizes: Vec<_> = syms.iter().map(|sym| (sym.mangled, sym.size, sym.section)).collect();
+        assert_eq!(
+            sizes,
+            [
+                ("main", 0x40, Some(1)),
+                ("_ZN5queue4pushEi", 0xc0, Some(1)),
+                ("table", 0x40, Some(2))
+            ]
+        );
+        assert_eq!(syms[1].name, "queue::push");
+    }
+
+    fn round_trip(arch: Architecture, endian: Endianness) {
+        let sections = sections();
+        let sections: Vec<&Section> = sections.iter().collect();
+        let index = index();
+        let bytes = elf_file(arch, endian, &sections, &sized_symbols(&sections, &index)).unwrap();
+
+        let obj = object::read::File::parse(&*bytes).unwrap();
+        assert_eq!(obj.architecture(), arch);
+        assert_eq!(obj.endianness(), endian);
+
+        let text = obj.section_by_name(".text").unwrap();
+        assert_eq!(
+            (text.index(), text.address(), text.size()),
+            (SectionIndex(1), 0x1000, 0x100)
+        );
+        let data = obj.section_by_name(".data").unwrap();
+        assert_eq!(
+            (data.index(), data.address(), data.size()),
+            (SectionIndex(2), 0x2000, 0x40)
+        );
+
+        let align = if arch.address_size() == Some(AddressSize::U32) {
+            4
+        } else {
+            8
+        };
+        assert_eq!(obj.section_by_name(".symtab").unwrap().align(), align);
+
+        let syms: Vec<_> = obj
+            .symbols()
+            .map(|sym| {
+                let section = match sym.section() {
+                    SymbolSection::Section(idx) => idx.0,
+                    _ => 0,
+                };
+                (
+                    sym.name().unwrap(),
+                    sym.address(),
+                    sym.size(),
+                    sym.kind(),
+                    section,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            syms,
+            [
+                ("", 0, 0, SymbolKind::Null, 0),
+                ("main", 0x1000, 0x40, SymbolKind::Text, 1),
+                ("_ZN5queue4pushEi", 0x1040, 0xc0, SymbolKind::Text, 1),
+                ("table", 0x2000, 0x40, SymbolKind::Data, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn elf_64() {
+        round_trip(Architecture::X86_64, Endianness::Little);
+    }
+
+    #[test]
+    fn elf_32() {
+        round_trip(Architecture::I386, Endianness::Little);
+        round_trip(Architecture::Mips, Endianness::Big);
+    }
+
+    #[test]
+    fn section_limit() {
+        let sections = sections();
+        let index = index();
+
+        // The null section and the three symbol tables take up the last indices below
+        // `SHN_LORESERVE`.
+        let most = vec![&sections[0]; elf::SHN_LORESERVE as usize - 4];
+        let bytes = elf_file(
+            Architecture::X86_64,
+            Endianness::Little,
+            &most,
+            &sized_symbols(&most, &index),
+        )
+        .unwrap();
+        let obj = object::read::File::parse(&*bytes).unwrap();
+        let symtab = obj.section_by_name(".symtab").unwrap();
+        assert_eq!(symtab.index(), SectionIndex(0xfefd));
+        assert_eq!(
+            obj.section_by_name(".shstrtab").unwrap().index(),
+            SectionIndex(0xfeff)
+        );
+
+        let too_many = vec![&sections[0]; elf::SHN_LORESERVE as usize - 3];
+        let syms = sized_symbols(&too_many, &index);
+        assert!(elf_file(Architecture::X86_64, Endianness::Little, &too_many, &syms).is_err());
+    }
+}
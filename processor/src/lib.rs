@@ -1,14 +1,17 @@
 mod fmt;
 mod blocks;
+mod export;
+mod locals;
 
 use decoder::{Decodable, Decoded};
-use object::{Endianness, Object, ObjectSegment};
+use object::{Endianness, Object, ObjectKind, ObjectSegment};
 use object::{Architecture, BinaryFormat};
 use object::read::File as ObjectFile;
 use processor_shared::{AddressMap, Addressed, PhysAddr, Section, SectionKind, Segment};
 use debugvault::Index;
 use tokenizing::Token;
-use binformat::{elf, macho, pe, RawSymbol};
+use binformat::{archive, elf, macho, pe, relocatable, Class, Datastructure, FileInfo, RawSymbol};
+use binformat::Reference;
 
 use memmap2::Mmap;
 use x86_64::long_mode as x64;
@@ -16,8 +19,10 @@ use x86_64::protected_mode as x86;
 use arm::armv7 as armv7;
 use arm::armv8::a64 as aarch64;
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::mem::ManuallyDrop;
+use std::sync::{RwLock, RwLockReadGuard};
 
 pub use blocks::{BlockContent, Block};
 
@@ -134,8 +139,14 @@ pub struct Processor {
     /// Where the binary is located.
     pub path: std::path::PathBuf,
 
-    /// Symbol lookup by physical address.
-    pub index: Index,
+    /// Symbol lookup by physical address, swapped out when symbols are imported.
+    index: RwLock<Index>,
+
+    /// Facts about the binary as a whole, such as it's toolchain and signature.
+    pub file_info: Vec<FileInfo>,
+
+    /// C++ classes recovered from runtime type information.
+    pub classes: Vec<Class>,
 
     /// File handle to binary,
     _file: File,
@@ -149,6 +160,14 @@ pub struct Processor {
     /// Object's segments sorted by address.
     segments: Vec<Segment>,
 
+    /// Datastructures the binary format parsed up front.
+    /// Sorted by address.
+    datastructures: AddressMap<Datastructure>,
+
+    /// Remarks shown after an instruction, such as the selector a message is sent with.
+    /// Sorted by address.
+    comments: AddressMap<String>,
+
     /// Errors occurred in decoding instructions.
     /// Sorted by address.
     errors: AddressMap<decoder::Error>,
@@ -178,43 +197,94 @@ impl Processor {
         let file = std::fs::File::open(path.as_ref()).map_err(Error::IO)?;
         let mmap = unsafe { Mmap::map(&file).map_err(Error::IO)? };
         let binary: &'static [u8] = unsafe { std::mem::transmute(&mmap[..]) };
-        let obj = ObjectFile::parse(binary)?;
 
         let path = path.as_ref().to_path_buf();
         let now = std::time::Instant::now();
 
         let mut syms = AddressMap::default();
         let mut sections = Vec::new();
+        let mut segments = Vec::new();
+        let mut datastructures = AddressMap::default();
+        let mut comments = AddressMap::default();
+        let mut file_info = Vec::new();
+        let mut classes = Vec::new();
+
+        let obj = if object::FileKind::parse(binary)? == object::FileKind::Archive {
+            let mut debug_info = archive::ArchiveDebugInfo::parse(binary)?;
+            sections.extend(debug_info.sections);
+            segments.extend(debug_info.segments);
+            syms.extend(debug_info.syms);
+
+            // Architecture and debug info are taken from the first member.
+            if debug_info.objects.is_empty() {
+                return Err(Error::NotAnExecutable);
+            }
+            debug_info.objects.swap_remove(0)
+        } else {
+            ObjectFile::parse(binary)?
+        };
+
         match &obj {
+            _ if !segments.is_empty() => {}
+            object::File::Elf32(_)
+            | object::File::Elf64(_)
+            | object::File::Coff(_)
+            | object::File::CoffBig(_)
+                if obj.kind() == ObjectKind::Relocatable =>
+            {
+                let debug_info =
+                    relocatable::RelocatableDebugInfo::parse(&obj, relocatable::BASE_ADDR);
+                sections.extend(debug_info.sections);
+                syms.extend(debug_info.syms);
+                segments.push(Segment {
+                    name: "relocatable (generated)".to_string(),
+                    start: relocatable::BASE_ADDR,
+                    end: debug_info.end,
+                });
+            }
             object::File::MachO32(macho) => {
                 let debug_info = macho::MachoDebugInfo::parse(macho)?;
                 sections.extend(debug_info.sections);
                 syms.extend(debug_info.syms);
+                comments.extend(debug_info.selectors);
+                file_info.extend(debug_info.file_info);
+                classes.extend(debug_info.classes);
             }
             object::File::MachO64(macho) => {
                 let debug_info = macho::MachoDebugInfo::parse(macho)?;
                 sections.extend(debug_info.sections);
                 syms.extend(debug_info.syms);
+                comments.extend(debug_info.selectors);
+                file_info.extend(debug_info.file_info);
+                classes.extend(debug_info.classes);
             }
             object::File::Elf32(elf) => {
                 let debug_info = elf::ElfDebugInfo::parse(elf)?;
                 sections.extend(debug_info.sections);
                 syms.extend(debug_info.syms);
+                classes.extend(debug_info.classes);
             }
             object::File::Elf64(elf) => {
                 let debug_info = elf::ElfDebugInfo::parse(elf)?;
                 sections.extend(debug_info.sections);
                 syms.extend(debug_info.syms);
+                classes.extend(debug_info.classes);
             }
             object::File::Pe32(pe) => {
                 let debug_info = pe::PeDebugInfo::parse(pe)?;
                 sections.extend(debug_info.sections);
                 syms.extend(debug_info.syms);
+                datastructures.extend(debug_info.datastructures);
+                file_info.extend(debug_info.file_info);
+                classes.extend(debug_info.classes);
             }
             object::File::Pe64(pe) => {
                 let debug_info = pe::PeDebugInfo::parse(pe)?;
                 sections.extend(debug_info.sections);
                 syms.extend(debug_info.syms);
+                datastructures.extend(debug_info.datastructures);
+                file_info.extend(debug_info.file_info);
+                classes.extend(debug_info.classes);
             }
             _ => {}
         }
@@ -222,7 +292,7 @@ impl Processor {
         for section in sections.iter() {
             syms.push(Addressed {
                 addr: section.start,
-                item: RawSymbol { name: &section.name, module: None }
+                item: RawSymbol { name: &section.name, module: None, ordinal: None }
             });
         }
 
@@ -237,7 +307,6 @@ impl Processor {
             );
         }
 
-        let mut segments = Vec::new();
         for segment in obj.segments() {
             let name = segment.name()?.unwrap_or("unknown").to_string();
             let start = segment.address() as PhysAddr;
@@ -246,8 +315,12 @@ impl Processor {
             segments.push(Segment { name, start, end });
         }
 
+        datastructures.extend(typed_variables(&index, &sections, obj.endianness()));
+
         segments.sort_unstable_by_key(|s| s.start);
         sections.sort_unstable_by_key(|s| s.start);
+        datastructures.sort_unstable();
+        comments.sort_unstable();
 
         if sections.is_empty() {
             let base = if obj.format() == BinaryFormat::Pe {
@@ -284,6 +357,18 @@ impl Processor {
         }
 
         let arch = obj.architecture();
+
+        file_info.insert(0, FileInfo {
+            title: "General",
+            entries: vec![
+                ("Path".to_string(), path.display().to_string()),
+                ("Format".to_string(), format!("{:?}", obj.format())),
+                ("Architecture".to_string(), format!("{arch:?}")),
+                ("Endianness".to_string(), format!("{:?}", obj.endianness())),
+                ("Entrypoint".to_string(), format!("{entrypoint:#x}")),
+            ],
+        });
+
         let (instruction_tokens, instruction_width) = unsafe {
             match arch {
                 Architecture::Riscv32 | Architecture::Riscv64 => (
@@ -414,9 +499,13 @@ impl Processor {
             path,
             sections,
             segments,
+            datastructures,
+            comments,
             errors,
             instructions,
-            index,
+            index: RwLock::new(index),
+            file_info,
+            classes,
             _file: file,
             _mmap: mmap,
             max_instruction_width,
@@ -427,6 +516,16 @@ impl Processor {
         })
     }
 
+    /// Symbol lookup by physical address.
+    pub fn index(&self) -> RwLockReadGuard<'_, Index> {
+        self.index.read().unwrap()
+    }
+
+    /// Merges the symbols at `path` into the index, see [`Index::import`].
+    pub fn import(&self, path: &std::path::Path) -> std::io::Result<usize> {
+        self.index.write().unwrap().import(path)
+    }
+
     /// Relatively slow tokenization of an [`Instruction`].
     /// Xref's get resolved which requires some extra computation.
     pub fn instruction_tokens(&self, instruction: &Instruction, symbols: &Index) -> Vec<Token> {
@@ -444,6 +543,13 @@ impl Processor {
         }
     }
 
+    pub fn comment_by_addr(&self, addr: PhysAddr) -> Option<&str> {
+        match self.comments.search(addr) {
+            Ok(idx) => Some(&self.comments[idx].item),
+            Err(..) => None,
+        }
+    }
+
     pub fn instruction_by_addr(&self, addr: PhysAddr) -> Option<&Instruction> {
         match self.instructions.search(addr) {
             Ok(idx) => Some(&self.instructions[idx].item),
@@ -511,3 +617,85 @@ impl From<object::Error> for Error {
         Error::Object(err)
     }
 }
+
+/// Datastructures for the global variables described by debug info.
+fn typed_variables(
+    index: &Index,
+    sections: &[Section],
+    endianness: Endianness,
+) -> AddressMap<Datastructure> {
+    let mut datastructures = AddressMap::default();
+
+    // Field names and types are shared between variables of the same type.
+    let mut interned: HashMap<String, &'static str> = HashMap::new();
+    let mut intern = |s: String| -> &'static str {
+        match interned.get(&s) {
+            Some(interned) => interned,
+            None => {
+                let leaked: &'static str = Box::leak(s.clone().into_boxed_str());
+                interned.insert(s, leaked);
+                leaked
+            }
+        }
+    };
+
+    for Addressed { addr, item: var } in index.types.variables.iter() {
+        // Only sections that would otherwise be shown as bytes.
+        let section = match sections.iter().find(|section| {
+            section.start <= *addr
+                && *addr < section.end
+                && matches!(
+                    section.kind,
+                    SectionKind::Raw | SectionKind::Raw4 | SectionKind::Raw8 | SectionKind::Raw16
+                )
+        }) {
+            Some(section) => section,
+            None => continue,
+        };
+
+        let size = match index.types.size_of(var.tipe) {
+            Some(size) if size > 0 => size,
+            _ => continue,
+        };
+
+        let layout = index.types.layout(var, section.bytes_by_addr(*addr, size), endianness);
+        if layout.fields.is_empty() {
+            continue;
+        }
+
+        let mut fields = Vec::with_capacity(layout.fields.len());
+        let mut references = Vec::new();
+        for field in layout.fields {
+            if let Some(pointer) = field.pointer {
+                references.push((fields.len(), Reference::Address(pointer)));
+            }
+
+            fields.push((
+                addr + field.offset,
+                intern(field.name),
+                intern(field.tipe),
+                field.value,
+            ));
+        }
+
+        datastructures.push(Addressed {
+            addr: *addr,
+            item: Datastructure {
+                ident: intern(index.types.name_of(var.tipe)),
+                size: layout.size,
+                fields,
+                references,
+            },
+        });
+    }
+
+    if !datastructures.is_empty() {
+        log::complex!(
+            w "[processor::typed_variables] found ",
+            g datastructures.len().to_string(),
+            w " typed variables."
+        );
+    }
+
+    datastructures
+}
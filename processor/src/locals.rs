@@ -0,0 +1,290 @@
+//! Matching where local variables live against the operands of an instruction.
+
+use debugvault::{Local, Location};
+use object::Architecture;
+use tokenizing::Token;
+
+#[rustfmt::skip]
+const X86_64: [[&str; 4]; 16] = [
+    ["rax", "eax", "ax", "al"], ["rdx", "edx", "dx", "dl"],
+    ["rcx", "ecx", "cx", "cl"], ["rbx", "ebx", "bx", "bl"],
+    ["rsi", "esi", "si", "sil"], ["rdi", "edi", "di", "dil"],
+    ["rbp", "ebp", "bp", "bpl"], ["rsp", "esp", "sp", "spl"],
+    ["r8", "r8d", "r8w", "r8b"], ["r9", "r9d", "r9w", "r9b"],
+    ["r10", "r10d", "r10w", "r10b"], ["r11", "r11d", "r11w", "r11b"],
+    ["r12", "r12d", "r12w", "r12b"], ["r13", "r13d", "r13w", "r13b"],
+    ["r14", "r14d", "r14w", "r14b"], ["r15", "r15d", "r15w", "r15b"],
+];
+
+#[rustfmt::skip]
+const X86: [[&str; 3]; 8] = [
+    ["eax", "ax", "al"], ["ecx", "cx", "cl"], ["edx", "dx", "dl"], ["ebx", "bx", "bl"],
+    ["esp", "sp", "spl"], ["ebp", "bp", "bpl"], ["esi", "si", "sil"], ["edi", "di", "dil"],
+];
+
+#[rustfmt::skip]
+const ARM: [&str; 16] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7",
+    "r8", "sb", "r10", "fp", "ip", "sp", "lr", "pc",
+];
+
+#[rustfmt::skip]
+const RISCV: [&str; 64] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2",
+    "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+    "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7",
+    "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7",
+    "fs0", "fs1", "fa0", "fa1", "fa2", "fa3", "fa4", "fa5",
+    "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7",
+    "fs8", "fs9", "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+/// Every name a DWARF register goes by in the disassembly, including views of part of it.
+fn register_names(arch: Architecture, register: u16) -> Vec<String> {
+    let idx = register as usize;
+    match arch {
+        Architecture::X86_64 => match idx {
+            0..=15 => X86_64[idx].iter().map(|name| name.to_string()).collect(),
+            17..=32 => vec![format!("xmm{}", idx - 17), format!("ymm{}", idx - 17)],
+            _ => Vec::new(),
+        },
+        Architecture::X86_64_X32 | Architecture::I386 => match idx {
+            0..=7 => X86[idx].iter().map(|name| name.to_string()).collect(),
+            21..=28 => vec![format!("xmm{}", idx - 21)],
+            _ => Vec::new(),
+        },
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => match idx {
+            0..=30 => vec![format!("x{idx}"), format!("w{idx}")],
+            31 => vec!["sp".to_string(), "wsp".to_string()],
+            64..=95 => ["v", "q", "d", "s", "h", "b"]
+                .iter()
+                .map(|prefix| format!("{prefix}{}", idx - 64))
+                .collect(),
+            _ => Vec::new(),
+        },
+        Architecture::Arm => match idx {
+            0..=15 => vec![ARM[idx].to_string(), format!("r{idx}")],
+            64..=95 => vec![format!("s{}", idx - 64)],
+            256..=287 => vec![format!("d{}", idx - 256)],
+            _ => Vec::new(),
+        },
+        Architecture::Riscv32 | Architecture::Riscv64 => match RISCV.get(idx) {
+            Some(name) => vec![name.to_string()],
+            None => Vec::new(),
+        },
+        Architecture::Mips | Architecture::Mips64 => match mips::REGISTERS.get(idx) {
+            Some(name) => vec![name.to_string()],
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Parses an offset such as `-0x18`, `+0x8` or `16`.
+fn parse_offset(offset: &str) -> Option<i64> {
+    let (negative, offset) = match offset.strip_prefix('-') {
+        Some(offset) => (true, offset),
+        None => (false, offset.strip_prefix('+').unwrap_or(offset)),
+    };
+
+    let value = match offset.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => offset.parse().ok()?,
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+/// Base register and offset of every memory operand without an index, such as `[rbp - 0x18]`
+/// or `[x29, #0x10]`.
+fn memory_operands(text: &str) -> Vec<(String, i64)> {
+    let mut operands = Vec::new();
+
+    for operand in text.split('[').skip(1) {
+        let inner = match operand.split_once(']') {
+            Some((inner, _)) => inner,
+            None => continue,
+        };
+
+        let inner: String = inner.chars().filter(|c| !c.is_whitespace() && *c != '#').collect();
+        let (base, offset) = match inner.split_once(',') {
+            Some((base, offset)) => (base, offset),
+            None => match inner.find(['+', '-']) {
+                Some(idx) => inner.split_at(idx),
+                None => (&inner[..], "0"),
+            },
+        };
+
+        if base.is_empty() || !base.chars().all(|c| c.is_ascii_alphanumeric()) {
+            continue;
+        }
+
+        if let Some(offset) = parse_offset(offset) {
+            operands.push((base.to_string(), offset));
+        }
+    }
+
+    operands
+}
+
+/// Names of the locals that an instruction's operands refer to.
+///
+/// Comments appended to the instruction, starting with a `;`, aren't operands.
+pub fn referenced(arch: Architecture, tokens: &[Token], locals: &[Local]) -> Vec<String> {
+    let text: String = tokens
+        .iter()
+        .map(|token| &token.text[..])
+        .take_while(|text| !text.trim_start().starts_with(';'))
+        .collect();
+
+    // The first word is the mnemonic, which might share it's name with a register.
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .skip(1)
+        .collect();
+    let operands = memory_operands(&text);
+    let mut names: Vec<String> = Vec::new();
+
+    for local in locals {
+        let referenced = match local.location {
+            Location::Register(register) => {
+                let aliases = register_names(arch, register);
+                words.iter().any(|word| aliases.iter().any(|alias| alias == word))
+            }
+            Location::Memory { register, offset } => {
+                let aliases = register_names(arch, register);
+                operands.iter().any(|(base, disp)| *disp == offset && aliases.contains(base))
+            }
+        };
+
+        if referenced && !names.iter().any(|name| **name == *local.name) {
+            names.push(local.name.to_string());
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenizing::colors;
+
+    fn tokens(text: &[&'static str]) -> Vec<Token> {
+        text.iter().map(|text| Token::from_str(text, colors::WHITE)).collect()
+    }
+
+    fn local(name: &str, location: Location) -> Local {
+        Local {
+            name: name.into(),
+            location,
+        }
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse_offset("-0x18"), Some(-0x18));
+        assert_eq!(parse_offset("+0x8"), Some(8));
+        assert_eq!(parse_offset("16"), Some(16));
+        assert_eq!(parse_offset("-4"), Some(-4));
+        assert_eq!(parse_offset("0xzz"), None);
+        assert_eq!(parse_offset("rax"), None);
+    }
+
+    #[test]
+    fn memory() {
+        assert_eq!(
+            memory_operands("mov eax, dword ptr [rbp - 0x18]"),
+            [("rbp".to_string(), -0x18)]
+        );
+        assert_eq!(
+            memory_operands("ldp x29, x30, [sp, #0x10]"),
+            [("sp".to_string(), 0x10)]
+        );
+        assert_eq!(memory_operands("mov rax, [rsp]"), [("rsp".to_string(), 0)]);
+        assert_eq!(
+            memory_operands("movsd xmm0, [rbp + 8]; mov [rbx + 0x10], rax"),
+            [("rbp".to_string(), 8), ("rbx".to_string(), 0x10)]
+        );
+
+        // Indexed and unterminated operands.
+        assert!(memory_operands("mov eax, [rbp + rcx * 4]").is_empty());
+        assert!(memory_operands("mov eax, [rbp - 0x18").is_empty());
+    }
+
+    #[test]
+    fn registers_and_stack() {
+        let locals = [
+            local("count", Location::Register(0)),
+            local(
+                "buf",
+                Location::Memory {
+                    register: 6,
+                    offset: -0x18,
+                },
+            ),
+            local(
+                "len",
+                Location::Memory {
+                    register: 6,
+                    offset: -0x20,
+                },
+            ),
+        ];
+
+        let inst = tokens(&["mov", " ", "eax", ", ", "dword ptr [rbp - 0x18]"]);
+        assert_eq!(
+            referenced(Architecture::X86_64, &inst, &locals),
+            ["count", "buf"]
+        );
+
+        // The mnemonic isn't an operand.
+        let inst = tokens(&["cbw", " ", "ax"]);
+        let locals = [local("ax", Location::Register(0))];
+        assert_eq!(referenced(Architecture::X86_64, &inst, &locals), ["ax"]);
+        let inst = tokens(&["al"]);
+        assert!(referenced(Architecture::X86_64, &inst, &locals).is_empty());
+    }
+
+    #[test]
+    fn comments() {
+        let locals = [
+            local("count", Location::Register(0)),
+            local(
+                "buf",
+                Location::Memory {
+                    register: 6,
+                    offset: -0x18,
+                },
+            ),
+        ];
+
+        let inst = tokens(&["nop", "  ; eax = [rbp - 0x18]"]);
+        assert!(referenced(Architecture::X86_64, &inst, &locals).is_empty());
+
+        let inst = tokens(&["ret", "  ; count"]);
+        assert!(referenced(Architecture::X86_64, &inst, &locals).is_empty());
+    }
+
+    #[test]
+    fn aarch64() {
+        let locals = [
+            local("idx", Location::Register(19)),
+            local(
+                "ptr",
+                Location::Memory {
+                    register: 29,
+                    offset: 0x10,
+                },
+            ),
+        ];
+
+        let inst = tokens(&["ldr", " ", "w19", ", ", "[x29, #0x10]"]);
+        assert_eq!(
+            referenced(Architecture::Aarch64, &inst, &locals),
+            ["idx", "ptr"]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use object::{Pod, ReadRef};
+use object::{Endianness, Pod, ReadRef};
 
 /// Address in memory.
 pub type VirtAddr = usize;
@@ -38,6 +38,26 @@ pub enum SectionKind {
     Elf32Dyn,
     /// Elf64Dyn.
     Elf64Dyn,
+    /// Elf32Rel.
+    Elf32Rel,
+    /// Elf64Rel.
+    Elf64Rel,
+    /// Elf32Rela.
+    Elf32Rela,
+    /// Elf64Rela.
+    Elf64Rela,
+    /// ELF notes such as the build-id.
+    ElfNote,
+    /// GNU-style symbol hash table.
+    ElfGnuHash,
+    /// Version index of every dynamic symbol.
+    ElfVersym,
+    /// Versions required from other libraries.
+    ElfVerneed,
+    /// Versions defined by this library.
+    ElfVerdef,
+    /// Datastructures parsed up front by the binary format, such as PE data directories.
+    Structured,
     /// DWARF debug info.
     Debug,
     /// Zero sized special sections.
@@ -63,6 +83,12 @@ pub struct Section {
 
     /// Section start + size of uncompressed data.
     pub end: PhysAddr,
+
+    /// Name of the string table that string offsets in this section index into (ELF `sh_link`).
+    pub strings: Option<String>,
+
+    /// Name of the symbol table that symbol indices in this section index into (ELF `sh_link`).
+    pub symbols: Option<String>,
 }
 
 impl Section {
@@ -80,7 +106,9 @@ impl Section {
             kind,
             bytes,
             start,
-            end
+            end,
+            strings: None,
+            symbols: None,
         }
     }
 
@@ -89,19 +117,71 @@ impl Section {
         self.bytes
     }
 
+    /// The section's bytes, which are either memory mapped or leaked and so live for as long
+    /// as the binary is loaded.
+    #[inline]
+    pub fn static_bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+
     pub fn bytes_by_addr(&self, addr: PhysAddr, len: usize) -> &[u8] {
         let rva = addr - self.start;
         let bytes = &self.bytes.get(rva..).unwrap_or(&[]);
         &bytes[..std::cmp::min(bytes.len(), len)]
     }
 
-    pub fn read_at<T: Pod>(&self, addr: PhysAddr) -> Result<&T, ()> {
+    /// Take the range `start..end` of this section as a new section.
+    pub fn slice(&self, name: String, kind: SectionKind, start: PhysAddr, end: PhysAddr) -> Self {
+        // Sections can be larger in memory than in the file.
+        let offset = std::cmp::min(start - self.start, self.bytes.len());
+        let len = std::cmp::min(end - start, self.bytes.len() - offset);
+
+        Self {
+            name,
+            ident: self.ident,
+            kind,
+            bytes: &self.bytes[offset..offset + len],
+            start,
+            end,
+            strings: self.strings.clone(),
+            symbols: self.symbols.clone(),
+        }
+    }
+
+    /// Read a structure at `addr`, swapping it's fields if the file's byte order differs from
+    /// the host's.
+    pub fn read_at<T: Pod + SwapBytes>(&self, addr: PhysAddr, endianness: Endianness) -> Result<T, ()> {
         let rva = addr - self.start;
-        let bytes = &self.bytes.get(rva..).unwrap_or(&[]);
-        bytes.read_at(rva as u64)
+        let value = *self.bytes.read_at::<T>(rva as u64)?;
+
+        // The default is the host's byte order.
+        if endianness == Endianness::default() {
+            Ok(value)
+        } else {
+            Ok(value.swap_bytes())
+        }
     }
 }
 
+/// Plain data that can be converted between byte orders.
+pub trait SwapBytes: Copy {
+    /// Reverse the byte order of every field.
+    fn swap_bytes(self) -> Self;
+}
+
+macro_rules! impl_swap_bytes {
+    ($($ty:ty),*) => {
+        $(impl SwapBytes for $ty {
+            #[inline]
+            fn swap_bytes(self) -> Self {
+                <$ty>::swap_bytes(self)
+            }
+        })*
+    };
+}
+
+impl_swap_bytes!(u8, u16, u32, u64, i8, i16, i32, i64);
+
 #[derive(Debug)]
 pub struct Segment {
     /// Segment identifier.
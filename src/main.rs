@@ -15,6 +15,19 @@ fn main() {
         wayland::set_env();
     }
 
+    debugvault::set_simplify(ARGS.simplify);
+
+    if ARGS.demangle {
+        if let Err(err) = commands::demangle(&ARGS) {
+            // Output piped into something like `head` that stopped reading isn't an error.
+            if err.kind() != std::io::ErrorKind::BrokenPipe {
+                eprintln!("Failed to demangle: {err}.");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     if ARGS.disassemble {
         let mut ui = gui::UI::new().unwrap();
         ui.process_args();